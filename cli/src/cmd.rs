@@ -14,43 +14,168 @@ pub enum Command {
     Nothing,
     Help(String),
     Auth(AuthCommand),
+    Admin(AdminCommand),
     Categories(CategoriesCommand),
     Notes(NotesCommand),
+    Graph(GraphCommand),
 }
 
 #[derive(Debug)]
 pub enum AuthCommand {
     Login(api::auth::LoginIn),
+    /// Code of a login with 2FA.
+    Code(String),
+    Register(api::auth::RegisterIn),
+    Refresh,
+    Logout,
+    KeysAll,
+    KeysCreate(api::api_key::ApiKeyIn),
+    KeysRevoke(i32),
+    TwoFactorSetup,
+    TwoFactorVerify(String),
+    TwoFactorDisable(String),
+}
+
+#[derive(Debug)]
+pub enum AdminCommand {
+    UsersAll,
+    UsersUpdate(i32, api::user::UserAdminIn),
+    UsersDelete(i32),
+    CategoriesAll,
+    CategoriesCreate(api::category::CategoryIn),
+    CategoriesUpdate(i32, api::category::CategoryIn),
+    CategoriesDelete(i32),
 }
 
 #[derive(Debug)]
 pub enum CategoriesCommand {
     All,
+    Tree,
     Get(i32),
+    Create(api::category::CategoryIn),
+    Update(i32, api::category::CategoryIn),
+    Delete(i32),
+}
+
+/// Options of a notes listing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotesListing {
+    pub tags: Vec<String>,
+    pub category_id: Option<i32>,
+    pub descendants: bool,
+    pub updated_since: Option<String>,
+    pub sort: Option<String>,
+    pub descending: bool,
+    pub limit: Option<i64>,
 }
 
 #[derive(Debug)]
 pub enum NotesCommand {
-    All,
+    All(NotesListing),
+    Next,
     Get(i32),
-    Create(api::note::NoteIn),
-    Update(i32, api::note::NoteIn),
+    Render(i32),
+    /// Note and whether to encrypt it.
+    Create(api::note::NoteIn, bool),
+    Update(i32, api::note::NoteIn, bool),
     Delete(i32),
+    Search(String, Option<i64>),
+    History(i32),
+    Diff(i32, i32, Option<i32>),
+    Restore(i32, i32),
+    TagAdd(i32, String),
+    TagRemove(i32, String),
+    Share(i32, api::note_share::NoteShareIn),
+    Unshare(i32, i32),
+    Shares(i32),
+    Shared,
+    Link(i32, api::public_link::PublicLinkIn),
+    Links(i32),
+    Unlink(i32, i32),
+    /// Note and path of the file to attach.
+    Attach(i32, String),
+    Attachments(i32),
+    Unlock(String),
+    Lock,
+}
+
+/// Options of a graph export.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphExport {
+    pub format: Option<String>,
+    pub category_id: Option<i32>,
+    pub descendants: bool,
+}
+
+#[derive(Debug)]
+pub enum GraphCommand {
+    /// Export and path of the file to write.
+    Export(GraphExport, String),
 }
 
 pub fn cmd_hints() -> HashSet<String> {
     let mut set = HashSet::new();
     set.insert(String::from("auth"));
     set.insert(String::from("auth login"));
+    set.insert(String::from("auth register"));
+    set.insert(String::from("auth refresh"));
+    set.insert(String::from("auth logout"));
+    set.insert(String::from("auth keys"));
+    set.insert(String::from("auth keys all"));
+    set.insert(String::from("auth keys create"));
+    set.insert(String::from("auth keys revoke"));
+    set.insert(String::from("auth code"));
+    set.insert(String::from("auth 2fa"));
+    set.insert(String::from("auth 2fa setup"));
+    set.insert(String::from("auth 2fa verify"));
+    set.insert(String::from("auth 2fa disable"));
+    set.insert(String::from("admin"));
+    set.insert(String::from("admin users"));
+    set.insert(String::from("admin users all"));
+    set.insert(String::from("admin users disable"));
+    set.insert(String::from("admin users enable"));
+    set.insert(String::from("admin users role"));
+    set.insert(String::from("admin users delete"));
+    set.insert(String::from("admin categories"));
+    set.insert(String::from("admin categories all"));
+    set.insert(String::from("admin categories create"));
+    set.insert(String::from("admin categories update"));
+    set.insert(String::from("admin categories delete"));
     set.insert(String::from("categories"));
     set.insert(String::from("categories all"));
+    set.insert(String::from("categories tree"));
     set.insert(String::from("categories get"));
+    set.insert(String::from("categories create"));
+    set.insert(String::from("categories update"));
+    set.insert(String::from("categories delete"));
     set.insert(String::from("notes"));
     set.insert(String::from("notes all"));
+    set.insert(String::from("notes next"));
     set.insert(String::from("notes get"));
+    set.insert(String::from("notes render"));
     set.insert(String::from("notes create"));
     set.insert(String::from("notes update"));
     set.insert(String::from("notes delete"));
+    set.insert(String::from("notes search"));
+    set.insert(String::from("notes history"));
+    set.insert(String::from("notes diff"));
+    set.insert(String::from("notes restore"));
+    set.insert(String::from("notes tag"));
+    set.insert(String::from("notes tag add"));
+    set.insert(String::from("notes tag remove"));
+    set.insert(String::from("notes share"));
+    set.insert(String::from("notes unshare"));
+    set.insert(String::from("notes shares"));
+    set.insert(String::from("notes shared"));
+    set.insert(String::from("notes link"));
+    set.insert(String::from("notes links"));
+    set.insert(String::from("notes unlink"));
+    set.insert(String::from("notes attach"));
+    set.insert(String::from("notes attachments"));
+    set.insert(String::from("notes unlock"));
+    set.insert(String::from("notes lock"));
+    set.insert(String::from("graph"));
+    set.insert(String::from("graph export"));
 
     for v in set.clone().into_iter() {
         set.insert(format!("help {}", v));
@@ -127,12 +252,18 @@ enum MainOpt {
     #[structopt(name = "auth")]
     /// Auth services.
     Auth(AuthOpt),
+    #[structopt(name = "admin")]
+    /// Admin services, only for admins.
+    Admin(AdminOpt),
     #[structopt(name = "categories")]
     /// Categories services.
     Categories(CategoriesOpt),
     #[structopt(name = "notes")]
     /// Notes services.
     Notes(NotesOpt),
+    #[structopt(name = "graph")]
+    /// Graph services.
+    Graph(GraphOpt),
     /// Help services.
     #[structopt(name = "help")]
     Help(HelpOpt),
@@ -154,6 +285,24 @@ enum AuthOpt {
     /// Login to the server.
     #[structopt(name = "login")]
     Login(AuthLoginOpt),
+    /// Send the code of a login with 2FA.
+    #[structopt(name = "code")]
+    Code(AuthCodeOpt),
+    /// Register a new user.
+    #[structopt(name = "register")]
+    Register(AuthRegisterOpt),
+    /// Renew the session before it expires.
+    #[structopt(name = "refresh")]
+    Refresh(AuthRefreshOpt),
+    /// Close the session in the server.
+    #[structopt(name = "logout")]
+    Logout(AuthLogoutOpt),
+    /// API keys services for scripts.
+    #[structopt(name = "keys")]
+    Keys(AuthKeysOpt),
+    /// Two-factor authentication services.
+    #[structopt(name = "2fa")]
+    TwoFactor(AuthTwoFactorOpt),
 }
 
 #[derive(Debug, StructOpt)]
@@ -165,21 +314,187 @@ struct AuthLoginOpt {
     password: String,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthCodeOpt {
+    /// Code of the authenticator app or a recovery code.
+    code: String,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthRegisterOpt {
+    /// Email of the user.
+    email: String,
+    /// Name of the user.
+    name: String,
+    /// Password of the user, at least 8 characters.
+    password: String,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthRefreshOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthLogoutOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum AuthKeysOpt {
+    /// Get all API keys.
+    #[structopt(name = "all")]
+    All(AuthKeysAllOpt),
+    /// Create an API key, it is only shown once.
+    #[structopt(name = "create")]
+    Create(AuthKeysCreateOpt),
+    /// Revoke an API key.
+    #[structopt(name = "revoke")]
+    Revoke(AuthKeysRevokeOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthKeysAllOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthKeysCreateOpt {
+    /// Name of the API key.
+    name: String,
+    /// Only allow reading with the API key.
+    #[structopt(long = "read-only")]
+    read_only: bool,
+    /// Days until the API key expires [Optional].
+    #[structopt(long = "expires-in-days")]
+    expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthKeysRevokeOpt {
+    /// Id of the API key.
+    id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum AuthTwoFactorOpt {
+    /// Create a TOTP secret and its recovery codes, they are only shown once.
+    #[structopt(name = "setup")]
+    Setup(AuthTwoFactorSetupOpt),
+    /// Enable the 2FA with a first code of the authenticator app.
+    #[structopt(name = "verify")]
+    Verify(AuthCodeOpt),
+    /// Disable the 2FA.
+    #[structopt(name = "disable")]
+    Disable(AuthCodeOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AuthTwoFactorSetupOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum AdminOpt {
+    /// Users services.
+    #[structopt(name = "users")]
+    Users(AdminUsersOpt),
+    /// Shared categories services.
+    #[structopt(name = "categories")]
+    Categories(AdminCategoriesOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum AdminUsersOpt {
+    /// Get all users.
+    #[structopt(name = "all")]
+    All(AdminUsersAllOpt),
+    /// Disable a user, it cannot login anymore.
+    #[structopt(name = "disable")]
+    Disable(AdminUsersIdOpt),
+    /// Enable a disabled user.
+    #[structopt(name = "enable")]
+    Enable(AdminUsersIdOpt),
+    /// Set the role of a user.
+    #[structopt(name = "role")]
+    Role(AdminUsersRoleOpt),
+    /// Delete a user without notes.
+    #[structopt(name = "delete")]
+    Delete(AdminUsersIdOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AdminUsersAllOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AdminUsersIdOpt {
+    /// Id of the user.
+    id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct AdminUsersRoleOpt {
+    /// Id of the user.
+    id: i32,
+    /// Role of the user, admin or user.
+    role: String,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum AdminCategoriesOpt {
+    /// Get all shared categories.
+    #[structopt(name = "all")]
+    All(CategoriesAllOpt),
+    /// Create a shared category.
+    #[structopt(name = "create")]
+    Create(CategoriesCreateOpt),
+    /// Update a shared category.
+    #[structopt(name = "update")]
+    Update(CategoriesUpdateOpt),
+    /// Delete a shared category.
+    #[structopt(name = "delete")]
+    Delete(CategoriesDeleteOpt),
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
 enum CategoriesOpt {
     /// Get all categories.
     #[structopt(name = "all")]
     All(CategoriesAllOpt),
+    /// Get all categories as a tree.
+    #[structopt(name = "tree")]
+    Tree(CategoriesTreeOpt),
     /// Get a category.
     #[structopt(name = "get")]
     Get(CategoriesGetOpt),
+    /// Create a category.
+    #[structopt(name = "create")]
+    Create(CategoriesCreateOpt),
+    /// Rename or move a category.
+    #[structopt(name = "update")]
+    Update(CategoriesUpdateOpt),
+    /// Delete a category, its notes are left without category and its children become roots.
+    #[structopt(name = "delete")]
+    Delete(CategoriesDeleteOpt),
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
 struct CategoriesAllOpt {}
 
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct CategoriesTreeOpt {}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
 struct CategoriesGetOpt {
@@ -187,15 +502,50 @@ struct CategoriesGetOpt {
     id: i32,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct CategoriesCreateOpt {
+    /// Name of the category.
+    name: String,
+    /// Id of the parent category [Optional].
+    #[structopt(long = "parent")]
+    parent_id: Option<i32>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct CategoriesUpdateOpt {
+    /// Id of the category.
+    id: i32,
+    /// New name of the category.
+    name: String,
+    /// Id of the new parent category, a root category if missing [Optional].
+    #[structopt(long = "parent")]
+    parent_id: Option<i32>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct CategoriesDeleteOpt {
+    /// Id of the category.
+    id: i32,
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
 enum NotesOpt {
     /// Get all notes.
     #[structopt(name = "all")]
     All(NotesAllOpt),
+    /// Get the next page of the last notes listing.
+    #[structopt(name = "next")]
+    Next(NotesNextOpt),
     /// Get a note.
     #[structopt(name = "get")]
     Get(NotesGetOpt),
+    /// Get a note as sanitized HTML.
+    #[structopt(name = "render")]
+    Render(NotesGetOpt),
     /// Create a note.
     #[structopt(name = "create")]
     Create(NotesCreateOpt),
@@ -205,11 +555,85 @@ enum NotesOpt {
     /// Delete a note.
     #[structopt(name = "delete")]
     Delete(NotesDeleteOpt),
+    /// Search notes by their title and data.
+    #[structopt(name = "search")]
+    Search(NotesSearchOpt),
+    /// Get the revisions of a note.
+    #[structopt(name = "history")]
+    History(NotesHistoryOpt),
+    /// Compare a revision of a note with another one or its current content.
+    #[structopt(name = "diff")]
+    Diff(NotesDiffOpt),
+    /// Restore a revision of a note.
+    #[structopt(name = "restore")]
+    Restore(NotesRestoreOpt),
+    /// Tag services of a note.
+    #[structopt(name = "tag")]
+    Tag(NotesTagOpt),
+    /// Share a note with another user.
+    #[structopt(name = "share")]
+    Share(NotesShareOpt),
+    /// Stop sharing a note with a user.
+    #[structopt(name = "unshare")]
+    Unshare(NotesUnshareOpt),
+    /// Get the users a note is shared with.
+    #[structopt(name = "shares")]
+    Shares(NotesSharesOpt),
+    /// Get the notes shared with you.
+    #[structopt(name = "shared")]
+    Shared(NotesSharedOpt),
+    /// Create a public read-only link to a note.
+    #[structopt(name = "link")]
+    Link(NotesLinkOpt),
+    /// Get the public links of a note.
+    #[structopt(name = "links")]
+    Links(NotesLinksOpt),
+    /// Revoke a public link of a note.
+    #[structopt(name = "unlink")]
+    Unlink(NotesUnlinkOpt),
+    /// Attach a file to a note.
+    #[structopt(name = "attach")]
+    Attach(NotesAttachOpt),
+    /// Get the attachments of a note.
+    #[structopt(name = "attachments")]
+    Attachments(NotesAttachmentsOpt),
+    /// Set the passphrase of the encrypted notes for this session.
+    #[structopt(name = "unlock")]
+    Unlock(NotesUnlockOpt),
+    /// Forget the passphrase of the encrypted notes.
+    #[structopt(name = "lock")]
+    Lock(NotesLockOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesAllOpt {
+    /// Only notes having this tag [Multiple].
+    #[structopt(long = "tag")]
+    tags: Vec<String>,
+    /// Only notes of this category [Optional].
+    #[structopt(long = "category")]
+    category_id: Option<i32>,
+    /// Also notes of the descendants of the category.
+    #[structopt(long = "descendants")]
+    descendants: bool,
+    /// Only notes updated since this RFC 3339 time [Optional].
+    #[structopt(long = "updated-since")]
+    updated_since: Option<String>,
+    /// Sort by create_time, update_time or title [Optional].
+    #[structopt(long = "sort")]
+    sort: Option<String>,
+    /// Sort in descending order.
+    #[structopt(long = "desc")]
+    descending: bool,
+    /// Number of notes of every page [Optional].
+    #[structopt(long = "limit")]
+    limit: Option<i64>,
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
-struct NotesAllOpt {}
+struct NotesNextOpt {}
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
@@ -227,6 +651,15 @@ struct NotesCreateOpt {
     data: String,
     /// Id of the category [Optional].
     category_id: Option<i32>,
+    /// Tag of the note [Multiple].
+    #[structopt(long = "tag")]
+    tags: Vec<String>,
+    /// Format of the data, plain or markdown [Optional].
+    #[structopt(long = "format")]
+    format: Option<String>,
+    /// Encrypt the title and data with the passphrase of 'notes unlock'.
+    #[structopt(long = "encrypt")]
+    encrypt: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -240,6 +673,15 @@ struct NotesUpdateOpt {
     data: String,
     /// Id of the category [Optional].
     category_id: Option<i32>,
+    /// Tag of the note, the current ones are kept if none is given [Multiple].
+    #[structopt(long = "tag")]
+    tags: Vec<String>,
+    /// Format of the data, plain or markdown [Optional].
+    #[structopt(long = "format")]
+    format: Option<String>,
+    /// Encrypt the title and data with the passphrase of 'notes unlock'.
+    #[structopt(long = "encrypt")]
+    encrypt: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -249,6 +691,175 @@ struct NotesDeleteOpt {
     id: i32,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesSearchOpt {
+    /// Words to search.
+    query: String,
+    /// Maximum number of results [Optional].
+    #[structopt(long = "limit")]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesHistoryOpt {
+    /// Id of the note.
+    id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesDiffOpt {
+    /// Id of the note.
+    id: i32,
+    /// Revision to compare.
+    revision: i32,
+    /// Revision compared with, the current content if missing [Optional].
+    to: Option<i32>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesRestoreOpt {
+    /// Id of the note.
+    id: i32,
+    /// Revision to restore.
+    revision: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum NotesTagOpt {
+    /// Add a tag to a note.
+    #[structopt(name = "add")]
+    Add(NotesTagChangeOpt),
+    /// Remove a tag from a note.
+    #[structopt(name = "remove")]
+    Remove(NotesTagChangeOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesTagChangeOpt {
+    /// Id of the note.
+    id: i32,
+    /// Name of the tag.
+    tag: String,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesShareOpt {
+    /// Id of the note.
+    id: i32,
+    /// Email of the user.
+    email: String,
+    /// Also allow the user to update the note.
+    #[structopt(long = "write")]
+    write: bool,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesUnshareOpt {
+    /// Id of the note.
+    id: i32,
+    /// Id of the user.
+    user_id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesSharesOpt {
+    /// Id of the note.
+    id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesSharedOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesUnlockOpt {
+    /// Passphrase of the encrypted notes, it never leaves the client.
+    passphrase: String,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesLockOpt {}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesLinkOpt {
+    /// Id of the note.
+    id: i32,
+    /// Password asked to open the link [Optional].
+    #[structopt(long = "password")]
+    password: Option<String>,
+    /// Hours until the link expires [Optional].
+    #[structopt(long = "expires-in-hours")]
+    expires_in_hours: Option<i64>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesLinksOpt {
+    /// Id of the note.
+    id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesUnlinkOpt {
+    /// Id of the note.
+    id: i32,
+    /// Id of the link.
+    link_id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesAttachOpt {
+    /// Id of the note.
+    id: i32,
+    /// Path of the file.
+    file: String,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct NotesAttachmentsOpt {
+    /// Id of the note.
+    id: i32,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+enum GraphOpt {
+    /// Export the graph of the notes, categories and wiki links to a file.
+    #[structopt(name = "export")]
+    Export(GraphExportOpt),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "_cmd", global_settings = &[AppSettings::ColorAuto])]
+struct GraphExportOpt {
+    /// Path of the file.
+    file: String,
+    /// Format json, dot or graphml [Optional].
+    #[structopt(long = "format")]
+    format: Option<String>,
+    /// Only notes of this category [Optional].
+    #[structopt(long = "category")]
+    category_id: Option<i32>,
+    /// Also notes of the descendants of the category.
+    #[structopt(long = "descendants")]
+    descendants: bool,
+}
+
 pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
     if _tokens.len() == 0 {
         return Ok(Command::Nothing);
@@ -269,31 +880,215 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                 email: login.email,
                 password: login.password,
             }))),
+            AuthOpt::Code(code) => Ok(Command::Auth(AuthCommand::Code(code.code))),
+            AuthOpt::Register(register) => Ok(Command::Auth(AuthCommand::Register(
+                api::auth::RegisterIn {
+                    email: register.email,
+                    name: register.name,
+                    password: register.password,
+                },
+            ))),
+            AuthOpt::Refresh(_) => Ok(Command::Auth(AuthCommand::Refresh)),
+            AuthOpt::Logout(_) => Ok(Command::Auth(AuthCommand::Logout)),
+            AuthOpt::Keys(AuthKeysOpt::All(_)) => Ok(Command::Auth(AuthCommand::KeysAll)),
+            AuthOpt::Keys(AuthKeysOpt::Create(create)) => Ok(Command::Auth(
+                AuthCommand::KeysCreate(api::api_key::ApiKeyIn {
+                    name: create.name,
+                    read_only: create.read_only,
+                    expires_in_days: create.expires_in_days,
+                }),
+            )),
+            AuthOpt::Keys(AuthKeysOpt::Revoke(revoke)) => {
+                Ok(Command::Auth(AuthCommand::KeysRevoke(revoke.id)))
+            }
+            AuthOpt::TwoFactor(AuthTwoFactorOpt::Setup(_)) => {
+                Ok(Command::Auth(AuthCommand::TwoFactorSetup))
+            }
+            AuthOpt::TwoFactor(AuthTwoFactorOpt::Verify(verify)) => {
+                Ok(Command::Auth(AuthCommand::TwoFactorVerify(verify.code)))
+            }
+            AuthOpt::TwoFactor(AuthTwoFactorOpt::Disable(disable)) => {
+                Ok(Command::Auth(AuthCommand::TwoFactorDisable(disable.code)))
+            }
+        },
+        Ok(MainOpt::Admin(admin)) => match admin {
+            AdminOpt::Users(AdminUsersOpt::All(_)) => Ok(Command::Admin(AdminCommand::UsersAll)),
+            AdminOpt::Users(AdminUsersOpt::Disable(disable)) => {
+                Ok(Command::Admin(AdminCommand::UsersUpdate(
+                    disable.id,
+                    api::user::UserAdminIn {
+                        role: None,
+                        disabled: Some(true),
+                    },
+                )))
+            }
+            AdminOpt::Users(AdminUsersOpt::Enable(enable)) => {
+                Ok(Command::Admin(AdminCommand::UsersUpdate(
+                    enable.id,
+                    api::user::UserAdminIn {
+                        role: None,
+                        disabled: Some(false),
+                    },
+                )))
+            }
+            AdminOpt::Users(AdminUsersOpt::Role(role)) => {
+                Ok(Command::Admin(AdminCommand::UsersUpdate(
+                    role.id,
+                    api::user::UserAdminIn {
+                        role: Some(role.role),
+                        disabled: None,
+                    },
+                )))
+            }
+            AdminOpt::Users(AdminUsersOpt::Delete(delete)) => {
+                Ok(Command::Admin(AdminCommand::UsersDelete(delete.id)))
+            }
+            AdminOpt::Categories(AdminCategoriesOpt::All(_)) => {
+                Ok(Command::Admin(AdminCommand::CategoriesAll))
+            }
+            AdminOpt::Categories(AdminCategoriesOpt::Create(create)) => Ok(Command::Admin(
+                AdminCommand::CategoriesCreate(api::category::CategoryIn {
+                    name: create.name,
+                    parent_id: create.parent_id,
+                }),
+            )),
+            AdminOpt::Categories(AdminCategoriesOpt::Update(update)) => {
+                Ok(Command::Admin(AdminCommand::CategoriesUpdate(
+                    update.id,
+                    api::category::CategoryIn {
+                        name: update.name,
+                        parent_id: update.parent_id,
+                    },
+                )))
+            }
+            AdminOpt::Categories(AdminCategoriesOpt::Delete(delete)) => {
+                Ok(Command::Admin(AdminCommand::CategoriesDelete(delete.id)))
+            }
         },
         Ok(MainOpt::Categories(categories)) => match categories {
             CategoriesOpt::All(_) => Ok(Command::Categories(CategoriesCommand::All)),
+            CategoriesOpt::Tree(_) => Ok(Command::Categories(CategoriesCommand::Tree)),
             CategoriesOpt::Get(get) => Ok(Command::Categories(CategoriesCommand::Get(get.id))),
+            CategoriesOpt::Create(create) => Ok(Command::Categories(CategoriesCommand::Create(
+                api::category::CategoryIn {
+                    name: create.name,
+                    parent_id: create.parent_id,
+                },
+            ))),
+            CategoriesOpt::Update(update) => Ok(Command::Categories(CategoriesCommand::Update(
+                update.id,
+                api::category::CategoryIn {
+                    name: update.name,
+                    parent_id: update.parent_id,
+                },
+            ))),
+            CategoriesOpt::Delete(delete) => {
+                Ok(Command::Categories(CategoriesCommand::Delete(delete.id)))
+            }
         },
         Ok(MainOpt::Notes(notes)) => match notes {
-            NotesOpt::All(_) => Ok(Command::Notes(NotesCommand::All)),
+            NotesOpt::All(all) => Ok(Command::Notes(NotesCommand::All(NotesListing {
+                tags: all.tags,
+                category_id: all.category_id,
+                descendants: all.descendants,
+                updated_since: all.updated_since,
+                sort: all.sort,
+                descending: all.descending,
+                limit: all.limit,
+            }))),
+            NotesOpt::Next(_) => Ok(Command::Notes(NotesCommand::Next)),
             NotesOpt::Get(get) => Ok(Command::Notes(NotesCommand::Get(get.id))),
-            NotesOpt::Create(create) => {
-                Ok(Command::Notes(NotesCommand::Create(api::note::NoteIn {
+            NotesOpt::Render(render) => Ok(Command::Notes(NotesCommand::Render(render.id))),
+            NotesOpt::Create(create) => Ok(Command::Notes(NotesCommand::Create(
+                api::note::NoteIn {
                     title: create.title,
                     data: create.data,
                     category_id: create.category_id,
-                })))
-            }
+                    tags: Some(create.tags),
+                    format: create.format,
+                    key_check: None,
+                },
+                create.encrypt,
+            ))),
             NotesOpt::Update(update) => Ok(Command::Notes(NotesCommand::Update(
                 update.id,
                 api::note::NoteIn {
                     title: update.title,
                     data: update.data,
                     category_id: update.category_id,
+                    tags: if update.tags.is_empty() {
+                        None
+                    } else {
+                        Some(update.tags)
+                    },
+                    format: update.format,
+                    key_check: None,
                 },
+                update.encrypt,
             ))),
             NotesOpt::Delete(delete) => Ok(Command::Notes(NotesCommand::Delete(delete.id))),
+            NotesOpt::Search(search) => Ok(Command::Notes(NotesCommand::Search(
+                search.query,
+                search.limit,
+            ))),
+            NotesOpt::History(history) => Ok(Command::Notes(NotesCommand::History(history.id))),
+            NotesOpt::Diff(diff) => Ok(Command::Notes(NotesCommand::Diff(
+                diff.id,
+                diff.revision,
+                diff.to,
+            ))),
+            NotesOpt::Restore(restore) => Ok(Command::Notes(NotesCommand::Restore(
+                restore.id,
+                restore.revision,
+            ))),
+            NotesOpt::Tag(NotesTagOpt::Add(add)) => {
+                Ok(Command::Notes(NotesCommand::TagAdd(add.id, add.tag)))
+            }
+            NotesOpt::Tag(NotesTagOpt::Remove(remove)) => Ok(Command::Notes(
+                NotesCommand::TagRemove(remove.id, remove.tag),
+            )),
+            NotesOpt::Share(share) => Ok(Command::Notes(NotesCommand::Share(
+                share.id,
+                api::note_share::NoteShareIn {
+                    email: share.email,
+                    permission: Some(if share.write { "write" } else { "read" }.to_string()),
+                },
+            ))),
+            NotesOpt::Unshare(unshare) => Ok(Command::Notes(NotesCommand::Unshare(
+                unshare.id,
+                unshare.user_id,
+            ))),
+            NotesOpt::Shares(shares) => Ok(Command::Notes(NotesCommand::Shares(shares.id))),
+            NotesOpt::Shared(_) => Ok(Command::Notes(NotesCommand::Shared)),
+            NotesOpt::Unlock(unlock) => Ok(Command::Notes(NotesCommand::Unlock(unlock.passphrase))),
+            NotesOpt::Lock(_) => Ok(Command::Notes(NotesCommand::Lock)),
+            NotesOpt::Link(link) => Ok(Command::Notes(NotesCommand::Link(
+                link.id,
+                api::public_link::PublicLinkIn {
+                    password: link.password,
+                    expires_in_hours: link.expires_in_hours,
+                },
+            ))),
+            NotesOpt::Links(links) => Ok(Command::Notes(NotesCommand::Links(links.id))),
+            NotesOpt::Unlink(unlink) => Ok(Command::Notes(NotesCommand::Unlink(
+                unlink.id,
+                unlink.link_id,
+            ))),
+            NotesOpt::Attach(attach) => {
+                Ok(Command::Notes(NotesCommand::Attach(attach.id, attach.file)))
+            }
+            NotesOpt::Attachments(attachments) => {
+                Ok(Command::Notes(NotesCommand::Attachments(attachments.id)))
+            }
         },
+        Ok(MainOpt::Graph(GraphOpt::Export(export))) => Ok(Command::Graph(GraphCommand::Export(
+            GraphExport {
+                format: export.format,
+                category_id: export.category_id,
+                descendants: export.descendants,
+            },
+            export.file,
+        ))),
         Ok(MainOpt::Help(HelpOpt { service, command })) => match (service, command) {
             (Some(service), None) => match &*service {
                 "auth" => Ok(Command::Help(get_help(
@@ -301,7 +1096,12 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                     Some("auth"),
                     None,
                 ))),
-                "categories" => Ok(Command::Help(get_help(
+                "admin" => Ok(Command::Help(get_help(
+                    &AdminOpt::clap(),
+                    Some("admin"),
+                    None,
+                ))),
+                "categories" => Ok(Command::Help(get_help(
                     &CategoriesOpt::clap(),
                     Some("categories"),
                     None,
@@ -311,6 +1111,11 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                     Some("notes"),
                     None,
                 ))),
+                "graph" => Ok(Command::Help(get_help(
+                    &GraphOpt::clap(),
+                    Some("graph"),
+                    None,
+                ))),
                 _ => Err(Error::Parse(format!(
                     "error: service '{}' is not valid.",
                     service
@@ -323,6 +1128,52 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                         Some("auth"),
                         Some("login"),
                     ))),
+                    "code" => Ok(Command::Help(get_help(
+                        &AuthCodeOpt::clap(),
+                        Some("auth"),
+                        Some("code"),
+                    ))),
+                    "register" => Ok(Command::Help(get_help(
+                        &AuthRegisterOpt::clap(),
+                        Some("auth"),
+                        Some("register"),
+                    ))),
+                    "refresh" => Ok(Command::Help(get_help(
+                        &AuthRefreshOpt::clap(),
+                        Some("auth"),
+                        Some("refresh"),
+                    ))),
+                    "logout" => Ok(Command::Help(get_help(
+                        &AuthLogoutOpt::clap(),
+                        Some("auth"),
+                        Some("logout"),
+                    ))),
+                    "keys" => Ok(Command::Help(get_help(
+                        &AuthKeysOpt::clap(),
+                        Some("auth"),
+                        Some("keys"),
+                    ))),
+                    "2fa" => Ok(Command::Help(get_help(
+                        &AuthTwoFactorOpt::clap(),
+                        Some("auth"),
+                        Some("2fa"),
+                    ))),
+                    _ => Err(Error::Parse(format!(
+                        "error: command '{}' for service '{}' is not valid.",
+                        command, service
+                    ))),
+                },
+                "admin" => match &*command {
+                    "users" => Ok(Command::Help(get_help(
+                        &AdminUsersOpt::clap(),
+                        Some("admin"),
+                        Some("users"),
+                    ))),
+                    "categories" => Ok(Command::Help(get_help(
+                        &AdminCategoriesOpt::clap(),
+                        Some("admin"),
+                        Some("categories"),
+                    ))),
                     _ => Err(Error::Parse(format!(
                         "error: command '{}' for service '{}' is not valid.",
                         command, service
@@ -334,11 +1185,31 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                         Some("categories"),
                         Some("all"),
                     ))),
+                    "tree" => Ok(Command::Help(get_help(
+                        &CategoriesTreeOpt::clap(),
+                        Some("categories"),
+                        Some("tree"),
+                    ))),
                     "get" => Ok(Command::Help(get_help(
                         &CategoriesGetOpt::clap(),
                         Some("categories"),
                         Some("get"),
                     ))),
+                    "create" => Ok(Command::Help(get_help(
+                        &CategoriesCreateOpt::clap(),
+                        Some("categories"),
+                        Some("create"),
+                    ))),
+                    "update" => Ok(Command::Help(get_help(
+                        &CategoriesUpdateOpt::clap(),
+                        Some("categories"),
+                        Some("update"),
+                    ))),
+                    "delete" => Ok(Command::Help(get_help(
+                        &CategoriesDeleteOpt::clap(),
+                        Some("categories"),
+                        Some("delete"),
+                    ))),
                     _ => Err(Error::Parse(format!(
                         "error: command '{}' for service '{}' is not valid.",
                         command, service
@@ -350,11 +1221,21 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                         Some("notes"),
                         Some("all"),
                     ))),
+                    "next" => Ok(Command::Help(get_help(
+                        &NotesNextOpt::clap(),
+                        Some("notes"),
+                        Some("next"),
+                    ))),
                     "get" => Ok(Command::Help(get_help(
                         &NotesGetOpt::clap(),
                         Some("notes"),
                         Some("get"),
                     ))),
+                    "render" => Ok(Command::Help(get_help(
+                        &NotesGetOpt::clap(),
+                        Some("notes"),
+                        Some("render"),
+                    ))),
                     "create" => Ok(Command::Help(get_help(
                         &NotesCreateOpt::clap(),
                         Some("notes"),
@@ -370,6 +1251,97 @@ pub fn parse_command(_tokens: Vec<String>) -> Result<Command, Error> {
                         Some("notes"),
                         Some("delete"),
                     ))),
+                    "search" => Ok(Command::Help(get_help(
+                        &NotesSearchOpt::clap(),
+                        Some("notes"),
+                        Some("search"),
+                    ))),
+                    "history" => Ok(Command::Help(get_help(
+                        &NotesHistoryOpt::clap(),
+                        Some("notes"),
+                        Some("history"),
+                    ))),
+                    "diff" => Ok(Command::Help(get_help(
+                        &NotesDiffOpt::clap(),
+                        Some("notes"),
+                        Some("diff"),
+                    ))),
+                    "restore" => Ok(Command::Help(get_help(
+                        &NotesRestoreOpt::clap(),
+                        Some("notes"),
+                        Some("restore"),
+                    ))),
+                    "tag" => Ok(Command::Help(get_help(
+                        &NotesTagOpt::clap(),
+                        Some("notes"),
+                        Some("tag"),
+                    ))),
+                    "share" => Ok(Command::Help(get_help(
+                        &NotesShareOpt::clap(),
+                        Some("notes"),
+                        Some("share"),
+                    ))),
+                    "unshare" => Ok(Command::Help(get_help(
+                        &NotesUnshareOpt::clap(),
+                        Some("notes"),
+                        Some("unshare"),
+                    ))),
+                    "shares" => Ok(Command::Help(get_help(
+                        &NotesSharesOpt::clap(),
+                        Some("notes"),
+                        Some("shares"),
+                    ))),
+                    "shared" => Ok(Command::Help(get_help(
+                        &NotesSharedOpt::clap(),
+                        Some("notes"),
+                        Some("shared"),
+                    ))),
+                    "link" => Ok(Command::Help(get_help(
+                        &NotesLinkOpt::clap(),
+                        Some("notes"),
+                        Some("link"),
+                    ))),
+                    "links" => Ok(Command::Help(get_help(
+                        &NotesLinksOpt::clap(),
+                        Some("notes"),
+                        Some("links"),
+                    ))),
+                    "unlink" => Ok(Command::Help(get_help(
+                        &NotesUnlinkOpt::clap(),
+                        Some("notes"),
+                        Some("unlink"),
+                    ))),
+                    "attach" => Ok(Command::Help(get_help(
+                        &NotesAttachOpt::clap(),
+                        Some("notes"),
+                        Some("attach"),
+                    ))),
+                    "attachments" => Ok(Command::Help(get_help(
+                        &NotesAttachmentsOpt::clap(),
+                        Some("notes"),
+                        Some("attachments"),
+                    ))),
+                    "unlock" => Ok(Command::Help(get_help(
+                        &NotesUnlockOpt::clap(),
+                        Some("notes"),
+                        Some("unlock"),
+                    ))),
+                    "lock" => Ok(Command::Help(get_help(
+                        &NotesLockOpt::clap(),
+                        Some("notes"),
+                        Some("lock"),
+                    ))),
+                    _ => Err(Error::Parse(format!(
+                        "error: command '{}' for service '{}' is not valid.",
+                        command, service
+                    ))),
+                },
+                "graph" => match &*command {
+                    "export" => Ok(Command::Help(get_help(
+                        &GraphExportOpt::clap(),
+                        Some("graph"),
+                        Some("export"),
+                    ))),
                     _ => Err(Error::Parse(format!(
                         "error: command '{}' for service '{}' is not valid.",
                         command, service
@@ -425,15 +1397,36 @@ mod tests {
                 vec!["help"],
                 vec!["help", "auth"],
                 vec!["help", "auth", "login"],
+                vec!["help", "auth", "register"],
+                vec!["help", "auth", "refresh"],
+                vec!["help", "auth", "logout"],
+                vec!["help", "auth", "keys"],
+                vec!["help", "admin"],
+                vec!["help", "admin", "users"],
+                vec!["help", "admin", "categories"],
                 vec!["help", "categories"],
                 vec!["help", "categories", "all"],
+                vec!["help", "categories", "tree"],
                 vec!["help", "categories", "get"],
+                vec!["help", "categories", "create"],
+                vec!["help", "categories", "update"],
+                vec!["help", "categories", "delete"],
                 vec!["help", "notes"],
                 vec!["help", "notes", "all"],
+                vec!["help", "notes", "next"],
                 vec!["help", "notes", "get"],
                 vec!["help", "notes", "create"],
                 vec!["help", "notes", "update"],
                 vec!["help", "notes", "delete"],
+                vec!["help", "notes", "search"],
+                vec!["help", "notes", "history"],
+                vec!["help", "notes", "diff"],
+                vec!["help", "notes", "restore"],
+                vec!["help", "notes", "tag"],
+                vec!["help", "graph"],
+                vec!["help", "graph", "export"],
+                vec!["help", "auth", "code"],
+                vec!["help", "auth", "2fa"],
             ];
             for tokens in help_commands {
                 match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
@@ -466,6 +1459,170 @@ mod tests {
                 _ => panic!("Unexpected response"),
             }
         }
+        {
+            let tokens = vec!["auth", "register", "aaa", "bbb"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec!["auth", "register", "aaa", "bbb", "ccc"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::Register(register_in))) => {
+                    assert_eq!(register_in.email, "aaa");
+                    assert_eq!(register_in.name, "bbb");
+                    assert_eq!(register_in.password, "ccc");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "refresh"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::Refresh)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "logout"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::Logout)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "keys", "all"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::KeysAll)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "keys", "create"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec![
+                "auth",
+                "keys",
+                "create",
+                "backup",
+                "--read-only",
+                "--expires-in-days",
+                "30",
+            ];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::KeysCreate(api_key_in))) => {
+                    assert_eq!(api_key_in.name, "backup");
+                    assert!(api_key_in.read_only);
+                    assert_eq!(api_key_in.expires_in_days, Some(30));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "keys", "revoke", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::KeysRevoke(id))) => assert_eq!(id, 123),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "code", "123456"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::Code(code))) => assert_eq!(code, "123456"),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "2fa", "setup"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::TwoFactorSetup)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "2fa", "verify"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec!["auth", "2fa", "verify", "123456"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::TwoFactorVerify(code))) => {
+                    assert_eq!(code, "123456")
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["auth", "2fa", "disable", "ab12c-3d4e5"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Auth(AuthCommand::TwoFactorDisable(code))) => {
+                    assert_eq!(code, "ab12c-3d4e5")
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["admin", "users", "all"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Admin(AdminCommand::UsersAll)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["admin", "users", "disable", "2"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Admin(AdminCommand::UsersUpdate(id, user_admin_in))) => {
+                    assert_eq!(id, 2);
+                    assert_eq!(user_admin_in.role, None);
+                    assert_eq!(user_admin_in.disabled, Some(true));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["admin", "users", "role", "2", "admin"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Admin(AdminCommand::UsersUpdate(id, user_admin_in))) => {
+                    assert_eq!(id, 2);
+                    assert_eq!(user_admin_in.role, Some("admin".to_string()));
+                    assert_eq!(user_admin_in.disabled, None);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["admin", "users", "delete", "2"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Admin(AdminCommand::UsersDelete(id))) => assert_eq!(id, 2),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["admin", "categories", "create", "cat_a"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Admin(AdminCommand::CategoriesCreate(category_in))) => {
+                    assert_eq!(category_in.name, "cat_a");
+                    assert_eq!(category_in.parent_id, None);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["admin", "categories", "delete", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Admin(AdminCommand::CategoriesDelete(id))) => assert_eq!(id, 123),
+                _ => panic!("Unexpected response"),
+            }
+        }
         {
             let tokens = vec!["categories"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
@@ -480,6 +1637,13 @@ mod tests {
                 _ => panic!("Unexpected response"),
             }
         }
+        {
+            let tokens = vec!["categories", "tree"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Categories(CategoriesCommand::Tree)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
         {
             let tokens = vec!["categories", "get"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
@@ -496,6 +1660,53 @@ mod tests {
                 _ => panic!("Unexpected response"),
             }
         }
+        {
+            let tokens = vec!["categories", "create", "cat_a"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Categories(CategoriesCommand::Create(category_in))) => {
+                    assert_eq!(category_in.name, "cat_a");
+                    assert_eq!(category_in.parent_id, None);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["categories", "create", "cat_a", "--parent", "1"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Categories(CategoriesCommand::Create(category_in))) => {
+                    assert_eq!(category_in.name, "cat_a");
+                    assert_eq!(category_in.parent_id, Some(1));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["categories", "update", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec!["categories", "update", "123", "cat_b", "--parent", "1"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Categories(CategoriesCommand::Update(id, category_in))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(category_in.name, "cat_b");
+                    assert_eq!(category_in.parent_id, Some(1));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["categories", "delete", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Categories(CategoriesCommand::Delete(id))) => {
+                    assert_eq!(id, 123);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
         {
             let tokens = vec!["notes"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
@@ -506,7 +1717,58 @@ mod tests {
         {
             let tokens = vec!["notes", "all"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
-                Ok(Command::Notes(NotesCommand::All)) => {}
+                Ok(Command::Notes(NotesCommand::All(listing))) => {
+                    assert_eq!(listing, NotesListing::default());
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "all", "--tag", "aaa", "--tag", "bbb"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::All(listing))) => {
+                    assert_eq!(listing.tags, vec!["aaa".to_string(), "bbb".to_string()]);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec![
+                "notes",
+                "all",
+                "--category",
+                "1",
+                "--descendants",
+                "--updated-since",
+                "2020-01-01T00:00:00Z",
+                "--sort",
+                "title",
+                "--desc",
+                "--limit",
+                "10",
+            ];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::All(listing))) => {
+                    assert_eq!(
+                        listing,
+                        NotesListing {
+                            tags: vec![],
+                            category_id: Some(1),
+                            descendants: true,
+                            updated_since: Some("2020-01-01T00:00:00Z".to_string()),
+                            sort: Some("title".to_string()),
+                            descending: true,
+                            limit: Some(10),
+                        }
+                    );
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "next"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Next)) => {}
                 _ => panic!("Unexpected response"),
             }
         }
@@ -526,6 +1788,13 @@ mod tests {
                 _ => panic!("Unexpected response"),
             }
         }
+        {
+            let tokens = vec!["notes", "render", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Render(id))) => assert_eq!(id, 123),
+                _ => panic!("Unexpected response"),
+            }
+        }
         {
             let tokens = vec!["notes", "create"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
@@ -536,9 +1805,10 @@ mod tests {
         {
             let tokens = vec!["notes", "create", "some_title", "some_data"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
-                Ok(Command::Notes(NotesCommand::Create(note_in))) => {
+                Ok(Command::Notes(NotesCommand::Create(note_in, encrypt))) => {
                     assert_eq!(note_in.title, "some_title");
                     assert_eq!(note_in.data, "some_data");
+                    assert!(!encrypt);
                 }
                 _ => panic!("Unexpected response"),
             }
@@ -546,7 +1816,7 @@ mod tests {
         {
             let tokens = vec!["notes", "create", "some_title", "some_data", "123"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
-                Ok(Command::Notes(NotesCommand::Create(note_in))) => {
+                Ok(Command::Notes(NotesCommand::Create(note_in, _))) => {
                     assert_eq!(note_in.title, "some_title");
                     assert_eq!(note_in.data, "some_data");
                     assert_eq!(note_in.category_id, Some(123));
@@ -554,6 +1824,77 @@ mod tests {
                 _ => panic!("Unexpected response"),
             }
         }
+        {
+            let tokens = vec!["notes", "create", "some_title", "some_data", "--tag", "aaa"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Create(note_in, _))) => {
+                    assert_eq!(note_in.title, "some_title");
+                    assert_eq!(note_in.tags, Some(vec!["aaa".to_string()]));
+                    assert_eq!(note_in.format, None);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec![
+                "notes",
+                "create",
+                "some_title",
+                "some_data",
+                "--format",
+                "markdown",
+            ];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Create(note_in, _))) => {
+                    assert_eq!(note_in.format, Some("markdown".to_string()));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "create", "some_title", "some_data", "--encrypt"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Create(note_in, encrypt))) => {
+                    assert_eq!(note_in.title, "some_title");
+                    assert_eq!(note_in.key_check, None);
+                    assert!(encrypt);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec![
+                "notes",
+                "update",
+                "123",
+                "some_title",
+                "some_data",
+                "--encrypt",
+            ];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Update(id, _, encrypt))) => {
+                    assert_eq!(id, 123);
+                    assert!(encrypt);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "unlock", "some_passphrase"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Unlock(passphrase))) => {
+                    assert_eq!(passphrase, "some_passphrase");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "lock"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Lock)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
         {
             let tokens = vec!["notes", "update"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
@@ -564,7 +1905,7 @@ mod tests {
         {
             let tokens = vec!["notes", "update", "123", "some_title", "some_data"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
-                Ok(Command::Notes(NotesCommand::Update(id, note_in))) => {
+                Ok(Command::Notes(NotesCommand::Update(id, note_in, _))) => {
                     assert_eq!(id, 123);
                     assert_eq!(note_in.title, "some_title");
                     assert_eq!(note_in.data, "some_data");
@@ -575,7 +1916,7 @@ mod tests {
         {
             let tokens = vec!["notes", "update", "123", "some_title", "some_data", "456"];
             match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
-                Ok(Command::Notes(NotesCommand::Update(id, note_in))) => {
+                Ok(Command::Notes(NotesCommand::Update(id, note_in, _))) => {
                     assert_eq!(id, 123);
                     assert_eq!(note_in.title, "some_title");
                     assert_eq!(note_in.data, "some_data");
@@ -600,5 +1941,225 @@ mod tests {
                 _ => panic!("Unexpected response"),
             }
         }
+        {
+            let tokens = vec!["notes", "search"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec!["notes", "search", "some words", "--limit", "5"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Search(query, limit))) => {
+                    assert_eq!(query, "some words");
+                    assert_eq!(limit, Some(5));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "history", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::History(id))) => {
+                    assert_eq!(id, 123);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "diff", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec!["notes", "diff", "123", "1"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Diff(id, revision, to))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(revision, 1);
+                    assert_eq!(to, None);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "diff", "123", "1", "2"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Diff(id, revision, to))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(revision, 1);
+                    assert_eq!(to, Some(2));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "restore", "123", "1"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Restore(id, revision))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(revision, 1);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "tag", "add", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(_) => panic!("Unexpected response"),
+                _ => {}
+            }
+        }
+        {
+            let tokens = vec!["notes", "tag", "add", "123", "aaa"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::TagAdd(id, tag))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(tag, "aaa");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "tag", "remove", "123", "aaa"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::TagRemove(id, tag))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(tag, "aaa");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "share", "123", "user@email.com"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Share(id, note_share))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(note_share.email, "user@email.com");
+                    assert_eq!(note_share.permission, Some("read".to_string()));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "share", "123", "user@email.com", "--write"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Share(_, note_share))) => {
+                    assert_eq!(note_share.permission, Some("write".to_string()));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "unshare", "123", "2"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Unshare(id, user_id))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(user_id, 2);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "shares", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Shares(id))) => assert_eq!(id, 123),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "shared"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Shared)) => {}
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec![
+                "notes",
+                "link",
+                "123",
+                "--password",
+                "secret",
+                "--expires-in-hours",
+                "24",
+            ];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Link(id, public_link))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(public_link.password, Some("secret".to_string()));
+                    assert_eq!(public_link.expires_in_hours, Some(24));
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "links", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Links(id))) => assert_eq!(id, 123),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "unlink", "123", "4"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Unlink(id, link_id))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(link_id, 4);
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "attach", "123", "/tmp/file.pdf"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Attach(id, file))) => {
+                    assert_eq!(id, 123);
+                    assert_eq!(file, "/tmp/file.pdf");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["notes", "attachments", "123"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Notes(NotesCommand::Attachments(id))) => assert_eq!(id, 123),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec!["graph", "export", "/tmp/notes.json"];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Graph(GraphCommand::Export(export, file))) => {
+                    assert_eq!(export, GraphExport::default());
+                    assert_eq!(file, "/tmp/notes.json");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
+        {
+            let tokens = vec![
+                "graph",
+                "export",
+                "/tmp/notes.dot",
+                "--format",
+                "dot",
+                "--category",
+                "2",
+                "--descendants",
+            ];
+            match parse_command(tokens.iter().map(|s| s.to_string()).collect()) {
+                Ok(Command::Graph(GraphCommand::Export(export, file))) => {
+                    assert_eq!(export.format, Some("dot".to_string()));
+                    assert_eq!(export.category_id, Some(2));
+                    assert!(export.descendants);
+                    assert_eq!(file, "/tmp/notes.dot");
+                }
+                _ => panic!("Unexpected response"),
+            }
+        }
     }
 }
@@ -0,0 +1,250 @@
+//! Client-side encryption of notes.
+//!
+//! The key is derived from a passphrase with scrypt and a random salt, and the
+//! title and data are encrypted with AES-256-GCM before they are sent. The
+//! server only stores the ciphertext along with the key-check value, which
+//! keeps the salt and tells a wrong passphrase apart from a corrupted note.
+
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+use crypto::aes::KeySize;
+use crypto::aes_gcm::AesGcm;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::scrypt::{scrypt, ScryptParams};
+use crypto::sha2::Sha256;
+use crypto::util::fixed_time_eq;
+use rnotes_core::models::api::note::{NoteIn, NoteOut};
+use rustc_serialize::base64::{FromBase64, ToBase64, STANDARD};
+use std::fmt;
+
+const KEY_CHECK_VERSION: &str = "v1";
+const KEY_CHECK_INFO: &[u8] = b"rnotes key check";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+// scrypt cost: N = 2^14, r = 8, p = 1.
+const SCRYPT_LOG_N: u8 = 14;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum CryptoError {
+    WrongPassphrase,
+    InvalidKeyCheck,
+    /// The field cannot be decrypted with the key.
+    Corrupted(String),
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CryptoError::WrongPassphrase => f.write_str("Passphrase is not correct"),
+            CryptoError::InvalidKeyCheck => f.write_str("Key-check value is not valid"),
+            CryptoError::Corrupted(field) => write!(f, "The {} cannot be decrypted", field),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+/// Key of an encrypted note.
+pub struct NoteKey {
+    key: Vec<u8>,
+    key_check: String,
+}
+
+impl NoteKey {
+    /// New key with a random salt, used to encrypt a note.
+    pub fn new(passphrase: &str) -> Self {
+        let salt: [u8; SALT_LENGTH] = rand::random();
+        NoteKey::derive(passphrase, &salt)
+    }
+
+    /// Key of an encrypted note, checked against its key-check value.
+    pub fn open(passphrase: &str, key_check: &str) -> Result<Self, CryptoError> {
+        let parts: Vec<&str> = key_check.split('$').collect();
+        let (salt, check) = match parts.as_slice() {
+            [version, salt, check] if *version == KEY_CHECK_VERSION => (
+                salt.from_base64()
+                    .map_err(|_| CryptoError::InvalidKeyCheck)?,
+                check
+                    .from_base64()
+                    .map_err(|_| CryptoError::InvalidKeyCheck)?,
+            ),
+            _ => return Err(CryptoError::InvalidKeyCheck),
+        };
+
+        let note_key = NoteKey::derive(passphrase, &salt);
+        if fixed_time_eq(&note_key.check(), &check) {
+            Ok(note_key)
+        } else {
+            Err(CryptoError::WrongPassphrase)
+        }
+    }
+
+    fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = vec![0u8; KEY_LENGTH];
+        scrypt(
+            passphrase.as_bytes(),
+            salt,
+            &ScryptParams::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P),
+            &mut key,
+        );
+
+        let mut note_key = NoteKey {
+            key: key,
+            key_check: String::new(),
+        };
+        note_key.key_check = format!(
+            "{}${}${}",
+            KEY_CHECK_VERSION,
+            salt.to_base64(STANDARD),
+            note_key.check().to_base64(STANDARD)
+        );
+        note_key
+    }
+
+    // MAC of a constant, so the passphrase can be checked without decrypting.
+    fn check(&self) -> Vec<u8> {
+        let mut mac = Hmac::new(Sha256::new(), &self.key);
+        mac.input(KEY_CHECK_INFO);
+        mac.result().code().to_vec()
+    }
+
+    /// Key-check value stored by the server along with the note.
+    pub fn key_check(&self) -> &str {
+        &self.key_check
+    }
+
+    /// Nonce, ciphertext and tag of the field in base64. The name of the field
+    /// is authenticated too, so the title and data cannot be swapped.
+    pub fn encrypt(&self, field: &str, plaintext: &str) -> String {
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let mut cipher = AesGcm::new(KeySize::KeySize256, &self.key, &nonce, field.as_bytes());
+        let mut ciphertext = vec![0u8; plaintext.len()];
+        let mut tag = [0u8; TAG_LENGTH];
+        cipher.encrypt(plaintext.as_bytes(), &mut ciphertext, &mut tag);
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        sealed.extend_from_slice(&tag);
+        sealed.to_base64(STANDARD)
+    }
+
+    pub fn decrypt(&self, field: &str, sealed: &str) -> Result<String, CryptoError> {
+        let corrupted = || CryptoError::Corrupted(field.to_string());
+
+        let sealed = sealed.from_base64().map_err(|_| corrupted())?;
+        if sealed.len() < NONCE_LENGTH + TAG_LENGTH {
+            return Err(corrupted());
+        }
+        let (nonce, rest) = sealed.split_at(NONCE_LENGTH);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+
+        let mut cipher = AesGcm::new(KeySize::KeySize256, &self.key, nonce, field.as_bytes());
+        let mut plaintext = vec![0u8; ciphertext.len()];
+        if !cipher.decrypt(ciphertext, &mut plaintext, tag) {
+            return Err(corrupted());
+        }
+
+        String::from_utf8(plaintext).map_err(|_| corrupted())
+    }
+}
+
+/// Encrypts the title and data of the note and sets its key-check value.
+pub fn encrypt_note(passphrase: &str, note_in: &mut NoteIn) {
+    let note_key = NoteKey::new(passphrase);
+    note_in.title = note_key.encrypt("title", &note_in.title);
+    note_in.data = note_key.encrypt("data", &note_in.data);
+    note_in.key_check = Some(note_key.key_check().to_string());
+}
+
+/// Decrypts the title and data of an encrypted note, plain notes are left as
+/// they are.
+pub fn decrypt_note(passphrase: &str, note_out: &mut NoteOut) -> Result<(), CryptoError> {
+    if let Some(key_check) = note_out.key_check.as_ref() {
+        let note_key = NoteKey::open(passphrase, key_check)?;
+        note_out.title = note_key.decrypt("title", &note_out.title)?;
+        note_out.data = note_key.decrypt("data", &note_out.data)?;
+    }
+
+    Ok(())
+}
+
+mod tests {
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        use super::*;
+
+        let note_key = NoteKey::new("some_passphrase");
+        let sealed = note_key.encrypt("data", "some_secret");
+        assert_ne!(sealed, "some_secret");
+        assert_ne!(sealed, note_key.encrypt("data", "some_secret"));
+
+        let opened = NoteKey::open("some_passphrase", note_key.key_check()).unwrap();
+        assert_eq!(opened.decrypt("data", &sealed).unwrap(), "some_secret");
+        assert_eq!(
+            opened.decrypt("title", &sealed).err(),
+            Some(CryptoError::Corrupted("title".to_string()))
+        );
+
+        let mut tampered = sealed.from_base64().unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(opened
+            .decrypt("data", &tampered.to_base64(STANDARD))
+            .is_err());
+    }
+
+    #[test]
+    fn test_open_ko() {
+        use super::*;
+
+        let note_key = NoteKey::new("some_passphrase");
+
+        assert_eq!(
+            NoteKey::open("other_passphrase", note_key.key_check()).err(),
+            Some(CryptoError::WrongPassphrase)
+        );
+        assert_eq!(
+            NoteKey::open("some_passphrase", "v0$AAAA$AAAA").err(),
+            Some(CryptoError::InvalidKeyCheck)
+        );
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_note() {
+        use super::*;
+
+        let mut note_in = NoteIn {
+            category_id: None,
+            title: "some_title".to_string(),
+            data: "some_data".to_string(),
+            tags: None,
+            format: None,
+            key_check: None,
+        };
+        encrypt_note("some_passphrase", &mut note_in);
+        assert_ne!(note_in.title, "some_title");
+        assert!(note_in.key_check.is_some());
+
+        let mut note_out = NoteOut {
+            id: 1,
+            category_id: None,
+            title: note_in.title,
+            data: note_in.data,
+            format: "plain".to_string(),
+            tags: vec![],
+            create_time: String::new(),
+            update_time: String::new(),
+            deleted_time: None,
+            key_check: note_in.key_check,
+        };
+        assert!(decrypt_note("other_passphrase", &mut note_out).is_err());
+        decrypt_note("some_passphrase", &mut note_out).unwrap();
+        assert_eq!(note_out.title, "some_title");
+        assert_eq!(note_out.data, "some_data");
+    }
+}
@@ -110,6 +110,78 @@ impl HttpClient {
         Err(HttpClientError::InternalError("Unexpected".to_string()))
     }
 
+    /// Uploads the file as the `file` field of a `multipart/form-data` body.
+    pub async fn post_file<S>(
+        &self,
+        url: String,
+        file_name: &str,
+        data: Vec<u8>,
+        jwt_token: Option<String>,
+    ) -> Result<S, HttpClientError>
+    where
+        S: DeserializeOwned,
+    {
+        let uri = url
+            .parse::<hyper::Uri>()
+            .map_err(|_| HttpClientError::InvalidArguments("url".to_string()))?;
+
+        let boundary = format!("rnotes-{:016x}", rand::random::<u64>());
+        let mut body = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            boundary = boundary,
+            file_name = file_name.replace('"', "%22")
+        )
+        .into_bytes();
+        body.extend_from_slice(&data);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let mut builder = hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .uri(uri)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            );
+        if let Some(jwt_token) = jwt_token {
+            builder = builder.header(AUTHORIZATION, format!("Bearer {}", jwt_token));
+        }
+
+        let req = builder
+            .body(hyper::Body::from(body))
+            .map_err(|err| HttpClientError::InternalError(err.to_string()))?;
+
+        let mut res = self
+            .client
+            .request(req)
+            .await
+            .map_err(|err| HttpClientError::InternalError(err.to_string()))?;
+
+        while let Some(Ok(body)) = res.body_mut().data().await {
+            let str_body = &std::str::from_utf8(&body)
+                .map_err(|err| HttpClientError::InternalError(err.to_string()))?
+                .to_string();
+
+            if res.status().is_client_error() || res.status().is_server_error() {
+                return Err(HttpClientError::HTTPError(res.status(), str_body.clone()));
+            }
+            let res_obj: S = serde_json::from_str(str_body)
+                .map_err(|_| HttpClientError::InvalidResponseType(str_body.clone()))?;
+
+            return Ok(res_obj);
+        }
+
+        if res.status().is_success() {
+            let res_obj: S = serde_json::from_str("{}")
+                .map_err(|_| HttpClientError::InvalidResponseType("{}".to_string()))?;
+
+            return Ok(res_obj);
+        }
+
+        Err(HttpClientError::HTTPError(res.status(), "".to_string()))
+    }
+
     pub async fn put<T, S>(
         &self,
         url: String,
@@ -227,6 +299,48 @@ impl HttpClient {
         Err(HttpClientError::InternalError("Unexpected".to_string()))
     }
 
+    /// Whole body of the response as text, for the exports that are not JSON.
+    pub async fn get_text(
+        &self,
+        url: String,
+        jwt_token: Option<String>,
+    ) -> Result<String, HttpClientError> {
+        let uri = url
+            .parse::<hyper::Uri>()
+            .map_err(|_| HttpClientError::InvalidArguments("url".to_string()))?;
+
+        let mut builder = hyper::Request::builder()
+            .method(hyper::Method::GET)
+            .uri(uri);
+        if let Some(jwt_token) = jwt_token {
+            builder = builder.header(AUTHORIZATION, format!("Bearer {}", jwt_token));
+        }
+
+        let req = builder
+            .body(hyper::Body::from(""))
+            .map_err(|err| HttpClientError::InternalError(err.to_string()))?;
+
+        let res = self
+            .client
+            .request(req)
+            .await
+            .map_err(|err| HttpClientError::InternalError(err.to_string()))?;
+        let status = res.status();
+
+        let body = hyper::body::to_bytes(res.into_body())
+            .await
+            .map_err(|err| HttpClientError::InternalError(err.to_string()))?;
+        let str_body = std::str::from_utf8(&body)
+            .map_err(|err| HttpClientError::InternalError(err.to_string()))?
+            .to_string();
+
+        if !status.is_success() {
+            return Err(HttpClientError::HTTPError(status, str_body));
+        }
+
+        Ok(str_body)
+    }
+
     pub async fn delete<S>(
         &self,
         url: String,
@@ -1,17 +1,21 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 extern crate clap;
+extern crate crypto;
 extern crate dirs;
 extern crate dotenv;
 extern crate hyper;
+extern crate percent_encoding;
+extern crate rand;
 extern crate rnotes_core;
+extern crate rustc_serialize;
 extern crate rustyline;
 extern crate rustyline_derive;
 extern crate serde_derive;
-extern crate sha2;
 extern crate structopt;
 extern crate tokio;
 
 pub mod cmd;
+pub mod encryption;
 pub mod http_client;
 pub mod run;
 pub mod ui;
@@ -1,16 +1,36 @@
 use crate::cmd::*;
+use crate::encryption::{decrypt_note, encrypt_note};
 use crate::http_client::HttpClient;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rnotes_core::models::api::api_key::{ApiKeyIn, ApiKeyOut};
+use rnotes_core::models::api::attachment::AttachmentOut;
 use rnotes_core::models::api::auth::*;
-use rnotes_core::models::api::category::CategoryOut;
+use rnotes_core::models::api::category::{CategoryIn, CategoryOut, CategoryTreeOut};
 use rnotes_core::models::api::note::*;
-use rnotes_core::models::api::Empty;
-use rnotes_core::utils::HexSlice;
-use sha2::{Digest, Sha256};
+use rnotes_core::models::api::note_link::BacklinkOut;
+use rnotes_core::models::api::note_share::{NoteShareIn, NoteShareOut, SharedNoteOut};
+use rnotes_core::models::api::public_link::{PublicLinkIn, PublicLinkOut};
+use rnotes_core::models::api::revision::*;
+use rnotes_core::models::api::user::{UserAdminIn, UserOut};
+use rnotes_core::models::api::{Empty, Page};
+use std::path::Path;
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, NON_ALPHANUMERIC).to_string()
+}
 
 pub struct Runner {
     server_url: String,
     http_client: HttpClient,
     jwt_token: Option<String>,
+    refresh_token: Option<String>,
+    // Pending login of a user with 2FA.
+    challenge_token: Option<String>,
+    // Last notes listing and the cursor of its next page.
+    notes_listing: Option<NotesListing>,
+    notes_cursor: Option<String>,
+    // Passphrase of the encrypted notes, only kept in memory.
+    passphrase: Option<String>,
 }
 
 impl Runner {
@@ -19,33 +39,411 @@ impl Runner {
             server_url: server_url,
             http_client: http_client,
             jwt_token: None,
+            refresh_token: None,
+            challenge_token: None,
+            notes_listing: None,
+            notes_cursor: None,
+            passphrase: None,
+        }
+    }
+
+    /// Whether the last login waits for the 2FA code.
+    pub fn awaits_code(&self) -> bool {
+        self.challenge_token.is_some()
+    }
+
+    fn notes_url(&self, listing: &NotesListing, cursor: Option<&str>) -> String {
+        let mut query: Vec<String> = listing
+            .tags
+            .iter()
+            .map(|tag| format!("tag={}", encode(tag)))
+            .collect();
+        if let Some(category_id) = listing.category_id {
+            query.push(format!("category_id={}", category_id));
+        }
+        if listing.descendants {
+            query.push("descendants=true".to_string());
+        }
+        if let Some(updated_since) = &listing.updated_since {
+            query.push(format!("updated_since={}", encode(updated_since)));
+        }
+        if let Some(sort) = &listing.sort {
+            query.push(format!("sort={}", encode(sort)));
+        }
+        if listing.descending {
+            query.push("order=desc".to_string());
+        }
+        if let Some(limit) = listing.limit {
+            query.push(format!("limit={}", limit));
+        }
+        if let Some(cursor) = cursor {
+            query.push(format!("cursor={}", encode(cursor)));
+        }
+
+        format!(
+            "{server}/notes/?{query}",
+            server = self.server_url,
+            query = query.join("&")
+        )
+    }
+
+    fn graph_url(&self, export: &GraphExport) -> String {
+        let mut query: Vec<String> = vec![];
+        if let Some(format) = &export.format {
+            query.push(format!("format={}", encode(format)));
+        }
+        if let Some(category_id) = export.category_id {
+            query.push(format!("category_id={}", category_id));
+        }
+        if export.descendants {
+            query.push("descendants=true".to_string());
+        }
+
+        format!(
+            "{server}/graph/?{query}",
+            server = self.server_url,
+            query = query.join("&")
+        )
+    }
+
+    async fn notes_page(&mut self, listing: NotesListing, cursor: Option<String>) -> String {
+        let url = self.notes_url(&listing, cursor.as_ref().map(|c| c.as_str()));
+
+        match self
+            .http_client
+            .get::<Page<NoteOut>>(url, self.jwt_token.clone())
+            .await
+        {
+            Ok(response) => {
+                self.notes_cursor = response.next_cursor;
+                if self.notes_cursor.is_some() {
+                    format!("{:?}\nRun 'notes next' to get more notes.", response.items)
+                } else {
+                    format!("{:?}", response.items)
+                }
+            }
+            Err(err) => {
+                if cursor.is_some() {
+                    format!("Failed 'notes next'. {}", err)
+                } else {
+                    format!("Failed 'notes all'. {}", err)
+                }
+            }
         }
     }
 
     pub async fn run(&mut self, cmd: Command) -> String {
         match cmd {
             Command::Help(message) => format!("{}", message),
-            Command::Auth(AuthCommand::Login(mut login_in)) => {
+            Command::Auth(AuthCommand::Login(login_in)) => {
                 let url = format!("{server}/auth/login", server = self.server_url);
 
-                let mut hasher = Sha256::new();
-                hasher.input(login_in.password.into_bytes());
-                let hashed_password = HexSlice::new(&hasher.result()).to_string();
-
-                login_in.password = hashed_password;
-
                 match self
                     .http_client
                     .post::<LoginIn, LoginOut>(url, &login_in, None)
                     .await
                 {
+                    Ok(LoginOut {
+                        challenge_token: Some(challenge_token),
+                        ..
+                    }) => {
+                        self.challenge_token = Some(challenge_token);
+                        format!("2FA is enabled, the code of the authenticator app or a recovery code is required.")
+                    }
                     Ok(login_out) => {
                         self.jwt_token = Some(login_out.jwt_token);
+                        self.refresh_token = Some(login_out.refresh_token);
                         format!("Login successful to rnotes server.")
                     }
                     Err(err) => format!("Failed 'auth login'. {err}", err = err),
                 }
             }
+            Command::Auth(AuthCommand::Code(code)) => {
+                let url = format!("{server}/auth/2fa/login", server = self.server_url);
+                let two_factor_login_in = match self.challenge_token.take() {
+                    Some(challenge_token) => TwoFactorLoginIn {
+                        challenge_token: challenge_token,
+                        code: code,
+                    },
+                    None => return format!("There is no pending login. Run 'auth login' first."),
+                };
+
+                match self
+                    .http_client
+                    .post::<TwoFactorLoginIn, LoginOut>(url, &two_factor_login_in, None)
+                    .await
+                {
+                    Ok(login_out) => {
+                        self.jwt_token = Some(login_out.jwt_token);
+                        self.refresh_token = Some(login_out.refresh_token);
+                        format!("Login successful to rnotes server.")
+                    }
+                    Err(err) => {
+                        // Wrong codes can be retried until the challenge expires.
+                        self.challenge_token = Some(two_factor_login_in.challenge_token);
+                        format!("Failed 'auth code'. {err}", err = err)
+                    }
+                }
+            }
+            Command::Auth(AuthCommand::Refresh) => {
+                let url = format!("{server}/auth/refresh", server = self.server_url);
+                let refresh_in = match &self.refresh_token {
+                    Some(refresh_token) => RefreshIn {
+                        refresh_token: refresh_token.clone(),
+                    },
+                    None => return format!("There is no session. Run 'auth login' first."),
+                };
+
+                match self
+                    .http_client
+                    .post::<RefreshIn, LoginOut>(url, &refresh_in, None)
+                    .await
+                {
+                    Ok(login_out) => {
+                        self.jwt_token = Some(login_out.jwt_token);
+                        self.refresh_token = Some(login_out.refresh_token);
+                        format!("Session renewed.")
+                    }
+                    Err(err) => format!("Failed 'auth refresh'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::Logout) => {
+                let url = format!("{server}/auth/logout", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<Empty, Empty>(url, &Empty {}, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(_) => {
+                        self.jwt_token = None;
+                        self.refresh_token = None;
+                        format!("Logout successful.")
+                    }
+                    Err(err) => format!("Failed 'auth logout'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::Register(register_in)) => {
+                let url = format!("{server}/auth/register", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<RegisterIn, UserOut>(url, &register_in, None)
+                    .await
+                {
+                    Ok(user_out) => format!(
+                        "User {name} registered, run 'auth login {email} ...' to login.",
+                        name = user_out.name,
+                        email = user_out.email
+                    ),
+                    Err(err) => format!("Failed 'auth register ...'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::KeysAll) => {
+                let url = format!("{server}/auth/keys/", server = self.server_url);
+
+                match self
+                    .http_client
+                    .get::<Vec<ApiKeyOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'auth keys all'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::KeysCreate(api_key_in)) => {
+                let url = format!("{server}/auth/keys/", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<ApiKeyIn, ApiKeyOut>(url, &api_key_in, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!(
+                        "API key {name} created, it will not be shown again: {key}",
+                        name = response.name,
+                        key = response.key.unwrap_or_default()
+                    ),
+                    Err(err) => format!("Failed 'auth keys create ...'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::KeysRevoke(id)) => {
+                let url = format!("{server}/auth/keys/{id}", server = self.server_url, id = id);
+
+                match self
+                    .http_client
+                    .delete::<Empty>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => {
+                        format!("Failed 'auth keys revoke {id}'. {err}", id = id, err = err)
+                    }
+                }
+            }
+            Command::Auth(AuthCommand::TwoFactorSetup) => {
+                let url = format!("{server}/auth/2fa/setup", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<Empty, TotpSetupOut>(url, &Empty {}, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!(
+                        "Add the secret {secret} to the authenticator app, or this URI:\n{uri}\n\
+                         Recovery codes, they will not be shown again:\n{codes}\n\
+                         Run 'auth 2fa verify <CODE>' to enable the 2FA.",
+                        secret = response.secret,
+                        uri = response.uri,
+                        codes = response.recovery_codes.join("\n")
+                    ),
+                    Err(err) => format!("Failed 'auth 2fa setup'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::TwoFactorVerify(code)) => {
+                let url = format!("{server}/auth/2fa/verify", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<TotpCodeIn, Empty>(
+                        url,
+                        &TotpCodeIn { code: code },
+                        self.jwt_token.clone(),
+                    )
+                    .await
+                {
+                    Ok(_) => format!("2FA enabled."),
+                    Err(err) => format!("Failed 'auth 2fa verify ...'. {err}", err = err),
+                }
+            }
+            Command::Auth(AuthCommand::TwoFactorDisable(code)) => {
+                let url = format!("{server}/auth/2fa/disable", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<TotpCodeIn, Empty>(
+                        url,
+                        &TotpCodeIn { code: code },
+                        self.jwt_token.clone(),
+                    )
+                    .await
+                {
+                    Ok(_) => format!("2FA disabled."),
+                    Err(err) => format!("Failed 'auth 2fa disable ...'. {err}", err = err),
+                }
+            }
+            Command::Admin(AdminCommand::UsersAll) => {
+                let url = format!("{server}/admin/users", server = self.server_url);
+
+                match self
+                    .http_client
+                    .get::<Vec<UserOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'admin users all'. {err}", err = err),
+                }
+            }
+            Command::Admin(AdminCommand::UsersUpdate(id, user_admin_in)) => {
+                let url = format!(
+                    "{server}/admin/users/{id}",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .put::<UserAdminIn, UserOut>(url, &user_admin_in, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'admin users ... {id}'. {err}", id = id, err = err),
+                }
+            }
+            Command::Admin(AdminCommand::UsersDelete(id)) => {
+                let url = format!(
+                    "{server}/admin/users/{id}",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .delete::<Empty>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'admin users delete {id}'. {err}",
+                        id = id,
+                        err = err
+                    ),
+                }
+            }
+            Command::Admin(AdminCommand::CategoriesAll) => {
+                let url = format!("{server}/admin/categories", server = self.server_url);
+
+                match self
+                    .http_client
+                    .get::<Vec<CategoryOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'admin categories all'. {err}", err = err),
+                }
+            }
+            Command::Admin(AdminCommand::CategoriesCreate(category)) => {
+                let url = format!("{server}/admin/categories", server = self.server_url);
+
+                match self
+                    .http_client
+                    .post::<CategoryIn, CategoryOut>(url, &category, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'admin categories create ...'. {err}", err = err),
+                }
+            }
+            Command::Admin(AdminCommand::CategoriesUpdate(id, category)) => {
+                let url = format!(
+                    "{server}/admin/categories/{id}",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .put::<CategoryIn, CategoryOut>(url, &category, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'admin categories update {id} ...'. {err}",
+                        id = id,
+                        err = err
+                    ),
+                }
+            }
+            Command::Admin(AdminCommand::CategoriesDelete(id)) => {
+                let url = format!(
+                    "{server}/admin/categories/{id}",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .delete::<Empty>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'admin categories delete {id}'. {err}",
+                        id = id,
+                        err = err
+                    ),
+                }
+            }
             Command::Categories(CategoriesCommand::All) => {
                 let url = format!("{server}/categories/", server = self.server_url);
 
@@ -58,6 +456,18 @@ impl Runner {
                     Err(err) => format!("Failed 'categories all'. {err}", err = err),
                 }
             }
+            Command::Categories(CategoriesCommand::Tree) => {
+                let url = format!("{server}/categories/tree", server = self.server_url);
+
+                match self
+                    .http_client
+                    .get::<Vec<CategoryTreeOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => CategoryTreeOut::render(&response),
+                    Err(err) => format!("Failed 'categories tree'. {err}", err = err),
+                }
+            }
             Command::Categories(CategoriesCommand::Get(id)) => {
                 let url = format!(
                     "{server}/categories/{id}",
@@ -74,33 +484,148 @@ impl Runner {
                     Err(err) => format!("Failed 'categories get {id}'. {err}", id = id, err = err),
                 }
             }
-            Command::Notes(NotesCommand::All) => {
-                let url = format!("{server}/notes/", server = self.server_url);
+            Command::Categories(CategoriesCommand::Create(category)) => {
+                let url = format!("{server}/categories", server = self.server_url);
 
                 match self
                     .http_client
-                    .get::<Vec<NoteOut>>(url, self.jwt_token.clone())
+                    .post::<CategoryIn, CategoryOut>(url, &category, self.jwt_token.clone())
                     .await
                 {
                     Ok(response) => format!("{:?}", response),
-                    Err(err) => format!("Failed 'notes all'. {}", err),
+                    Err(err) => format!("Failed 'categories create ...'. {err}", err = err),
+                }
+            }
+            Command::Categories(CategoriesCommand::Update(id, category)) => {
+                let url = format!(
+                    "{server}/categories/{id}",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .put::<CategoryIn, CategoryOut>(url, &category, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'categories update {id} ...'. {err}",
+                        id = id,
+                        err = err
+                    ),
+                }
+            }
+            Command::Categories(CategoriesCommand::Delete(id)) => {
+                let url = format!(
+                    "{server}/categories/{id}",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .delete::<Empty>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => {
+                        format!("Failed 'categories delete {id}'. {err}", id = id, err = err)
+                    }
+                }
+            }
+            Command::Notes(NotesCommand::All(listing)) => {
+                self.notes_listing = Some(listing.clone());
+                self.notes_cursor = None;
+
+                self.notes_page(listing, None).await
+            }
+            Command::Notes(NotesCommand::Next) => {
+                match (self.notes_listing.clone(), self.notes_cursor.clone()) {
+                    (Some(listing), Some(cursor)) => self.notes_page(listing, Some(cursor)).await,
+                    _ => format!("There are no more notes. Run 'notes all' first."),
                 }
             }
             Command::Notes(NotesCommand::Get(id)) => {
                 let url = format!("{server}/notes/{id}", server = self.server_url, id = id);
 
-                match self
+                let note = match self
                     .http_client
                     .get::<NoteOut>(url, self.jwt_token.clone())
                     .await
                 {
-                    Ok(response) => format!("{:?}", response),
-                    Err(err) => format!("Failed 'notes get {id}'. {err}", id = id, err = err),
+                    Ok(mut response) => match (response.key_check.is_some(), &self.passphrase) {
+                        (false, _) => format!("{:?}", response),
+                        (true, Some(passphrase)) => {
+                            match decrypt_note(passphrase, &mut response) {
+                                Ok(_) => format!("{:?}", response),
+                                Err(err) => {
+                                    return format!(
+                                        "Failed 'notes get {id}'. {err}",
+                                        id = id,
+                                        err = err
+                                    )
+                                }
+                            }
+                        }
+                        (true, None) => format!(
+                            "{:?}\nThe note is encrypted, run 'notes unlock <PASSPHRASE>' to read it.",
+                            response
+                        ),
+                    },
+                    Err(err) => {
+                        return format!("Failed 'notes get {id}'. {err}", id = id, err = err)
+                    }
+                };
+
+                let url = format!(
+                    "{server}/notes/{id}/backlinks",
+                    server = self.server_url,
+                    id = id
+                );
+
+                // The note is still shown if its backlinks cannot be read.
+                match self
+                    .http_client
+                    .get::<Vec<BacklinkOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(backlinks) if !backlinks.is_empty() => {
+                        format!("{}\nBacklinks: {:?}", note, backlinks)
+                    }
+                    _ => note,
+                }
+            }
+            Command::Notes(NotesCommand::Render(id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/render",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .get::<NoteRenderOut>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => response.html,
+                    Err(err) => format!("Failed 'notes render {id}'. {err}", id = id, err = err),
                 }
             }
-            Command::Notes(NotesCommand::Create(note)) => {
+            Command::Notes(NotesCommand::Create(mut note, encrypt)) => {
                 let url = format!("{server}/notes", server = self.server_url);
 
+                if encrypt {
+                    match &self.passphrase {
+                        Some(passphrase) => encrypt_note(passphrase, &mut note),
+                        None => {
+                            return format!(
+                                "Failed 'notes create ...'. Run 'notes unlock <PASSPHRASE>' first."
+                            )
+                        }
+                    }
+                }
+
                 match self
                     .http_client
                     .post::<NoteIn, NoteOut>(url, &note, self.jwt_token.clone())
@@ -110,9 +635,21 @@ impl Runner {
                     Err(err) => format!("Failed 'notes create ...'. {err}", err = err),
                 }
             }
-            Command::Notes(NotesCommand::Update(id, note)) => {
+            Command::Notes(NotesCommand::Update(id, mut note, encrypt)) => {
                 let url = format!("{server}/notes/{id}", server = self.server_url, id = id);
 
+                if encrypt {
+                    match &self.passphrase {
+                        Some(passphrase) => encrypt_note(passphrase, &mut note),
+                        None => {
+                            return format!(
+                                "Failed 'notes update {id} ...'. Run 'notes unlock <PASSPHRASE>' first.",
+                                id = id
+                            )
+                        }
+                    }
+                }
+
                 match self
                     .http_client
                     .put::<NoteIn, NoteOut>(url, &note, self.jwt_token.clone())
@@ -124,6 +661,14 @@ impl Runner {
                     }
                 }
             }
+            Command::Notes(NotesCommand::Unlock(passphrase)) => {
+                self.passphrase = Some(passphrase);
+                format!("Encrypted notes are decrypted with the passphrase until 'notes lock'.")
+            }
+            Command::Notes(NotesCommand::Lock) => {
+                self.passphrase = None;
+                format!("Passphrase forgotten.")
+            }
             Command::Notes(NotesCommand::Delete(id)) => {
                 let url = format!("{server}/notes/{id}", server = self.server_url, id = id);
 
@@ -136,6 +681,348 @@ impl Runner {
                     Err(err) => format!("Failed 'notes delete {id}'. {err}", id = id, err = err),
                 }
             }
+            Command::Notes(NotesCommand::Search(query, limit)) => {
+                let url = format!(
+                    "{server}/notes/search?q={query}{limit}",
+                    server = self.server_url,
+                    query = encode(&query),
+                    limit = limit.map_or(String::new(), |limit| format!("&limit={}", limit))
+                );
+
+                match self
+                    .http_client
+                    .get::<Vec<NoteSearchOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes search {query}'. {err}",
+                        query = query,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::History(id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/revisions",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .get::<Vec<RevisionOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'notes history {id}'. {err}", id = id, err = err),
+                }
+            }
+            Command::Notes(NotesCommand::Diff(id, revision, to)) => {
+                let url = format!(
+                    "{server}/notes/{id}/revisions/{revision}/diff{to}",
+                    server = self.server_url,
+                    id = id,
+                    revision = revision,
+                    to = to.map_or(String::new(), |to| format!("?to={}", to))
+                );
+
+                match self
+                    .http_client
+                    .get::<RevisionDiffOut>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!(
+                        "--- revision {from}\n+++ {to}\n{title}\n\n{data}",
+                        from = response.from,
+                        to = response
+                            .to
+                            .map_or("current".to_string(), |to| format!("revision {}", to)),
+                        title = response.title,
+                        data = response.data
+                    ),
+                    Err(err) => format!(
+                        "Failed 'notes diff {id} {revision}'. {err}",
+                        id = id,
+                        revision = revision,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::Restore(id, revision)) => {
+                let url = format!(
+                    "{server}/notes/{id}/revisions/{revision}/restore",
+                    server = self.server_url,
+                    id = id,
+                    revision = revision
+                );
+
+                match self
+                    .http_client
+                    .post::<Empty, NoteOut>(url, &Empty {}, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes restore {id} {revision}'. {err}",
+                        id = id,
+                        revision = revision,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::TagAdd(id, tag)) => {
+                let url = format!(
+                    "{server}/notes/{id}/tags/{tag}",
+                    server = self.server_url,
+                    id = id,
+                    tag = encode(&tag)
+                );
+
+                match self
+                    .http_client
+                    .post::<Empty, NoteOut>(url, &Empty {}, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes tag add {id} {tag}'. {err}",
+                        id = id,
+                        tag = tag,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::TagRemove(id, tag)) => {
+                let url = format!(
+                    "{server}/notes/{id}/tags/{tag}",
+                    server = self.server_url,
+                    id = id,
+                    tag = encode(&tag)
+                );
+
+                match self
+                    .http_client
+                    .delete::<NoteOut>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes tag remove {id} {tag}'. {err}",
+                        id = id,
+                        tag = tag,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::Share(id, note_share)) => {
+                let url = format!(
+                    "{server}/notes/{id}/shares",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .post::<NoteShareIn, NoteShareOut>(url, &note_share, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes share {id} {email}'. {err}",
+                        id = id,
+                        email = note_share.email,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::Unshare(id, user_id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/shares/{user_id}",
+                    server = self.server_url,
+                    id = id,
+                    user_id = user_id
+                );
+
+                match self
+                    .http_client
+                    .delete::<Empty>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes unshare {id} {user_id}'. {err}",
+                        id = id,
+                        user_id = user_id,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::Shares(id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/shares",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .get::<Vec<NoteShareOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'notes shares {id}'. {err}", id = id, err = err),
+                }
+            }
+            Command::Notes(NotesCommand::Shared) => {
+                let url = format!("{server}/notes/shared", server = self.server_url);
+
+                match self
+                    .http_client
+                    .get::<Vec<SharedNoteOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'notes shared'. {err}", err = err),
+                }
+            }
+            Command::Notes(NotesCommand::Link(id, public_link)) => {
+                let url = format!(
+                    "{server}/notes/{id}/public-links",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .post::<PublicLinkIn, PublicLinkOut>(url, &public_link, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!(
+                        "Link {link_id} created, it will not be shown again: {server}/public/{token}",
+                        link_id = response.id,
+                        server = self.server_url,
+                        token = response.token.unwrap_or_default()
+                    ),
+                    Err(err) => format!("Failed 'notes link {id}'. {err}", id = id, err = err),
+                }
+            }
+            Command::Notes(NotesCommand::Links(id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/public-links",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .get::<Vec<PublicLinkOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!("Failed 'notes links {id}'. {err}", id = id, err = err),
+                }
+            }
+            Command::Notes(NotesCommand::Unlink(id, link_id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/public-links/{link_id}",
+                    server = self.server_url,
+                    id = id,
+                    link_id = link_id
+                );
+
+                match self
+                    .http_client
+                    .delete::<Empty>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes unlink {id} {link_id}'. {err}",
+                        id = id,
+                        link_id = link_id,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::Attach(id, file)) => {
+                let url = format!(
+                    "{server}/notes/{id}/attachments",
+                    server = self.server_url,
+                    id = id
+                );
+                let file_name = Path::new(&file)
+                    .file_name()
+                    .map(|file_name| file_name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                let data = match tokio::fs::read(&file).await {
+                    Ok(data) => data,
+                    Err(err) => {
+                        return format!(
+                            "Failed 'notes attach {id} {file}'. Cannot read the file: {err}",
+                            id = id,
+                            file = file,
+                            err = err
+                        )
+                    }
+                };
+
+                match self
+                    .http_client
+                    .post_file::<AttachmentOut>(url, &file_name, data, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => format!(
+                        "Failed 'notes attach {id} {file}'. {err}",
+                        id = id,
+                        file = file,
+                        err = err
+                    ),
+                }
+            }
+            Command::Notes(NotesCommand::Attachments(id)) => {
+                let url = format!(
+                    "{server}/notes/{id}/attachments",
+                    server = self.server_url,
+                    id = id
+                );
+
+                match self
+                    .http_client
+                    .get::<Vec<AttachmentOut>>(url, self.jwt_token.clone())
+                    .await
+                {
+                    Ok(response) => format!("{:?}", response),
+                    Err(err) => {
+                        format!("Failed 'notes attachments {id}'. {err}", id = id, err = err)
+                    }
+                }
+            }
+            Command::Graph(GraphCommand::Export(export, file)) => {
+                let url = self.graph_url(&export);
+
+                let graph = match self.http_client.get_text(url, self.jwt_token.clone()).await {
+                    Ok(graph) => graph,
+                    Err(err) => {
+                        return format!(
+                            "Failed 'graph export {file}'. {err}",
+                            file = file,
+                            err = err
+                        )
+                    }
+                };
+
+                match tokio::fs::write(&file, graph).await {
+                    Ok(()) => format!("Graph written to {file}", file = file),
+                    Err(err) => format!(
+                        "Failed 'graph export {file}'. Cannot write the file: {err}",
+                        file = file,
+                        err = err
+                    ),
+                }
+            }
             other => format!("Received {:?}", other),
         }
     }
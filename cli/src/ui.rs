@@ -99,8 +99,6 @@ pub async fn ui_loop(opt: CliOpt) -> Result<(), GenericError> {
     let (in_tx, in_rx) = unbounded_channel();
     let (out_tx, mut out_rx) = unbounded_channel();
 
-    let progress_chars = vec!["-", "\\", "|", "/"];
-
     tokio::spawn(runner_task(opt, in_rx, out_tx));
 
     loop {
@@ -111,24 +109,26 @@ pub async fn ui_loop(opt: CliOpt) -> Result<(), GenericError> {
                 match parse_line(&line) {
                     Ok(Command::Nothing) => (),
                     Ok(cmd) => {
+                        let login = match cmd {
+                            Command::Auth(AuthCommand::Login(_)) => true,
+                            _ => false,
+                        };
                         in_tx.send(cmd)?;
-                        let mut x = 0;
-                        loop {
-                            match out_rx.try_recv() {
-                                Ok(msg) => {
-                                    println!("{}", msg);
-                                    break;
+                        let mut awaits_code = wait_output(&mut out_rx)? && login;
+
+                        // Logins with 2FA ask for the code until it is right or
+                        // the prompt is left empty.
+                        while awaits_code {
+                            match rl.readline("2FA code: ") {
+                                Ok(code) if !code.trim().is_empty() => {
+                                    in_tx.send(Command::Auth(AuthCommand::Code(
+                                        code.trim().to_string(),
+                                    )))?;
+                                    awaits_code = wait_output(&mut out_rx)?;
                                 }
-                                Err(_) => {
-                                    print!("Executing command {}", progress_chars[x % 4]);
-                                    io::stdout().flush()?;
-
-                                    std::thread::sleep(std::time::Duration::from_millis(250));
-
-                                    print!("\r");
-                                    io::stdout().flush()?;
-
-                                    x = x + 1;
+                                _ => {
+                                    println!("Run 'auth code <CODE>' to finish the login.");
+                                    break;
                                 }
                             }
                         }
@@ -163,17 +163,45 @@ pub async fn ui_loop(opt: CliOpt) -> Result<(), GenericError> {
     Ok(())
 }
 
+// Prints the output of the command, returns whether the runner waits for a
+// 2FA code.
+fn wait_output(out_rx: &mut UnboundedReceiver<(String, bool)>) -> Result<bool, GenericError> {
+    let progress_chars = vec!["-", "\\", "|", "/"];
+
+    let mut x = 0;
+    loop {
+        match out_rx.try_recv() {
+            Ok((msg, awaits_code)) => {
+                println!("{}", msg);
+                return Ok(awaits_code);
+            }
+            Err(_) => {
+                print!("Executing command {}", progress_chars[x % 4]);
+                io::stdout().flush()?;
+
+                std::thread::sleep(std::time::Duration::from_millis(250));
+
+                print!("\r");
+                io::stdout().flush()?;
+
+                x = x + 1;
+            }
+        }
+    }
+}
+
 async fn runner_task(
     opt: CliOpt,
     mut rx: UnboundedReceiver<Command>,
-    tx: UnboundedSender<String>,
+    tx: UnboundedSender<(String, bool)>,
 ) -> Result<(), GenericError> {
     let client = HttpClient::new();
 
     let mut runner = Runner::new(opt.server, client);
 
     while let Some(cmd) = rx.recv().await {
-        tx.send(runner.run(cmd).await)?;
+        let msg = runner.run(cmd).await;
+        tx.send((msg, runner.awaits_code()))?;
     }
 
     Ok(())
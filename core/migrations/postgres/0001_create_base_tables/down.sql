@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS notes;
+DROP TABLE IF EXISTS categories;
+DROP TABLE IF EXISTS users;
+DROP SEQUENCE IF EXISTS notes_id_seq;
+DROP SEQUENCE IF EXISTS categories_id_seq;
+DROP SEQUENCE IF EXISTS users_id_seq;
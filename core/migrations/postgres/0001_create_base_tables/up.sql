@@ -0,0 +1,38 @@
+-- Baseline schema. It is written with IF NOT EXISTS so deployments created
+-- from db/db_init.sql adopt the migrations without losing any data.
+CREATE SEQUENCE IF NOT EXISTS users_id_seq;
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER NOT NULL DEFAULT nextval('users_id_seq'),
+    email VARCHAR(256) NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    password VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_users UNIQUE(name)
+  );
+ALTER SEQUENCE users_id_seq OWNED BY users.id;
+CREATE SEQUENCE IF NOT EXISTS categories_id_seq;
+CREATE TABLE IF NOT EXISTS categories (
+    id INTEGER NOT NULL DEFAULT nextval('categories_id_seq'),
+    name VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_categories UNIQUE(name)
+  );
+ALTER SEQUENCE categories_id_seq OWNED BY categories.id;
+CREATE SEQUENCE IF NOT EXISTS notes_id_seq;
+CREATE TABLE IF NOT EXISTS notes (
+    id INTEGER NOT NULL DEFAULT nextval('notes_id_seq'),
+    user_id INTEGER NOT NULL,
+    category_id INTEGER DEFAULT NULL,
+    title VARCHAR(256) DEFAULT NULL,
+    data TEXT,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    FOREIGN KEY (category_id) REFERENCES categories (id),
+    FOREIGN KEY (user_id) REFERENCES users (id)
+  );
+ALTER SEQUENCE notes_id_seq OWNED BY notes.id;
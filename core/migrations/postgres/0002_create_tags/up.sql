@@ -0,0 +1,19 @@
+CREATE SEQUENCE tags_id_seq;
+CREATE TABLE tags (
+    id INTEGER NOT NULL DEFAULT nextval('tags_id_seq'),
+    user_id INTEGER NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_tags UNIQUE(user_id, name),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE tags_id_seq OWNED BY tags.id;
+CREATE TABLE note_tags (
+    note_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (note_id, tag_id),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+  );
+CREATE INDEX idx_note_tags_tag_id ON note_tags (tag_id);
@@ -0,0 +1,7 @@
+-- Full-text search document of every note. Titles weigh more than bodies.
+ALTER TABLE notes
+  ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(data, '')), 'B')
+  ) STORED;
+CREATE INDEX idx_notes_search_vector ON notes USING GIN (search_vector);
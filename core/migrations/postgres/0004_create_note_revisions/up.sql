@@ -0,0 +1,14 @@
+CREATE SEQUENCE note_revisions_id_seq;
+CREATE TABLE note_revisions (
+    id INTEGER NOT NULL DEFAULT nextval('note_revisions_id_seq'),
+    note_id INTEGER NOT NULL,
+    revision INTEGER NOT NULL,
+    category_id INTEGER DEFAULT NULL,
+    title VARCHAR(256) NOT NULL,
+    data TEXT NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_note_revisions UNIQUE(note_id, revision),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE note_revisions_id_seq OWNED BY note_revisions.id;
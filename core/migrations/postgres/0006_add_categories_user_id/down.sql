@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS unq_categories_shared_name;
+ALTER TABLE categories DROP CONSTRAINT unq_categories;
+ALTER TABLE categories DROP CONSTRAINT fk_categories_user_id;
+ALTER TABLE categories DROP COLUMN user_id;
+ALTER TABLE categories ADD CONSTRAINT unq_categories UNIQUE(name);
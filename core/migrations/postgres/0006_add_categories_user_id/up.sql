@@ -0,0 +1,9 @@
+-- Categories belong to a user. The existing ones keep a NULL user_id and stay
+-- shared, read-only, by every user. NULL user ids never collide in
+-- unq_categories, so the names of the shared categories have their own index.
+ALTER TABLE categories ADD COLUMN user_id INTEGER DEFAULT NULL;
+ALTER TABLE categories
+  ADD CONSTRAINT fk_categories_user_id FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE;
+ALTER TABLE categories DROP CONSTRAINT unq_categories;
+ALTER TABLE categories ADD CONSTRAINT unq_categories UNIQUE(user_id, name);
+CREATE UNIQUE INDEX unq_categories_shared_name ON categories (name) WHERE user_id IS NULL;
@@ -0,0 +1,5 @@
+-- Categories nest into a tree. Deleting a category moves its children to the
+-- root.
+ALTER TABLE categories
+  ADD COLUMN parent_id INTEGER DEFAULT NULL REFERENCES categories (id) ON DELETE SET NULL;
+CREATE INDEX idx_categories_parent_id ON categories (parent_id);
@@ -0,0 +1 @@
+ALTER TABLE users DROP CONSTRAINT IF EXISTS unq_users_email;
@@ -0,0 +1,2 @@
+-- Users log in and register by email, so it has to be unique as well.
+ALTER TABLE users ADD CONSTRAINT unq_users_email UNIQUE (email);
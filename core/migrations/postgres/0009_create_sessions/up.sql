@@ -0,0 +1,18 @@
+-- Logins of the users. Access tokens carry the jti of their session and the
+-- refresh token is only stored as its SHA-256, both rotate on every refresh.
+CREATE SEQUENCE sessions_id_seq;
+CREATE TABLE sessions (
+    id INTEGER NOT NULL DEFAULT nextval('sessions_id_seq'),
+    user_id INTEGER NOT NULL,
+    jti VARCHAR(64) NOT NULL,
+    refresh_token_hash VARCHAR(64) NOT NULL,
+    expire_time TIMESTAMP NOT NULL,
+    revoked_time TIMESTAMP DEFAULT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_sessions_jti UNIQUE(jti),
+    CONSTRAINT unq_sessions_refresh_token_hash UNIQUE(refresh_token_hash),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE sessions_id_seq OWNED BY sessions.id;
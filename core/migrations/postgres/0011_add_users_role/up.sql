@@ -0,0 +1,4 @@
+-- Admins manage the users and the shared categories, disabled users cannot login.
+ALTER TABLE users ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'user';
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD CONSTRAINT chk_users_role CHECK (role IN ('admin', 'user'));
@@ -0,0 +1,45 @@
+-- TOTP second factor of the users. The secret is only required at login once
+-- it is enabled, recovery codes and login challenges are stored as SHA-256.
+-- The wrong codes of a user are counted across its challenges to lock the
+-- login until `locked_until`.
+CREATE SEQUENCE totp_secrets_id_seq;
+CREATE TABLE totp_secrets (
+    id INTEGER NOT NULL DEFAULT nextval('totp_secrets_id_seq'),
+    user_id INTEGER NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    last_step BIGINT NOT NULL DEFAULT 0,
+    failed_codes INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP DEFAULT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_totp_secrets_user_id UNIQUE(user_id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE totp_secrets_id_seq OWNED BY totp_secrets.id;
+CREATE SEQUENCE recovery_codes_id_seq;
+CREATE TABLE recovery_codes (
+    id INTEGER NOT NULL DEFAULT nextval('recovery_codes_id_seq'),
+    user_id INTEGER NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_time TIMESTAMP DEFAULT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE recovery_codes_id_seq OWNED BY recovery_codes.id;
+CREATE INDEX idx_recovery_codes_user_id ON recovery_codes (user_id);
+CREATE SEQUENCE login_challenges_id_seq;
+CREATE TABLE login_challenges (
+    id INTEGER NOT NULL DEFAULT nextval('login_challenges_id_seq'),
+    user_id INTEGER NOT NULL,
+    token_hash VARCHAR(64) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expire_time TIMESTAMP NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_login_challenges_token_hash UNIQUE(token_hash),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE login_challenges_id_seq OWNED BY login_challenges.id;
@@ -0,0 +1,15 @@
+-- Identities of the users in external OpenID Connect providers, keyed by the
+-- issuer and the subject of their ID tokens.
+CREATE SEQUENCE user_identities_id_seq;
+CREATE TABLE user_identities (
+    id INTEGER NOT NULL DEFAULT nextval('user_identities_id_seq'),
+    user_id INTEGER NOT NULL,
+    issuer VARCHAR(256) NOT NULL,
+    subject VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_user_identities_issuer_subject UNIQUE(issuer, subject),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE user_identities_id_seq OWNED BY user_identities.id;
+CREATE INDEX idx_user_identities_user_id ON user_identities (user_id);
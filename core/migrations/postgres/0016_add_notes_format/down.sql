@@ -0,0 +1,2 @@
+ALTER TABLE notes DROP CONSTRAINT IF EXISTS chk_notes_format;
+ALTER TABLE notes DROP COLUMN IF EXISTS format;
@@ -0,0 +1,3 @@
+-- Format of the data of the notes, used to render them as HTML.
+ALTER TABLE notes ADD COLUMN format VARCHAR(16) NOT NULL DEFAULT 'plain';
+ALTER TABLE notes ADD CONSTRAINT chk_notes_format CHECK (format IN ('plain', 'markdown'));
@@ -0,0 +1,5 @@
+-- Key-check value of the notes encrypted by the clients, NULL for plain notes.
+-- The server never sees the passphrase, it only stores the value along with
+-- the ciphertext of the title and data.
+ALTER TABLE notes ADD COLUMN key_check VARCHAR(256) DEFAULT NULL;
+ALTER TABLE note_revisions ADD COLUMN key_check VARCHAR(256) DEFAULT NULL;
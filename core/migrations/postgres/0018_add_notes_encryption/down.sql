@@ -0,0 +1,16 @@
+DROP INDEX IF EXISTS idx_note_revisions_key_id;
+ALTER TABLE note_revisions DROP COLUMN IF EXISTS key_id;
+ALTER TABLE note_revisions DROP COLUMN IF EXISTS data_key;
+ALTER TABLE note_revisions ALTER COLUMN title TYPE VARCHAR(256);
+DROP INDEX IF EXISTS idx_notes_search_vector;
+ALTER TABLE notes DROP COLUMN IF EXISTS search_vector;
+DROP INDEX IF EXISTS idx_notes_key_id;
+ALTER TABLE notes DROP COLUMN IF EXISTS key_id;
+ALTER TABLE notes DROP COLUMN IF EXISTS data_key;
+ALTER TABLE notes ALTER COLUMN title TYPE VARCHAR(256);
+ALTER TABLE notes
+  ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(data, '')), 'B')
+  ) STORED;
+CREATE INDEX idx_notes_search_vector ON notes USING GIN (search_vector);
@@ -0,0 +1,22 @@
+-- Envelope encryption of the notes at rest: the data key of every row wrapped
+-- by a master key and the id of that master key, both NULL for plain rows.
+-- Encrypted titles are longer, so they become TEXT. The search document
+-- depends on the title and is created again, empty for the encrypted rows
+-- which are searched once decrypted by the server.
+ALTER TABLE notes ADD COLUMN data_key VARCHAR(256) DEFAULT NULL;
+ALTER TABLE notes ADD COLUMN key_id VARCHAR(64) DEFAULT NULL;
+CREATE INDEX idx_notes_key_id ON notes (key_id);
+ALTER TABLE notes DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE notes ALTER COLUMN title TYPE TEXT;
+ALTER TABLE notes
+  ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    CASE WHEN key_id IS NULL THEN
+      setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+      setweight(to_tsvector('english', coalesce(data, '')), 'B')
+    ELSE ''::tsvector END
+  ) STORED;
+CREATE INDEX idx_notes_search_vector ON notes USING GIN (search_vector);
+ALTER TABLE note_revisions ALTER COLUMN title TYPE TEXT;
+ALTER TABLE note_revisions ADD COLUMN data_key VARCHAR(256) DEFAULT NULL;
+ALTER TABLE note_revisions ADD COLUMN key_id VARCHAR(64) DEFAULT NULL;
+CREATE INDEX idx_note_revisions_key_id ON note_revisions (key_id);
@@ -0,0 +1,18 @@
+-- Files attached to notes. Their content is kept by the storage backend under
+-- storage_key, the rows of the deleted notes lose their note_id until their
+-- content is removed from the storage.
+CREATE SEQUENCE attachments_id_seq;
+CREATE TABLE attachments (
+    id INTEGER NOT NULL DEFAULT nextval('attachments_id_seq'),
+    note_id INTEGER DEFAULT NULL,
+    file_name VARCHAR(256) NOT NULL,
+    content_type VARCHAR(256) NOT NULL,
+    size BIGINT NOT NULL,
+    storage_key VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT unq_attachments_storage_key UNIQUE(storage_key),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE SET NULL
+  );
+ALTER SEQUENCE attachments_id_seq OWNED BY attachments.id;
+CREATE INDEX idx_attachments_note_id ON attachments (note_id);
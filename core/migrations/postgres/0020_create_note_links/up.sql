@@ -0,0 +1,18 @@
+-- Wiki links written in the data of the notes, either [[#<id>]] or
+-- [[<title>]]. The target is not a foreign key, so the links to notes that do
+-- not exist are kept and reported as broken. The titles are stored as their
+-- key, lowercase and with single spaces, or as a blind index with the
+-- encryption at rest.
+CREATE SEQUENCE note_links_id_seq;
+CREATE TABLE note_links (
+    id INTEGER NOT NULL DEFAULT nextval('note_links_id_seq'),
+    note_id INTEGER NOT NULL,
+    target_id INTEGER DEFAULT NULL,
+    target_title VARCHAR(256) DEFAULT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE
+  );
+ALTER SEQUENCE note_links_id_seq OWNED BY note_links.id;
+CREATE INDEX idx_note_links_note_id ON note_links (note_id);
+CREATE INDEX idx_note_links_target_id ON note_links (target_id);
+CREATE INDEX idx_note_links_target_title ON note_links (target_title);
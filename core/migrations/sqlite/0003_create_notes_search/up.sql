@@ -0,0 +1,2 @@
+-- SQLite has no tsvector, so searches scan the notes of the user.
+SELECT 1;
@@ -0,0 +1,11 @@
+CREATE TABLE note_revisions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    note_id INTEGER NOT NULL,
+    revision INTEGER NOT NULL,
+    category_id INTEGER DEFAULT NULL,
+    title VARCHAR(256) NOT NULL,
+    data TEXT NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_note_revisions UNIQUE(note_id, revision),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE
+  );
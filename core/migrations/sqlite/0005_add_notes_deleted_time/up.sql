@@ -0,0 +1,3 @@
+-- Notes with a deleted_time are in the trash until they are purged.
+ALTER TABLE notes ADD COLUMN deleted_time TIMESTAMP DEFAULT NULL;
+CREATE INDEX idx_notes_deleted_time ON notes (deleted_time);
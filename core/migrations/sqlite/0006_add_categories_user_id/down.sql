@@ -0,0 +1,12 @@
+PRAGMA defer_foreign_keys = ON;
+CREATE TABLE categories_old (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_categories UNIQUE(name)
+  );
+INSERT INTO categories_old (id, name, create_time, update_time)
+  SELECT id, name, create_time, update_time FROM categories;
+DROP TABLE categories;
+ALTER TABLE categories_old RENAME TO categories;
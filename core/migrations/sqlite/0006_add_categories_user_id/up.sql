@@ -0,0 +1,21 @@
+-- Categories belong to a user. The existing ones keep a NULL user_id and stay
+-- shared, read-only, by every user. NULL user ids never collide in
+-- unq_categories, so the names of the shared categories have their own index.
+--
+-- SQLite cannot change the constraints of a table, so it is rebuilt. The
+-- foreign keys of the notes are checked on commit, once the new table exists.
+PRAGMA defer_foreign_keys = ON;
+CREATE TABLE categories_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER DEFAULT NULL,
+    name VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_categories UNIQUE(user_id, name),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+INSERT INTO categories_new (id, name, create_time, update_time)
+  SELECT id, name, create_time, update_time FROM categories;
+DROP TABLE categories;
+ALTER TABLE categories_new RENAME TO categories;
+CREATE UNIQUE INDEX unq_categories_shared_name ON categories (name) WHERE user_id IS NULL;
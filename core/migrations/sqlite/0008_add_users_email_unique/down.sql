@@ -0,0 +1 @@
+DROP INDEX IF EXISTS unq_users_email;
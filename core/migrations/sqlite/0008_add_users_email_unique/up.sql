@@ -0,0 +1,2 @@
+-- Users log in and register by email, so it has to be unique as well.
+CREATE UNIQUE INDEX unq_users_email ON users (email);
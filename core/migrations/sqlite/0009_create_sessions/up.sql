@@ -0,0 +1,15 @@
+-- Logins of the users. Access tokens carry the jti of their session and the
+-- refresh token is only stored as its SHA-256, both rotate on every refresh.
+CREATE TABLE sessions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    jti VARCHAR(64) NOT NULL,
+    refresh_token_hash VARCHAR(64) NOT NULL,
+    expire_time TIMESTAMP NOT NULL,
+    revoked_time TIMESTAMP DEFAULT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_sessions_jti UNIQUE(jti),
+    CONSTRAINT unq_sessions_refresh_token_hash UNIQUE(refresh_token_hash),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
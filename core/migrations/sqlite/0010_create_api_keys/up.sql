@@ -0,0 +1,15 @@
+-- Personal API keys of the users, only stored as their SHA-256. The prefix is
+-- kept in clear to tell the keys apart.
+CREATE TABLE api_keys (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    prefix VARCHAR(16) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL,
+    read_only BOOLEAN NOT NULL DEFAULT 0,
+    expire_time TIMESTAMP DEFAULT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_api_keys_key_hash UNIQUE(key_hash),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+CREATE INDEX idx_api_keys_user_id ON api_keys (user_id);
@@ -0,0 +1,3 @@
+-- Admins manage the users and the shared categories, disabled users cannot login.
+ALTER TABLE users ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'user' CHECK (role IN ('admin', 'user'));
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT 0;
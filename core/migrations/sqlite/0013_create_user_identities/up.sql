@@ -0,0 +1,12 @@
+-- Identities of the users in external OpenID Connect providers, keyed by the
+-- issuer and the subject of their ID tokens.
+CREATE TABLE user_identities (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    issuer VARCHAR(256) NOT NULL,
+    subject VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_user_identities_issuer_subject UNIQUE(issuer, subject),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+CREATE INDEX idx_user_identities_user_id ON user_identities (user_id);
@@ -0,0 +1,15 @@
+-- Notes shared by their owner with other users, who can read them or also
+-- update them.
+CREATE TABLE note_shares (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    note_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    permission VARCHAR(8) NOT NULL DEFAULT 'read',
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_note_shares_note_id_user_id UNIQUE(note_id, user_id),
+    CONSTRAINT chk_note_shares_permission CHECK (permission IN ('read', 'write')),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+CREATE INDEX idx_note_shares_user_id ON note_shares (user_id);
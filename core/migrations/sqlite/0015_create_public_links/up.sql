@@ -0,0 +1,13 @@
+-- Public read-only links to notes, only stored as the SHA-256 of their token.
+-- They can expire and be protected by a password.
+CREATE TABLE public_links (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    note_id INTEGER NOT NULL,
+    token_hash VARCHAR(64) NOT NULL,
+    password_hash VARCHAR(256) DEFAULT NULL,
+    expire_time TIMESTAMP DEFAULT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_public_links_token_hash UNIQUE(token_hash),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE
+  );
+CREATE INDEX idx_public_links_note_id ON public_links (note_id);
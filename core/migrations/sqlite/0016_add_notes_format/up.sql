@@ -0,0 +1,2 @@
+-- Format of the data of the notes, used to render them as HTML.
+ALTER TABLE notes ADD COLUMN format VARCHAR(16) NOT NULL DEFAULT 'plain' CHECK (format IN ('plain', 'markdown'));
@@ -0,0 +1,2 @@
+ALTER TABLE note_revisions DROP COLUMN key_check;
+ALTER TABLE notes DROP COLUMN key_check;
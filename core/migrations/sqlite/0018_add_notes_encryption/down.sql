@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_note_revisions_key_id;
+ALTER TABLE note_revisions DROP COLUMN key_id;
+ALTER TABLE note_revisions DROP COLUMN data_key;
+DROP INDEX IF EXISTS idx_notes_key_id;
+ALTER TABLE notes DROP COLUMN key_id;
+ALTER TABLE notes DROP COLUMN data_key;
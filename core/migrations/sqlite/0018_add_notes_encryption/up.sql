@@ -0,0 +1,8 @@
+-- Envelope encryption of the notes at rest: the data key of every row wrapped
+-- by a master key and the id of that master key, both NULL for plain rows.
+ALTER TABLE notes ADD COLUMN data_key VARCHAR(256) DEFAULT NULL;
+ALTER TABLE notes ADD COLUMN key_id VARCHAR(64) DEFAULT NULL;
+CREATE INDEX idx_notes_key_id ON notes (key_id);
+ALTER TABLE note_revisions ADD COLUMN data_key VARCHAR(256) DEFAULT NULL;
+ALTER TABLE note_revisions ADD COLUMN key_id VARCHAR(64) DEFAULT NULL;
+CREATE INDEX idx_note_revisions_key_id ON note_revisions (key_id);
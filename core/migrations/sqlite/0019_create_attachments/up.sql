@@ -0,0 +1,15 @@
+-- Files attached to notes. Their content is kept by the storage backend under
+-- storage_key, the rows of the deleted notes lose their note_id until their
+-- content is removed from the storage.
+CREATE TABLE attachments (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    note_id INTEGER DEFAULT NULL,
+    file_name VARCHAR(256) NOT NULL,
+    content_type VARCHAR(256) NOT NULL,
+    size BIGINT NOT NULL,
+    storage_key VARCHAR(256) NOT NULL,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unq_attachments_storage_key UNIQUE(storage_key),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE SET NULL
+  );
+CREATE INDEX idx_attachments_note_id ON attachments (note_id);
@@ -0,0 +1,15 @@
+-- Wiki links written in the data of the notes, either [[#<id>]] or
+-- [[<title>]]. The target is not a foreign key, so the links to notes that do
+-- not exist are kept and reported as broken. The titles are stored as their
+-- key, lowercase and with single spaces, or as a blind index with the
+-- encryption at rest.
+CREATE TABLE note_links (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    note_id INTEGER NOT NULL,
+    target_id INTEGER DEFAULT NULL,
+    target_title VARCHAR(256) DEFAULT NULL,
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE
+  );
+CREATE INDEX idx_note_links_note_id ON note_links (note_id);
+CREATE INDEX idx_note_links_target_id ON note_links (target_id);
+CREATE INDEX idx_note_links_target_title ON note_links (target_title);
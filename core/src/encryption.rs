@@ -0,0 +1,481 @@
+//! Envelope encryption of the notes at rest.
+//!
+//! Every row has its own random data key, which encrypts the title and data
+//! with AES-256-GCM. The data key is stored next to them wrapped by one of the
+//! master keys, along with the id of that master key, so rotating the master
+//! key only needs the rows of the old one to be encrypted again.
+//!
+//! The ciphertexts are bound to the table and the id of their row, so they
+//! cannot be copied to another row and decrypted there.
+//!
+//! The master keys are configured with `ENCRYPTION_KEYS`, a comma-separated
+//! list of `<KEY_ID>:<BASE64 32-BYTE KEY>`, and `ENCRYPTION_KEY_ID`, the one
+//! used for new rows, which defaults to the last of the list. Without them
+//! the rows are stored in plain text.
+//!
+//! The database cannot search or sort the encrypted titles and data, so the
+//! server does it once decrypted, a batch of notes at a time. The values the
+//! database has to match, like the titles of the wiki links, are stored as a
+//! blind index instead: their HMAC-SHA256 with a key derived from the current master
+//! key, computed again by the rotation.
+
+use crate::models::db::note::NoteRow;
+use crate::models::db::note_link::NoteLink;
+use crate::models::db::revision::NoteRevisionRow;
+use crate::DBConnection;
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+use crypto::aes::KeySize;
+
+use crypto::aes_gcm::AesGcm;
+use diesel::result::{Error as DieselError, QueryResult};
+use dotenv::dotenv;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use log::info;
+use rustc_serialize::base64::{FromBase64, ToBase64, STANDARD};
+use sha2::Sha256;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const MAX_KEY_ID_LENGTH: usize = 64;
+
+lazy_static! {
+    static ref KEYRING: Result<Option<Keyring>, EncryptionError> = Keyring::from_env();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncryptionError {
+    InvalidConfig(String),
+    UnknownKey(String),
+    /// The field cannot be decrypted with its key.
+    Corrupted(String),
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncryptionError::InvalidConfig(o) => {
+                f.write_str(&format!("Encryption is not well configured: {}", o))
+            }
+            EncryptionError::UnknownKey(o) => {
+                f.write_str(&format!("Master key '{}' is not configured", o))
+            }
+            EncryptionError::Corrupted(o) => f.write_str(&format!("The {} cannot be decrypted", o)),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+/// Title and data of a row as they are stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub title: String,
+    pub data: String,
+    /// Data key wrapped by the master key, `None` if the row is plain.
+    pub data_key: Option<String>,
+    pub key_id: Option<String>,
+}
+
+impl Envelope {
+    pub fn plain(title: &str, data: &str) -> Self {
+        Envelope {
+            title: title.to_string(),
+            data: data.to_string(),
+            data_key: None,
+            key_id: None,
+        }
+    }
+}
+
+/// Table and id of the row of an envelope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RowId {
+    pub table: &'static str,
+    pub id: i32,
+}
+
+impl RowId {
+    pub fn new(table: &'static str, id: i32) -> Self {
+        RowId {
+            table: table,
+            id: id,
+        }
+    }
+
+    // Additional data of the field of the row.
+    fn aad(&self, field: &str) -> Vec<u8> {
+        format!("{}/{}/{}", self.table, self.id, field).into_bytes()
+    }
+}
+
+// Nonce, ciphertext and tag in base64.
+fn seal(key: &[u8], aad: &[u8], plaintext: &[u8]) -> String {
+    let nonce: [u8; NONCE_LENGTH] = rand::random();
+    let mut cipher = AesGcm::new(KeySize::KeySize256, key, &nonce, aad);
+    let mut ciphertext = vec![0u8; plaintext.len()];
+    let mut tag = [0u8; TAG_LENGTH];
+    cipher.encrypt(plaintext, &mut ciphertext, &mut tag);
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    sealed.extend_from_slice(&tag);
+    sealed.to_base64(STANDARD)
+}
+
+fn open(key: &[u8], aad: &[u8], sealed: &str) -> Option<Vec<u8>> {
+    let sealed = sealed.from_base64().ok()?;
+    if sealed.len() < NONCE_LENGTH + TAG_LENGTH {
+        return None;
+    }
+    let (nonce, rest) = sealed.split_at(NONCE_LENGTH);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+
+    let mut cipher = AesGcm::new(KeySize::KeySize256, key, nonce, aad);
+    let mut plaintext = vec![0u8; ciphertext.len()];
+    if cipher.decrypt(ciphertext, &mut plaintext, tag) {
+        Some(plaintext)
+    } else {
+        None
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC takes keys of any length");
+    mac.input(data);
+    mac.result().code().to_vec()
+}
+
+/// Master keys by id.
+pub struct Keyring {
+    current: String,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl Keyring {
+    pub fn new(current: &str, keys: Vec<(String, Vec<u8>)>) -> Result<Self, EncryptionError> {
+        for (key_id, key) in keys.iter() {
+            if key_id.is_empty() || key_id.len() > MAX_KEY_ID_LENGTH {
+                return Err(EncryptionError::InvalidConfig(format!(
+                    "key id '{}' is not valid",
+                    key_id
+                )));
+            }
+            if key.len() != KEY_LENGTH {
+                return Err(EncryptionError::InvalidConfig(format!(
+                    "key '{}' must have {} bytes",
+                    key_id, KEY_LENGTH
+                )));
+            }
+        }
+
+        let keyring = Keyring {
+            current: current.to_string(),
+            keys: keys.into_iter().collect(),
+        };
+        if !keyring.keys.contains_key(current) {
+            return Err(EncryptionError::UnknownKey(current.to_string()));
+        }
+
+        Ok(keyring)
+    }
+
+    /// Keyring of `ENCRYPTION_KEYS` and `ENCRYPTION_KEY_ID`, `None` if no keys
+    /// are configured.
+    pub fn from_env() -> Result<Option<Self>, EncryptionError> {
+        dotenv().ok();
+
+        let encryption_keys = match env::var("ENCRYPTION_KEYS") {
+            Ok(encryption_keys) if !encryption_keys.trim().is_empty() => encryption_keys,
+            _ => return Ok(None),
+        };
+
+        let mut keys = vec![];
+        for entry in encryption_keys.split(',') {
+            let mut parts = entry.trim().splitn(2, ':');
+            match (parts.next(), parts.next().map(|key| key.from_base64())) {
+                (Some(key_id), Some(Ok(key))) => keys.push((key_id.to_string(), key)),
+                _ => {
+                    return Err(EncryptionError::InvalidConfig(
+                        "ENCRYPTION_KEYS must be a list of <KEY_ID>:<BASE64_KEY>".to_string(),
+                    ))
+                }
+            }
+        }
+
+        let current = env::var("ENCRYPTION_KEY_ID")
+            .ok()
+            .or_else(|| keys.last().map(|(key_id, _)| key_id.clone()))
+            .unwrap_or_default();
+
+        Keyring::new(&current, keys).map(Some)
+    }
+
+    /// Id of the master key of the new rows.
+    pub fn current_id(&self) -> &str {
+        &self.current
+    }
+
+    /// Encrypts the title and data of the row with a new data key.
+    pub fn encrypt(&self, row: RowId, title: &str, data: &str) -> Envelope {
+        let data_key: [u8; KEY_LENGTH] = rand::random();
+
+        Envelope {
+            title: seal(&data_key, &row.aad("title"), title.as_bytes()),
+            data: seal(&data_key, &row.aad("data"), data.as_bytes()),
+            data_key: Some(seal(
+                &self.keys[&self.current],
+                &row.aad(&format!("key/{}", self.current)),
+                &data_key,
+            )),
+            key_id: Some(self.current.clone()),
+        }
+    }
+
+    /// Blind index of the value, equal for equal values but that does not
+    /// reveal them. It changes along with the current master key.
+    pub fn blind_index(&self, value: &str) -> String {
+        let key = hmac_sha256(&self.keys[&self.current], b"blind_index");
+        format!(
+            "hmac:{}",
+            hmac_sha256(&key, value.as_bytes()).to_base64(STANDARD)
+        )
+    }
+
+    /// Title and data of the envelope of the row, as they are if it is plain.
+    pub fn decrypt(
+        &self,
+        row: RowId,
+        envelope: &Envelope,
+    ) -> Result<(String, String), EncryptionError> {
+        let (data_key, key_id) = match (&envelope.data_key, &envelope.key_id) {
+            (Some(data_key), Some(key_id)) => (data_key, key_id),
+            _ => return Ok((envelope.title.clone(), envelope.data.clone())),
+        };
+
+        let master_key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.clone()))?;
+        let data_key = open(master_key, &row.aad(&format!("key/{}", key_id)), data_key)
+            .ok_or_else(|| EncryptionError::Corrupted("data key".to_string()))?;
+
+        let field = |name: &str, sealed: &str| {
+            open(&data_key, &row.aad(name), sealed)
+                .and_then(|plaintext| String::from_utf8(plaintext).ok())
+                .ok_or_else(|| EncryptionError::Corrupted(name.to_string()))
+        };
+
+        Ok((
+            field("title", &envelope.title)?,
+            field("data", &envelope.data)?,
+        ))
+    }
+}
+
+/// The configured keyring, `None` if the notes are stored in plain text.
+pub fn keyring() -> Result<Option<&'static Keyring>, EncryptionError> {
+    match &*KEYRING {
+        Ok(keyring) => Ok(keyring.as_ref()),
+        Err(err) => Err(err.clone()),
+    }
+}
+
+/// Whether the title and data of the rows may be encrypted, so the queries
+/// cannot search or sort them in the database.
+pub fn is_enabled() -> bool {
+    match keyring() {
+        Ok(None) => false,
+        _ => true,
+    }
+}
+
+/// Envelope of the title and data of the row to store with the configured
+/// keyring.
+pub fn encrypt(row: RowId, title: &str, data: &str) -> QueryResult<Envelope> {
+    match keyring() {
+        Ok(Some(keyring)) => Ok(keyring.encrypt(row, title, data)),
+        Ok(None) => Ok(Envelope::plain(title, data)),
+        Err(err) => Err(DieselError::SerializationError(Box::new(err))),
+    }
+}
+
+/// Blind index of the value to store with the configured keyring, the value
+/// itself if the rows are stored in plain text.
+pub fn blind_index(value: &str) -> QueryResult<String> {
+    match keyring() {
+        Ok(Some(keyring)) => Ok(keyring.blind_index(value)),
+        Ok(None) => Ok(value.to_string()),
+        Err(err) => Err(DieselError::SerializationError(Box::new(err))),
+    }
+}
+
+/// Title and data of the stored envelope of the row.
+pub fn decrypt(row: RowId, envelope: &Envelope) -> QueryResult<(String, String)> {
+    match (keyring(), &envelope.key_id) {
+        (_, None) => Ok((envelope.title.clone(), envelope.data.clone())),
+        (Ok(Some(keyring)), Some(_)) => keyring
+            .decrypt(row, envelope)
+            .map_err(|err| DieselError::DeserializationError(Box::new(err))),
+        (Ok(None), Some(key_id)) => Err(DieselError::DeserializationError(Box::new(
+            EncryptionError::UnknownKey(key_id.clone()),
+        ))),
+        (Err(err), Some(_)) => Err(DieselError::DeserializationError(Box::new(err))),
+    }
+}
+
+/// Encrypts again with the current master key the notes and then the
+/// revisions of other master keys or in plain text, and stores again the
+/// links of every note with the blind indexes of the new key. Every
+/// batch of `batch_size` rows has its own transaction, so the rotation can be
+/// stopped and resumed. Returns the number of notes and revisions.
+pub fn rotate_keys(
+    connection: &DBConnection,
+    keyring: &Keyring,
+    batch_size: i64,
+) -> QueryResult<(usize, usize)> {
+    let mut notes = 0;
+    loop {
+        let num = NoteRow::rotate_keys(connection, keyring, batch_size)?;
+        if num == 0 {
+            break;
+        }
+        notes += num;
+        info!("Re-encrypted {} notes", notes);
+    }
+
+    let mut revisions = 0;
+    loop {
+        let num = NoteRevisionRow::rotate_keys(connection, keyring, batch_size)?;
+        if num == 0 {
+            break;
+        }
+        revisions += num;
+        info!("Re-encrypted {} revisions", revisions);
+    }
+
+    let mut after_id = 0;
+    while let Some(last_id) = NoteLink::rebuild(connection, keyring, after_id, batch_size)? {
+        after_id = last_id;
+        info!("Indexed the links of the notes up to {}", after_id);
+    }
+
+    Ok((notes, revisions))
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn test_keyring(current: &str) -> super::Keyring {
+        super::Keyring::new(
+            current,
+            vec![
+                ("key_a".to_string(), vec![1u8; super::KEY_LENGTH]),
+                ("key_b".to_string(), vec![2u8; super::KEY_LENGTH]),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        use super::*;
+
+        let keyring = test_keyring("key_a");
+        let row = RowId::new("notes", 1);
+
+        let envelope = keyring.encrypt(row, "some_title", "some_data");
+        assert_eq!(envelope.key_id, Some("key_a".to_string()));
+        assert_ne!(envelope.title, "some_title");
+        assert_ne!(envelope.data, "some_data");
+        assert_ne!(envelope, keyring.encrypt(row, "some_title", "some_data"));
+        assert_eq!(
+            keyring.decrypt(row, &envelope),
+            Ok(("some_title".to_string(), "some_data".to_string()))
+        );
+
+        // Rows of the previous master key are still readable after a rotation.
+        assert_eq!(
+            test_keyring("key_b").decrypt(row, &envelope),
+            Ok(("some_title".to_string(), "some_data".to_string()))
+        );
+
+        assert_eq!(
+            keyring.decrypt(row, &Envelope::plain("some_title", "some_data")),
+            Ok(("some_title".to_string(), "some_data".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_decrypt_ko() {
+        use super::*;
+
+        let keyring = test_keyring("key_a");
+        let row = RowId::new("notes", 1);
+        let envelope = keyring.encrypt(row, "some_title", "some_data");
+
+        let mut swapped = envelope.clone();
+        swapped.title = envelope.data.clone();
+        assert_eq!(
+            keyring.decrypt(row, &swapped),
+            Err(EncryptionError::Corrupted("title".to_string()))
+        );
+
+        let mut other_key = envelope.clone();
+        other_key.key_id = Some("key_b".to_string());
+        assert_eq!(
+            keyring.decrypt(row, &other_key),
+            Err(EncryptionError::Corrupted("data key".to_string()))
+        );
+
+        let mut unknown_key = envelope.clone();
+        unknown_key.key_id = Some("key_c".to_string());
+        assert_eq!(
+            keyring.decrypt(row, &unknown_key),
+            Err(EncryptionError::UnknownKey("key_c".to_string()))
+        );
+
+        // The envelope cannot be copied to other row or table.
+        assert_eq!(
+            keyring.decrypt(RowId::new("notes", 2), &envelope),
+            Err(EncryptionError::Corrupted("data key".to_string()))
+        );
+        assert_eq!(
+            keyring.decrypt(RowId::new("note_revisions", 1), &envelope),
+            Err(EncryptionError::Corrupted("data key".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_blind_index() {
+        use super::*;
+
+        let keyring = test_keyring("key_a");
+        let index = keyring.blind_index("some title");
+        assert!(index.starts_with("hmac:"));
+        assert!(!index.contains("some title"));
+        assert_eq!(index, keyring.blind_index("some title"));
+        assert_ne!(index, keyring.blind_index("other title"));
+        assert_ne!(index, test_keyring("key_b").blind_index("some title"));
+    }
+
+    #[test]
+    fn test_keyring_new_ko() {
+        use super::*;
+
+        assert_eq!(
+            Keyring::new("key_a", vec![("key_a".to_string(), vec![1u8; 16])]).err(),
+            Some(EncryptionError::InvalidConfig(
+                "key 'key_a' must have 32 bytes".to_string()
+            ))
+        );
+        assert_eq!(
+            Keyring::new("key_b", vec![("key_a".to_string(), vec![1u8; 32])]).err(),
+            Some(EncryptionError::UnknownKey("key_b".to_string()))
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Graph of the notes of a user for visualization.
+//!
+//! The notes and the categories are the nodes. The edges go from the notes to
+//! their categories, from the categories to their parents and from the notes
+//! to the notes they link with wiki links. It is exported as JSON, Graphviz DOT
+//! or GraphML.
+
+use crate::models::api::graph::{GraphEdgeOut, GraphNodeOut, GraphOut};
+use crate::models::db::category::Category;
+use crate::models::db::note::Note;
+use crate::render::escape_html;
+
+use std::fmt;
+
+pub const NOTE: &str = "note";
+pub const CATEGORY: &str = "category";
+pub const PARENT: &str = "parent";
+pub const REFERENCE: &str = "reference";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+    GraphMl,
+}
+
+impl GraphFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GraphFormat::Json => "json",
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+        }
+    }
+
+    pub fn parse(format: &str) -> Option<GraphFormat> {
+        match format {
+            "json" => Some(GraphFormat::Json),
+            "dot" => Some(GraphFormat::Dot),
+            "graphml" => Some(GraphFormat::GraphMl),
+            _ => None,
+        }
+    }
+}
+
+impl Default for GraphFormat {
+    fn default() -> Self {
+        GraphFormat::Json
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn node_id(kind: &str, id: i32) -> String {
+    format!("{}:{}", kind, id)
+}
+
+/// Graph of the notes and categories. `links` are pairs of linking and linked
+/// note ids, the edges whose ends are not in the graph are left out.
+pub fn build(notes: &[Note], categories: &[Category], links: &[(i32, i32)]) -> GraphOut {
+    let has_note = |id: i32| notes.iter().any(|note| note.id == id);
+    let has_category = |id: i32| categories.iter().any(|category| category.id == id);
+
+    let mut nodes: Vec<GraphNodeOut> = categories
+        .iter()
+        .map(|category| GraphNodeOut {
+            id: node_id(CATEGORY, category.id),
+            kind: CATEGORY.to_string(),
+            label: category.name.clone(),
+        })
+        .collect();
+    // The titles of the notes encrypted by the client cannot be read.
+    nodes.extend(notes.iter().map(|note| GraphNodeOut {
+        id: node_id(NOTE, note.id),
+        kind: NOTE.to_string(),
+        label: if note.is_encrypted() {
+            format!("#{}", note.id)
+        } else {
+            note.title.clone()
+        },
+    }));
+
+    let mut edges: Vec<GraphEdgeOut> = vec![];
+    for category in categories.iter() {
+        if let Some(parent_id) = category.parent_id.filter(|id| has_category(*id)) {
+            edges.push(GraphEdgeOut {
+                source: node_id(CATEGORY, category.id),
+                target: node_id(CATEGORY, parent_id),
+                kind: PARENT.to_string(),
+            });
+        }
+    }
+    for note in notes.iter() {
+        if let Some(category_id) = note.category_id.filter(|id| has_category(*id)) {
+            edges.push(GraphEdgeOut {
+                source: node_id(NOTE, note.id),
+                target: node_id(CATEGORY, category_id),
+                kind: CATEGORY.to_string(),
+            });
+        }
+    }
+    for (source_id, target_id) in links.iter() {
+        let edge = GraphEdgeOut {
+            source: node_id(NOTE, *source_id),
+            target: node_id(NOTE, *target_id),
+            kind: REFERENCE.to_string(),
+        };
+        if has_note(*source_id) && has_note(*target_id) && !edges.contains(&edge) {
+            edges.push(edge);
+        }
+    }
+
+    GraphOut {
+        nodes: nodes,
+        edges: edges,
+    }
+}
+
+// Quoted DOT identifier.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The graph in the Graphviz DOT language, categories are drawn as boxes.
+pub fn to_dot(graph: &GraphOut) -> String {
+    let mut dot = String::from("digraph notes {\n");
+    for node in graph.nodes.iter() {
+        let shape = if node.kind == CATEGORY {
+            "box"
+        } else {
+            "ellipse"
+        };
+        dot.push_str(&format!(
+            "  {} [label={}, shape={}];\n",
+            quote(&node.id),
+            quote(&node.label),
+            shape
+        ));
+    }
+    for edge in graph.edges.iter() {
+        let style = if edge.kind == REFERENCE {
+            "solid"
+        } else {
+            "dashed"
+        };
+        dot.push_str(&format!(
+            "  {} -> {} [label={}, style={}];\n",
+            quote(&edge.source),
+            quote(&edge.target),
+            quote(&edge.kind),
+            style
+        ));
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+/// The graph in GraphML, with the `kind` of the nodes and edges and the
+/// `label` of the nodes as data.
+pub fn to_graphml(graph: &GraphOut) -> String {
+    let mut graphml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="kind" for="all" attr.name="kind" attr.type="string"/>
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <graph id="notes" edgedefault="directed">
+"#,
+    );
+    for node in graph.nodes.iter() {
+        graphml.push_str(&format!(
+            "    <node id=\"{}\"><data key=\"kind\">{}</data><data key=\"label\">{}</data></node>\n",
+            escape_html(&node.id),
+            escape_html(&node.kind),
+            escape_html(&node.label)
+        ));
+    }
+    for (index, edge) in graph.edges.iter().enumerate() {
+        graphml.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"><data key=\"kind\">{}</data></edge>\n",
+            index,
+            escape_html(&edge.source),
+            escape_html(&edge.target),
+            escape_html(&edge.kind)
+        ));
+    }
+    graphml.push_str("  </graph>\n</graphml>\n");
+
+    graphml
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn test_graph() -> crate::models::api::graph::GraphOut {
+        use crate::models::db::category::Category;
+        use crate::models::db::note::Note;
+        use crate::utils::now;
+
+        let time = now();
+        let category = |id: i32, parent_id: Option<i32>, name: &str| Category {
+            id: id,
+            user_id: Some(1),
+            parent_id: parent_id,
+            name: name.to_string(),
+            create_time: time,
+            update_time: time,
+        };
+        let note = |id: i32, category_id: Option<i32>, title: &str, key_check: Option<&str>| Note {
+            id: id,
+            user_id: 1,
+            category_id: category_id,
+            title: title.to_string(),
+            data: "some_data".to_string(),
+            create_time: time,
+            update_time: time,
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: key_check.map(|key_check| key_check.to_string()),
+        };
+
+        super::build(
+            &[
+                note(1, Some(2), "note \"a\"", None),
+                note(2, Some(3), "note <b>", None),
+                note(3, None, "ciphertext", Some("some_key_check")),
+            ],
+            &[category(1, None, "cat_a"), category(2, Some(1), "cat_b")],
+            &[(1, 2), (1, 2), (2, 1), (2, 4)],
+        )
+    }
+
+    #[test]
+    fn test_build() {
+        use super::*;
+
+        let graph = test_graph();
+
+        assert_eq!(
+            graph
+                .nodes
+                .iter()
+                .map(|node| (node.id.as_str(), node.label.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("category:1", "cat_a"),
+                ("category:2", "cat_b"),
+                ("note:1", "note \"a\""),
+                ("note:2", "note <b>"),
+                ("note:3", "#3"),
+            ]
+        );
+        // The category 3 and the note 4 are not in the graph.
+        assert_eq!(
+            graph
+                .edges
+                .iter()
+                .map(|edge| (
+                    edge.source.as_str(),
+                    edge.target.as_str(),
+                    edge.kind.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("category:2", "category:1", PARENT),
+                ("note:1", "category:2", CATEGORY),
+                ("note:1", "note:2", REFERENCE),
+                ("note:2", "note:1", REFERENCE),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        use super::*;
+
+        let dot = to_dot(&test_graph());
+
+        assert!(dot.starts_with("digraph notes {\n"));
+        assert!(dot.contains("  \"category:1\" [label=\"cat_a\", shape=box];\n"));
+        assert!(dot.contains("  \"note:1\" [label=\"note \\\"a\\\"\", shape=ellipse];\n"));
+        assert!(dot.contains("  \"note:1\" -> \"note:2\" [label=\"reference\", style=solid];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_to_graphml() {
+        use super::*;
+
+        let graphml = to_graphml(&test_graph());
+
+        assert!(graphml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml"));
+        assert!(graphml.contains(
+            "<node id=\"note:2\"><data key=\"kind\">note</data><data key=\"label\">note &lt;b&gt;</data></node>"
+        ));
+        assert!(graphml.contains(
+            "<edge id=\"e0\" source=\"category:2\" target=\"category:1\"><data key=\"kind\">parent</data></edge>"
+        ));
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn test_graph_format_parse() {
+        use super::*;
+
+        for format in &[GraphFormat::Json, GraphFormat::Dot, GraphFormat::GraphMl] {
+            assert_eq!(GraphFormat::parse(format.as_str()), Some(*format));
+        }
+        assert_eq!(GraphFormat::parse("svg"), None);
+    }
+}
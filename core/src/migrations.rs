@@ -0,0 +1,223 @@
+//! Embedded and versioned schema migrations.
+//!
+//! Every migration lives in `core/migrations/<backend>/<version>_<name>/` as a
+//! pair of `up.sql` and `down.sql` scripts that are embedded in the binary at
+//! compile time. The versions already applied to a database are tracked in the
+//! `__migrations` table.
+
+use crate::DBConnection;
+
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+#[cfg(not(feature = "sqlite"))]
+use diesel::sql_query;
+
+use log::*;
+
+table! {
+    __migrations (version) {
+        version -> Varchar,
+        name -> Varchar,
+        run_time -> Timestamp,
+    }
+}
+
+const CREATE_MIGRATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS __migrations (
+    version VARCHAR(64) NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    run_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (version)
+  )";
+
+// Arbitrary key of the advisory lock that serializes concurrent migration runs.
+#[cfg(not(feature = "sqlite"))]
+const MIGRATIONS_LOCK_KEY: i64 = 7_236_521_459;
+
+#[derive(Debug, PartialEq)]
+pub struct Migration {
+    pub version: &'static str,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+#[cfg(not(feature = "sqlite"))]
+macro_rules! migration {
+    ($version:expr, $name:expr) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!(
+                "../migrations/postgres/",
+                $version,
+                "_",
+                $name,
+                "/up.sql"
+            )),
+            down: include_str!(concat!(
+                "../migrations/postgres/",
+                $version,
+                "_",
+                $name,
+                "/down.sql"
+            )),
+        }
+    };
+}
+
+#[cfg(feature = "sqlite")]
+macro_rules! migration {
+    ($version:expr, $name:expr) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!(
+                "../migrations/sqlite/",
+                $version,
+                "_",
+                $name,
+                "/up.sql"
+            )),
+            down: include_str!(concat!(
+                "../migrations/sqlite/",
+                $version,
+                "_",
+                $name,
+                "/down.sql"
+            )),
+        }
+    };
+}
+
+/// All the known migrations, sorted by version.
+pub const MIGRATIONS: &[Migration] = &[
+    migration!("0001", "create_base_tables"),
+    migration!("0002", "create_tags"),
+    migration!("0003", "create_notes_search"),
+    migration!("0004", "create_note_revisions"),
+    migration!("0005", "add_notes_deleted_time"),
+    migration!("0006", "add_categories_user_id"),
+    migration!("0007", "add_categories_parent_id"),
+    migration!("0008", "add_users_email_unique"),
+    migration!("0009", "create_sessions"),
+    migration!("0010", "create_api_keys"),
+    migration!("0011", "add_users_role"),
+    migration!("0012", "create_two_factor"),
+    migration!("0013", "create_user_identities"),
+    migration!("0014", "create_note_shares"),
+    migration!("0015", "create_public_links"),
+    migration!("0016", "add_notes_format"),
+    migration!("0017", "add_notes_key_check"),
+    migration!("0018", "add_notes_encryption"),
+    migration!("0019", "create_attachments"),
+    migration!("0020", "create_note_links"),
+];
+
+#[cfg(not(feature = "sqlite"))]
+fn lock(connection: &DBConnection) -> QueryResult<()> {
+    connection.batch_execute(CREATE_MIGRATIONS_TABLE)?;
+    sql_query(format!(
+        "SELECT pg_advisory_xact_lock({})",
+        MIGRATIONS_LOCK_KEY
+    ))
+    .execute(connection)
+    .map(|_| ())
+}
+
+// SQLite serializes the writers of a database, so the transaction is enough.
+#[cfg(feature = "sqlite")]
+fn lock(connection: &DBConnection) -> QueryResult<()> {
+    connection.batch_execute(CREATE_MIGRATIONS_TABLE)
+}
+
+/// Versions already applied to the database, sorted in ascending order.
+pub fn applied_versions(connection: &DBConnection) -> QueryResult<Vec<String>> {
+    use self::__migrations::dsl::*;
+
+    connection.batch_execute(CREATE_MIGRATIONS_TABLE)?;
+    __migrations
+        .select(version)
+        .order(version.asc())
+        .load::<String>(connection)
+}
+
+/// Migrations that have not been applied to the database yet.
+pub fn pending(connection: &DBConnection) -> QueryResult<Vec<&'static Migration>> {
+    let applied = applied_versions(connection)?;
+
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.iter().any(|v| v == migration.version))
+        .collect())
+}
+
+/// Applies every pending migration in a single transaction and returns them.
+pub fn run_pending(connection: &DBConnection) -> QueryResult<Vec<&'static Migration>> {
+    use self::__migrations::dsl::*;
+
+    connection.transaction(|| {
+        lock(connection)?;
+
+        let migrations = pending(connection)?;
+        for migration in migrations.iter() {
+            info!(
+                "Applying migration {}_{}",
+                migration.version, migration.name
+            );
+
+            connection.batch_execute(migration.up)?;
+            diesel::insert_into(__migrations)
+                .values((version.eq(migration.version), name.eq(migration.name)))
+                .execute(connection)?;
+        }
+
+        Ok(migrations)
+    })
+}
+
+/// Reverts the last applied migration, if any, and returns it.
+pub fn revert_last(connection: &DBConnection) -> QueryResult<Option<&'static Migration>> {
+    use self::__migrations::dsl::*;
+
+    connection.transaction(|| {
+        lock(connection)?;
+
+        let last = applied_versions(connection)?.pop();
+        let migration = last
+            .as_ref()
+            .and_then(|last| MIGRATIONS.iter().find(|m| m.version == last));
+
+        match (last, migration) {
+            (Some(_), Some(migration)) => {
+                info!(
+                    "Reverting migration {}_{}",
+                    migration.version, migration.name
+                );
+
+                connection.batch_execute(migration.down)?;
+                diesel::delete(__migrations.find(migration.version)).execute(connection)?;
+
+                Ok(Some(migration))
+            }
+            (Some(last), None) => {
+                warn!("Migration {} is unknown and cannot be reverted", last);
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    })
+}
+
+mod tests {
+    #[test]
+    fn test_migrations_sorted() {
+        use super::*;
+
+        let mut versions: Vec<&str> = MIGRATIONS.iter().map(|m| m.version).collect();
+        versions.sort();
+        versions.dedup();
+
+        assert_eq!(versions.len(), MIGRATIONS.len());
+        assert!(MIGRATIONS.iter().zip(versions).all(|(m, v)| m.version == v));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::models::db::api_key::ApiKey;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::convert::From;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyOut {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub read_only: bool,
+    pub expire_time: Option<String>,
+    pub create_time: String,
+    /// The key itself, only returned when it is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl ApiKeyOut {
+    pub fn with_key(mut self, key: String) -> Self {
+        self.key = Some(key);
+        self
+    }
+}
+
+impl PartialEq for ApiKeyOut {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.name == other.name
+            && self.prefix == other.prefix
+            && self.read_only == other.read_only
+            && self.key == other.key
+    }
+}
+
+impl From<&ApiKey> for ApiKeyOut {
+    fn from(api_key: &ApiKey) -> Self {
+        ApiKeyOut {
+            id: api_key.id,
+            name: api_key.name.clone(),
+            prefix: api_key.prefix.clone(),
+            read_only: api_key.read_only,
+            expire_time: api_key
+                .expire_time
+                .map(|time| DateTime::<Utc>::from_utc(time, Utc).to_rfc3339()),
+            create_time: DateTime::<Utc>::from_utc(api_key.create_time, Utc).to_rfc3339(),
+            key: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyIn {
+    pub name: String,
+    #[serde(default)]
+    pub read_only: bool,
+    /// Days until the key expires, up to 3650, it never expires if missing.
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+}
+
+mod tests {
+
+    #[test]
+    fn test_api_key_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let str_time = DateTime::<Utc>::from_utc(time, Utc).to_rfc3339();
+        let api_key = ApiKey {
+            id: 12345,
+            user_id: 1,
+            name: "some_name".to_string(),
+            prefix: "rn_0123".to_string(),
+            key_hash: "some_hash".to_string(),
+            read_only: true,
+            expire_time: Some(time),
+            create_time: time,
+        };
+
+        let result = ApiKeyOut::from(&api_key);
+
+        let expected = ApiKeyOut {
+            id: 12345,
+            name: "some_name".to_string(),
+            prefix: "rn_0123".to_string(),
+            read_only: true,
+            expire_time: Some(str_time.clone()),
+            create_time: str_time.clone(),
+            key: None,
+        };
+
+        assert_eq!(result, expected);
+        assert_eq!(
+            result.with_key("rn_0123456789".to_string()).key,
+            Some("rn_0123456789".to_string())
+        );
+    }
+}
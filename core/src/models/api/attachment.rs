@@ -0,0 +1,44 @@
+use crate::models::db::attachment::Attachment;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::convert::From;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentOut {
+    pub id: i32,
+    pub note_id: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub create_time: String,
+}
+
+impl PartialEq for AttachmentOut {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.note_id == other.note_id
+            && self.file_name == other.file_name
+            && self.content_type == other.content_type
+            && self.size == other.size
+    }
+}
+
+impl From<&Attachment> for AttachmentOut {
+    fn from(attachment: &Attachment) -> Self {
+        AttachmentOut {
+            id: attachment.id,
+            note_id: attachment.note_id.unwrap_or_default(),
+            file_name: attachment.file_name.clone(),
+            content_type: attachment.content_type.clone(),
+            size: attachment.size,
+            create_time: DateTime::<Utc>::from_utc(attachment.create_time, Utc).to_rfc3339(),
+        }
+    }
+}
+
+/// Bytes used by the attachments of the user and the most allowed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentQuotaOut {
+    pub used: i64,
+    pub quota: i64,
+}
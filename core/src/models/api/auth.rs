@@ -1,10 +1,159 @@
+const MAX_LENGTH: usize = 256;
+const MIN_PASSWORD_LENGTH: usize = 8;
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct LoginIn {
     pub email: String,
     pub password: String,
 }
 
+/// Short-lived access token and the refresh token that renews it. Users with
+/// 2FA only get a challenge token, to exchange along with a code for them.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct LoginOut {
+    #[serde(default)]
     pub jwt_token: String,
+    #[serde(default)]
+    pub refresh_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_token: Option<String>,
+}
+
+impl LoginOut {
+    pub fn challenge(challenge_token: String) -> Self {
+        LoginOut {
+            jwt_token: String::new(),
+            refresh_token: String::new(),
+            challenge_token: Some(challenge_token),
+        }
+    }
+}
+
+/// Second step of the login of a user with 2FA, the code is either a TOTP
+/// code or a recovery code.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TwoFactorLoginIn {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+/// TOTP secret to add to an authenticator app, along with the recovery
+/// codes. They are only shown once.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TotpSetupOut {
+    pub secret: String,
+    pub uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TotpCodeIn {
+    pub code: String,
+}
+
+/// Where to send the user to login with the OpenID Connect provider.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct OidcAuthorizeOut {
+    pub authorization_url: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RefreshIn {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RegisterIn {
+    pub email: String,
+    pub name: String,
+    pub password: String,
+}
+
+impl RegisterIn {
+    /// Checks the email, the name and the length of the password.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut parts = self.email.splitn(2, '@');
+        let valid_email = match (parts.next(), parts.next()) {
+            (Some(local), Some(domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+            }
+            _ => false,
+        };
+        if !valid_email
+            || self.email.len() > MAX_LENGTH
+            || self.email.chars().any(char::is_whitespace)
+        {
+            return Err(format!("email '{}' is not valid", self.email));
+        }
+
+        if self.name.trim().is_empty()
+            || self.name.trim() != self.name
+            || self.name.len() > MAX_LENGTH
+        {
+            return Err(format!("name '{}' is not valid", self.name));
+        }
+
+        if self.password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(format!(
+                "password must have at least {} characters",
+                MIN_PASSWORD_LENGTH
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_login_out_challenge() {
+        use super::*;
+
+        let login_out = LoginOut {
+            jwt_token: "jwt".to_string(),
+            refresh_token: "refresh".to_string(),
+            challenge_token: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&login_out).unwrap(),
+            r#"{"jwt_token":"jwt","refresh_token":"refresh"}"#
+        );
+
+        let login_out: LoginOut =
+            serde_json::from_str(r#"{"challenge_token":"challenge"}"#).unwrap();
+        assert_eq!(login_out, LoginOut::challenge("challenge".to_string()));
+    }
+
+    #[test]
+    fn test_register_in_validate() {
+        use super::*;
+
+        let register_in = |email: &str, name: &str, password: &str| RegisterIn {
+            email: email.to_string(),
+            name: name.to_string(),
+            password: password.to_string(),
+        };
+
+        assert!(register_in("user@email.com", "user", "some_password")
+            .validate()
+            .is_ok());
+
+        for (email, name, password) in &[
+            ("user", "user", "some_password"),
+            ("@email.com", "user", "some_password"),
+            ("user@email", "user", "some_password"),
+            ("user@@email.com", "user", "some_password"),
+            ("us er@email.com", "user", "some_password"),
+            ("user@email.com", " ", "some_password"),
+            ("user@email.com", " user", "some_password"),
+            ("user@email.com", "user", "short"),
+        ] {
+            assert!(register_in(email, name, password).validate().is_err());
+        }
+    }
 }
@@ -1,19 +1,28 @@
-use crate::models::db::category::Category;
+use crate::models::db::category::{Category, NewCategory};
 use chrono::offset::Utc;
 use chrono::DateTime;
+use std::collections::HashSet;
 use std::convert::From;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CategoryOut {
     pub id: i32,
     pub name: String,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    /// Shared categories belong to every user and cannot be modified.
+    #[serde(default)]
+    pub shared: bool,
     pub create_time: String,
     pub update_time: String,
 }
 
 impl PartialEq for CategoryOut {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.name == other.name
+        self.id == other.id
+            && self.name == other.name
+            && self.parent_id == other.parent_id
+            && self.shared == other.shared
     }
 }
 
@@ -22,23 +31,113 @@ impl From<&Category> for CategoryOut {
         CategoryOut {
             id: category.id,
             name: category.name.clone(),
-            create_time: DateTime::<Utc>::from(category.create_time).to_rfc3339(),
-            update_time: DateTime::<Utc>::from(category.update_time).to_rfc3339(),
+            parent_id: category.parent_id,
+            shared: category.user_id.is_none(),
+            create_time: DateTime::<Utc>::from_utc(category.create_time, Utc).to_rfc3339(),
+            update_time: DateTime::<Utc>::from_utc(category.update_time, Utc).to_rfc3339(),
         }
     }
 }
 
+/// A category along with its descendants.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CategoryTreeOut {
+    #[serde(flatten)]
+    pub category: CategoryOut,
+    pub children: Vec<CategoryTreeOut>,
+}
+
+impl CategoryTreeOut {
+    /// Trees of the given categories sorted by name. Categories whose parent
+    /// is not among them are roots.
+    pub fn build(categories: &[Category]) -> Vec<CategoryTreeOut> {
+        let roots: Vec<&Category> = categories
+            .iter()
+            .filter(|category| {
+                category.parent_id.map_or(true, |parent_id| {
+                    !categories.iter().any(|c| c.id == parent_id)
+                })
+            })
+            .collect();
+
+        CategoryTreeOut::nodes(categories, roots, &mut HashSet::new())
+    }
+
+    // Categories already visited are skipped, so a cycle among the categories
+    // cannot recurse forever.
+    fn nodes(
+        categories: &[Category],
+        mut level: Vec<&Category>,
+        visited: &mut HashSet<i32>,
+    ) -> Vec<CategoryTreeOut> {
+        level.retain(|category| visited.insert(category.id));
+        level.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+
+        level
+            .into_iter()
+            .map(|category| CategoryTreeOut {
+                category: CategoryOut::from(category),
+                children: CategoryTreeOut::nodes(
+                    categories,
+                    categories
+                        .iter()
+                        .filter(|child| child.parent_id == Some(category.id))
+                        .collect(),
+                    visited,
+                ),
+            })
+            .collect()
+    }
+
+    /// The tree as indented lines of `<id> <name>`.
+    pub fn render(trees: &[CategoryTreeOut]) -> String {
+        fn lines(tree: &CategoryTreeOut, depth: usize, result: &mut Vec<String>) {
+            result.push(format!(
+                "{}{} {}",
+                "  ".repeat(depth),
+                tree.category.id,
+                tree.category.name
+            ));
+            for child in tree.children.iter() {
+                lines(child, depth + 1, result);
+            }
+        }
+
+        let mut result = vec![];
+        for tree in trees.iter() {
+            lines(tree, 0, &mut result);
+        }
+
+        result.join("\n")
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CategoryIn {
+    pub name: String,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+}
+
+impl CategoryIn {
+    pub fn to_new_category(&self, user_id: i32) -> NewCategory {
+        NewCategory::new(Some(user_id), &self.name).with_parent(self.parent_id)
+    }
+}
+
 mod tests {
 
     #[test]
     fn test_category_out_from() {
         use super::*;
-        use std::time::SystemTime;
+        use crate::utils::now;
 
-        let time = SystemTime::now();
-        let str_time = DateTime::<Utc>::from(time).to_rfc3339();
+        let time = now();
+        let str_time = DateTime::<Utc>::from_utc(time, Utc).to_rfc3339();
         let category = Category {
             id: 12345,
+            user_id: Some(1),
+            parent_id: Some(2),
             name: "some_name".to_string(),
             create_time: time,
             update_time: time,
@@ -49,10 +148,78 @@ mod tests {
         let expected = CategoryOut {
             id: 12345,
             name: "some_name".to_string(),
+            parent_id: Some(2),
+            shared: false,
             create_time: str_time.clone(),
             update_time: str_time.clone(),
         };
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_category_in_to_new_category() {
+        use super::*;
+
+        let category_in = CategoryIn {
+            name: "some_name".to_string(),
+            parent_id: Some(2),
+        };
+
+        let result = category_in.to_new_category(1);
+
+        assert_eq!(result.user_id, Some(1));
+        assert_eq!(result.parent_id, Some(2));
+        assert_eq!(result.name, "some_name");
+    }
+
+    #[test]
+    fn test_category_tree_out_build() {
+        use super::*;
+        use crate::utils::now;
+
+        let category = |id: i32, parent_id: Option<i32>, name: &str| Category {
+            id: id,
+            user_id: Some(1),
+            parent_id: parent_id,
+            name: name.to_string(),
+            create_time: now(),
+            update_time: now(),
+        };
+        let categories = vec![
+            category(1, None, "work"),
+            category(2, Some(1), "rnotes"),
+            category(3, Some(2), "design"),
+            category(4, None, "home"),
+            category(5, Some(1), "meetings"),
+            category(6, Some(99), "orphan"),
+        ];
+
+        let result = CategoryTreeOut::build(&categories);
+
+        assert_eq!(
+            CategoryTreeOut::render(&result),
+            "4 home\n6 orphan\n1 work\n  5 meetings\n  2 rnotes\n    3 design"
+        );
+    }
+
+    #[test]
+    fn test_category_tree_out_build_cycle() {
+        use super::*;
+        use crate::utils::now;
+
+        let category = |id: i32, parent_id: Option<i32>, name: &str| Category {
+            id: id,
+            user_id: Some(1),
+            parent_id: parent_id,
+            name: name.to_string(),
+            create_time: now(),
+            update_time: now(),
+        };
+        let categories = vec![category(3, Some(4), "a"), category(4, Some(3), "b")];
+
+        let result = CategoryTreeOut::nodes(&categories, vec![&categories[0]], &mut HashSet::new());
+
+        assert_eq!(CategoryTreeOut::render(&result), "3 a\n  4 b");
+    }
 }
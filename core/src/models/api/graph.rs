@@ -0,0 +1,23 @@
+/// Note or category of the graph. `id` is unique among both kinds, e.g.
+/// `note:1` or `category:1`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GraphNodeOut {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+}
+
+/// Edge of the graph: `category` from a note to its category, `parent` from a
+/// category to its parent and `reference` from a note to the note it links.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdgeOut {
+    pub source: String,
+    pub target: String,
+    pub kind: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GraphOut {
+    pub nodes: Vec<GraphNodeOut>,
+    pub edges: Vec<GraphEdgeOut>,
+}
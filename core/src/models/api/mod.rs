@@ -1,6 +1,15 @@
+pub mod api_key;
+pub mod attachment;
 pub mod auth;
 pub mod category;
+pub mod graph;
 pub mod note;
+pub mod note_link;
+pub mod note_share;
+pub mod public_link;
+pub mod revision;
+pub mod tag;
+pub mod user;
 
 #[derive(Debug, PartialEq, Serialize)]
 pub struct Error {
@@ -19,3 +28,10 @@ impl Error {
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Empty {}
+
+/// A page of a listing, `next_cursor` is used to request the following one.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
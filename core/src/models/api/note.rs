@@ -1,10 +1,10 @@
-use crate::models::db::note::{NewNote, Note};
+use crate::models::db::note::{NewNote, Note, NoteFormat, NoteSearchHit};
+use crate::utils::now;
 
 use chrono::offset::Utc;
 use chrono::DateTime;
 
 use std::convert::{From, Into};
-use std::time::SystemTime;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NoteOut {
@@ -12,8 +12,24 @@ pub struct NoteOut {
     pub category_id: Option<i32>,
     pub title: String,
     pub data: String,
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub create_time: String,
     pub update_time: String,
+    #[serde(default)]
+    pub deleted_time: Option<String>,
+    /// Set when the title and data are encrypted by the client.
+    #[serde(default)]
+    pub key_check: Option<String>,
+}
+
+impl NoteOut {
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
 }
 
 impl PartialEq for NoteOut {
@@ -22,6 +38,9 @@ impl PartialEq for NoteOut {
             && self.category_id == other.category_id
             && self.title == other.title
             && self.data == other.data
+            && self.format == other.format
+            && self.tags == other.tags
+            && self.key_check == other.key_check
     }
 }
 
@@ -32,22 +51,70 @@ impl From<&Note> for NoteOut {
             category_id: note.category_id,
             title: note.title.clone(),
             data: note.data.clone(),
-            create_time: DateTime::<Utc>::from(note.create_time).to_rfc3339(),
-            update_time: DateTime::<Utc>::from(note.update_time).to_rfc3339(),
+            format: note.format.clone(),
+            tags: vec![],
+            create_time: DateTime::<Utc>::from_utc(note.create_time, Utc).to_rfc3339(),
+            update_time: DateTime::<Utc>::from_utc(note.update_time, Utc).to_rfc3339(),
+            deleted_time: note
+                .deleted_time
+                .map(|time| DateTime::<Utc>::from_utc(time, Utc).to_rfc3339()),
+            key_check: note.key_check.clone(),
         }
     }
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteSearchOut {
+    pub note: NoteOut,
+    pub rank: f32,
+    pub title: String,
+    pub snippet: String,
+}
+
+impl From<&NoteSearchHit> for NoteSearchOut {
+    fn from(hit: &NoteSearchHit) -> Self {
+        NoteSearchOut {
+            note: NoteOut::from(&hit.note),
+            rank: hit.rank,
+            title: hit.title.clone(),
+            snippet: hit.snippet.clone(),
+        }
+    }
+}
+
+/// Sanitized HTML of a note.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteRenderOut {
+    pub id: i32,
+    pub format: String,
+    pub html: String,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct NoteIn {
     pub category_id: Option<i32>,
     pub title: String,
     pub data: String,
+    /// Tags of the note, unchanged for the updated notes if missing.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// `plain` or `markdown`, `plain` for new notes and unchanged for the
+    /// updated ones if missing.
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Key-check value of a note encrypted by the client, the title and data
+    /// are then ciphertext. Plain notes leave it empty.
+    #[serde(default)]
+    pub key_check: Option<String>,
 }
 
 impl Into<NewNote> for NoteIn {
     fn into(self) -> NewNote {
-        NewNote::new(0, self.category_id, self.title.clone(), self.data.clone())
+        let mut new_note = NewNote::new(0, self.category_id, self.title.clone(), self.data.clone());
+        if let Some(format) = self.format {
+            new_note.format = format;
+        }
+        new_note.with_key_check(self.key_check.as_ref().map(|k| k.as_str()))
     }
 }
 
@@ -59,8 +126,13 @@ impl Into<Note> for NoteIn {
             category_id: self.category_id,
             title: self.title.to_string(),
             data: self.data.to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: self
+                .format
+                .unwrap_or_else(|| NoteFormat::default().to_string()),
+            key_check: self.key_check,
         }
     }
 }
@@ -69,11 +141,14 @@ mod tests {
     #[test]
     fn test_note_in_into() {
         use super::*;
-        use std::time::SystemTime;
+        use crate::utils::now;
         let note_in = NoteIn {
             category_id: Some(321),
             title: "some_name".to_string(),
             data: "some_data".to_string(),
+            tags: Some(vec!["some_tag".to_string()]),
+            format: None,
+            key_check: None,
         };
 
         let result: Note = NoteIn::into(note_in);
@@ -84,8 +159,11 @@ mod tests {
             category_id: Some(321),
             title: "some_name".to_string(),
             data: "some_data".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         };
         expected.create_time = result.create_time;
         expected.create_time = result.create_time;
@@ -96,10 +174,10 @@ mod tests {
     #[test]
     fn test_note_out_from() {
         use super::*;
-        use std::time::SystemTime;
+        use crate::utils::now;
 
-        let time = SystemTime::now();
-        let str_time = DateTime::<Utc>::from(time).to_rfc3339();
+        let time = now();
+        let str_time = DateTime::<Utc>::from_utc(time, Utc).to_rfc3339();
         let note = Note {
             id: 12345,
             user_id: 0,
@@ -108,19 +186,58 @@ mod tests {
             data: "some_data".to_string(),
             create_time: time,
             update_time: time,
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         };
 
-        let result = NoteOut::from(&note);
+        let result = NoteOut::from(&note).with_tags(vec!["some_tag".to_string()]);
 
         let expected = NoteOut {
             id: 12345,
             category_id: Some(321),
             title: "some_name".to_string(),
             data: "some_data".to_string(),
+            tags: vec!["some_tag".to_string()],
             create_time: str_time.clone(),
             update_time: str_time.clone(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         };
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_note_search_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let note = Note {
+            id: 12345,
+            user_id: 0,
+            category_id: None,
+            title: "some_name".to_string(),
+            data: "some_data".to_string(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
+        };
+        let hit = NoteSearchHit {
+            note: note.clone(),
+            rank: 0.5,
+            title: "<mark>some_name</mark>".to_string(),
+            snippet: "some_data".to_string(),
+        };
+
+        let result = NoteSearchOut::from(&hit);
+
+        assert_eq!(result.note, NoteOut::from(&note));
+        assert_eq!(result.rank, 0.5);
+        assert_eq!(result.title, "<mark>some_name</mark>");
+        assert_eq!(result.snippet, "some_data");
+    }
 }
@@ -0,0 +1,127 @@
+use crate::models::db::note::Note;
+use crate::models::db::note_link::NoteLink;
+use std::convert::From;
+
+/// Wiki link written in a note. `title` is the one of the target note, or the
+/// link as written if it is broken, given by `written_title` since the
+/// links only store the key of the titles.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteLinkOut {
+    pub target_id: Option<i32>,
+    pub title: String,
+    pub broken: bool,
+}
+
+impl NoteLinkOut {
+    pub fn new(link: &NoteLink, target: Option<&Note>, written_title: Option<&str>) -> Self {
+        match target {
+            Some(note) => NoteLinkOut {
+                target_id: Some(note.id),
+                title: note.title.clone(),
+                broken: false,
+            },
+            None => NoteLinkOut {
+                target_id: link.target_id,
+                title: match (written_title, link.target_id) {
+                    (Some(title), _) => title.to_string(),
+                    (None, Some(id)) => format!("#{}", id),
+                    (None, None) => String::new(),
+                },
+                broken: true,
+            },
+        }
+    }
+}
+
+/// Note with a wiki link to another one.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BacklinkOut {
+    pub note_id: i32,
+    pub title: String,
+}
+
+impl From<&Note> for BacklinkOut {
+    fn from(note: &Note) -> Self {
+        BacklinkOut {
+            note_id: note.id,
+            title: note.title.clone(),
+        }
+    }
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn test_note() -> crate::models::db::note::Note {
+        let time = crate::utils::now();
+        crate::models::db::note::Note {
+            id: 12,
+            user_id: 1,
+            category_id: None,
+            title: "some_title".to_string(),
+            data: "some_data".to_string(),
+            create_time: time,
+            update_time: time,
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
+        }
+    }
+
+    #[test]
+    fn test_note_link_out_new() {
+        use super::*;
+
+        let note = test_note();
+        let by_id = NoteLink {
+            id: 1,
+            note_id: 2,
+            target_id: Some(12),
+            target_title: None,
+        };
+        let by_title = NoteLink {
+            id: 2,
+            note_id: 2,
+            target_id: None,
+            target_title: Some("some_title".to_string()),
+        };
+        let written = Some("Some_Title");
+
+        let expected = NoteLinkOut {
+            target_id: Some(12),
+            title: "some_title".to_string(),
+            broken: false,
+        };
+        assert_eq!(NoteLinkOut::new(&by_id, Some(&note), None), expected);
+        assert_eq!(NoteLinkOut::new(&by_title, Some(&note), written), expected);
+
+        assert_eq!(
+            NoteLinkOut::new(&by_id, None, None),
+            NoteLinkOut {
+                target_id: Some(12),
+                title: "#12".to_string(),
+                broken: true,
+            }
+        );
+        assert_eq!(
+            NoteLinkOut::new(&by_title, None, written),
+            NoteLinkOut {
+                target_id: None,
+                title: "Some_Title".to_string(),
+                broken: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_backlink_out_from() {
+        use super::*;
+
+        assert_eq!(
+            BacklinkOut::from(&test_note()),
+            BacklinkOut {
+                note_id: 12,
+                title: "some_title".to_string(),
+            }
+        );
+    }
+}
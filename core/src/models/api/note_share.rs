@@ -0,0 +1,137 @@
+use crate::models::api::note::NoteOut;
+use crate::models::db::note::Note;
+use crate::models::db::note_share::NoteShare;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::convert::From;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteShareOut {
+    pub note_id: i32,
+    pub user_id: i32,
+    /// Email of the user the note is shared with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub permission: String,
+    pub create_time: String,
+}
+
+impl NoteShareOut {
+    pub fn with_email(mut self, email: String) -> Self {
+        self.email = Some(email);
+        self
+    }
+}
+
+impl PartialEq for NoteShareOut {
+    fn eq(&self, other: &Self) -> bool {
+        self.note_id == other.note_id
+            && self.user_id == other.user_id
+            && self.email == other.email
+            && self.permission == other.permission
+    }
+}
+
+impl From<&NoteShare> for NoteShareOut {
+    fn from(note_share: &NoteShare) -> Self {
+        NoteShareOut {
+            note_id: note_share.note_id,
+            user_id: note_share.user_id,
+            email: None,
+            permission: note_share.permission.clone(),
+            create_time: DateTime::<Utc>::from_utc(note_share.create_time, Utc).to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteShareIn {
+    /// Email of the user to share the note with.
+    pub email: String,
+    /// `read` or `write`, `read` if missing.
+    #[serde(default)]
+    pub permission: Option<String>,
+}
+
+/// Note shared with the user by its owner.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SharedNoteOut {
+    pub note: NoteOut,
+    pub owner_id: i32,
+    pub permission: String,
+}
+
+impl From<(&Note, &NoteShare)> for SharedNoteOut {
+    fn from((note, note_share): (&Note, &NoteShare)) -> Self {
+        SharedNoteOut {
+            note: NoteOut::from(note),
+            owner_id: note.user_id,
+            permission: note_share.permission.clone(),
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_note_share_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let note_share = NoteShare {
+            id: 1,
+            note_id: 12345,
+            user_id: 2,
+            permission: "write".to_string(),
+            create_time: time,
+            update_time: time,
+        };
+
+        let result = NoteShareOut::from(&note_share).with_email("user@email.com".to_string());
+
+        let expected = NoteShareOut {
+            note_id: 12345,
+            user_id: 2,
+            email: Some("user@email.com".to_string()),
+            permission: "write".to_string(),
+            create_time: DateTime::<Utc>::from_utc(time, Utc).to_rfc3339(),
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_shared_note_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let note = Note {
+            id: 12345,
+            user_id: 1,
+            category_id: None,
+            title: "some_title".to_string(),
+            data: "some_data".to_string(),
+            create_time: time,
+            update_time: time,
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
+        };
+        let note_share = NoteShare {
+            id: 1,
+            note_id: 12345,
+            user_id: 2,
+            permission: "read".to_string(),
+            create_time: time,
+            update_time: time,
+        };
+
+        let result = SharedNoteOut::from((&note, &note_share));
+
+        assert_eq!(result.note, NoteOut::from(&note));
+        assert_eq!(result.owner_id, 1);
+        assert_eq!(result.permission, "read");
+    }
+}
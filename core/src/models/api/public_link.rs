@@ -0,0 +1,119 @@
+use crate::models::db::note::Note;
+use crate::models::db::public_link::PublicLink;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::convert::From;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicLinkOut {
+    pub id: i32,
+    pub note_id: i32,
+    pub has_password: bool,
+    pub expire_time: Option<String>,
+    pub create_time: String,
+    /// Token of the link, only returned when it is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl PublicLinkOut {
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+}
+
+impl PartialEq for PublicLinkOut {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.note_id == other.note_id
+            && self.has_password == other.has_password
+            && self.token == other.token
+    }
+}
+
+impl From<&PublicLink> for PublicLinkOut {
+    fn from(public_link: &PublicLink) -> Self {
+        PublicLinkOut {
+            id: public_link.id,
+            note_id: public_link.note_id,
+            has_password: public_link.password_hash.is_some(),
+            expire_time: public_link
+                .expire_time
+                .map(|time| DateTime::<Utc>::from_utc(time, Utc).to_rfc3339()),
+            create_time: DateTime::<Utc>::from_utc(public_link.create_time, Utc).to_rfc3339(),
+            token: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicLinkIn {
+    /// Password asked to open the link [Optional].
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Hours until the link expires, up to 87600, it never expires if missing.
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>,
+}
+
+/// Note opened with a public link, without anything about its owner.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicNoteOut {
+    pub title: String,
+    pub data: String,
+    pub format: String,
+    pub update_time: String,
+    /// Set when the note is encrypted, the passphrase is needed to read it.
+    #[serde(default)]
+    pub key_check: Option<String>,
+}
+
+impl From<&Note> for PublicNoteOut {
+    fn from(note: &Note) -> Self {
+        PublicNoteOut {
+            title: note.title.clone(),
+            data: note.data.clone(),
+            format: note.format.clone(),
+            update_time: DateTime::<Utc>::from_utc(note.update_time, Utc).to_rfc3339(),
+            key_check: note.key_check.clone(),
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_public_link_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let str_time = DateTime::<Utc>::from_utc(time, Utc).to_rfc3339();
+        let public_link = PublicLink {
+            id: 1,
+            note_id: 12345,
+            token_hash: "some_hash".to_string(),
+            password_hash: Some("some_password_hash".to_string()),
+            expire_time: Some(time),
+            create_time: time,
+        };
+
+        let result = PublicLinkOut::from(&public_link);
+
+        let expected = PublicLinkOut {
+            id: 1,
+            note_id: 12345,
+            has_password: true,
+            expire_time: Some(str_time.clone()),
+            create_time: str_time.clone(),
+            token: None,
+        };
+
+        assert_eq!(result, expected);
+        assert_eq!(
+            result.with_token("some_token".to_string()).token,
+            Some("some_token".to_string())
+        );
+    }
+}
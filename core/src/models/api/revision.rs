@@ -0,0 +1,83 @@
+use crate::models::db::revision::NoteRevision;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::convert::From;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevisionOut {
+    pub revision: i32,
+    pub category_id: Option<i32>,
+    pub title: String,
+    pub data: String,
+    pub create_time: String,
+    #[serde(default)]
+    pub key_check: Option<String>,
+}
+
+impl PartialEq for RevisionOut {
+    fn eq(&self, other: &Self) -> bool {
+        self.revision == other.revision
+            && self.category_id == other.category_id
+            && self.title == other.title
+            && self.data == other.data
+            && self.key_check == other.key_check
+    }
+}
+
+impl From<&NoteRevision> for RevisionOut {
+    fn from(revision: &NoteRevision) -> Self {
+        RevisionOut {
+            revision: revision.revision,
+            category_id: revision.category_id,
+            title: revision.title.clone(),
+            data: revision.data.clone(),
+            create_time: DateTime::<Utc>::from_utc(revision.create_time, Utc).to_rfc3339(),
+            key_check: revision.key_check.clone(),
+        }
+    }
+}
+
+/// Line by line diff between two revisions of a note. `to` is `None` when the
+/// revision is compared with the current content of the note.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RevisionDiffOut {
+    pub from: i32,
+    pub to: Option<i32>,
+    pub title: String,
+    pub data: String,
+}
+
+mod tests {
+
+    #[test]
+    fn test_revision_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let str_time = DateTime::<Utc>::from_utc(time, Utc).to_rfc3339();
+        let revision = NoteRevision {
+            id: 12345,
+            note_id: 1,
+            revision: 2,
+            category_id: Some(321),
+            title: "some_name".to_string(),
+            data: "some_data".to_string(),
+            create_time: time,
+            key_check: Some("some_key_check".to_string()),
+        };
+
+        let result = RevisionOut::from(&revision);
+
+        let expected = RevisionOut {
+            revision: 2,
+            category_id: Some(321),
+            title: "some_name".to_string(),
+            data: "some_data".to_string(),
+            create_time: str_time.clone(),
+            key_check: Some("some_key_check".to_string()),
+        };
+
+        assert_eq!(result, expected);
+    }
+}
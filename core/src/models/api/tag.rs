@@ -0,0 +1,42 @@
+use crate::models::db::tag::Tag;
+use std::convert::From;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TagOut {
+    pub name: String,
+    pub count: i64,
+}
+
+impl From<&(Tag, i64)> for TagOut {
+    fn from(tag: &(Tag, i64)) -> Self {
+        TagOut {
+            name: tag.0.name.clone(),
+            count: tag.1,
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_tag_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let tag = Tag {
+            id: 12345,
+            user_id: 1,
+            name: "some_name".to_string(),
+            create_time: now(),
+        };
+
+        let result = TagOut::from(&(tag, 3));
+
+        let expected = TagOut {
+            name: "some_name".to_string(),
+            count: 3,
+        };
+
+        assert_eq!(result, expected);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::models::db::user::{Role, User};
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::convert::From;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserOut {
+    pub id: i32,
+    pub email: String,
+    pub name: String,
+    pub role: String,
+    pub disabled: bool,
+    pub create_time: String,
+}
+
+impl PartialEq for UserOut {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.email == other.email
+            && self.name == other.name
+            && self.role == other.role
+            && self.disabled == other.disabled
+    }
+}
+
+impl From<&User> for UserOut {
+    fn from(user: &User) -> Self {
+        UserOut {
+            id: user.id,
+            email: user.email.clone(),
+            name: user.name.clone(),
+            role: user.role().to_string(),
+            disabled: user.disabled,
+            create_time: DateTime::<Utc>::from_utc(user.create_time, Utc).to_rfc3339(),
+        }
+    }
+}
+
+/// Changes of a user made by an admin, missing fields are left unchanged.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserAdminIn {
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub disabled: Option<bool>,
+}
+
+impl UserAdminIn {
+    /// Applies the changes to the user, failing if the role is unknown.
+    pub fn apply(&self, user: &mut User) -> Result<(), String> {
+        if let Some(role) = &self.role {
+            let role = Role::parse(role).ok_or_else(|| format!("Role {} is not valid", role))?;
+            user.role = role.to_string();
+        }
+        if let Some(disabled) = self.disabled {
+            user.disabled = disabled;
+        }
+
+        Ok(())
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_user_out_from() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let user = User {
+            id: 12345,
+            email: "user@email.com".to_string(),
+            name: "user".to_string(),
+            password: "some_hash".to_string(),
+            create_time: time,
+            update_time: time,
+            role: "admin".to_string(),
+            disabled: false,
+        };
+
+        let result = UserOut::from(&user);
+
+        let expected = UserOut {
+            id: 12345,
+            email: "user@email.com".to_string(),
+            name: "user".to_string(),
+            role: "admin".to_string(),
+            disabled: false,
+            create_time: DateTime::<Utc>::from_utc(time, Utc).to_rfc3339(),
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_user_admin_in_apply() {
+        use super::*;
+        use crate::utils::now;
+
+        let time = now();
+        let mut user = User {
+            id: 12345,
+            email: "user@email.com".to_string(),
+            name: "user".to_string(),
+            password: "some_hash".to_string(),
+            create_time: time,
+            update_time: time,
+            role: "user".to_string(),
+            disabled: false,
+        };
+
+        let user_admin_in = UserAdminIn {
+            role: Some("admin".to_string()),
+            disabled: None,
+        };
+        assert!(user_admin_in.apply(&mut user).is_ok());
+        assert_eq!(user.role(), Role::Admin);
+        assert!(!user.disabled);
+
+        let user_admin_in = UserAdminIn {
+            role: None,
+            disabled: Some(true),
+        };
+        assert!(user_admin_in.apply(&mut user).is_ok());
+        assert_eq!(user.role(), Role::Admin);
+        assert!(user.disabled);
+
+        let user_admin_in = UserAdminIn {
+            role: Some("root".to_string()),
+            disabled: Some(false),
+        };
+        assert!(user_admin_in.apply(&mut user).is_err());
+    }
+}
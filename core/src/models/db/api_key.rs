@@ -0,0 +1,127 @@
+use crate::schema::*;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+use std::cmp::Ordering;
+
+/// Personal key of a user for scripts, accepted instead of an access token.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    /// First characters of the key, to tell the keys apart.
+    pub prefix: String,
+    pub key_hash: String,
+    /// Read-only keys can only be used in `GET` requests.
+    pub read_only: bool,
+    pub expire_time: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+}
+
+impl PartialEq for ApiKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.user_id == other.user_id
+            && self.name == other.name
+            && self.prefix == other.prefix
+            && self.key_hash == other.key_hash
+            && self.read_only == other.read_only
+            && self.expire_time == other.expire_time
+    }
+}
+
+impl Ord for ApiKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for ApiKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ApiKey {
+    /// Whether the key has not expired at the given time.
+    pub fn is_active(&self, time: NaiveDateTime) -> bool {
+        self.expire_time
+            .map_or(true, |expire_time| expire_time > time)
+    }
+
+    pub fn find_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<Vec<ApiKey>> {
+        use crate::schema::api_keys::dsl::*;
+        api_keys
+            .filter(user_id.eq(_user_id))
+            .order(id.asc())
+            .load::<ApiKey>(connection)
+    }
+
+    pub fn find_by_key_hash(connection: &DBConnection, _key_hash: &str) -> QueryResult<ApiKey> {
+        use crate::schema::api_keys::dsl::*;
+        api_keys
+            .filter(key_hash.eq(_key_hash))
+            .get_result::<ApiKey>(connection)
+    }
+
+    pub fn delete_by_id_and_user_id(
+        connection: &DBConnection,
+        _id: i32,
+        _user_id: i32,
+    ) -> QueryResult<usize> {
+        use crate::schema::api_keys::dsl::*;
+        diesel::delete(api_keys.filter(id.eq(_id)).filter(user_id.eq(_user_id))).execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "api_keys"]
+pub struct NewApiKey<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+    pub prefix: &'a str,
+    pub key_hash: &'a str,
+    pub read_only: bool,
+    pub expire_time: Option<NaiveDateTime>,
+}
+
+impl<'a> NewApiKey<'a> {
+    pub fn new(user_id: i32, name: &'a str, prefix: &'a str, key_hash: &'a str) -> Self {
+        NewApiKey {
+            user_id: user_id,
+            name: name,
+            prefix: prefix,
+            key_hash: key_hash,
+            read_only: false,
+            expire_time: None,
+        }
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_expire_time(mut self, expire_time: Option<NaiveDateTime>) -> Self {
+        self.expire_time = expire_time;
+        self
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<ApiKey> {
+        use crate::schema::api_keys::dsl::*;
+
+        connection.transaction(|| {
+            diesel::insert_into(api_keys)
+                .values(self)
+                .execute(connection)?;
+
+            api_keys
+                .find(last_insert_id(connection)?)
+                .get_result::<ApiKey>(connection)
+        })
+    }
+}
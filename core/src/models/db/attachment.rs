@@ -0,0 +1,153 @@
+use crate::schema::*;
+use crate::{last_insert_id, DBConnection};
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use diesel::dsl::sum;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+use num_traits::ToPrimitive;
+
+use std::cmp::Ordering;
+
+/// File attached to a note, its content is kept by the storage under
+/// `storage_key`.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct Attachment {
+    pub id: i32,
+    /// `None` once the note is deleted, until the content is removed from the
+    /// storage.
+    pub note_id: Option<i32>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub storage_key: String,
+    pub create_time: NaiveDateTime,
+}
+
+impl PartialEq for Attachment {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.note_id == other.note_id
+            && self.file_name == other.file_name
+            && self.content_type == other.content_type
+            && self.size == other.size
+            && self.storage_key == other.storage_key
+    }
+}
+
+impl Ord for Attachment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for Attachment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Attachment {
+    pub fn find_by_note_id(
+        connection: &DBConnection,
+        _note_id: i32,
+    ) -> QueryResult<Vec<Attachment>> {
+        use crate::schema::attachments::dsl::*;
+        attachments
+            .filter(note_id.eq(_note_id))
+            .order(id.asc())
+            .load::<Attachment>(connection)
+    }
+
+    pub fn find_by_id_and_note_id(
+        connection: &DBConnection,
+        _id: i32,
+        _note_id: i32,
+    ) -> QueryResult<Attachment> {
+        use crate::schema::attachments::dsl::*;
+        attachments
+            .filter(id.eq(_id))
+            .filter(note_id.eq(_note_id))
+            .get_result::<Attachment>(connection)
+    }
+
+    /// Bytes attached to the notes of the user, including the ones in the
+    /// trash. The attachments of the deleted notes no longer count, even if
+    /// their files are only removed from the storage by the next purge.
+    pub fn total_size_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<i64> {
+        attachments::table
+            .inner_join(notes::table.on(attachments::note_id.eq(notes::id.nullable())))
+            .filter(notes::user_id.eq(_user_id))
+            .select(sum(attachments::size))
+            .first::<Option<BigDecimal>>(connection)
+            .map(|total| total.and_then(|total| total.to_i64()).unwrap_or(0))
+    }
+
+    /// Attachments of the deleted notes, the oldest first.
+    pub fn find_orphans(connection: &DBConnection, limit: i64) -> QueryResult<Vec<Attachment>> {
+        use crate::schema::attachments::dsl::*;
+        attachments
+            .filter(note_id.is_null())
+            .order(id.asc())
+            .limit(limit)
+            .load::<Attachment>(connection)
+    }
+
+    pub fn delete_by_id_and_note_id(
+        connection: &DBConnection,
+        _id: i32,
+        _note_id: i32,
+    ) -> QueryResult<usize> {
+        use crate::schema::attachments::dsl::*;
+        diesel::delete(attachments.filter(id.eq(_id)).filter(note_id.eq(_note_id)))
+            .execute(connection)
+    }
+
+    pub fn delete_orphan(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
+        use crate::schema::attachments::dsl::*;
+        diesel::delete(attachments.filter(id.eq(_id)).filter(note_id.is_null())).execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "attachments"]
+pub struct NewAttachment<'a> {
+    pub note_id: Option<i32>,
+    pub file_name: &'a str,
+    pub content_type: &'a str,
+    pub size: i64,
+    pub storage_key: &'a str,
+}
+
+impl<'a> NewAttachment<'a> {
+    pub fn new(
+        note_id: i32,
+        file_name: &'a str,
+        content_type: &'a str,
+        size: i64,
+        storage_key: &'a str,
+    ) -> Self {
+        NewAttachment {
+            note_id: Some(note_id),
+            file_name: file_name,
+            content_type: content_type,
+            size: size,
+            storage_key: storage_key,
+        }
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<Attachment> {
+        use crate::schema::attachments::dsl::*;
+
+        connection.transaction(|| {
+            diesel::insert_into(attachments)
+                .values(self)
+                .execute(connection)?;
+
+            attachments
+                .find(last_insert_id(connection)?)
+                .get_result::<Attachment>(connection)
+        })
+    }
+}
@@ -1,21 +1,55 @@
 use crate::schema::*;
+use crate::utils::now;
+use crate::{last_insert_id, DBConnection};
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::result::QueryResult;
+use diesel::sql_query;
+use diesel::sql_types::Integer;
 use std::cmp::Ordering;
-use std::time::SystemTime;
+use std::collections::BTreeSet;
 
-#[derive(Debug, Eq, Queryable, AsChangeset)]
+#[derive(QueryableByName)]
+struct IdRow {
+    #[sql_type = "Integer"]
+    id: i32,
+}
+
+#[cfg(not(feature = "sqlite"))]
+const DESCENDANTS_QUERY: &str = "WITH RECURSIVE descendants(id) AS (
+    SELECT id FROM categories WHERE id = $1
+    UNION
+    SELECT c.id FROM categories c JOIN descendants d ON c.parent_id = d.id
+  )
+  SELECT id FROM descendants";
+
+#[cfg(feature = "sqlite")]
+const DESCENDANTS_QUERY: &str = "WITH RECURSIVE descendants(id) AS (
+    SELECT id FROM categories WHERE id = ?
+    UNION
+    SELECT c.id FROM categories c JOIN descendants d ON c.parent_id = d.id
+  )
+  SELECT id FROM descendants";
+
+#[derive(Debug, Clone, Eq, Queryable, AsChangeset)]
 #[table_name = "categories"]
 pub struct Category {
     pub id: i32,
+    /// Owner of the category, `None` if it is shared by every user.
+    pub user_id: Option<i32>,
+    /// Parent of the category in the tree, `None` for the roots.
+    pub parent_id: Option<i32>,
     pub name: String,
-    pub create_time: SystemTime,
-    pub update_time: SystemTime,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
 }
 
 impl PartialEq for Category {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.name == other.name
+        self.id == other.id
+            && self.user_id == other.user_id
+            && self.parent_id == other.parent_id
+            && self.name == other.name
     }
 }
 
@@ -32,45 +66,168 @@ impl PartialOrd for Category {
 }
 
 impl Category {
-    pub fn find_all(connection: &PgConnection) -> QueryResult<Vec<Category>> {
+    /// Whether the user can see the category.
+    pub fn is_visible_to(&self, user_id: i32) -> bool {
+        self.user_id.map_or(true, |owner| owner == user_id)
+    }
+
+    /// Whether making `parent_id` the parent of the category `id` would make
+    /// the category its own ancestor.
+    pub fn creates_cycle(categories: &[Category], id: i32, parent_id: Option<i32>) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut current = parent_id;
+
+        while let Some(ancestor_id) = current {
+            if ancestor_id == id || !visited.insert(ancestor_id) {
+                return true;
+            }
+            current = categories
+                .iter()
+                .find(|category| category.id == ancestor_id)
+                .and_then(|category| category.parent_id);
+        }
+
+        false
+    }
+
+    /// Ids of the category and all its descendants among the given ones.
+    pub fn descendant_ids(categories: &[Category], id: i32) -> Vec<i32> {
+        let mut ids = vec![id];
+        let mut index = 0;
+        while index < ids.len() {
+            let parent_id = ids[index];
+            for category in categories.iter() {
+                if category.parent_id == Some(parent_id) && !ids.contains(&category.id) {
+                    ids.push(category.id);
+                }
+            }
+            index += 1;
+        }
+        ids.sort();
+
+        ids
+    }
+
+    pub fn find_all(connection: &DBConnection) -> QueryResult<Vec<Category>> {
         use crate::schema::categories::dsl::*;
         categories.get_results::<Category>(connection)
     }
 
-    pub fn find_by_id(connection: &PgConnection, _id: i32) -> QueryResult<Category> {
+    pub fn find_by_id(connection: &DBConnection, _id: i32) -> QueryResult<Category> {
         use crate::schema::categories::dsl::*;
         categories.find(_id).get_result::<Category>(connection)
     }
 
-    pub fn update(connection: &PgConnection, obj: &Category) -> QueryResult<Category> {
+    pub fn find_shared(connection: &DBConnection) -> QueryResult<Vec<Category>> {
+        use crate::schema::categories::dsl::*;
+        categories
+            .filter(user_id.is_null())
+            .order(id.asc())
+            .load::<Category>(connection)
+    }
+
+    /// Categories of the user along with the shared ones.
+    pub fn find_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<Vec<Category>> {
         use crate::schema::categories::dsl::*;
-        diesel::update(categories.find(obj.id))
-            .set((name.eq(obj.name.clone()), update_time.eq(SystemTime::now())))
-            .get_result(connection)
+        categories
+            .filter(user_id.eq(_user_id).or(user_id.is_null()))
+            .order(id.asc())
+            .load::<Category>(connection)
     }
 
-    pub fn delete(connection: &PgConnection, _id: i32) -> QueryResult<usize> {
+    /// The category if it belongs to the user or it is shared.
+    pub fn find_by_id_and_user_id(
+        connection: &DBConnection,
+        _id: i32,
+        _user_id: i32,
+    ) -> QueryResult<Category> {
         use crate::schema::categories::dsl::*;
-        diesel::delete(categories.find(_id)).execute(connection)
+        categories
+            .filter(id.eq(_id))
+            .filter(user_id.eq(_user_id).or(user_id.is_null()))
+            .first::<Category>(connection)
+    }
+
+    /// Ids of the category and all its descendants.
+    pub fn find_descendant_ids(connection: &DBConnection, _id: i32) -> QueryResult<Vec<i32>> {
+        let mut ids: Vec<i32> = sql_query(DESCENDANTS_QUERY)
+            .bind::<Integer, _>(_id)
+            .load::<IdRow>(connection)?
+            .into_iter()
+            .map(|row| row.id)
+            .collect();
+        ids.sort();
+
+        Ok(ids)
+    }
+
+    pub fn update(connection: &DBConnection, obj: &Category) -> QueryResult<Category> {
+        use crate::schema::categories::dsl::*;
+
+        connection.transaction(|| {
+            diesel::update(categories.find(obj.id))
+                .set((
+                    parent_id.eq(obj.parent_id),
+                    name.eq(obj.name.clone()),
+                    update_time.eq(now()),
+                ))
+                .execute(connection)?;
+
+            categories.find(obj.id).get_result::<Category>(connection)
+        })
+    }
+
+    /// Deletes the category. Its notes, and their revisions, are left without
+    /// category and its children become roots.
+    pub fn delete(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
+        use crate::schema::categories::dsl::*;
+
+        connection.transaction(|| {
+            diesel::update(notes::table.filter(notes::category_id.eq(_id)))
+                .set(notes::category_id.eq(None::<i32>))
+                .execute(connection)?;
+            diesel::update(note_revisions::table.filter(note_revisions::category_id.eq(_id)))
+                .set(note_revisions::category_id.eq(None::<i32>))
+                .execute(connection)?;
+
+            diesel::delete(categories.find(_id)).execute(connection)
+        })
     }
 }
 
 #[derive(Debug, Copy, Clone, Insertable)]
 #[table_name = "categories"]
 pub struct NewCategory<'a> {
+    pub user_id: Option<i32>,
+    pub parent_id: Option<i32>,
     pub name: &'a str,
 }
 
 impl<'a> NewCategory<'a> {
-    pub fn new(name: &'a str) -> Self {
-        NewCategory { name: name }
+    pub fn new(user_id: Option<i32>, name: &'a str) -> Self {
+        NewCategory {
+            user_id: user_id,
+            parent_id: None,
+            name: name,
+        }
+    }
+
+    pub fn with_parent(mut self, parent_id: Option<i32>) -> Self {
+        self.parent_id = parent_id;
+        self
     }
 
-    pub fn create(&self, connection: &PgConnection) -> QueryResult<Category> {
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<Category> {
         use crate::schema::categories::dsl::*;
 
-        diesel::insert_into(categories)
-            .values(self)
-            .get_result(connection)
+        connection.transaction(|| {
+            diesel::insert_into(categories)
+                .values(self)
+                .execute(connection)?;
+
+            categories
+                .find(last_insert_id(connection)?)
+                .get_result::<Category>(connection)
+        })
     }
 }
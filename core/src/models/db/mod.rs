@@ -1,3 +1,13 @@
+pub mod api_key;
+pub mod attachment;
 pub mod category;
 pub mod note;
+pub mod note_link;
+pub mod note_share;
+pub mod public_link;
+pub mod revision;
+pub mod session;
+pub mod tag;
+pub mod two_factor;
 pub mod user;
+pub mod user_identity;
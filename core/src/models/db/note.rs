@@ -1,12 +1,68 @@
+use crate::encryption::{self, Envelope, Keyring, RowId};
+use crate::models::db::category::Category;
+use crate::models::db::revision::NoteRevision;
+use crate::models::db::tag::Tag;
 use crate::schema::*;
+use crate::search;
+use crate::utils::now;
+use crate::{last_insert_id, DBConnection};
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::result::QueryResult;
+#[cfg(not(feature = "sqlite"))]
+use diesel::sql_query;
+#[cfg(not(feature = "sqlite"))]
+use diesel::sql_types::{BigInt, Float, Integer, Text};
+
+use rustc_serialize::base64::{FromBase64, ToBase64, URL_SAFE};
 
 use std::cmp::Ordering;
-use std::time::SystemTime;
+use std::fmt;
+use std::str::FromStr;
+
+const CURSOR_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+/// Rows decrypted at once to search or sort the encrypted notes.
+const DECRYPT_BATCH_SIZE: usize = 500;
+
+/// Format of the data of a note, stored in `notes.format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteFormat {
+    Plain,
+    /// CommonMark with the GitHub tables and task lists.
+    Markdown,
+}
+
+impl NoteFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteFormat::Plain => "plain",
+            NoteFormat::Markdown => "markdown",
+        }
+    }
+
+    pub fn parse(format: &str) -> Option<NoteFormat> {
+        match format {
+            "plain" => Some(NoteFormat::Plain),
+            "markdown" => Some(NoteFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+impl Default for NoteFormat {
+    fn default() -> Self {
+        NoteFormat::Plain
+    }
+}
 
-#[derive(Debug, Eq, Queryable, AsChangeset)]
+impl fmt::Display for NoteFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Eq, AsChangeset)]
 #[table_name = "notes"]
 pub struct Note {
     pub id: i32,
@@ -14,8 +70,14 @@ pub struct Note {
     pub category_id: Option<i32>,
     pub title: String,
     pub data: String,
-    pub create_time: SystemTime,
-    pub update_time: SystemTime,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+    /// Time the note was moved to the trash, `None` if it was not.
+    pub deleted_time: Option<NaiveDateTime>,
+    pub format: String,
+    /// Key-check value of a note encrypted by the client, whose title and data
+    /// are then ciphertext. `None` for plain notes.
+    pub key_check: Option<String>,
 }
 
 impl PartialEq for Note {
@@ -27,6 +89,130 @@ impl PartialEq for Note {
     }
 }
 
+impl Note {
+    /// Format of the data, `Plain` if the stored value is not known.
+    pub fn format(&self) -> NoteFormat {
+        NoteFormat::parse(&self.format).unwrap_or_default()
+    }
+
+    /// Whether the title and data are encrypted by the client, the server
+    /// cannot read them.
+    pub fn is_encrypted(&self) -> bool {
+        self.key_check.is_some()
+    }
+}
+
+/// Row of `notes` as it is stored, its title and data may be encrypted at rest.
+#[derive(Debug, Clone, Queryable)]
+pub struct NoteRow {
+    pub id: i32,
+    pub user_id: i32,
+    pub category_id: Option<i32>,
+    pub title: String,
+    pub data: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+    pub deleted_time: Option<NaiveDateTime>,
+    pub format: String,
+    pub key_check: Option<String>,
+    pub data_key: Option<String>,
+    pub key_id: Option<String>,
+}
+
+/// Table of the rows of the notes in the encryption context.
+const TABLE: &str = "notes";
+
+impl NoteRow {
+    fn envelope(&self) -> Envelope {
+        Envelope {
+            title: self.title.clone(),
+            data: self.data.clone(),
+            data_key: self.data_key.clone(),
+            key_id: self.key_id.clone(),
+        }
+    }
+
+    /// The note with its title and data decrypted.
+    pub fn decrypt(self) -> QueryResult<Note> {
+        let (title, data) = encryption::decrypt(RowId::new(TABLE, self.id), &self.envelope())?;
+
+        Ok(Note {
+            id: self.id,
+            user_id: self.user_id,
+            category_id: self.category_id,
+            title: title,
+            data: data,
+            create_time: self.create_time,
+            update_time: self.update_time,
+            deleted_time: self.deleted_time,
+            format: self.format,
+            key_check: self.key_check,
+        })
+    }
+
+    pub fn decrypt_all(rows: Vec<NoteRow>) -> QueryResult<Vec<Note>> {
+        rows.into_iter().map(NoteRow::decrypt).collect()
+    }
+
+    /// Decrypts the notes of the given ids one batch at a time and hands every
+    /// batch to `keep`, so only what it keeps stays in memory.
+    fn decrypt_in_batches<F>(connection: &DBConnection, ids: &[i32], mut keep: F) -> QueryResult<()>
+    where
+        F: FnMut(Vec<Note>),
+    {
+        use crate::schema::notes::dsl::*;
+
+        for batch in ids.chunks(DECRYPT_BATCH_SIZE) {
+            let rows = notes
+                .filter(id.eq_any(batch.to_vec()))
+                .order(id.asc())
+                .load::<NoteRow>(connection)?;
+            keep(NoteRow::decrypt_all(rows)?);
+        }
+
+        Ok(())
+    }
+
+    /// Encrypts again with the current master key up to `batch_size` rows of
+    /// other master keys or in plain text, trashed or not. Returns the number
+    /// of rows, 0 once every row uses the current master key.
+    pub fn rotate_keys(
+        connection: &DBConnection,
+        keyring: &Keyring,
+        batch_size: i64,
+    ) -> QueryResult<usize> {
+        use crate::schema::notes::dsl::*;
+
+        connection.transaction(|| {
+            let rows = notes
+                .filter(key_id.is_null())
+                .or_filter(key_id.ne(keyring.current_id()))
+                .order(id.asc())
+                .limit(batch_size)
+                .load::<NoteRow>(connection)?;
+
+            for row in rows.iter() {
+                let row_id = RowId::new(TABLE, row.id);
+                let (plain_title, plain_data) = keyring
+                    .decrypt(row_id, &row.envelope())
+                    .map_err(|err| diesel::result::Error::DeserializationError(Box::new(err)))?;
+                let envelope = keyring.encrypt(row_id, &plain_title, &plain_data);
+
+                diesel::update(notes.find(row.id))
+                    .set((
+                        title.eq(envelope.title),
+                        data.eq(envelope.data),
+                        data_key.eq(envelope.data_key),
+                        key_id.eq(envelope.key_id),
+                    ))
+                    .execute(connection)?;
+            }
+
+            Ok(rows.len())
+        })
+    }
+}
+
 impl Ord for Note {
     fn cmp(&self, other: &Self) -> Ordering {
         self.id.cmp(&other.id)
@@ -39,24 +225,442 @@ impl PartialOrd for Note {
     }
 }
 
+/// Field used to sort a notes listing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteSort {
+    CreateTime,
+    UpdateTime,
+    Title,
+}
+
+impl NoteSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteSort::CreateTime => "create_time",
+            NoteSort::UpdateTime => "update_time",
+            NoteSort::Title => "title",
+        }
+    }
+}
+
+impl Default for NoteSort {
+    fn default() -> Self {
+        NoteSort::CreateTime
+    }
+}
+
+impl FromStr for NoteSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create_time" => Ok(NoteSort::CreateTime),
+            "update_time" => Ok(NoteSort::UpdateTime),
+            "title" => Ok(NoteSort::Title),
+            other => Err(format!("sort '{}' is not valid", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Time(NaiveDateTime),
+    Title(String),
+}
+
+/// Position after the last note of a page.
+///
+/// It is sent to the clients as an opaque string and keeps the sort of the
+/// listing, so a cursor cannot be used with a different one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteCursor {
+    pub sort: NoteSort,
+    pub descending: bool,
+    pub id: i32,
+    pub value: String,
+}
+
+impl NoteCursor {
+    fn new(filter: &NoteFilter, note: &Note) -> Self {
+        let value = match filter.sort {
+            NoteSort::CreateTime => note.create_time.format(CURSOR_TIME_FORMAT).to_string(),
+            NoteSort::UpdateTime => note.update_time.format(CURSOR_TIME_FORMAT).to_string(),
+            NoteSort::Title => note.title.clone(),
+        };
+
+        NoteCursor {
+            sort: filter.sort,
+            descending: filter.descending,
+            id: note.id,
+            value: value,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.sort.as_str(),
+            if self.descending { "desc" } else { "asc" },
+            self.id,
+            self.value
+        )
+        .as_bytes()
+        .to_base64(URL_SAFE)
+    }
+
+    pub fn decode(cursor: &str) -> Option<NoteCursor> {
+        let bytes = cursor.from_base64().ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let mut parts = raw.splitn(4, '|');
+
+        let sort = parts.next()?.parse::<NoteSort>().ok()?;
+        let descending = match parts.next()? {
+            "asc" => false,
+            "desc" => true,
+            _ => return None,
+        };
+        let id = parts.next()?.parse::<i32>().ok()?;
+        let value = parts.next()?.to_string();
+
+        let cursor = NoteCursor {
+            sort: sort,
+            descending: descending,
+            id: id,
+            value: value,
+        };
+        cursor.key().map(|_| cursor)
+    }
+
+    fn key(&self) -> Option<SortKey> {
+        match self.sort {
+            NoteSort::CreateTime | NoteSort::UpdateTime => {
+                NaiveDateTime::parse_from_str(&self.value, CURSOR_TIME_FORMAT)
+                    .ok()
+                    .map(SortKey::Time)
+            }
+            NoteSort::Title => Some(SortKey::Title(self.value.clone())),
+        }
+    }
+}
+
+/// Optional conditions of a notes listing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NoteFilter {
+    /// Notes must have all these tags.
+    pub tags: Vec<String>,
+    pub category_id: Option<i32>,
+    /// Also notes of the descendants of `category_id`.
+    pub descendants: bool,
+    /// Notes updated at or after this time.
+    pub updated_since: Option<NaiveDateTime>,
+    pub sort: NoteSort,
+    pub descending: bool,
+    /// Maximum number of notes of the page, all of them if `None`.
+    pub limit: Option<i64>,
+    /// Notes after this position.
+    pub cursor: Option<NoteCursor>,
+}
+
+impl NoteFilter {
+    fn key(&self, note: &Note) -> SortKey {
+        match self.sort {
+            NoteSort::CreateTime => SortKey::Time(note.create_time),
+            NoteSort::UpdateTime => SortKey::Time(note.update_time),
+            NoteSort::Title => SortKey::Title(note.title.clone()),
+        }
+    }
+
+    // Sort key and id of the cursor, ignoring the ones of other sorts.
+    fn position(&self) -> Option<(SortKey, i32)> {
+        self.cursor
+            .as_ref()
+            .filter(|cursor| cursor.sort == self.sort && cursor.descending == self.descending)
+            .and_then(|cursor| cursor.key().map(|key| (key, cursor.id)))
+    }
+
+    /// Order of two notes in the listing.
+    pub fn compare(&self, a: &Note, b: &Note) -> Ordering {
+        let ordering = (self.key(a), a.id).cmp(&(self.key(b), b.id));
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Whether the note passes every condition but the tags and, if
+    /// `descendants` is set, the category.
+    pub fn accepts(&self, note: &Note) -> bool {
+        if self.category_id.is_some() && !self.descendants && note.category_id != self.category_id {
+            return false;
+        }
+        if let Some(updated_since) = self.updated_since {
+            if note.update_time < updated_since {
+                return false;
+            }
+        }
+        match self.position() {
+            Some(position) => {
+                let ordering = (self.key(note), note.id).cmp(&position);
+                if self.descending {
+                    ordering == Ordering::Less
+                } else {
+                    ordering == Ordering::Greater
+                }
+            }
+            None => true,
+        }
+    }
+}
+
+/// A page of a notes listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotePage {
+    pub notes: Vec<Note>,
+    /// Cursor of the next page, if any.
+    pub next_cursor: Option<NoteCursor>,
+}
+
+impl NotePage {
+    /// Builds the page from the sorted notes, which may exceed the limit of the
+    /// filter to tell whether there is a next page.
+    pub fn new(mut notes: Vec<Note>, filter: &NoteFilter) -> Self {
+        let next_cursor = match filter.limit {
+            Some(limit) if notes.len() > limit.max(0) as usize => {
+                notes.truncate(limit.max(0) as usize);
+                notes.last().map(|note| NoteCursor::new(filter, note))
+            }
+            _ => None,
+        };
+
+        NotePage {
+            notes: notes,
+            next_cursor: next_cursor,
+        }
+    }
+}
+
+/// A note matching a full-text search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteSearchHit {
+    pub note: Note,
+    pub rank: f32,
+    /// Title with the matched terms highlighted.
+    pub title: String,
+    /// Fragment of the data with the matched terms highlighted.
+    pub snippet: String,
+}
+
+impl NoteSearchHit {
+    /// Searches the query in the given notes, for backends without native
+    /// full-text search.
+    pub fn search_in(notes: Vec<Note>, query: &str, limit: i64) -> Vec<NoteSearchHit> {
+        let terms = search::terms(query);
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let mut hits: Vec<NoteSearchHit> = notes
+            .into_iter()
+            .filter(|note| !note.is_encrypted())
+            .filter(|note| search::contains_all(&format!("{} {}", note.title, note.data), &terms))
+            .map(|note| NoteSearchHit {
+                rank: search::rank(&note.title, &note.data, &terms),
+                title: search::highlight(&note.title, &terms),
+                snippet: search::snippet(&note.data, &terms),
+                note: note,
+            })
+            .collect();
+        NoteSearchHit::keep_best(&mut hits, limit);
+
+        hits
+    }
+
+    // Sorts the hits by relevance and keeps the first `limit`.
+    fn keep_best(hits: &mut Vec<NoteSearchHit>, limit: i64) {
+        hits.sort_by(|a, b| {
+            b.rank
+                .partial_cmp(&a.rank)
+                .unwrap_or(Ordering::Equal)
+                .then(a.note.id.cmp(&b.note.id))
+        });
+        hits.truncate(limit.max(0) as usize);
+    }
+
+    /// Searches the query in the notes of the given ids, decrypted one batch
+    /// at a time since the database cannot match the encrypted ones.
+    fn search_decrypted(
+        connection: &DBConnection,
+        ids: &[i32],
+        query: &str,
+        limit: i64,
+    ) -> QueryResult<Vec<NoteSearchHit>> {
+        let mut hits = vec![];
+        NoteRow::decrypt_in_batches(connection, ids, |batch| {
+            hits.extend(NoteSearchHit::search_in(batch, query, limit));
+            NoteSearchHit::keep_best(&mut hits, limit);
+        })?;
+
+        Ok(hits)
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[derive(QueryableByName)]
+struct SearchRow {
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Float"]
+    rank: f32,
+    #[sql_type = "Text"]
+    title: String,
+    #[sql_type = "Text"]
+    snippet: String,
+}
+
+#[cfg(not(feature = "sqlite"))]
+const SEARCH_QUERY: &str = "SELECT id,
+    ts_rank(search_vector, query) AS rank,
+    ts_headline('english', coalesce(title, ''), query,
+      'StartSel=<mark>, StopSel=</mark>, HighlightAll=TRUE') AS title,
+    ts_headline('english', coalesce(data, ''), query,
+      'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=20, MinWords=5') AS snippet
+  FROM notes, websearch_to_tsquery('english', $1) query
+  WHERE user_id = $2 AND deleted_time IS NULL AND key_check IS NULL
+    AND search_vector @@ query
+  ORDER BY rank DESC, id ASC
+  LIMIT $3";
+
+// Queries of the notes out of the trash, the only ones most callers can see.
 impl Note {
-    pub fn find_all(connection: &PgConnection) -> QueryResult<Vec<Note>> {
+    pub fn find_all(connection: &DBConnection) -> QueryResult<Vec<Note>> {
+        use crate::schema::notes::dsl::*;
+        notes
+            .filter(deleted_time.is_null())
+            .get_results::<NoteRow>(connection)
+            .and_then(NoteRow::decrypt_all)
+    }
+
+    pub fn find_by_id(connection: &DBConnection, _id: i32) -> QueryResult<Note> {
         use crate::schema::notes::dsl::*;
-        notes.get_results::<Note>(connection)
+        notes
+            .find(_id)
+            .filter(deleted_time.is_null())
+            .get_result::<NoteRow>(connection)
+            .and_then(NoteRow::decrypt)
     }
 
-    pub fn find_by_id(connection: &PgConnection, _id: i32) -> QueryResult<Note> {
+    pub fn find_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<Vec<Note>> {
         use crate::schema::notes::dsl::*;
-        notes.find(_id).get_result::<Note>(connection)
+        notes
+            .filter(user_id.eq(_user_id))
+            .filter(deleted_time.is_null())
+            .load::<NoteRow>(connection)
+            .and_then(NoteRow::decrypt_all)
     }
 
-    pub fn find_by_user_id(connection: &PgConnection, _user_id: i32) -> QueryResult<Vec<Note>> {
+    pub fn find_by_user_id_and_filter(
+        connection: &DBConnection,
+        _user_id: i32,
+        filter: &NoteFilter,
+    ) -> QueryResult<NotePage> {
         use crate::schema::notes::dsl::*;
-        notes.filter(user_id.eq(_user_id)).load::<Note>(connection)
+
+        let mut query = notes
+            .filter(user_id.eq(_user_id))
+            .filter(deleted_time.is_null())
+            .into_boxed();
+        if !Tag::normalize_names(&filter.tags).is_empty() {
+            let note_ids = Tag::find_note_ids_by_names(connection, _user_id, &filter.tags)?;
+            query = query.filter(id.eq_any(note_ids));
+        }
+        if let Some(_category_id) = filter.category_id {
+            if filter.descendants {
+                let category_ids = Category::find_descendant_ids(connection, _category_id)?;
+                query = query.filter(category_id.eq_any(category_ids));
+            } else {
+                query = query.filter(category_id.eq(_category_id));
+            }
+        }
+        if let Some(updated_since) = filter.updated_since {
+            query = query.filter(update_time.ge(updated_since));
+        }
+
+        // Encrypted titles cannot be compared by the database, so the notes are
+        // sorted and paged once decrypted, one batch at a time keeping the
+        // first ones only.
+        if filter.sort == NoteSort::Title && encryption::is_enabled() {
+            let ids = query.select(id).order(id.asc()).load::<i32>(connection)?;
+            let mut found: Vec<Note> = vec![];
+            NoteRow::decrypt_in_batches(connection, &ids, |batch| {
+                found.extend(batch.into_iter().filter(|note| filter.accepts(note)));
+                found.sort_by(|a, b| filter.compare(a, b));
+                if let Some(limit) = filter.limit {
+                    // One more note tells whether there is a next page.
+                    found.truncate(limit.max(0) as usize + 1);
+                }
+            })?;
+
+            return Ok(NotePage::new(found, filter));
+        }
+
+        if let Some((key, cursor_id)) = filter.position() {
+            query = match (key, filter.descending) {
+                (SortKey::Time(time), false) if filter.sort == NoteSort::UpdateTime => query
+                    .filter(
+                        update_time
+                            .gt(time)
+                            .or(update_time.eq(time).and(id.gt(cursor_id))),
+                    ),
+                (SortKey::Time(time), true) if filter.sort == NoteSort::UpdateTime => query.filter(
+                    update_time
+                        .lt(time)
+                        .or(update_time.eq(time).and(id.lt(cursor_id))),
+                ),
+                (SortKey::Time(time), false) => query.filter(
+                    create_time
+                        .gt(time)
+                        .or(create_time.eq(time).and(id.gt(cursor_id))),
+                ),
+                (SortKey::Time(time), true) => query.filter(
+                    create_time
+                        .lt(time)
+                        .or(create_time.eq(time).and(id.lt(cursor_id))),
+                ),
+                (SortKey::Title(value), false) => query.filter(
+                    title
+                        .gt(value.clone())
+                        .or(title.eq(value).and(id.gt(cursor_id))),
+                ),
+                (SortKey::Title(value), true) => query.filter(
+                    title
+                        .lt(value.clone())
+                        .or(title.eq(value).and(id.lt(cursor_id))),
+                ),
+            };
+        }
+
+        query = match (filter.sort, filter.descending) {
+            (NoteSort::CreateTime, false) => query.order((create_time.asc(), id.asc())),
+            (NoteSort::CreateTime, true) => query.order((create_time.desc(), id.desc())),
+            (NoteSort::UpdateTime, false) => query.order((update_time.asc(), id.asc())),
+            (NoteSort::UpdateTime, true) => query.order((update_time.desc(), id.desc())),
+            (NoteSort::Title, false) => query.order((title.asc(), id.asc())),
+            (NoteSort::Title, true) => query.order((title.desc(), id.desc())),
+        };
+        if let Some(limit) = filter.limit {
+            // One more note tells whether there is a next page.
+            query = query.limit(limit.max(0) + 1);
+        }
+
+        Ok(NotePage::new(
+            NoteRow::decrypt_all(query.load::<NoteRow>(connection)?)?,
+            filter,
+        ))
     }
 
     pub fn find_by_id_and_user_id(
-        connection: &PgConnection,
+        connection: &DBConnection,
         _id: i32,
         _user_id: i32,
     ) -> QueryResult<Note> {
@@ -64,28 +668,206 @@ impl Note {
         notes
             .filter(id.eq(_id))
             .filter(user_id.eq(_user_id))
-            .first::<Note>(connection)
+            .filter(deleted_time.is_null())
+            .first::<NoteRow>(connection)
+            .and_then(NoteRow::decrypt)
+    }
+
+    /// Notes of the user matching the query, the most relevant first.
+    #[cfg(not(feature = "sqlite"))]
+    pub fn search(
+        connection: &DBConnection,
+        _user_id: i32,
+        query: &str,
+        limit: i64,
+    ) -> QueryResult<Vec<NoteSearchHit>> {
+        use crate::schema::notes::dsl::*;
+
+        if search::terms(query).is_empty() {
+            return Ok(vec![]);
+        }
+        // Encrypted rows have an empty search document, they can only be
+        // matched once decrypted.
+        if encryption::is_enabled() {
+            let ids = notes
+                .filter(user_id.eq(_user_id))
+                .filter(deleted_time.is_null())
+                .filter(key_check.is_null())
+                .select(id)
+                .order(id.asc())
+                .load::<i32>(connection)?;
+
+            return NoteSearchHit::search_decrypted(connection, &ids, query, limit);
+        }
+
+        let rows = sql_query(SEARCH_QUERY)
+            .bind::<Text, _>(query)
+            .bind::<Integer, _>(_user_id)
+            .bind::<BigInt, _>(limit)
+            .load::<SearchRow>(connection)?;
+
+        let ids: Vec<i32> = rows.iter().map(|row| row.id).collect();
+        let found =
+            NoteRow::decrypt_all(notes.filter(id.eq_any(ids)).load::<NoteRow>(connection)?)?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                found
+                    .iter()
+                    .find(|note| note.id == row.id)
+                    .map(|note| NoteSearchHit {
+                        note: note.clone(),
+                        rank: row.rank,
+                        title: row.title,
+                        snippet: row.snippet,
+                    })
+            })
+            .collect())
+    }
+
+    /// Notes of the user matching the query, the most relevant first.
+    #[cfg(feature = "sqlite")]
+    pub fn search(
+        connection: &DBConnection,
+        _user_id: i32,
+        query: &str,
+        limit: i64,
+    ) -> QueryResult<Vec<NoteSearchHit>> {
+        use crate::schema::notes::dsl::*;
+
+        let mut candidates = notes
+            .filter(user_id.eq(_user_id))
+            .filter(deleted_time.is_null())
+            .filter(key_check.is_null())
+            .into_boxed();
+        // Encrypted rows can only be matched once decrypted.
+        if encryption::is_enabled() {
+            let ids = candidates
+                .select(id)
+                .order(id.asc())
+                .load::<i32>(connection)?;
+
+            return NoteSearchHit::search_decrypted(connection, &ids, query, limit);
+        }
+        for term in search::terms(query).iter() {
+            let pattern = format!("%{}%", term);
+            candidates = candidates.filter(title.like(pattern.clone()).or(data.like(pattern)));
+        }
+
+        Ok(NoteSearchHit::search_in(
+            NoteRow::decrypt_all(candidates.load::<NoteRow>(connection)?)?,
+            query,
+            limit,
+        ))
     }
 
-    pub fn update(connection: &PgConnection, obj: &Note) -> QueryResult<Note> {
+    /// Updates the note keeping its previous content as a new revision.
+    pub fn update(connection: &DBConnection, obj: &Note) -> QueryResult<Note> {
         use crate::schema::notes::dsl::*;
-        diesel::update(notes.find(obj.id))
-            .set((
-                user_id.eq(obj.user_id),
-                category_id.eq(obj.category_id),
-                title.eq(obj.title.clone()),
-                data.eq(obj.data.clone()),
-                update_time.eq(SystemTime::now()),
-            ))
-            .get_result(connection)
+
+        let envelope = encryption::encrypt(RowId::new(TABLE, obj.id), &obj.title, &obj.data)?;
+
+        connection.transaction(|| {
+            let current = notes
+                .find(obj.id)
+                .filter(deleted_time.is_null())
+                .get_result::<NoteRow>(connection)
+                .and_then(NoteRow::decrypt)?;
+            NoteRevision::create_from(connection, &current)?;
+
+            diesel::update(notes.find(obj.id))
+                .set((
+                    user_id.eq(obj.user_id),
+                    category_id.eq(obj.category_id),
+                    title.eq(envelope.title.clone()),
+                    data.eq(envelope.data.clone()),
+                    format.eq(obj.format.clone()),
+                    key_check.eq(obj.key_check.clone()),
+                    data_key.eq(envelope.data_key.clone()),
+                    key_id.eq(envelope.key_id.clone()),
+                    update_time.eq(now()),
+                ))
+                .execute(connection)?;
+
+            notes
+                .find(obj.id)
+                .get_result::<NoteRow>(connection)
+                .and_then(NoteRow::decrypt)
+        })
     }
 
-    pub fn delete(connection: &PgConnection, _id: i32) -> QueryResult<usize> {
+    /// Moves the note to the trash.
+    pub fn trash(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
+        use crate::schema::notes::dsl::*;
+        diesel::update(notes.find(_id).filter(deleted_time.is_null()))
+            .set(deleted_time.eq(Some(now())))
+            .execute(connection)
+    }
+
+    /// Permanently deletes the note, whether it is in the trash or not.
+    pub fn delete(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
         use crate::schema::notes::dsl::*;
         diesel::delete(notes.find(_id)).execute(connection)
     }
 }
 
+// Queries of the notes in the trash.
+impl Note {
+    /// Trashed notes of the user, the last deleted first.
+    pub fn find_trashed_by_user_id(
+        connection: &DBConnection,
+        _user_id: i32,
+    ) -> QueryResult<Vec<Note>> {
+        use crate::schema::notes::dsl::*;
+        notes
+            .filter(user_id.eq(_user_id))
+            .filter(deleted_time.is_not_null())
+            .order((deleted_time.desc(), id.desc()))
+            .load::<NoteRow>(connection)
+            .and_then(NoteRow::decrypt_all)
+    }
+
+    pub fn find_trashed_by_id_and_user_id(
+        connection: &DBConnection,
+        _id: i32,
+        _user_id: i32,
+    ) -> QueryResult<Note> {
+        use crate::schema::notes::dsl::*;
+        notes
+            .filter(id.eq(_id))
+            .filter(user_id.eq(_user_id))
+            .filter(deleted_time.is_not_null())
+            .first::<NoteRow>(connection)
+            .and_then(NoteRow::decrypt)
+    }
+
+    /// Takes the note out of the trash.
+    pub fn restore(connection: &DBConnection, _id: i32) -> QueryResult<Note> {
+        use crate::schema::notes::dsl::*;
+
+        connection.transaction(|| {
+            let num = diesel::update(notes.find(_id).filter(deleted_time.is_not_null()))
+                .set(deleted_time.eq(None::<NaiveDateTime>))
+                .execute(connection)?;
+            if num == 0 {
+                return Err(diesel::result::Error::NotFound);
+            }
+
+            notes
+                .find(_id)
+                .get_result::<NoteRow>(connection)
+                .and_then(NoteRow::decrypt)
+        })
+    }
+
+    /// Permanently deletes the notes trashed before the given time.
+    pub fn purge(connection: &DBConnection, before: NaiveDateTime) -> QueryResult<usize> {
+        use crate::schema::notes::dsl::*;
+        diesel::delete(notes.filter(deleted_time.lt(before))).execute(connection)
+    }
+}
+
 #[derive(Debug, Clone, Insertable)]
 #[table_name = "notes"]
 pub struct NewNote {
@@ -93,6 +875,8 @@ pub struct NewNote {
     pub category_id: Option<i32>,
     pub title: String,
     pub data: String,
+    pub format: String,
+    pub key_check: Option<String>,
 }
 
 impl NewNote {
@@ -102,14 +886,53 @@ impl NewNote {
             category_id: category_id,
             title: title,
             data: data,
+            format: NoteFormat::default().to_string(),
+            key_check: None,
         }
     }
 
-    pub fn create(&self, connection: &PgConnection) -> QueryResult<Note> {
+    pub fn with_format(mut self, format: NoteFormat) -> Self {
+        self.format = format.to_string();
+        self
+    }
+
+    /// Marks the note as encrypted by the client with the given key-check value.
+    pub fn with_key_check(mut self, key_check: Option<&str>) -> Self {
+        self.key_check = key_check.map(|k| k.to_string());
+        self
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<Note> {
         use crate::schema::notes::dsl::*;
 
-        diesel::insert_into(notes)
-            .values(self)
-            .get_result(connection)
+        connection.transaction(|| {
+            diesel::insert_into(notes)
+                .values((
+                    user_id.eq(self.user_id),
+                    category_id.eq(self.category_id),
+                    title.eq(""),
+                    data.eq(""),
+                    format.eq(self.format.clone()),
+                    key_check.eq(self.key_check.clone()),
+                ))
+                .execute(connection)?;
+            let _id = last_insert_id(connection)?;
+
+            // The envelope is bound to the id, so it is only known once inserted.
+            let envelope = encryption::encrypt(RowId::new(TABLE, _id), &self.title, &self.data)?;
+            diesel::update(notes.find(_id))
+                .set((
+                    title.eq(envelope.title),
+                    data.eq(envelope.data),
+                    data_key.eq(envelope.data_key),
+                    key_id.eq(envelope.key_id),
+                ))
+                .execute(connection)?;
+
+            notes
+                .find(_id)
+                .get_result::<NoteRow>(connection)
+                .and_then(NoteRow::decrypt)
+        })
     }
 }
@@ -0,0 +1,258 @@
+use crate::encryption::{self, Keyring};
+use crate::models::db::note::{Note, NoteRow};
+use crate::schema::*;
+use crate::wikilinks::{self, WikiLink};
+use crate::DBConnection;
+
+use diesel::prelude::*;
+use diesel::result::{Error as DieselError, QueryResult};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// Key of the title of a link with the keyring: the normalized title, or its
+// blind index so it is not stored in plain text.
+fn title_key_with(keyring: Option<&Keyring>, title: &str) -> String {
+    let title = wikilinks::normalize_title(title);
+    match keyring {
+        Some(keyring) => keyring.blind_index(&title),
+        None => title,
+    }
+}
+
+fn configured_keyring() -> QueryResult<Option<&'static Keyring>> {
+    encryption::keyring().map_err(|err| DieselError::SerializationError(Box::new(err)))
+}
+
+/// Key the titles of the links are stored and matched with, so the case and
+/// the spaces of the titles do not matter. With the encryption at rest it is
+/// a blind index of the title.
+pub fn title_key(title: &str) -> QueryResult<String> {
+    Ok(title_key_with(configured_keyring()?, title))
+}
+
+/// Wiki link written in the data of a note, to another note by id or by the
+/// key of its title, see `title_key`.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct NoteLink {
+    pub id: i32,
+    pub note_id: i32,
+    pub target_id: Option<i32>,
+    pub target_title: Option<String>,
+}
+
+impl PartialEq for NoteLink {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.note_id == other.note_id
+            && self.target_id == other.target_id
+            && self.target_title == other.target_title
+    }
+}
+
+impl Ord for NoteLink {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for NoteLink {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Notes the links may point to, by id and by the key of their title.
+pub struct LinkTargets<'a> {
+    by_id: HashMap<i32, &'a Note>,
+    by_title: HashMap<String, &'a Note>,
+}
+
+impl<'a> LinkTargets<'a> {
+    /// A title shared by several notes points to the first created.
+    pub fn new(notes: &'a [Note]) -> QueryResult<Self> {
+        let keyring = configured_keyring()?;
+
+        let mut sorted: Vec<&Note> = notes.iter().collect();
+        sorted.sort_by_key(|note| note.id);
+
+        let mut targets = LinkTargets {
+            by_id: HashMap::new(),
+            by_title: HashMap::new(),
+        };
+        for note in sorted {
+            targets.by_id.insert(note.id, note);
+            targets
+                .by_title
+                .entry(title_key_with(keyring, &note.title))
+                .or_insert(note);
+        }
+
+        Ok(targets)
+    }
+}
+
+impl NoteLink {
+    /// The note it points to among the targets, `None` if the link is broken.
+    pub fn resolve<'a>(&self, targets: &LinkTargets<'a>) -> Option<&'a Note> {
+        match (self.target_id, &self.target_title) {
+            (Some(id), _) => targets.by_id.get(&id).cloned(),
+            (None, Some(key)) => targets.by_title.get(key).cloned(),
+            (None, None) => None,
+        }
+    }
+
+    /// Titles of the links written in the note by their key, the stored links
+    /// only have the keys.
+    pub fn written_titles(note: &Note) -> QueryResult<HashMap<String, String>> {
+        let keyring = configured_keyring()?;
+
+        let mut titles = HashMap::new();
+        for link in wikilinks::parse(&note.data) {
+            if let WikiLink::Title(title) = link {
+                titles
+                    .entry(title_key_with(keyring, &title))
+                    .or_insert(title);
+            }
+        }
+
+        Ok(titles)
+    }
+
+    /// Links of the note in the order they are written.
+    pub fn find_by_note_id(connection: &DBConnection, _note_id: i32) -> QueryResult<Vec<NoteLink>> {
+        use crate::schema::note_links::dsl::*;
+        note_links
+            .filter(note_id.eq(_note_id))
+            .order(id.asc())
+            .load::<NoteLink>(connection)
+    }
+
+    /// Links written in the given notes.
+    pub fn find_by_note_ids(
+        connection: &DBConnection,
+        _note_ids: &[i32],
+    ) -> QueryResult<Vec<NoteLink>> {
+        use crate::schema::note_links::dsl::*;
+        note_links
+            .filter(note_id.eq_any(_note_ids))
+            .order(id.asc())
+            .load::<NoteLink>(connection)
+    }
+
+    /// Links written in the notes of the user out of the trash that point
+    /// to the given id or title, whatever its case and spaces.
+    pub fn find_by_target(
+        connection: &DBConnection,
+        _user_id: i32,
+        _target_id: i32,
+        _target_title: &str,
+    ) -> QueryResult<Vec<NoteLink>> {
+        let key = title_key(_target_title)?;
+
+        note_links::table
+            .inner_join(notes::table)
+            .filter(notes::user_id.eq(_user_id))
+            .filter(notes::deleted_time.is_null())
+            .filter(
+                note_links::target_id
+                    .eq(_target_id)
+                    .or(note_links::target_title.eq(key)),
+            )
+            .order(note_links::id.asc())
+            .select(note_links::all_columns)
+            .load::<NoteLink>(connection)
+    }
+
+    /// Replaces the links of the note with the ones written in its data.
+    pub fn set_note_links(connection: &DBConnection, note: &Note) -> QueryResult<()> {
+        NoteLink::replace(connection, note, configured_keyring()?)
+    }
+
+    fn replace(
+        connection: &DBConnection,
+        note: &Note,
+        keyring: Option<&Keyring>,
+    ) -> QueryResult<()> {
+        use crate::schema::note_links::dsl::*;
+
+        connection.transaction(|| {
+            diesel::delete(note_links.filter(note_id.eq(note.id))).execute(connection)?;
+
+            for new_link in NewNoteLink::from_note(note, keyring).iter() {
+                diesel::insert_into(note_links)
+                    .values(new_link)
+                    .execute(connection)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Replaces the links of up to `batch_size` notes after `after_id`,
+    /// trashed or not, with the title keys of the keyring. Returns the id of
+    /// the last note, `None` once there are no more.
+    pub fn rebuild(
+        connection: &DBConnection,
+        keyring: &Keyring,
+        after_id: i32,
+        batch_size: i64,
+    ) -> QueryResult<Option<i32>> {
+        connection.transaction(|| {
+            let rows = notes::table
+                .filter(notes::id.gt(after_id))
+                .order(notes::id.asc())
+                .limit(batch_size)
+                .load::<NoteRow>(connection)?;
+            let last_id = rows.last().map(|row| row.id);
+
+            for note in NoteRow::decrypt_all(rows)?.iter() {
+                NoteLink::replace(connection, note, Some(keyring))?;
+            }
+
+            Ok(last_id)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Insertable)]
+#[table_name = "note_links"]
+pub struct NewNoteLink {
+    pub note_id: i32,
+    pub target_id: Option<i32>,
+    pub target_title: Option<String>,
+}
+
+impl NewNoteLink {
+    /// Row of the link, its title is stored as its key with the keyring.
+    pub fn new(note_id: i32, link: &WikiLink, keyring: Option<&Keyring>) -> Self {
+        let (target_id, target_title) = match link {
+            WikiLink::Id(id) => (Some(*id), None),
+            WikiLink::Title(title) => (None, Some(title_key_with(keyring, title))),
+        };
+
+        NewNoteLink {
+            note_id: note_id,
+            target_id: target_id,
+            target_title: target_title,
+        }
+    }
+
+    /// Links written in the data of the note, once per target. Notes
+    /// encrypted by the client have none, their data cannot be read.
+    pub fn from_note(note: &Note, keyring: Option<&Keyring>) -> Vec<NewNoteLink> {
+        if note.is_encrypted() {
+            return vec![];
+        }
+
+        let mut links: Vec<NewNoteLink> = vec![];
+        for link in wikilinks::parse(&note.data).iter() {
+            let new_link = NewNoteLink::new(note.id, link, keyring);
+            if !links.contains(&new_link) {
+                links.push(new_link);
+            }
+        }
+
+        links
+    }
+}
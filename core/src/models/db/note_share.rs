@@ -0,0 +1,206 @@
+use crate::models::db::note::{Note, NoteRow};
+use crate::schema::*;
+use crate::utils::now;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// What a user can do with a note shared with it, stored in
+/// `note_shares.permission`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Read,
+    /// Also update the note.
+    Write,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::Write => "write",
+        }
+    }
+
+    pub fn parse(permission: &str) -> Option<Permission> {
+        match permission {
+            "read" => Some(Permission::Read),
+            "write" => Some(Permission::Write),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Note shared by its owner with another user.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct NoteShare {
+    pub id: i32,
+    pub note_id: i32,
+    /// User the note is shared with.
+    pub user_id: i32,
+    pub permission: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl PartialEq for NoteShare {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.note_id == other.note_id
+            && self.user_id == other.user_id
+            && self.permission == other.permission
+    }
+}
+
+impl Ord for NoteShare {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for NoteShare {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NoteShare {
+    /// Permission of the share, `Read` if the stored value is not known.
+    pub fn permission(&self) -> Permission {
+        Permission::parse(&self.permission).unwrap_or(Permission::Read)
+    }
+
+    pub fn find_by_note_id(
+        connection: &DBConnection,
+        _note_id: i32,
+    ) -> QueryResult<Vec<NoteShare>> {
+        use crate::schema::note_shares::dsl::*;
+        note_shares
+            .filter(note_id.eq(_note_id))
+            .order(id.asc())
+            .load::<NoteShare>(connection)
+    }
+
+    pub fn find_by_note_id_and_user_id(
+        connection: &DBConnection,
+        _note_id: i32,
+        _user_id: i32,
+    ) -> QueryResult<NoteShare> {
+        use crate::schema::note_shares::dsl::*;
+        note_shares
+            .filter(note_id.eq(_note_id))
+            .filter(user_id.eq(_user_id))
+            .get_result::<NoteShare>(connection)
+    }
+
+    /// Notes out of the trash shared with the user, along with their shares,
+    /// the last updated first.
+    pub fn find_notes_by_user_id(
+        connection: &DBConnection,
+        _user_id: i32,
+    ) -> QueryResult<Vec<(Note, NoteShare)>> {
+        note_shares::table
+            .inner_join(notes::table)
+            .filter(note_shares::user_id.eq(_user_id))
+            .filter(notes::deleted_time.is_null())
+            .order((notes::update_time.desc(), notes::id.desc()))
+            .select((notes::all_columns, note_shares::all_columns))
+            .load::<(NoteRow, NoteShare)>(connection)?
+            .into_iter()
+            .map(|(row, note_share)| Ok((row.decrypt()?, note_share)))
+            .collect()
+    }
+
+    /// The note if it is out of the trash and shared with the user, along
+    /// with its share.
+    pub fn find_note_by_note_id_and_user_id(
+        connection: &DBConnection,
+        _note_id: i32,
+        _user_id: i32,
+    ) -> QueryResult<(Note, NoteShare)> {
+        let (row, note_share) = note_shares::table
+            .inner_join(notes::table)
+            .filter(note_shares::note_id.eq(_note_id))
+            .filter(note_shares::user_id.eq(_user_id))
+            .filter(notes::deleted_time.is_null())
+            .select((notes::all_columns, note_shares::all_columns))
+            .get_result::<(NoteRow, NoteShare)>(connection)?;
+
+        Ok((row.decrypt()?, note_share))
+    }
+
+    pub fn delete_by_note_id_and_user_id(
+        connection: &DBConnection,
+        _note_id: i32,
+        _user_id: i32,
+    ) -> QueryResult<usize> {
+        use crate::schema::note_shares::dsl::*;
+        diesel::delete(
+            note_shares
+                .filter(note_id.eq(_note_id))
+                .filter(user_id.eq(_user_id)),
+        )
+        .execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "note_shares"]
+pub struct NewNoteShare<'a> {
+    pub note_id: i32,
+    pub user_id: i32,
+    pub permission: &'a str,
+}
+
+impl<'a> NewNoteShare<'a> {
+    pub fn new(note_id: i32, user_id: i32, permission: Permission) -> Self {
+        NewNoteShare {
+            note_id: note_id,
+            user_id: user_id,
+            permission: permission.as_str(),
+        }
+    }
+
+    /// Shares the note with the user, or changes the permission if it was
+    /// already shared.
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<NoteShare> {
+        use crate::schema::note_shares::dsl::*;
+
+        connection.transaction(|| {
+            let num = diesel::update(
+                note_shares
+                    .filter(note_id.eq(self.note_id))
+                    .filter(user_id.eq(self.user_id)),
+            )
+            .set((permission.eq(self.permission), update_time.eq(now())))
+            .execute(connection)?;
+
+            if num > 0 {
+                return NoteShare::find_by_note_id_and_user_id(
+                    connection,
+                    self.note_id,
+                    self.user_id,
+                );
+            }
+
+            diesel::insert_into(note_shares)
+                .values(self)
+                .execute(connection)?;
+
+            note_shares
+                .find(last_insert_id(connection)?)
+                .get_result::<NoteShare>(connection)
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use crate::models::db::note::{Note, NoteRow};
+use crate::schema::*;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+use std::cmp::Ordering;
+
+/// Public read-only link to a note, it needs no account.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct PublicLink {
+    pub id: i32,
+    pub note_id: i32,
+    pub token_hash: String,
+    /// Hash of the password asked to open the link, if any.
+    pub password_hash: Option<String>,
+    pub expire_time: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+}
+
+impl PartialEq for PublicLink {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.note_id == other.note_id
+            && self.token_hash == other.token_hash
+            && self.password_hash == other.password_hash
+            && self.expire_time == other.expire_time
+    }
+}
+
+impl Ord for PublicLink {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for PublicLink {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PublicLink {
+    /// Whether the link has not expired at the given time.
+    pub fn is_active(&self, time: NaiveDateTime) -> bool {
+        self.expire_time
+            .map_or(true, |expire_time| expire_time > time)
+    }
+
+    pub fn find_by_note_id(
+        connection: &DBConnection,
+        _note_id: i32,
+    ) -> QueryResult<Vec<PublicLink>> {
+        use crate::schema::public_links::dsl::*;
+        public_links
+            .filter(note_id.eq(_note_id))
+            .order(id.asc())
+            .load::<PublicLink>(connection)
+    }
+
+    /// The link of the token along with its note, if the note is not in the
+    /// trash.
+    pub fn find_by_token_hash(
+        connection: &DBConnection,
+        _token_hash: &str,
+    ) -> QueryResult<(Note, PublicLink)> {
+        public_links::table
+            .inner_join(notes::table)
+            .filter(public_links::token_hash.eq(_token_hash))
+            .filter(notes::deleted_time.is_null())
+            .select((notes::all_columns, public_links::all_columns))
+            .get_result::<(NoteRow, PublicLink)>(connection)
+            .and_then(|(row, public_link)| Ok((row.decrypt()?, public_link)))
+    }
+
+    pub fn delete_by_id_and_note_id(
+        connection: &DBConnection,
+        _id: i32,
+        _note_id: i32,
+    ) -> QueryResult<usize> {
+        use crate::schema::public_links::dsl::*;
+        diesel::delete(public_links.filter(id.eq(_id)).filter(note_id.eq(_note_id)))
+            .execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "public_links"]
+pub struct NewPublicLink<'a> {
+    pub note_id: i32,
+    pub token_hash: &'a str,
+    pub password_hash: Option<&'a str>,
+    pub expire_time: Option<NaiveDateTime>,
+}
+
+impl<'a> NewPublicLink<'a> {
+    pub fn new(note_id: i32, token_hash: &'a str) -> Self {
+        NewPublicLink {
+            note_id: note_id,
+            token_hash: token_hash,
+            password_hash: None,
+            expire_time: None,
+        }
+    }
+
+    pub fn with_password_hash(mut self, password_hash: Option<&'a str>) -> Self {
+        self.password_hash = password_hash;
+        self
+    }
+
+    pub fn with_expire_time(mut self, expire_time: Option<NaiveDateTime>) -> Self {
+        self.expire_time = expire_time;
+        self
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<PublicLink> {
+        use crate::schema::public_links::dsl::*;
+
+        connection.transaction(|| {
+            diesel::insert_into(public_links)
+                .values(self)
+                .execute(connection)?;
+
+            public_links
+                .find(last_insert_id(connection)?)
+                .get_result::<PublicLink>(connection)
+        })
+    }
+}
@@ -0,0 +1,200 @@
+use crate::encryption::{self, Envelope, Keyring, RowId};
+use crate::models::db::note::Note;
+use crate::schema::*;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::dsl::max;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+use std::cmp::Ordering;
+
+/// Content of a note before one of its updates.
+#[derive(Debug, Clone, Eq)]
+pub struct NoteRevision {
+    pub id: i32,
+    pub note_id: i32,
+    /// Number of the revision inside the note, starting at 1.
+    pub revision: i32,
+    pub category_id: Option<i32>,
+    pub title: String,
+    pub data: String,
+    pub create_time: NaiveDateTime,
+    /// Key-check value of the note at the time, if it was encrypted.
+    pub key_check: Option<String>,
+}
+
+impl PartialEq for NoteRevision {
+    fn eq(&self, other: &Self) -> bool {
+        self.note_id == other.note_id
+            && self.revision == other.revision
+            && self.category_id == other.category_id
+            && self.title == other.title
+            && self.data == other.data
+            && self.key_check == other.key_check
+    }
+}
+
+impl Ord for NoteRevision {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.note_id, self.revision).cmp(&(other.note_id, other.revision))
+    }
+}
+
+impl PartialOrd for NoteRevision {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Row of `note_revisions` as it is stored, its title and data may be
+/// encrypted at rest like the ones of the notes.
+#[derive(Debug, Clone, Queryable)]
+pub struct NoteRevisionRow {
+    pub id: i32,
+    pub note_id: i32,
+    pub revision: i32,
+    pub category_id: Option<i32>,
+    pub title: String,
+    pub data: String,
+    pub create_time: NaiveDateTime,
+    pub key_check: Option<String>,
+    pub data_key: Option<String>,
+    pub key_id: Option<String>,
+}
+
+/// Table of the rows of the revisions in the encryption context.
+const TABLE: &str = "note_revisions";
+
+impl NoteRevisionRow {
+    fn envelope(&self) -> Envelope {
+        Envelope {
+            title: self.title.clone(),
+            data: self.data.clone(),
+            data_key: self.data_key.clone(),
+            key_id: self.key_id.clone(),
+        }
+    }
+
+    /// The revision with its title and data decrypted.
+    pub fn decrypt(self) -> QueryResult<NoteRevision> {
+        let (title, data) = encryption::decrypt(RowId::new(TABLE, self.id), &self.envelope())?;
+
+        Ok(NoteRevision {
+            id: self.id,
+            note_id: self.note_id,
+            revision: self.revision,
+            category_id: self.category_id,
+            title: title,
+            data: data,
+            create_time: self.create_time,
+            key_check: self.key_check,
+        })
+    }
+
+    /// Encrypts again with the current master key up to `batch_size` rows of
+    /// other master keys or in plain text. Returns the number of rows, 0 once
+    /// every row uses the current master key.
+    pub fn rotate_keys(
+        connection: &DBConnection,
+        keyring: &Keyring,
+        batch_size: i64,
+    ) -> QueryResult<usize> {
+        use crate::schema::note_revisions::dsl::*;
+
+        connection.transaction(|| {
+            let rows = note_revisions
+                .filter(key_id.is_null())
+                .or_filter(key_id.ne(keyring.current_id()))
+                .order(id.asc())
+                .limit(batch_size)
+                .load::<NoteRevisionRow>(connection)?;
+
+            for row in rows.iter() {
+                let row_id = RowId::new(TABLE, row.id);
+                let (plain_title, plain_data) = keyring
+                    .decrypt(row_id, &row.envelope())
+                    .map_err(|err| diesel::result::Error::DeserializationError(Box::new(err)))?;
+                let envelope = keyring.encrypt(row_id, &plain_title, &plain_data);
+
+                diesel::update(note_revisions.find(row.id))
+                    .set((
+                        title.eq(envelope.title),
+                        data.eq(envelope.data),
+                        data_key.eq(envelope.data_key),
+                        key_id.eq(envelope.key_id),
+                    ))
+                    .execute(connection)?;
+            }
+
+            Ok(rows.len())
+        })
+    }
+}
+
+impl NoteRevision {
+    pub fn find_by_note_id(
+        connection: &DBConnection,
+        _note_id: i32,
+    ) -> QueryResult<Vec<NoteRevision>> {
+        use crate::schema::note_revisions::dsl::*;
+        note_revisions
+            .filter(note_id.eq(_note_id))
+            .order(revision.asc())
+            .load::<NoteRevisionRow>(connection)?
+            .into_iter()
+            .map(NoteRevisionRow::decrypt)
+            .collect()
+    }
+
+    pub fn find_by_note_id_and_revision(
+        connection: &DBConnection,
+        _note_id: i32,
+        _revision: i32,
+    ) -> QueryResult<NoteRevision> {
+        use crate::schema::note_revisions::dsl::*;
+        note_revisions
+            .filter(note_id.eq(_note_id))
+            .filter(revision.eq(_revision))
+            .first::<NoteRevisionRow>(connection)
+            .and_then(NoteRevisionRow::decrypt)
+    }
+
+    /// Saves the current content of the note as its next revision.
+    pub fn create_from(connection: &DBConnection, note: &Note) -> QueryResult<usize> {
+        use crate::schema::note_revisions::dsl::*;
+
+        connection.transaction(|| {
+            let last = note_revisions
+                .filter(note_id.eq(note.id))
+                .select(max(revision))
+                .first::<Option<i32>>(connection)?;
+
+            let num = diesel::insert_into(note_revisions)
+                .values((
+                    note_id.eq(note.id),
+                    revision.eq(last.unwrap_or(0) + 1),
+                    category_id.eq(note.category_id),
+                    title.eq(""),
+                    data.eq(""),
+                    key_check.eq(note.key_check.clone()),
+                ))
+                .execute(connection)?;
+            let _id = last_insert_id(connection)?;
+
+            // The envelope is bound to the id, so it is only known once inserted.
+            let envelope = encryption::encrypt(RowId::new(TABLE, _id), &note.title, &note.data)?;
+            diesel::update(note_revisions.find(_id))
+                .set((
+                    title.eq(envelope.title),
+                    data.eq(envelope.data),
+                    data_key.eq(envelope.data_key),
+                    key_id.eq(envelope.key_id),
+                ))
+                .execute(connection)?;
+
+            Ok(num)
+        })
+    }
+}
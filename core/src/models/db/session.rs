@@ -0,0 +1,163 @@
+use crate::schema::*;
+use crate::utils::now;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::{Error, QueryResult};
+
+use std::cmp::Ordering;
+
+/// Login of a user. Its access tokens carry the `jti` and its refresh token is
+/// only stored hashed, both change every time the session is refreshed.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub jti: String,
+    pub refresh_token_hash: String,
+    pub expire_time: NaiveDateTime,
+    pub revoked_time: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl PartialEq for Session {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.user_id == other.user_id
+            && self.jti == other.jti
+            && self.refresh_token_hash == other.refresh_token_hash
+            && self.expire_time == other.expire_time
+            && self.revoked_time == other.revoked_time
+    }
+}
+
+impl Ord for Session {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for Session {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Session {
+    /// Whether the session is neither revoked nor expired at the given time.
+    pub fn is_active(&self, time: NaiveDateTime) -> bool {
+        self.revoked_time.is_none() && self.expire_time > time
+    }
+
+    pub fn find_by_jti(connection: &DBConnection, _jti: &str) -> QueryResult<Session> {
+        use crate::schema::sessions::dsl::*;
+        sessions
+            .filter(jti.eq(_jti))
+            .get_result::<Session>(connection)
+    }
+
+    pub fn find_by_refresh_token_hash(
+        connection: &DBConnection,
+        _refresh_token_hash: &str,
+    ) -> QueryResult<Session> {
+        use crate::schema::sessions::dsl::*;
+        sessions
+            .filter(refresh_token_hash.eq(_refresh_token_hash))
+            .get_result::<Session>(connection)
+    }
+
+    /// Replaces the jti and the refresh token of an active session. Fails with
+    /// `NotFound` if the session was refreshed or revoked in the meantime.
+    pub fn rotate(
+        connection: &DBConnection,
+        session: &Session,
+        _jti: &str,
+        _refresh_token_hash: &str,
+        _expire_time: NaiveDateTime,
+    ) -> QueryResult<Session> {
+        use crate::schema::sessions::dsl::*;
+
+        connection.transaction(|| {
+            let num = diesel::update(
+                sessions
+                    .filter(id.eq(session.id))
+                    .filter(refresh_token_hash.eq(session.refresh_token_hash.as_str()))
+                    .filter(revoked_time.is_null()),
+            )
+            .set((
+                jti.eq(_jti),
+                refresh_token_hash.eq(_refresh_token_hash),
+                expire_time.eq(_expire_time),
+                update_time.eq(now()),
+            ))
+            .execute(connection)?;
+
+            if num == 0 {
+                return Err(Error::NotFound);
+            }
+
+            sessions.find(session.id).get_result::<Session>(connection)
+        })
+    }
+
+    pub fn revoke_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<usize> {
+        use crate::schema::sessions::dsl::*;
+        let time = now();
+        diesel::update(
+            sessions
+                .filter(user_id.eq(_user_id))
+                .filter(revoked_time.is_null()),
+        )
+        .set((revoked_time.eq(time), update_time.eq(time)))
+        .execute(connection)
+    }
+
+    pub fn revoke(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
+        use crate::schema::sessions::dsl::*;
+        let time = now();
+        diesel::update(sessions.find(_id).filter(revoked_time.is_null()))
+            .set((revoked_time.eq(time), update_time.eq(time)))
+            .execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "sessions"]
+pub struct NewSession<'a> {
+    pub user_id: i32,
+    pub jti: &'a str,
+    pub refresh_token_hash: &'a str,
+    pub expire_time: NaiveDateTime,
+}
+
+impl<'a> NewSession<'a> {
+    pub fn new(
+        user_id: i32,
+        jti: &'a str,
+        refresh_token_hash: &'a str,
+        expire_time: NaiveDateTime,
+    ) -> Self {
+        NewSession {
+            user_id: user_id,
+            jti: jti,
+            refresh_token_hash: refresh_token_hash,
+            expire_time: expire_time,
+        }
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<Session> {
+        use crate::schema::sessions::dsl::*;
+
+        connection.transaction(|| {
+            diesel::insert_into(sessions)
+                .values(self)
+                .execute(connection)?;
+
+            sessions
+                .find(last_insert_id(connection)?)
+                .get_result::<Session>(connection)
+        })
+    }
+}
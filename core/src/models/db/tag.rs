@@ -0,0 +1,239 @@
+use crate::schema::*;
+use crate::DBConnection;
+
+use chrono::NaiveDateTime;
+use diesel::dsl::count;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct Tag {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub create_time: NaiveDateTime,
+}
+
+impl PartialEq for Tag {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.user_id == other.user_id && self.name == other.name
+    }
+}
+
+impl Ord for Tag {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for Tag {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Tag {
+    /// Trims, lowercases and deduplicates tag names, dropping the empty ones.
+    pub fn normalize_names(names: &[String]) -> Vec<String> {
+        let mut names: Vec<String> = names
+            .iter()
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// Tags of the user along with the number of notes using each one, not
+    /// counting the notes in the trash.
+    pub fn find_by_user_id_with_counts(
+        connection: &DBConnection,
+        _user_id: i32,
+    ) -> QueryResult<Vec<(Tag, i64)>> {
+        let tags = tags::table
+            .filter(tags::user_id.eq(_user_id))
+            .order(tags::name.asc())
+            .load::<Tag>(connection)?;
+
+        let counts = note_tags::table
+            .inner_join(tags::table)
+            .inner_join(notes::table)
+            .filter(tags::user_id.eq(_user_id))
+            .filter(notes::deleted_time.is_null())
+            .group_by(note_tags::tag_id)
+            .select((note_tags::tag_id, count(note_tags::note_id)))
+            .load::<(i32, i64)>(connection)?;
+
+        Ok(tags
+            .into_iter()
+            .map(|tag| {
+                let num = counts
+                    .iter()
+                    .find(|(tag_id, _)| *tag_id == tag.id)
+                    .map_or(0, |(_, num)| *num);
+                (tag, num)
+            })
+            .collect())
+    }
+
+    /// Pairs of note id and tag name of the given notes.
+    pub fn find_names_by_note_ids(
+        connection: &DBConnection,
+        _note_ids: &[i32],
+    ) -> QueryResult<Vec<(i32, String)>> {
+        note_tags::table
+            .inner_join(tags::table)
+            .filter(note_tags::note_id.eq_any(_note_ids))
+            .order((note_tags::note_id.asc(), tags::name.asc()))
+            .select((note_tags::note_id, tags::name))
+            .load::<(i32, String)>(connection)
+    }
+
+    /// Ids of the notes of the user that have all the given tags.
+    pub fn find_note_ids_by_names(
+        connection: &DBConnection,
+        _user_id: i32,
+        _names: &[String],
+    ) -> QueryResult<Vec<i32>> {
+        let names = Tag::normalize_names(_names);
+
+        let pairs = note_tags::table
+            .inner_join(tags::table)
+            .filter(tags::user_id.eq(_user_id))
+            .filter(tags::name.eq_any(&names))
+            .select((note_tags::note_id, tags::name))
+            .load::<(i32, String)>(connection)?;
+
+        let mut note_ids: Vec<i32> = pairs.iter().map(|(note_id, _)| *note_id).collect();
+        note_ids.sort();
+        note_ids.dedup();
+        note_ids.retain(|note_id| {
+            pairs
+                .iter()
+                .filter(|(other_id, _)| other_id == note_id)
+                .count()
+                == names.len()
+        });
+
+        Ok(note_ids)
+    }
+
+    /// The tag of the user with the name, created if needed. The insert
+    /// ignores the tag created by a concurrent request, found afterwards.
+    pub fn find_or_create(
+        connection: &DBConnection,
+        _user_id: i32,
+        _name: &str,
+    ) -> QueryResult<Tag> {
+        use crate::schema::tags::dsl::*;
+
+        let find = || {
+            tags.filter(user_id.eq(_user_id))
+                .filter(name.eq(_name))
+                .first::<Tag>(connection)
+        };
+
+        match find().optional()? {
+            Some(tag) => Ok(tag),
+            None => {
+                Tag::insert_or_ignore(connection, _user_id, _name)?;
+                find()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn insert_or_ignore(
+        connection: &DBConnection,
+        _user_id: i32,
+        _name: &str,
+    ) -> QueryResult<usize> {
+        use crate::schema::tags::dsl::*;
+        diesel::insert_into(tags)
+            .values((user_id.eq(_user_id), name.eq(_name)))
+            .on_conflict_do_nothing()
+            .execute(connection)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn insert_or_ignore(
+        connection: &DBConnection,
+        _user_id: i32,
+        _name: &str,
+    ) -> QueryResult<usize> {
+        use crate::schema::tags::dsl::*;
+        diesel::insert_or_ignore_into(tags)
+            .values((user_id.eq(_user_id), name.eq(_name)))
+            .execute(connection)
+    }
+
+    /// Adds the tag to the note, creating the tag if needed.
+    pub fn add_to_note(
+        connection: &DBConnection,
+        _user_id: i32,
+        _note_id: i32,
+        _name: &str,
+    ) -> QueryResult<()> {
+        connection.transaction(|| {
+            let tag = Tag::find_or_create(connection, _user_id, _name)?;
+
+            let exists = note_tags::table
+                .find((_note_id, tag.id))
+                .first::<(i32, i32)>(connection)
+                .optional()?
+                .is_some();
+            if !exists {
+                diesel::insert_into(note_tags::table)
+                    .values((
+                        note_tags::note_id.eq(_note_id),
+                        note_tags::tag_id.eq(tag.id),
+                    ))
+                    .execute(connection)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn remove_from_note(
+        connection: &DBConnection,
+        _user_id: i32,
+        _note_id: i32,
+        _name: &str,
+    ) -> QueryResult<usize> {
+        let tag_ids = tags::table
+            .filter(tags::user_id.eq(_user_id))
+            .filter(tags::name.eq(_name))
+            .select(tags::id);
+
+        diesel::delete(
+            note_tags::table
+                .filter(note_tags::note_id.eq(_note_id))
+                .filter(note_tags::tag_id.eq_any(tag_ids)),
+        )
+        .execute(connection)
+    }
+
+    /// Replaces the tags of the note.
+    pub fn set_note_tags(
+        connection: &DBConnection,
+        _user_id: i32,
+        _note_id: i32,
+        _names: &[String],
+    ) -> QueryResult<()> {
+        connection.transaction(|| {
+            diesel::delete(note_tags::table.filter(note_tags::note_id.eq(_note_id)))
+                .execute(connection)?;
+
+            for name in Tag::normalize_names(_names).iter() {
+                Tag::add_to_note(connection, _user_id, _note_id, name)?;
+            }
+
+            Ok(())
+        })
+    }
+}
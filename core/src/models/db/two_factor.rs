@@ -0,0 +1,295 @@
+use crate::schema::*;
+use crate::utils::now;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+use std::cmp::Ordering;
+
+/// TOTP secret of a user. It is only required at login once it is enabled by
+/// verifying a first code.
+#[derive(Debug, Clone, Eq, Queryable)]
+pub struct TotpSecret {
+    pub id: i32,
+    pub user_id: i32,
+    pub secret: String,
+    pub enabled: bool,
+    /// Last step used, 0 if none. Its codes and the ones of the previous steps
+    /// cannot be used again.
+    pub last_step: i64,
+    /// Wrong codes sent since the last right one or lock.
+    pub failed_codes: i32,
+    /// The login with 2FA is locked until then after too many wrong codes.
+    pub locked_until: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl PartialEq for TotpSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.user_id == other.user_id
+            && self.secret == other.secret
+            && self.enabled == other.enabled
+            && self.last_step == other.last_step
+            && self.failed_codes == other.failed_codes
+            && self.locked_until == other.locked_until
+    }
+}
+
+impl Ord for TotpSecret {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialOrd for TotpSecret {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl TotpSecret {
+    /// Whether the login with 2FA is locked at the time.
+    pub fn is_locked(&self, time: NaiveDateTime) -> bool {
+        self.locked_until.map_or(false, |until| until > time)
+    }
+
+    pub fn find_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<TotpSecret> {
+        use crate::schema::totp_secrets::dsl::*;
+        totp_secrets
+            .filter(user_id.eq(_user_id))
+            .get_result::<TotpSecret>(connection)
+    }
+
+    /// Enables the secret of the user with the step of its first code.
+    pub fn enable(connection: &DBConnection, _user_id: i32, step: i64) -> QueryResult<usize> {
+        use crate::schema::totp_secrets::dsl::*;
+        diesel::update(
+            totp_secrets
+                .filter(user_id.eq(_user_id))
+                .filter(enabled.eq(false)),
+        )
+        .set((enabled.eq(true), last_step.eq(step), update_time.eq(now())))
+        .execute(connection)
+    }
+
+    /// Marks the step as used, it updates nothing if it was already used.
+    pub fn use_step(connection: &DBConnection, _user_id: i32, step: i64) -> QueryResult<usize> {
+        use crate::schema::totp_secrets::dsl::*;
+        diesel::update(
+            totp_secrets
+                .filter(user_id.eq(_user_id))
+                .filter(enabled.eq(true))
+                .filter(last_step.lt(step)),
+        )
+        .set((last_step.eq(step), update_time.eq(now())))
+        .execute(connection)
+    }
+
+    /// Counts a wrong code of the user. Once `max_failed` are counted the
+    /// login is locked until `_locked_until` and the count starts again.
+    pub fn add_failed_code(
+        connection: &DBConnection,
+        _user_id: i32,
+        max_failed: i32,
+        _locked_until: NaiveDateTime,
+    ) -> QueryResult<TotpSecret> {
+        use crate::schema::totp_secrets::dsl::*;
+
+        connection.transaction(|| {
+            diesel::update(totp_secrets.filter(user_id.eq(_user_id)))
+                .set(failed_codes.eq(failed_codes + 1))
+                .execute(connection)?;
+            diesel::update(
+                totp_secrets
+                    .filter(user_id.eq(_user_id))
+                    .filter(failed_codes.ge(max_failed)),
+            )
+            .set((failed_codes.eq(0), locked_until.eq(Some(_locked_until))))
+            .execute(connection)?;
+
+            TotpSecret::find_by_user_id(connection, _user_id)
+        })
+    }
+
+    /// Forgets the wrong codes of the user, after a right one.
+    pub fn reset_failed_codes(connection: &DBConnection, _user_id: i32) -> QueryResult<usize> {
+        use crate::schema::totp_secrets::dsl::*;
+        diesel::update(totp_secrets.filter(user_id.eq(_user_id)))
+            .set(failed_codes.eq(0))
+            .execute(connection)
+    }
+
+    /// Deletes the secret of the user along with its recovery codes.
+    pub fn delete_by_user_id(connection: &DBConnection, _user_id: i32) -> QueryResult<usize> {
+        connection.transaction(|| {
+            diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(_user_id)))
+                .execute(connection)?;
+            diesel::delete(totp_secrets::table.filter(totp_secrets::user_id.eq(_user_id)))
+                .execute(connection)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "totp_secrets"]
+pub struct NewTotpSecret<'a> {
+    pub user_id: i32,
+    pub secret: &'a str,
+}
+
+impl<'a> NewTotpSecret<'a> {
+    pub fn new(user_id: i32, secret: &'a str) -> Self {
+        NewTotpSecret {
+            user_id: user_id,
+            secret: secret,
+        }
+    }
+
+    /// Creates the secret, not enabled yet, and the recovery codes replacing
+    /// the previous ones of the user.
+    pub fn create(
+        &self,
+        connection: &DBConnection,
+        code_hashes: &[String],
+    ) -> QueryResult<TotpSecret> {
+        use crate::schema::totp_secrets::dsl::*;
+
+        connection.transaction(|| {
+            TotpSecret::delete_by_user_id(connection, self.user_id)?;
+
+            diesel::insert_into(totp_secrets)
+                .values(self)
+                .execute(connection)?;
+            let totp_secret = totp_secrets
+                .find(last_insert_id(connection)?)
+                .get_result::<TotpSecret>(connection)?;
+
+            for hash in code_hashes {
+                diesel::insert_into(recovery_codes::table)
+                    .values(&NewRecoveryCode::new(self.user_id, hash))
+                    .execute(connection)?;
+            }
+
+            Ok(totp_secret)
+        })
+    }
+}
+
+/// Single-use code to login without the TOTP, only its SHA-256 is stored.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable)]
+pub struct RecoveryCode {
+    pub id: i32,
+    pub user_id: i32,
+    pub code_hash: String,
+    pub used_time: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+}
+
+impl RecoveryCode {
+    /// Marks the code as used, it updates nothing if it does not exist or was
+    /// already used.
+    pub fn use_code(
+        connection: &DBConnection,
+        _user_id: i32,
+        _code_hash: &str,
+    ) -> QueryResult<usize> {
+        use crate::schema::recovery_codes::dsl::*;
+        diesel::update(
+            recovery_codes
+                .filter(user_id.eq(_user_id))
+                .filter(code_hash.eq(_code_hash))
+                .filter(used_time.is_null()),
+        )
+        .set(used_time.eq(now()))
+        .execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "recovery_codes"]
+pub struct NewRecoveryCode<'a> {
+    pub user_id: i32,
+    pub code_hash: &'a str,
+}
+
+impl<'a> NewRecoveryCode<'a> {
+    pub fn new(user_id: i32, code_hash: &'a str) -> Self {
+        NewRecoveryCode {
+            user_id: user_id,
+            code_hash: code_hash,
+        }
+    }
+}
+
+/// Pending login of a user with 2FA, its token is exchanged along with a code
+/// for a session.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable)]
+pub struct LoginChallenge {
+    pub id: i32,
+    pub user_id: i32,
+    pub token_hash: String,
+    /// Wrong codes sent with the challenge.
+    pub attempts: i32,
+    pub expire_time: NaiveDateTime,
+    pub create_time: NaiveDateTime,
+}
+
+impl LoginChallenge {
+    pub fn find_by_token_hash(
+        connection: &DBConnection,
+        _token_hash: &str,
+    ) -> QueryResult<LoginChallenge> {
+        use crate::schema::login_challenges::dsl::*;
+        login_challenges
+            .filter(token_hash.eq(_token_hash))
+            .get_result::<LoginChallenge>(connection)
+    }
+
+    pub fn add_attempt(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
+        use crate::schema::login_challenges::dsl::*;
+        diesel::update(login_challenges.find(_id))
+            .set(attempts.eq(attempts + 1))
+            .execute(connection)
+    }
+
+    pub fn delete(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
+        use crate::schema::login_challenges::dsl::*;
+        diesel::delete(login_challenges.find(_id)).execute(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "login_challenges"]
+pub struct NewLoginChallenge<'a> {
+    pub user_id: i32,
+    pub token_hash: &'a str,
+    pub expire_time: NaiveDateTime,
+}
+
+impl<'a> NewLoginChallenge<'a> {
+    pub fn new(user_id: i32, token_hash: &'a str, expire_time: NaiveDateTime) -> Self {
+        NewLoginChallenge {
+            user_id: user_id,
+            token_hash: token_hash,
+            expire_time: expire_time,
+        }
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<LoginChallenge> {
+        use crate::schema::login_challenges::dsl::*;
+
+        connection.transaction(|| {
+            diesel::insert_into(login_challenges)
+                .values(self)
+                .execute(connection)?;
+
+            login_challenges
+                .find(last_insert_id(connection)?)
+                .get_result::<LoginChallenge>(connection)
+        })
+    }
+}
@@ -1,25 +1,66 @@
 use crate::schema::*;
+use crate::utils::now;
+use crate::{last_insert_id, DBConnection};
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::result::QueryResult;
 
 use std::cmp::Ordering;
-use std::time::SystemTime;
+use std::fmt;
 
-#[derive(Debug, Eq, Queryable, AsChangeset)]
+/// Role of a user, stored in `users.role` and in the access tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Manages the users and the shared categories.
+    Admin,
+    User,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::User => "user",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Role> {
+        match role {
+            "admin" => Some(Role::Admin),
+            "user" => Some(Role::User),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Eq, Queryable, AsChangeset)]
 #[table_name = "users"]
 pub struct User {
     pub id: i32,
     pub email: String,
     pub name: String,
-    pub password: String, //SHA256 of the password
-    pub create_time: SystemTime,
-    pub update_time: SystemTime,
+    pub password: String, //Argon2id PHC string or legacy SHA256 of the password
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+    pub role: String,
+    /// Disabled users cannot login nor use their sessions and API keys.
+    pub disabled: bool,
 }
 
 impl PartialEq for User {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.name == other.name && self.password == other.password
+        self.id == other.id
+            && self.name == other.name
+            && self.password == other.password
+            && self.role == other.role
+            && self.disabled == other.disabled
     }
 }
 
@@ -36,41 +77,73 @@ impl PartialOrd for User {
 }
 
 impl User {
-    pub fn find_all(connection: &PgConnection) -> QueryResult<Vec<User>> {
+    /// The role of the user, unknown roles are taken as `Role::User`.
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role).unwrap_or(Role::User)
+    }
+
+    pub fn find_all(connection: &DBConnection) -> QueryResult<Vec<User>> {
         use crate::schema::users::dsl::*;
-        users.get_results::<User>(connection)
+        users.order(id.asc()).get_results::<User>(connection)
     }
 
-    pub fn find_by_id(connection: &PgConnection, _id: i32) -> QueryResult<User> {
+    pub fn find_by_id(connection: &DBConnection, _id: i32) -> QueryResult<User> {
         use crate::schema::users::dsl::*;
         users.find(_id).get_result::<User>(connection)
     }
 
-    pub fn find_by_email_and_password(
-        connection: &PgConnection,
-        _email: String,
-        _password: String,
-    ) -> QueryResult<User> {
+    /// Locks the row of the user until the end of the transaction, so the
+    /// writes that depend on the rest of its rows are done one at a time.
+    #[cfg(not(feature = "sqlite"))]
+    pub fn lock(connection: &DBConnection, _id: i32) -> QueryResult<()> {
+        use crate::schema::users::dsl::*;
+        users
+            .find(_id)
+            .select(id)
+            .for_update()
+            .get_result::<i32>(connection)
+            .map(|_| ())
+    }
+
+    /// SQLite has a single writer at a time, the transactions that would
+    /// overlap fail with `SQLITE_BUSY` instead.
+    #[cfg(feature = "sqlite")]
+    pub fn lock(connection: &DBConnection, _id: i32) -> QueryResult<()> {
+        use crate::schema::users::dsl::*;
+        users
+            .find(_id)
+            .select(id)
+            .get_result::<i32>(connection)
+            .map(|_| ())
+    }
+
+    pub fn find_by_email(connection: &DBConnection, _email: String) -> QueryResult<User> {
         use crate::schema::users::dsl::*;
         users
             .filter(email.eq(_email))
-            .filter(password.eq(_password))
             .get_result::<User>(connection)
     }
 
-    pub fn update(connection: &PgConnection, obj: &User) -> QueryResult<User> {
+    pub fn update(connection: &DBConnection, obj: &User) -> QueryResult<User> {
         use crate::schema::users::dsl::*;
-        diesel::update(users.find(obj.id))
-            .set((
-                email.eq(obj.email.clone()),
-                name.eq(obj.name.clone()),
-                password.eq(obj.password.clone()),
-                update_time.eq(SystemTime::now()),
-            ))
-            .get_result(connection)
+
+        connection.transaction(|| {
+            diesel::update(users.find(obj.id))
+                .set((
+                    email.eq(obj.email.clone()),
+                    name.eq(obj.name.clone()),
+                    password.eq(obj.password.clone()),
+                    role.eq(obj.role.clone()),
+                    disabled.eq(obj.disabled),
+                    update_time.eq(now()),
+                ))
+                .execute(connection)?;
+
+            users.find(obj.id).get_result::<User>(connection)
+        })
     }
 
-    pub fn delete(connection: &PgConnection, _id: i32) -> QueryResult<usize> {
+    pub fn delete(connection: &DBConnection, _id: i32) -> QueryResult<usize> {
         use crate::schema::users::dsl::*;
         diesel::delete(users.find(_id)).execute(connection)
     }
@@ -82,6 +155,7 @@ pub struct NewUser<'a> {
     pub email: &'a str,
     pub name: &'a str,
     pub password: &'a str,
+    pub role: &'a str,
 }
 
 impl<'a> NewUser<'a> {
@@ -90,14 +164,26 @@ impl<'a> NewUser<'a> {
             email: email,
             name: name,
             password: password,
+            role: Role::User.as_str(),
         }
     }
 
-    pub fn create(&self, connection: &PgConnection) -> QueryResult<User> {
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role.as_str();
+        self
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<User> {
         use crate::schema::users::dsl::*;
 
-        diesel::insert_into(users)
-            .values(self)
-            .get_result(connection)
+        connection.transaction(|| {
+            diesel::insert_into(users)
+                .values(self)
+                .execute(connection)?;
+
+            users
+                .find(last_insert_id(connection)?)
+                .get_result::<User>(connection)
+        })
     }
 }
@@ -0,0 +1,64 @@
+use crate::schema::*;
+use crate::{last_insert_id, DBConnection};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::QueryResult;
+
+/// Identity of a user in an OpenID Connect provider, so the logins with the
+/// provider always map to the same user.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable)]
+pub struct UserIdentity {
+    pub id: i32,
+    pub user_id: i32,
+    pub issuer: String,
+    /// `sub` claim of the ID tokens, unique within the issuer.
+    pub subject: String,
+    pub create_time: NaiveDateTime,
+}
+
+impl UserIdentity {
+    pub fn find_by_subject(
+        connection: &DBConnection,
+        _issuer: &str,
+        _subject: &str,
+    ) -> QueryResult<UserIdentity> {
+        use crate::schema::user_identities::dsl::*;
+        user_identities
+            .filter(issuer.eq(_issuer))
+            .filter(subject.eq(_subject))
+            .get_result::<UserIdentity>(connection)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "user_identities"]
+pub struct NewUserIdentity<'a> {
+    pub user_id: i32,
+    pub issuer: &'a str,
+    pub subject: &'a str,
+}
+
+impl<'a> NewUserIdentity<'a> {
+    pub fn new(user_id: i32, issuer: &'a str, subject: &'a str) -> Self {
+        NewUserIdentity {
+            user_id: user_id,
+            issuer: issuer,
+            subject: subject,
+        }
+    }
+
+    pub fn create(&self, connection: &DBConnection) -> QueryResult<UserIdentity> {
+        use crate::schema::user_identities::dsl::*;
+
+        connection.transaction(|| {
+            diesel::insert_into(user_identities)
+                .values(self)
+                .execute(connection)?;
+
+            user_identities
+                .find(last_insert_id(connection)?)
+                .get_result::<UserIdentity>(connection)
+        })
+    }
+}
@@ -0,0 +1,90 @@
+//! Password hashing of the users.
+//!
+//! Passwords are stored as Argon2id hashes in the PHC string format, which
+//! keeps the salt and the parameters along with the hash. Rows created before
+//! that store the hex SHA-256 of the password, they are still accepted and
+//! should be re-hashed with [`hash`] after a successful [`verify`].
+
+use argon2::{Config, Variant};
+use sha2::{Digest, Sha256};
+
+use crate::utils::HexSlice;
+
+const SALT_LENGTH: usize = 16;
+const PHC_PREFIX: &str = "$argon2id$";
+
+/// Stored for the users without a local password, e.g. the ones provisioned
+/// by an OpenID Connect provider. No password verifies against it.
+pub const NO_PASSWORD: &str = "!";
+
+fn config<'a>() -> Config<'a> {
+    Config {
+        variant: Variant::Argon2id,
+        mem_cost: 19456,
+        time_cost: 2,
+        ..Config::default()
+    }
+}
+
+/// Compares every byte so that the time does not depend on the first mismatch.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Argon2id PHC string of the password with a random salt.
+pub fn hash(password: &str) -> Result<String, String> {
+    let salt: [u8; SALT_LENGTH] = rand::random();
+
+    argon2::hash_encoded(password.as_bytes(), &salt, &config()).map_err(|err| err.to_string())
+}
+
+/// Whether the password matches the stored hash, either a PHC string or a
+/// legacy SHA-256.
+pub fn verify(password: &str, stored: &str) -> bool {
+    if stored.starts_with("$argon2") {
+        argon2::verify_encoded(stored, password.as_bytes()).unwrap_or(false)
+    } else {
+        let legacy = HexSlice::new(&Sha256::digest(password.as_bytes())).to_string();
+        constant_time_eq(legacy.as_bytes(), stored.to_uppercase().as_bytes())
+    }
+}
+
+/// Whether the stored hash is not an Argon2id PHC string.
+pub fn needs_rehash(stored: &str) -> bool {
+    !stored.starts_with(PHC_PREFIX)
+}
+
+mod tests {
+    #[test]
+    fn test_hash_and_verify() {
+        use super::*;
+
+        let stored = hash("some_password").unwrap();
+
+        assert!(stored.starts_with(PHC_PREFIX));
+        assert_ne!(stored, hash("some_password").unwrap());
+        assert!(verify("some_password", &stored));
+        assert!(!verify("bad_password", &stored));
+        assert!(!needs_rehash(&stored));
+    }
+
+    #[test]
+    fn test_verify_legacy() {
+        use super::*;
+
+        let stored = "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B";
+
+        assert!(verify("some_password", stored));
+        assert!(!verify("bad_password", stored));
+        assert!(!verify(stored, stored));
+        assert!(needs_rehash(stored));
+    }
+
+    #[test]
+    fn test_verify_no_password() {
+        use super::*;
+
+        assert!(!verify("", NO_PASSWORD));
+        assert!(!verify(NO_PASSWORD, NO_PASSWORD));
+    }
+}
@@ -0,0 +1,105 @@
+//! Rendering of the notes as HTML.
+//!
+//! Markdown notes are rendered from CommonMark with the GitHub tables, task
+//! lists and strikethrough, plain notes are escaped into a `<pre>` block. The
+//! output is always sanitized, so clients can insert it as is.
+
+use crate::models::db::note::NoteFormat;
+
+use pulldown_cmark::{html, Options, Parser};
+
+/// Escapes the HTML special characters of the text.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn markdown_to_html(data: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut unsafe_html = String::with_capacity(data.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(data, options));
+
+    // The checkboxes of the task lists are the only inputs allowed.
+    ammonia::Builder::default()
+        .add_tags(&["input"])
+        .add_tag_attributes("input", &["type", "checked", "disabled"])
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+/// Sanitized HTML fragment of the data of a note.
+pub fn render(format: NoteFormat, data: &str) -> String {
+    match format {
+        NoteFormat::Plain => format!("<pre>{}</pre>", escape_html(data)),
+        NoteFormat::Markdown => markdown_to_html(data),
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_escape_html() {
+        use super::*;
+
+        assert_eq!(
+            escape_html("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render_plain() {
+        use super::*;
+
+        assert_eq!(
+            render(NoteFormat::Plain, "# title\n<b>bold</b>"),
+            "<pre># title\n&lt;b&gt;bold&lt;/b&gt;</pre>"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        use super::*;
+
+        let html = render(
+            NoteFormat::Markdown,
+            "# Title\n\n*some* ~~text~~\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n- [x] done\n- [ ] todo\n",
+        );
+
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<em>some</em>"));
+        assert!(html.contains("<del>text</del>"));
+        assert!(html.contains("<table>"));
+        assert!(html.contains("<td>1</td>"));
+        assert!(html.contains("type=\"checkbox\""));
+        assert!(html.contains("checked"));
+    }
+
+    #[test]
+    fn test_render_markdown_sanitized() {
+        use super::*;
+
+        let html = render(
+            NoteFormat::Markdown,
+            "<script>alert(1)</script>\n\n[link](javascript:alert(1)) <img src=\"x\" onerror=\"alert(1)\">\n\n<input type=\"text\" name=\"user\">",
+        );
+
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("name=\"user\""));
+    }
+}
@@ -0,0 +1,611 @@
+use super::*;
+use crate::models::db::two_factor::RecoveryCode;
+use crate::{BDPool, DBConn, DBConnection};
+
+use diesel::Connection;
+
+/// `Repository` backed by the database of a `BDPool`.
+pub struct DBRepository {
+    pool: BDPool,
+}
+
+impl DBRepository {
+    pub fn new(pool: BDPool) -> Self {
+        DBRepository { pool: pool }
+    }
+
+    fn connection(&self) -> RepositoryResult<DBConn> {
+        Ok(self.pool.get()?)
+    }
+}
+
+impl Repository for DBRepository {
+    fn notes(&self) -> &dyn NoteRepository {
+        self
+    }
+
+    fn users(&self) -> &dyn UserRepository {
+        self
+    }
+
+    fn categories(&self) -> &dyn CategoryRepository {
+        self
+    }
+
+    fn tags(&self) -> &dyn TagRepository {
+        self
+    }
+
+    fn revisions(&self) -> &dyn RevisionRepository {
+        self
+    }
+
+    fn sessions(&self) -> &dyn SessionRepository {
+        self
+    }
+
+    fn api_keys(&self) -> &dyn ApiKeyRepository {
+        self
+    }
+
+    fn two_factor(&self) -> &dyn TwoFactorRepository {
+        self
+    }
+
+    fn identities(&self) -> &dyn IdentityRepository {
+        self
+    }
+
+    fn shares(&self) -> &dyn ShareRepository {
+        self
+    }
+
+    fn public_links(&self) -> &dyn PublicLinkRepository {
+        self
+    }
+
+    fn attachments(&self) -> &dyn AttachmentRepository {
+        self
+    }
+
+    fn links(&self) -> &dyn LinkRepository {
+        self
+    }
+}
+
+impl NoteRepository for DBRepository {
+    fn find_by_user_id(&self, user_id: i32, filter: &NoteFilter) -> RepositoryResult<NotePage> {
+        Ok(Note::find_by_user_id_and_filter(
+            &self.connection()?,
+            user_id,
+            filter,
+        )?)
+    }
+
+    fn find_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Note> {
+        Ok(Note::find_by_id_and_user_id(
+            &self.connection()?,
+            id,
+            user_id,
+        )?)
+    }
+
+    fn search(
+        &self,
+        user_id: i32,
+        query: &str,
+        limit: i64,
+    ) -> RepositoryResult<Vec<NoteSearchHit>> {
+        Ok(Note::search(&self.connection()?, user_id, query, limit)?)
+    }
+
+    fn create(&self, note: &NewNote, tags: &[String]) -> RepositoryResult<Note> {
+        let connection = self.connection()?;
+
+        Ok(connection.transaction::<_, DieselError, _>(|| {
+            let note = note.create(&connection)?;
+            Tag::set_note_tags(&connection, note.user_id, note.id, tags)?;
+            NoteLink::set_note_links(&connection, &note)?;
+
+            Ok(note)
+        })?)
+    }
+
+    fn update(&self, note: &Note, tags: &[String]) -> RepositoryResult<Note> {
+        let connection = self.connection()?;
+
+        Ok(connection.transaction::<_, DieselError, _>(|| {
+            let note = Note::update(&connection, note)?;
+            Tag::set_note_tags(&connection, note.user_id, note.id, tags)?;
+            NoteLink::set_note_links(&connection, &note)?;
+
+            Ok(note)
+        })?)
+    }
+
+    fn trash(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(Note::trash(&self.connection()?, id)?)
+    }
+
+    fn find_trashed_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<Note>> {
+        Ok(Note::find_trashed_by_user_id(&self.connection()?, user_id)?)
+    }
+
+    fn find_trashed_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Note> {
+        Ok(Note::find_trashed_by_id_and_user_id(
+            &self.connection()?,
+            id,
+            user_id,
+        )?)
+    }
+
+    fn restore(&self, id: i32) -> RepositoryResult<Note> {
+        Ok(Note::restore(&self.connection()?, id)?)
+    }
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(Note::delete(&self.connection()?, id)?)
+    }
+
+    fn purge(&self, before: NaiveDateTime) -> RepositoryResult<usize> {
+        Ok(Note::purge(&self.connection()?, before)?)
+    }
+}
+
+impl UserRepository for DBRepository {
+    fn find_all(&self) -> RepositoryResult<Vec<User>> {
+        Ok(User::find_all(&self.connection()?)?)
+    }
+
+    fn find_by_id(&self, id: i32) -> RepositoryResult<User> {
+        Ok(User::find_by_id(&self.connection()?, id)?)
+    }
+
+    fn find_by_email(&self, email: &str) -> RepositoryResult<User> {
+        Ok(User::find_by_email(&self.connection()?, email.to_string())?)
+    }
+
+    fn create(&self, user: &NewUser) -> RepositoryResult<User> {
+        Ok(user.create(&self.connection()?)?)
+    }
+
+    fn update(&self, user: &User) -> RepositoryResult<User> {
+        Ok(User::update(&self.connection()?, user)?)
+    }
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(User::delete(&self.connection()?, id)?)
+    }
+}
+
+impl CategoryRepository for DBRepository {
+    fn find_all(&self) -> RepositoryResult<Vec<Category>> {
+        Ok(Category::find_all(&self.connection()?)?)
+    }
+
+    fn find_by_id(&self, id: i32) -> RepositoryResult<Category> {
+        Ok(Category::find_by_id(&self.connection()?, id)?)
+    }
+
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<Category>> {
+        Ok(Category::find_by_user_id(&self.connection()?, user_id)?)
+    }
+
+    fn find_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Category> {
+        Ok(Category::find_by_id_and_user_id(
+            &self.connection()?,
+            id,
+            user_id,
+        )?)
+    }
+
+    fn find_descendant_ids(&self, id: i32) -> RepositoryResult<Vec<i32>> {
+        Ok(Category::find_descendant_ids(&self.connection()?, id)?)
+    }
+
+    fn create(&self, category: &NewCategory) -> RepositoryResult<Category> {
+        let connection = self.connection()?;
+
+        connection.transaction::<_, RepositoryError, _>(|| {
+            check_category_parent(
+                &lock_categories(&connection, category.user_id)?,
+                None,
+                category.user_id,
+                category.parent_id,
+            )?;
+            Ok(category.create(&connection)?)
+        })
+    }
+
+    fn update(&self, category: &Category) -> RepositoryResult<Category> {
+        let connection = self.connection()?;
+
+        connection.transaction::<_, RepositoryError, _>(|| {
+            check_category_parent(
+                &lock_categories(&connection, category.user_id)?,
+                Some(category.id),
+                category.user_id,
+                category.parent_id,
+            )?;
+            Ok(Category::update(&connection, category)?)
+        })
+    }
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(Category::delete(&self.connection()?, id)?)
+    }
+}
+
+// Categories a category of the user can be attached to. The row of the user
+// stays locked until the end of the transaction, so two concurrent moves
+// cannot create a cycle.
+fn lock_categories(
+    connection: &DBConnection,
+    user_id: Option<i32>,
+) -> RepositoryResult<Vec<Category>> {
+    match user_id {
+        Some(user_id) => {
+            User::lock(connection, user_id)?;
+            Ok(Category::find_by_user_id(connection, user_id)?)
+        }
+        None => Ok(Category::find_shared(connection)?),
+    }
+}
+
+impl TagRepository for DBRepository {
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<(Tag, i64)>> {
+        Ok(Tag::find_by_user_id_with_counts(
+            &self.connection()?,
+            user_id,
+        )?)
+    }
+
+    fn find_by_note_ids(&self, note_ids: &[i32]) -> RepositoryResult<Vec<(i32, String)>> {
+        Ok(Tag::find_names_by_note_ids(&self.connection()?, note_ids)?)
+    }
+
+    fn add(&self, user_id: i32, note_id: i32, name: &str) -> RepositoryResult<()> {
+        Ok(Tag::add_to_note(
+            &self.connection()?,
+            user_id,
+            note_id,
+            name,
+        )?)
+    }
+
+    fn remove(&self, user_id: i32, note_id: i32, name: &str) -> RepositoryResult<usize> {
+        Ok(Tag::remove_from_note(
+            &self.connection()?,
+            user_id,
+            note_id,
+            name,
+        )?)
+    }
+}
+
+impl RevisionRepository for DBRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteRevision>> {
+        Ok(NoteRevision::find_by_note_id(&self.connection()?, note_id)?)
+    }
+
+    fn find_by_note_id_and_revision(
+        &self,
+        note_id: i32,
+        revision: i32,
+    ) -> RepositoryResult<NoteRevision> {
+        Ok(NoteRevision::find_by_note_id_and_revision(
+            &self.connection()?,
+            note_id,
+            revision,
+        )?)
+    }
+}
+
+impl SessionRepository for DBRepository {
+    fn find_by_jti(&self, jti: &str) -> RepositoryResult<Session> {
+        Ok(Session::find_by_jti(&self.connection()?, jti)?)
+    }
+
+    fn find_by_refresh_token_hash(&self, refresh_token_hash: &str) -> RepositoryResult<Session> {
+        Ok(Session::find_by_refresh_token_hash(
+            &self.connection()?,
+            refresh_token_hash,
+        )?)
+    }
+
+    fn create(&self, session: &NewSession) -> RepositoryResult<Session> {
+        Ok(session.create(&self.connection()?)?)
+    }
+
+    fn rotate(
+        &self,
+        session: &Session,
+        jti: &str,
+        refresh_token_hash: &str,
+        expire_time: NaiveDateTime,
+    ) -> RepositoryResult<Session> {
+        Ok(Session::rotate(
+            &self.connection()?,
+            session,
+            jti,
+            refresh_token_hash,
+            expire_time,
+        )?)
+    }
+
+    fn revoke(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(Session::revoke(&self.connection()?, id)?)
+    }
+
+    fn revoke_by_user_id(&self, user_id: i32) -> RepositoryResult<usize> {
+        Ok(Session::revoke_by_user_id(&self.connection()?, user_id)?)
+    }
+}
+
+impl ApiKeyRepository for DBRepository {
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<ApiKey>> {
+        Ok(ApiKey::find_by_user_id(&self.connection()?, user_id)?)
+    }
+
+    fn find_by_key_hash(&self, key_hash: &str) -> RepositoryResult<ApiKey> {
+        Ok(ApiKey::find_by_key_hash(&self.connection()?, key_hash)?)
+    }
+
+    fn create(&self, api_key: &NewApiKey) -> RepositoryResult<ApiKey> {
+        Ok(api_key.create(&self.connection()?)?)
+    }
+
+    fn delete(&self, id: i32, user_id: i32) -> RepositoryResult<usize> {
+        Ok(ApiKey::delete_by_id_and_user_id(
+            &self.connection()?,
+            id,
+            user_id,
+        )?)
+    }
+}
+
+impl TwoFactorRepository for DBRepository {
+    fn find_totp(&self, user_id: i32) -> RepositoryResult<TotpSecret> {
+        Ok(TotpSecret::find_by_user_id(&self.connection()?, user_id)?)
+    }
+
+    fn create_totp(
+        &self,
+        totp_secret: &NewTotpSecret,
+        code_hashes: &[String],
+    ) -> RepositoryResult<TotpSecret> {
+        Ok(totp_secret.create(&self.connection()?, code_hashes)?)
+    }
+
+    fn enable_totp(&self, user_id: i32, step: i64) -> RepositoryResult<usize> {
+        Ok(TotpSecret::enable(&self.connection()?, user_id, step)?)
+    }
+
+    fn use_totp_step(&self, user_id: i32, step: i64) -> RepositoryResult<usize> {
+        Ok(TotpSecret::use_step(&self.connection()?, user_id, step)?)
+    }
+
+    fn add_failed_code(
+        &self,
+        user_id: i32,
+        max_failed: i32,
+        locked_until: NaiveDateTime,
+    ) -> RepositoryResult<TotpSecret> {
+        Ok(TotpSecret::add_failed_code(
+            &self.connection()?,
+            user_id,
+            max_failed,
+            locked_until,
+        )?)
+    }
+
+    fn reset_failed_codes(&self, user_id: i32) -> RepositoryResult<usize> {
+        Ok(TotpSecret::reset_failed_codes(
+            &self.connection()?,
+            user_id,
+        )?)
+    }
+
+    fn delete_totp(&self, user_id: i32) -> RepositoryResult<usize> {
+        Ok(TotpSecret::delete_by_user_id(&self.connection()?, user_id)?)
+    }
+
+    fn use_recovery_code(&self, user_id: i32, code_hash: &str) -> RepositoryResult<usize> {
+        Ok(RecoveryCode::use_code(
+            &self.connection()?,
+            user_id,
+            code_hash,
+        )?)
+    }
+
+    fn create_challenge(&self, challenge: &NewLoginChallenge) -> RepositoryResult<LoginChallenge> {
+        Ok(challenge.create(&self.connection()?)?)
+    }
+
+    fn find_challenge(&self, token_hash: &str) -> RepositoryResult<LoginChallenge> {
+        Ok(LoginChallenge::find_by_token_hash(
+            &self.connection()?,
+            token_hash,
+        )?)
+    }
+
+    fn add_challenge_attempt(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(LoginChallenge::add_attempt(&self.connection()?, id)?)
+    }
+
+    fn delete_challenge(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(LoginChallenge::delete(&self.connection()?, id)?)
+    }
+}
+
+impl IdentityRepository for DBRepository {
+    fn find_by_subject(&self, issuer: &str, subject: &str) -> RepositoryResult<UserIdentity> {
+        Ok(UserIdentity::find_by_subject(
+            &self.connection()?,
+            issuer,
+            subject,
+        )?)
+    }
+
+    fn create(&self, identity: &NewUserIdentity) -> RepositoryResult<UserIdentity> {
+        Ok(identity.create(&self.connection()?)?)
+    }
+}
+
+impl ShareRepository for DBRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteShare>> {
+        Ok(NoteShare::find_by_note_id(&self.connection()?, note_id)?)
+    }
+
+    fn find_by_note_id_and_user_id(
+        &self,
+        note_id: i32,
+        user_id: i32,
+    ) -> RepositoryResult<NoteShare> {
+        Ok(NoteShare::find_by_note_id_and_user_id(
+            &self.connection()?,
+            note_id,
+            user_id,
+        )?)
+    }
+
+    fn find_note_by_note_id_and_user_id(
+        &self,
+        note_id: i32,
+        user_id: i32,
+    ) -> RepositoryResult<(Note, NoteShare)> {
+        Ok(NoteShare::find_note_by_note_id_and_user_id(
+            &self.connection()?,
+            note_id,
+            user_id,
+        )?)
+    }
+
+    fn find_notes_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<(Note, NoteShare)>> {
+        Ok(NoteShare::find_notes_by_user_id(
+            &self.connection()?,
+            user_id,
+        )?)
+    }
+
+    fn create(&self, note_share: &NewNoteShare) -> RepositoryResult<NoteShare> {
+        Ok(note_share.create(&self.connection()?)?)
+    }
+
+    fn delete(&self, note_id: i32, user_id: i32) -> RepositoryResult<usize> {
+        Ok(NoteShare::delete_by_note_id_and_user_id(
+            &self.connection()?,
+            note_id,
+            user_id,
+        )?)
+    }
+}
+
+impl PublicLinkRepository for DBRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<PublicLink>> {
+        Ok(PublicLink::find_by_note_id(&self.connection()?, note_id)?)
+    }
+
+    fn find_by_token_hash(&self, token_hash: &str) -> RepositoryResult<(Note, PublicLink)> {
+        Ok(PublicLink::find_by_token_hash(
+            &self.connection()?,
+            token_hash,
+        )?)
+    }
+
+    fn create(&self, public_link: &NewPublicLink) -> RepositoryResult<PublicLink> {
+        Ok(public_link.create(&self.connection()?)?)
+    }
+
+    fn delete(&self, id: i32, note_id: i32) -> RepositoryResult<usize> {
+        Ok(PublicLink::delete_by_id_and_note_id(
+            &self.connection()?,
+            id,
+            note_id,
+        )?)
+    }
+}
+
+impl AttachmentRepository for DBRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<Attachment>> {
+        Ok(Attachment::find_by_note_id(&self.connection()?, note_id)?)
+    }
+
+    fn find_by_id_and_note_id(&self, id: i32, note_id: i32) -> RepositoryResult<Attachment> {
+        Ok(Attachment::find_by_id_and_note_id(
+            &self.connection()?,
+            id,
+            note_id,
+        )?)
+    }
+
+    fn total_size_by_user_id(&self, user_id: i32) -> RepositoryResult<i64> {
+        Ok(Attachment::total_size_by_user_id(
+            &self.connection()?,
+            user_id,
+        )?)
+    }
+
+    fn create(&self, attachment: &NewAttachment) -> RepositoryResult<Attachment> {
+        Ok(attachment.create(&self.connection()?)?)
+    }
+
+    fn create_within_quota(
+        &self,
+        attachment: &NewAttachment,
+        user_id: i32,
+        quota: i64,
+    ) -> RepositoryResult<Attachment> {
+        let connection = self.connection()?;
+
+        connection.transaction::<_, RepositoryError, _>(|| {
+            User::lock(&connection, user_id)?;
+            check_quota(
+                Attachment::total_size_by_user_id(&connection, user_id)?,
+                attachment.size,
+                quota,
+            )?;
+            Ok(attachment.create(&connection)?)
+        })
+    }
+
+    fn delete(&self, id: i32, note_id: i32) -> RepositoryResult<usize> {
+        Ok(Attachment::delete_by_id_and_note_id(
+            &self.connection()?,
+            id,
+            note_id,
+        )?)
+    }
+
+    fn find_orphans(&self, limit: i64) -> RepositoryResult<Vec<Attachment>> {
+        Ok(Attachment::find_orphans(&self.connection()?, limit)?)
+    }
+
+    fn delete_orphan(&self, id: i32) -> RepositoryResult<usize> {
+        Ok(Attachment::delete_orphan(&self.connection()?, id)?)
+    }
+}
+
+impl LinkRepository for DBRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteLink>> {
+        Ok(NoteLink::find_by_note_id(&self.connection()?, note_id)?)
+    }
+
+    fn find_by_note_ids(&self, note_ids: &[i32]) -> RepositoryResult<Vec<NoteLink>> {
+        Ok(NoteLink::find_by_note_ids(&self.connection()?, note_ids)?)
+    }
+
+    fn find_by_target(
+        &self,
+        user_id: i32,
+        target_id: i32,
+        target_title: &str,
+    ) -> RepositoryResult<Vec<NoteLink>> {
+        Ok(NoteLink::find_by_target(
+            &self.connection()?,
+            user_id,
+            target_id,
+            target_title,
+        )?)
+    }
+}
@@ -0,0 +1,1695 @@
+use super::*;
+use crate::encryption;
+use crate::models::db::note_link::{self, NewNoteLink};
+use crate::models::db::two_factor::RecoveryCode;
+use crate::utils::now;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+
+struct Table<T> {
+    rows: BTreeMap<i32, T>,
+    last_id: i32,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table {
+            rows: BTreeMap::new(),
+            last_id: 0,
+        }
+    }
+}
+
+impl<T: Clone> Table<T> {
+    fn next_id(&mut self) -> i32 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn get(&self, id: i32) -> RepositoryResult<T> {
+        self.rows.get(&id).cloned().ok_or(RepositoryError::NotFound)
+    }
+
+    fn all(&self) -> Vec<T> {
+        self.rows.values().cloned().collect()
+    }
+
+    fn remove(&mut self, id: i32) -> usize {
+        self.rows.remove(&id).map_or(0, |_| 1)
+    }
+}
+
+#[derive(Default)]
+struct Store {
+    users: Table<User>,
+    categories: Table<Category>,
+    notes: Table<Note>,
+    tags: Table<Tag>,
+    note_revisions: Table<NoteRevision>,
+    sessions: Table<Session>,
+    api_keys: Table<ApiKey>,
+    totp_secrets: Table<TotpSecret>,
+    recovery_codes: Table<RecoveryCode>,
+    login_challenges: Table<LoginChallenge>,
+    user_identities: Table<UserIdentity>,
+    note_shares: Table<NoteShare>,
+    public_links: Table<PublicLink>,
+    attachments: Table<Attachment>,
+    note_links: Table<NoteLink>,
+    // Pairs of note id and tag id.
+    note_tags: BTreeSet<(i32, i32)>,
+}
+
+impl Store {
+    fn note_ids_by_tag_names(&self, user_id: i32, names: &[String]) -> BTreeSet<i32> {
+        let names = Tag::normalize_names(names);
+        let tag_ids: Vec<i32> = self
+            .tags
+            .rows
+            .values()
+            .filter(|tag| tag.user_id == user_id && names.contains(&tag.name))
+            .map(|tag| tag.id)
+            .collect();
+
+        self.note_tags
+            .iter()
+            .map(|(note_id, _)| *note_id)
+            .filter(|note_id| {
+                tag_ids.len() == names.len()
+                    && tag_ids
+                        .iter()
+                        .all(|tag_id| self.note_tags.contains(&(*note_id, *tag_id)))
+            })
+            .collect()
+    }
+
+    fn add_note_tag(&mut self, user_id: i32, note_id: i32, name: &str) {
+        let tag_id = match self
+            .tags
+            .rows
+            .values()
+            .find(|tag| tag.user_id == user_id && tag.name == name)
+        {
+            Some(tag) => tag.id,
+            None => {
+                let tag = Tag {
+                    id: self.tags.next_id(),
+                    user_id: user_id,
+                    name: name.to_string(),
+                    create_time: now(),
+                };
+                self.tags.rows.insert(tag.id, tag.clone());
+                tag.id
+            }
+        };
+
+        self.note_tags.insert((note_id, tag_id));
+    }
+
+    // Whether other category of the user, or other shared category, has the
+    // name, like the unique indexes of the database.
+    fn category_exists(&self, except_id: Option<i32>, user_id: Option<i32>, name: &str) -> bool {
+        self.categories.rows.values().any(|category| {
+            Some(category.id) != except_id && category.user_id == user_id && category.name == name
+        })
+    }
+
+    // Removes the note along with its tags, revisions, shares, public links
+    // and wiki links. Its attachments are left orphaned.
+    fn remove_note(&mut self, note_id: i32) -> usize {
+        for attachment in self.attachments.rows.values_mut() {
+            if attachment.note_id == Some(note_id) {
+                attachment.note_id = None;
+            }
+        }
+        self.note_tags.retain(|(other_id, _)| *other_id != note_id);
+        self.note_links
+            .rows
+            .retain(|_, link| link.note_id != note_id);
+        self.public_links
+            .rows
+            .retain(|_, public_link| public_link.note_id != note_id);
+        self.note_shares
+            .rows
+            .retain(|_, note_share| note_share.note_id != note_id);
+        self.note_revisions
+            .rows
+            .retain(|_, revision| revision.note_id != note_id);
+        self.notes.remove(note_id)
+    }
+
+    // Bytes attached to the notes of the user, like the database query.
+    fn attachments_size(&self, user_id: i32) -> i64 {
+        self.attachments
+            .rows
+            .values()
+            .filter(|attachment| {
+                attachment
+                    .note_id
+                    .and_then(|note_id| self.notes.rows.get(&note_id))
+                    .map_or(false, |note| note.user_id == user_id)
+            })
+            .map(|attachment| attachment.size)
+            .sum()
+    }
+
+    fn insert_attachment(&mut self, attachment: &NewAttachment) -> RepositoryResult<Attachment> {
+        if let Some(note_id) = attachment.note_id {
+            if !self.notes.rows.contains_key(&note_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "note {} does not exist",
+                    note_id
+                )));
+            }
+        }
+        if self
+            .attachments
+            .rows
+            .values()
+            .any(|other| other.storage_key == attachment.storage_key)
+        {
+            return Err(RepositoryError::Conflict(
+                "Attachment already exists".to_string(),
+            ));
+        }
+
+        let attachment = Attachment {
+            id: self.attachments.next_id(),
+            note_id: attachment.note_id,
+            file_name: attachment.file_name.to_string(),
+            content_type: attachment.content_type.to_string(),
+            size: attachment.size,
+            storage_key: attachment.storage_key.to_string(),
+            create_time: now(),
+        };
+        self.attachments
+            .rows
+            .insert(attachment.id, attachment.clone());
+
+        Ok(attachment)
+    }
+
+    // The note if it is out of the trash.
+    fn active_note(&self, id: i32) -> RepositoryResult<Note> {
+        self.notes.get(id).and_then(|note| match note.deleted_time {
+            None => Ok(note),
+            Some(_) => Err(RepositoryError::NotFound),
+        })
+    }
+
+    fn set_note_tags(&mut self, user_id: i32, note_id: i32, names: &[String]) {
+        self.note_tags.retain(|(other_id, _)| *other_id != note_id);
+        for name in Tag::normalize_names(names).iter() {
+            self.add_note_tag(user_id, note_id, name);
+        }
+    }
+
+    fn set_note_links(&mut self, note: &Note) -> RepositoryResult<()> {
+        let keyring =
+            encryption::keyring().map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+
+        self.note_links
+            .rows
+            .retain(|_, link| link.note_id != note.id);
+        for new_link in NewNoteLink::from_note(note, keyring) {
+            let link = NoteLink {
+                id: self.note_links.next_id(),
+                note_id: new_link.note_id,
+                target_id: new_link.target_id,
+                target_title: new_link.target_title,
+            };
+            self.note_links.rows.insert(link.id, link);
+        }
+
+        Ok(())
+    }
+}
+
+/// Thread-safe `Repository` that keeps every model in memory.
+///
+/// It mimics the constraints of the database schema, so it is a drop-in
+/// replacement of `DBRepository` for tests.
+#[derive(Default)]
+pub struct MemoryRepository {
+    store: RwLock<Store>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn read<T, F>(&self, f: F) -> RepositoryResult<T>
+    where
+        F: FnOnce(&Store) -> RepositoryResult<T>,
+    {
+        let store = self
+            .store
+            .read()
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        f(&store)
+    }
+
+    fn write<T, F>(&self, f: F) -> RepositoryResult<T>
+    where
+        F: FnOnce(&mut Store) -> RepositoryResult<T>,
+    {
+        let mut store = self
+            .store
+            .write()
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        f(&mut store)
+    }
+}
+
+fn check_note_references(
+    store: &Store,
+    user_id: i32,
+    category_id: Option<i32>,
+) -> RepositoryResult<()> {
+    if !store.users.rows.contains_key(&user_id) {
+        return Err(RepositoryError::InvalidReference(format!(
+            "user {} does not exist",
+            user_id
+        )));
+    }
+    match category_id {
+        Some(category_id) if !store.categories.rows.contains_key(&category_id) => Err(
+            RepositoryError::InvalidReference(format!("category {} does not exist", category_id)),
+        ),
+        _ => Ok(()),
+    }
+}
+
+impl Repository for MemoryRepository {
+    fn notes(&self) -> &dyn NoteRepository {
+        self
+    }
+
+    fn users(&self) -> &dyn UserRepository {
+        self
+    }
+
+    fn categories(&self) -> &dyn CategoryRepository {
+        self
+    }
+
+    fn tags(&self) -> &dyn TagRepository {
+        self
+    }
+
+    fn revisions(&self) -> &dyn RevisionRepository {
+        self
+    }
+
+    fn sessions(&self) -> &dyn SessionRepository {
+        self
+    }
+
+    fn api_keys(&self) -> &dyn ApiKeyRepository {
+        self
+    }
+
+    fn two_factor(&self) -> &dyn TwoFactorRepository {
+        self
+    }
+
+    fn identities(&self) -> &dyn IdentityRepository {
+        self
+    }
+
+    fn shares(&self) -> &dyn ShareRepository {
+        self
+    }
+
+    fn public_links(&self) -> &dyn PublicLinkRepository {
+        self
+    }
+
+    fn attachments(&self) -> &dyn AttachmentRepository {
+        self
+    }
+
+    fn links(&self) -> &dyn LinkRepository {
+        self
+    }
+}
+
+impl NoteRepository for MemoryRepository {
+    fn find_by_user_id(&self, user_id: i32, filter: &NoteFilter) -> RepositoryResult<NotePage> {
+        self.read(|store| {
+            let tagged = if Tag::normalize_names(&filter.tags).is_empty() {
+                None
+            } else {
+                Some(store.note_ids_by_tag_names(user_id, &filter.tags))
+            };
+            let categorized = match filter.category_id {
+                Some(category_id) if filter.descendants => Some(Category::descendant_ids(
+                    &store.categories.all(),
+                    category_id,
+                )),
+                _ => None,
+            };
+
+            let mut notes: Vec<Note> = store
+                .notes
+                .all()
+                .into_iter()
+                .filter(|note| note.user_id == user_id && note.deleted_time.is_none())
+                .filter(|note| tagged.as_ref().map_or(true, |ids| ids.contains(&note.id)))
+                .filter(|note| {
+                    categorized.as_ref().map_or(true, |ids| {
+                        note.category_id.map_or(false, |id| ids.contains(&id))
+                    })
+                })
+                .filter(|note| filter.accepts(note))
+                .collect();
+            notes.sort_by(|a, b| filter.compare(a, b));
+
+            Ok(NotePage::new(notes, filter))
+        })
+    }
+
+    fn find_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Note> {
+        self.read(|store| {
+            store.active_note(id).and_then(|note| {
+                if note.user_id == user_id {
+                    Ok(note)
+                } else {
+                    Err(RepositoryError::NotFound)
+                }
+            })
+        })
+    }
+
+    fn search(
+        &self,
+        user_id: i32,
+        query: &str,
+        limit: i64,
+    ) -> RepositoryResult<Vec<NoteSearchHit>> {
+        self.read(|store| {
+            let notes = store
+                .notes
+                .all()
+                .into_iter()
+                .filter(|note| note.user_id == user_id && note.deleted_time.is_none())
+                .collect();
+
+            Ok(NoteSearchHit::search_in(notes, query, limit))
+        })
+    }
+
+    fn create(&self, note: &NewNote, tags: &[String]) -> RepositoryResult<Note> {
+        self.write(|store| {
+            check_note_references(store, note.user_id, note.category_id)?;
+
+            let time = now();
+            let note = Note {
+                id: store.notes.next_id(),
+                user_id: note.user_id,
+                category_id: note.category_id,
+                title: note.title.clone(),
+                data: note.data.clone(),
+                create_time: time,
+                update_time: time,
+                deleted_time: None,
+                format: note.format.clone(),
+                key_check: note.key_check.clone(),
+            };
+            store.notes.rows.insert(note.id, note.clone());
+            store.set_note_tags(note.user_id, note.id, tags);
+            store.set_note_links(&note)?;
+
+            Ok(note)
+        })
+    }
+
+    fn update(&self, note: &Note, tags: &[String]) -> RepositoryResult<Note> {
+        self.write(|store| {
+            check_note_references(store, note.user_id, note.category_id)?;
+
+            let mut current = store.active_note(note.id)?;
+
+            let last = store
+                .note_revisions
+                .rows
+                .values()
+                .filter(|revision| revision.note_id == current.id)
+                .map(|revision| revision.revision)
+                .max();
+            let revision = NoteRevision {
+                id: store.note_revisions.next_id(),
+                note_id: current.id,
+                revision: last.unwrap_or(0) + 1,
+                category_id: current.category_id,
+                title: current.title.clone(),
+                data: current.data.clone(),
+                create_time: now(),
+                key_check: current.key_check.clone(),
+            };
+            store.note_revisions.rows.insert(revision.id, revision);
+
+            current.user_id = note.user_id;
+            current.category_id = note.category_id;
+            current.title = note.title.clone();
+            current.data = note.data.clone();
+            current.format = note.format.clone();
+            current.key_check = note.key_check.clone();
+            current.update_time = now();
+            store.notes.rows.insert(current.id, current.clone());
+            store.set_note_tags(current.user_id, current.id, tags);
+            store.set_note_links(&current)?;
+
+            Ok(current)
+        })
+    }
+
+    fn trash(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| match store.active_note(id) {
+            Ok(mut note) => {
+                note.deleted_time = Some(now());
+                store.notes.rows.insert(note.id, note);
+                Ok(1)
+            }
+            Err(_) => Ok(0),
+        })
+    }
+
+    fn find_trashed_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<Note>> {
+        self.read(|store| {
+            let mut notes: Vec<Note> = store
+                .notes
+                .all()
+                .into_iter()
+                .filter(|note| note.user_id == user_id && note.deleted_time.is_some())
+                .collect();
+            notes.sort_by(|a, b| (b.deleted_time, b.id).cmp(&(a.deleted_time, a.id)));
+
+            Ok(notes)
+        })
+    }
+
+    fn find_trashed_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Note> {
+        self.read(|store| {
+            store.notes.get(id).and_then(|note| {
+                if note.user_id == user_id && note.deleted_time.is_some() {
+                    Ok(note)
+                } else {
+                    Err(RepositoryError::NotFound)
+                }
+            })
+        })
+    }
+
+    fn restore(&self, id: i32) -> RepositoryResult<Note> {
+        self.write(|store| {
+            let mut note = store.notes.get(id)?;
+            if note.deleted_time.is_none() {
+                return Err(RepositoryError::NotFound);
+            }
+
+            note.deleted_time = None;
+            store.notes.rows.insert(note.id, note.clone());
+
+            Ok(note)
+        })
+    }
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| Ok(store.remove_note(id)))
+    }
+
+    fn purge(&self, before: NaiveDateTime) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let ids: Vec<i32> = store
+                .notes
+                .rows
+                .values()
+                .filter(|note| note.deleted_time.map_or(false, |time| time < before))
+                .map(|note| note.id)
+                .collect();
+
+            Ok(ids.iter().map(|id| store.remove_note(*id)).sum())
+        })
+    }
+}
+
+impl UserRepository for MemoryRepository {
+    fn find_all(&self) -> RepositoryResult<Vec<User>> {
+        self.read(|store| Ok(store.users.all()))
+    }
+
+    fn find_by_id(&self, id: i32) -> RepositoryResult<User> {
+        self.read(|store| store.users.get(id))
+    }
+
+    fn find_by_email(&self, email: &str) -> RepositoryResult<User> {
+        self.read(|store| {
+            store
+                .users
+                .all()
+                .into_iter()
+                .find(|user| user.email == email)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn create(&self, user: &NewUser) -> RepositoryResult<User> {
+        self.write(|store| {
+            if store
+                .users
+                .rows
+                .values()
+                .any(|u| u.name == user.name || u.email == user.email)
+            {
+                return Err(RepositoryError::Conflict(format!(
+                    "user {} already exists",
+                    user.name
+                )));
+            }
+
+            let time = now();
+            let user = User {
+                id: store.users.next_id(),
+                email: user.email.to_string(),
+                name: user.name.to_string(),
+                password: user.password.to_string(),
+                create_time: time,
+                update_time: time,
+                role: user.role.to_string(),
+                disabled: false,
+            };
+            store.users.rows.insert(user.id, user.clone());
+
+            Ok(user)
+        })
+    }
+
+    fn update(&self, user: &User) -> RepositoryResult<User> {
+        self.write(|store| {
+            if store
+                .users
+                .rows
+                .values()
+                .any(|u| u.id != user.id && (u.name == user.name || u.email == user.email))
+            {
+                return Err(RepositoryError::Conflict(format!(
+                    "user {} already exists",
+                    user.name
+                )));
+            }
+
+            let mut current = store.users.get(user.id)?;
+            current.email = user.email.clone();
+            current.name = user.name.clone();
+            current.password = user.password.clone();
+            current.role = user.role.clone();
+            current.disabled = user.disabled;
+            current.update_time = now();
+            store.users.rows.insert(current.id, current.clone());
+
+            Ok(current)
+        })
+    }
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            if store.notes.rows.values().any(|note| note.user_id == id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "user {} has notes",
+                    id
+                )));
+            }
+
+            store.tags.rows.retain(|_, tag| tag.user_id != id);
+            store
+                .sessions
+                .rows
+                .retain(|_, session| session.user_id != id);
+            store
+                .api_keys
+                .rows
+                .retain(|_, api_key| api_key.user_id != id);
+            store
+                .totp_secrets
+                .rows
+                .retain(|_, totp_secret| totp_secret.user_id != id);
+            store
+                .recovery_codes
+                .rows
+                .retain(|_, recovery_code| recovery_code.user_id != id);
+            store
+                .login_challenges
+                .rows
+                .retain(|_, challenge| challenge.user_id != id);
+            store
+                .user_identities
+                .rows
+                .retain(|_, identity| identity.user_id != id);
+            store
+                .note_shares
+                .rows
+                .retain(|_, note_share| note_share.user_id != id);
+            store
+                .categories
+                .rows
+                .retain(|_, category| category.user_id != Some(id));
+            Ok(store.users.remove(id))
+        })
+    }
+}
+
+impl CategoryRepository for MemoryRepository {
+    fn find_all(&self) -> RepositoryResult<Vec<Category>> {
+        self.read(|store| Ok(store.categories.all()))
+    }
+
+    fn find_by_id(&self, id: i32) -> RepositoryResult<Category> {
+        self.read(|store| store.categories.get(id))
+    }
+
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<Category>> {
+        self.read(|store| {
+            Ok(store
+                .categories
+                .all()
+                .into_iter()
+                .filter(|category| category.is_visible_to(user_id))
+                .collect())
+        })
+    }
+
+    fn find_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Category> {
+        self.read(|store| {
+            store.categories.get(id).and_then(|category| {
+                if category.is_visible_to(user_id) {
+                    Ok(category)
+                } else {
+                    Err(RepositoryError::NotFound)
+                }
+            })
+        })
+    }
+
+    fn find_descendant_ids(&self, id: i32) -> RepositoryResult<Vec<i32>> {
+        self.read(|store| {
+            store.categories.get(id)?;
+            Ok(Category::descendant_ids(&store.categories.all(), id))
+        })
+    }
+
+    fn create(&self, category: &NewCategory) -> RepositoryResult<Category> {
+        self.write(|store| {
+            if let Some(user_id) = category.user_id {
+                if !store.users.rows.contains_key(&user_id) {
+                    return Err(RepositoryError::InvalidReference(format!(
+                        "user {} does not exist",
+                        user_id
+                    )));
+                }
+            }
+            check_category_parent(
+                &store.categories.all(),
+                None,
+                category.user_id,
+                category.parent_id,
+            )?;
+            if store.category_exists(None, category.user_id, category.name) {
+                return Err(RepositoryError::Conflict(format!(
+                    "category {} already exists",
+                    category.name
+                )));
+            }
+
+            let time = now();
+            let category = Category {
+                id: store.categories.next_id(),
+                user_id: category.user_id,
+                parent_id: category.parent_id,
+                name: category.name.to_string(),
+                create_time: time,
+                update_time: time,
+            };
+            store.categories.rows.insert(category.id, category.clone());
+
+            Ok(category)
+        })
+    }
+
+    fn update(&self, category: &Category) -> RepositoryResult<Category> {
+        self.write(|store| {
+            let mut current = store.categories.get(category.id)?;
+            check_category_parent(
+                &store.categories.all(),
+                Some(current.id),
+                current.user_id,
+                category.parent_id,
+            )?;
+            if store.category_exists(Some(current.id), current.user_id, &category.name) {
+                return Err(RepositoryError::Conflict(format!(
+                    "category {} already exists",
+                    category.name
+                )));
+            }
+
+            current.parent_id = category.parent_id;
+            current.name = category.name.clone();
+            current.update_time = now();
+            store.categories.rows.insert(current.id, current.clone());
+
+            Ok(current)
+        })
+    }
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            for note in store.notes.rows.values_mut() {
+                if note.category_id == Some(id) {
+                    note.category_id = None;
+                }
+            }
+            for revision in store.note_revisions.rows.values_mut() {
+                if revision.category_id == Some(id) {
+                    revision.category_id = None;
+                }
+            }
+            for category in store.categories.rows.values_mut() {
+                if category.parent_id == Some(id) {
+                    category.parent_id = None;
+                }
+            }
+
+            Ok(store.categories.remove(id))
+        })
+    }
+}
+
+impl TagRepository for MemoryRepository {
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<(Tag, i64)>> {
+        self.read(|store| {
+            let mut tags: Vec<(Tag, i64)> = store
+                .tags
+                .all()
+                .into_iter()
+                .filter(|tag| tag.user_id == user_id)
+                .map(|tag| {
+                    let num = store
+                        .note_tags
+                        .iter()
+                        .filter(|(note_id, tag_id)| {
+                            *tag_id == tag.id && store.active_note(*note_id).is_ok()
+                        })
+                        .count();
+                    (tag, num as i64)
+                })
+                .collect();
+            tags.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+
+            Ok(tags)
+        })
+    }
+
+    fn find_by_note_ids(&self, note_ids: &[i32]) -> RepositoryResult<Vec<(i32, String)>> {
+        self.read(|store| {
+            let mut names: Vec<(i32, String)> = store
+                .note_tags
+                .iter()
+                .filter(|(note_id, _)| note_ids.contains(note_id))
+                .map(|(note_id, tag_id)| Ok((*note_id, store.tags.get(*tag_id)?.name)))
+                .collect::<RepositoryResult<_>>()?;
+            names.sort();
+
+            Ok(names)
+        })
+    }
+
+    fn add(&self, user_id: i32, note_id: i32, name: &str) -> RepositoryResult<()> {
+        self.write(|store| {
+            if !store.notes.rows.contains_key(&note_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "note {} does not exist",
+                    note_id
+                )));
+            }
+
+            store.add_note_tag(user_id, note_id, name);
+            Ok(())
+        })
+    }
+
+    fn remove(&self, user_id: i32, note_id: i32, name: &str) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let tag_id = store
+                .tags
+                .rows
+                .values()
+                .find(|tag| tag.user_id == user_id && tag.name == name)
+                .map(|tag| tag.id);
+
+            Ok(tag_id.map_or(0, |tag_id| {
+                store.note_tags.remove(&(note_id, tag_id)) as usize
+            }))
+        })
+    }
+}
+
+impl RevisionRepository for MemoryRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteRevision>> {
+        self.read(|store| {
+            let mut revisions: Vec<NoteRevision> = store
+                .note_revisions
+                .all()
+                .into_iter()
+                .filter(|revision| revision.note_id == note_id)
+                .collect();
+            revisions.sort();
+
+            Ok(revisions)
+        })
+    }
+
+    fn find_by_note_id_and_revision(
+        &self,
+        note_id: i32,
+        revision: i32,
+    ) -> RepositoryResult<NoteRevision> {
+        self.read(|store| {
+            store
+                .note_revisions
+                .all()
+                .into_iter()
+                .find(|r| r.note_id == note_id && r.revision == revision)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+}
+
+impl SessionRepository for MemoryRepository {
+    fn find_by_jti(&self, jti: &str) -> RepositoryResult<Session> {
+        self.read(|store| {
+            store
+                .sessions
+                .all()
+                .into_iter()
+                .find(|session| session.jti == jti)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn find_by_refresh_token_hash(&self, refresh_token_hash: &str) -> RepositoryResult<Session> {
+        self.read(|store| {
+            store
+                .sessions
+                .all()
+                .into_iter()
+                .find(|session| session.refresh_token_hash == refresh_token_hash)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn create(&self, session: &NewSession) -> RepositoryResult<Session> {
+        self.write(|store| {
+            if !store.users.rows.contains_key(&session.user_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "user {} does not exist",
+                    session.user_id
+                )));
+            }
+            if store.sessions.rows.values().any(|other| {
+                other.jti == session.jti || other.refresh_token_hash == session.refresh_token_hash
+            }) {
+                return Err(RepositoryError::Conflict(
+                    "session token already exists".to_string(),
+                ));
+            }
+
+            let time = now();
+            let session = Session {
+                id: store.sessions.next_id(),
+                user_id: session.user_id,
+                jti: session.jti.to_string(),
+                refresh_token_hash: session.refresh_token_hash.to_string(),
+                expire_time: session.expire_time,
+                revoked_time: None,
+                create_time: time,
+                update_time: time,
+            };
+            store.sessions.rows.insert(session.id, session.clone());
+
+            Ok(session)
+        })
+    }
+
+    fn rotate(
+        &self,
+        session: &Session,
+        jti: &str,
+        refresh_token_hash: &str,
+        expire_time: NaiveDateTime,
+    ) -> RepositoryResult<Session> {
+        self.write(|store| {
+            let mut current = store.sessions.get(session.id)?;
+            if current.refresh_token_hash != session.refresh_token_hash
+                || current.revoked_time.is_some()
+            {
+                return Err(RepositoryError::NotFound);
+            }
+
+            current.jti = jti.to_string();
+            current.refresh_token_hash = refresh_token_hash.to_string();
+            current.expire_time = expire_time;
+            current.update_time = now();
+            store.sessions.rows.insert(current.id, current.clone());
+
+            Ok(current)
+        })
+    }
+
+    fn revoke(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| match store.sessions.rows.get_mut(&id) {
+            Some(session) if session.revoked_time.is_none() => {
+                let time = now();
+                session.revoked_time = Some(time);
+                session.update_time = time;
+                Ok(1)
+            }
+            _ => Ok(0),
+        })
+    }
+
+    fn revoke_by_user_id(&self, user_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let time = now();
+            let mut num = 0;
+            for session in store.sessions.rows.values_mut() {
+                if session.user_id == user_id && session.revoked_time.is_none() {
+                    session.revoked_time = Some(time);
+                    session.update_time = time;
+                    num += 1;
+                }
+            }
+            Ok(num)
+        })
+    }
+}
+
+impl ApiKeyRepository for MemoryRepository {
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<ApiKey>> {
+        self.read(|store| {
+            Ok(store
+                .api_keys
+                .all()
+                .into_iter()
+                .filter(|api_key| api_key.user_id == user_id)
+                .collect())
+        })
+    }
+
+    fn find_by_key_hash(&self, key_hash: &str) -> RepositoryResult<ApiKey> {
+        self.read(|store| {
+            store
+                .api_keys
+                .all()
+                .into_iter()
+                .find(|api_key| api_key.key_hash == key_hash)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn create(&self, api_key: &NewApiKey) -> RepositoryResult<ApiKey> {
+        self.write(|store| {
+            if !store.users.rows.contains_key(&api_key.user_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "user {} does not exist",
+                    api_key.user_id
+                )));
+            }
+            if store
+                .api_keys
+                .rows
+                .values()
+                .any(|other| other.key_hash == api_key.key_hash)
+            {
+                return Err(RepositoryError::Conflict(
+                    "API key already exists".to_string(),
+                ));
+            }
+
+            let api_key = ApiKey {
+                id: store.api_keys.next_id(),
+                user_id: api_key.user_id,
+                name: api_key.name.to_string(),
+                prefix: api_key.prefix.to_string(),
+                key_hash: api_key.key_hash.to_string(),
+                read_only: api_key.read_only,
+                expire_time: api_key.expire_time,
+                create_time: now(),
+            };
+            store.api_keys.rows.insert(api_key.id, api_key.clone());
+
+            Ok(api_key)
+        })
+    }
+
+    fn delete(&self, id: i32, user_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let owned = store
+                .api_keys
+                .rows
+                .get(&id)
+                .map_or(false, |api_key| api_key.user_id == user_id);
+
+            Ok(if owned { store.api_keys.remove(id) } else { 0 })
+        })
+    }
+}
+
+impl TwoFactorRepository for MemoryRepository {
+    fn find_totp(&self, user_id: i32) -> RepositoryResult<TotpSecret> {
+        self.read(|store| {
+            store
+                .totp_secrets
+                .all()
+                .into_iter()
+                .find(|totp_secret| totp_secret.user_id == user_id)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn create_totp(
+        &self,
+        totp_secret: &NewTotpSecret,
+        code_hashes: &[String],
+    ) -> RepositoryResult<TotpSecret> {
+        self.write(|store| {
+            store.users.get(totp_secret.user_id).map_err(|_| {
+                RepositoryError::InvalidReference(format!(
+                    "user {} does not exist",
+                    totp_secret.user_id
+                ))
+            })?;
+
+            let user_id = totp_secret.user_id;
+            store
+                .totp_secrets
+                .rows
+                .retain(|_, other| other.user_id != user_id);
+            store
+                .recovery_codes
+                .rows
+                .retain(|_, recovery_code| recovery_code.user_id != user_id);
+
+            let time = now();
+            let totp_secret = TotpSecret {
+                id: store.totp_secrets.next_id(),
+                user_id: user_id,
+                secret: totp_secret.secret.to_string(),
+                enabled: false,
+                last_step: 0,
+                failed_codes: 0,
+                locked_until: None,
+                create_time: time,
+                update_time: time,
+            };
+            store
+                .totp_secrets
+                .rows
+                .insert(totp_secret.id, totp_secret.clone());
+
+            for hash in code_hashes {
+                let recovery_code = RecoveryCode {
+                    id: store.recovery_codes.next_id(),
+                    user_id: user_id,
+                    code_hash: hash.clone(),
+                    used_time: None,
+                    create_time: time,
+                };
+                store
+                    .recovery_codes
+                    .rows
+                    .insert(recovery_code.id, recovery_code);
+            }
+
+            Ok(totp_secret)
+        })
+    }
+
+    fn enable_totp(&self, user_id: i32, step: i64) -> RepositoryResult<usize> {
+        self.write(|store| {
+            match store
+                .totp_secrets
+                .rows
+                .values_mut()
+                .find(|totp_secret| totp_secret.user_id == user_id && !totp_secret.enabled)
+            {
+                Some(totp_secret) => {
+                    totp_secret.enabled = true;
+                    totp_secret.last_step = step;
+                    totp_secret.update_time = now();
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        })
+    }
+
+    fn use_totp_step(&self, user_id: i32, step: i64) -> RepositoryResult<usize> {
+        self.write(|store| {
+            match store.totp_secrets.rows.values_mut().find(|totp_secret| {
+                totp_secret.user_id == user_id
+                    && totp_secret.enabled
+                    && totp_secret.last_step < step
+            }) {
+                Some(totp_secret) => {
+                    totp_secret.last_step = step;
+                    totp_secret.update_time = now();
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        })
+    }
+
+    fn add_failed_code(
+        &self,
+        user_id: i32,
+        max_failed: i32,
+        locked_until: NaiveDateTime,
+    ) -> RepositoryResult<TotpSecret> {
+        self.write(|store| {
+            let totp_secret = store
+                .totp_secrets
+                .rows
+                .values_mut()
+                .find(|totp_secret| totp_secret.user_id == user_id)
+                .ok_or(RepositoryError::NotFound)?;
+
+            totp_secret.failed_codes += 1;
+            if totp_secret.failed_codes >= max_failed {
+                totp_secret.failed_codes = 0;
+                totp_secret.locked_until = Some(locked_until);
+            }
+
+            Ok(totp_secret.clone())
+        })
+    }
+
+    fn reset_failed_codes(&self, user_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            match store
+                .totp_secrets
+                .rows
+                .values_mut()
+                .find(|totp_secret| totp_secret.user_id == user_id)
+            {
+                Some(totp_secret) => {
+                    totp_secret.failed_codes = 0;
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        })
+    }
+
+    fn delete_totp(&self, user_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            store
+                .recovery_codes
+                .rows
+                .retain(|_, recovery_code| recovery_code.user_id != user_id);
+
+            let before = store.totp_secrets.rows.len();
+            store
+                .totp_secrets
+                .rows
+                .retain(|_, totp_secret| totp_secret.user_id != user_id);
+            Ok(before - store.totp_secrets.rows.len())
+        })
+    }
+
+    fn use_recovery_code(&self, user_id: i32, code_hash: &str) -> RepositoryResult<usize> {
+        self.write(|store| {
+            match store
+                .recovery_codes
+                .rows
+                .values_mut()
+                .find(|recovery_code| {
+                    recovery_code.user_id == user_id
+                        && recovery_code.code_hash == code_hash
+                        && recovery_code.used_time.is_none()
+                }) {
+                Some(recovery_code) => {
+                    recovery_code.used_time = Some(now());
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        })
+    }
+
+    fn create_challenge(&self, challenge: &NewLoginChallenge) -> RepositoryResult<LoginChallenge> {
+        self.write(|store| {
+            store.users.get(challenge.user_id).map_err(|_| {
+                RepositoryError::InvalidReference(format!(
+                    "user {} does not exist",
+                    challenge.user_id
+                ))
+            })?;
+            if store
+                .login_challenges
+                .rows
+                .values()
+                .any(|other| other.token_hash == challenge.token_hash)
+            {
+                return Err(RepositoryError::Conflict(
+                    "login challenge already exists".to_string(),
+                ));
+            }
+
+            let challenge = LoginChallenge {
+                id: store.login_challenges.next_id(),
+                user_id: challenge.user_id,
+                token_hash: challenge.token_hash.to_string(),
+                attempts: 0,
+                expire_time: challenge.expire_time,
+                create_time: now(),
+            };
+            store
+                .login_challenges
+                .rows
+                .insert(challenge.id, challenge.clone());
+
+            Ok(challenge)
+        })
+    }
+
+    fn find_challenge(&self, token_hash: &str) -> RepositoryResult<LoginChallenge> {
+        self.read(|store| {
+            store
+                .login_challenges
+                .all()
+                .into_iter()
+                .find(|challenge| challenge.token_hash == token_hash)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn add_challenge_attempt(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| match store.login_challenges.rows.get_mut(&id) {
+            Some(challenge) => {
+                challenge.attempts += 1;
+                Ok(1)
+            }
+            None => Ok(0),
+        })
+    }
+
+    fn delete_challenge(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| Ok(store.login_challenges.remove(id)))
+    }
+}
+
+impl IdentityRepository for MemoryRepository {
+    fn find_by_subject(&self, issuer: &str, subject: &str) -> RepositoryResult<UserIdentity> {
+        self.read(|store| {
+            store
+                .user_identities
+                .all()
+                .into_iter()
+                .find(|identity| identity.issuer == issuer && identity.subject == subject)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn create(&self, identity: &NewUserIdentity) -> RepositoryResult<UserIdentity> {
+        self.write(|store| {
+            if !store.users.rows.contains_key(&identity.user_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "user {} does not exist",
+                    identity.user_id
+                )));
+            }
+            if store
+                .user_identities
+                .rows
+                .values()
+                .any(|other| other.issuer == identity.issuer && other.subject == identity.subject)
+            {
+                return Err(RepositoryError::Conflict(
+                    "identity already exists".to_string(),
+                ));
+            }
+
+            let identity = UserIdentity {
+                id: store.user_identities.next_id(),
+                user_id: identity.user_id,
+                issuer: identity.issuer.to_string(),
+                subject: identity.subject.to_string(),
+                create_time: now(),
+            };
+            store
+                .user_identities
+                .rows
+                .insert(identity.id, identity.clone());
+
+            Ok(identity)
+        })
+    }
+}
+
+impl ShareRepository for MemoryRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteShare>> {
+        self.read(|store| {
+            Ok(store
+                .note_shares
+                .all()
+                .into_iter()
+                .filter(|note_share| note_share.note_id == note_id)
+                .collect())
+        })
+    }
+
+    fn find_by_note_id_and_user_id(
+        &self,
+        note_id: i32,
+        user_id: i32,
+    ) -> RepositoryResult<NoteShare> {
+        self.read(|store| {
+            store
+                .note_shares
+                .all()
+                .into_iter()
+                .find(|note_share| note_share.note_id == note_id && note_share.user_id == user_id)
+                .ok_or(RepositoryError::NotFound)
+        })
+    }
+
+    fn find_note_by_note_id_and_user_id(
+        &self,
+        note_id: i32,
+        user_id: i32,
+    ) -> RepositoryResult<(Note, NoteShare)> {
+        self.read(|store| {
+            let note_share = store
+                .note_shares
+                .all()
+                .into_iter()
+                .find(|note_share| note_share.note_id == note_id && note_share.user_id == user_id)
+                .ok_or(RepositoryError::NotFound)?;
+
+            Ok((store.active_note(note_id)?, note_share))
+        })
+    }
+
+    fn find_notes_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<(Note, NoteShare)>> {
+        self.read(|store| {
+            let mut shared: Vec<(Note, NoteShare)> = store
+                .note_shares
+                .all()
+                .into_iter()
+                .filter(|note_share| note_share.user_id == user_id)
+                .filter_map(|note_share| {
+                    store
+                        .active_note(note_share.note_id)
+                        .ok()
+                        .map(|note| (note, note_share))
+                })
+                .collect();
+            shared.sort_by(|(a, _), (b, _)| {
+                b.update_time
+                    .cmp(&a.update_time)
+                    .then_with(|| b.id.cmp(&a.id))
+            });
+
+            Ok(shared)
+        })
+    }
+
+    fn create(&self, note_share: &NewNoteShare) -> RepositoryResult<NoteShare> {
+        self.write(|store| {
+            if !store.notes.rows.contains_key(&note_share.note_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "note {} does not exist",
+                    note_share.note_id
+                )));
+            }
+            if !store.users.rows.contains_key(&note_share.user_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "user {} does not exist",
+                    note_share.user_id
+                )));
+            }
+
+            let time = now();
+            if let Some(current) = store.note_shares.rows.values_mut().find(|other| {
+                other.note_id == note_share.note_id && other.user_id == note_share.user_id
+            }) {
+                current.permission = note_share.permission.to_string();
+                current.update_time = time;
+                return Ok(current.clone());
+            }
+
+            let note_share = NoteShare {
+                id: store.note_shares.next_id(),
+                note_id: note_share.note_id,
+                user_id: note_share.user_id,
+                permission: note_share.permission.to_string(),
+                create_time: time,
+                update_time: time,
+            };
+            store
+                .note_shares
+                .rows
+                .insert(note_share.id, note_share.clone());
+
+            Ok(note_share)
+        })
+    }
+
+    fn delete(&self, note_id: i32, user_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let before = store.note_shares.rows.len();
+            store.note_shares.rows.retain(|_, note_share| {
+                note_share.note_id != note_id || note_share.user_id != user_id
+            });
+            Ok(before - store.note_shares.rows.len())
+        })
+    }
+}
+
+impl PublicLinkRepository for MemoryRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<PublicLink>> {
+        self.read(|store| {
+            Ok(store
+                .public_links
+                .all()
+                .into_iter()
+                .filter(|public_link| public_link.note_id == note_id)
+                .collect())
+        })
+    }
+
+    fn find_by_token_hash(&self, token_hash: &str) -> RepositoryResult<(Note, PublicLink)> {
+        self.read(|store| {
+            let public_link = store
+                .public_links
+                .rows
+                .values()
+                .find(|public_link| public_link.token_hash == token_hash)
+                .cloned()
+                .ok_or(RepositoryError::NotFound)?;
+
+            Ok((store.active_note(public_link.note_id)?, public_link))
+        })
+    }
+
+    fn create(&self, public_link: &NewPublicLink) -> RepositoryResult<PublicLink> {
+        self.write(|store| {
+            if !store.notes.rows.contains_key(&public_link.note_id) {
+                return Err(RepositoryError::InvalidReference(format!(
+                    "note {} does not exist",
+                    public_link.note_id
+                )));
+            }
+            if store
+                .public_links
+                .rows
+                .values()
+                .any(|other| other.token_hash == public_link.token_hash)
+            {
+                return Err(RepositoryError::Conflict("Link already exists".to_string()));
+            }
+
+            let public_link = PublicLink {
+                id: store.public_links.next_id(),
+                note_id: public_link.note_id,
+                token_hash: public_link.token_hash.to_string(),
+                password_hash: public_link.password_hash.map(|hash| hash.to_string()),
+                expire_time: public_link.expire_time,
+                create_time: now(),
+            };
+            store
+                .public_links
+                .rows
+                .insert(public_link.id, public_link.clone());
+
+            Ok(public_link)
+        })
+    }
+
+    fn delete(&self, id: i32, note_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let linked = store
+                .public_links
+                .rows
+                .get(&id)
+                .map_or(false, |public_link| public_link.note_id == note_id);
+
+            Ok(if linked {
+                store.public_links.remove(id)
+            } else {
+                0
+            })
+        })
+    }
+}
+
+impl AttachmentRepository for MemoryRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<Attachment>> {
+        self.read(|store| {
+            Ok(store
+                .attachments
+                .all()
+                .into_iter()
+                .filter(|attachment| attachment.note_id == Some(note_id))
+                .collect())
+        })
+    }
+
+    fn find_by_id_and_note_id(&self, id: i32, note_id: i32) -> RepositoryResult<Attachment> {
+        self.read(|store| {
+            store
+                .attachments
+                .get(id)
+                .and_then(|attachment| match attachment.note_id {
+                    Some(other_id) if other_id == note_id => Ok(attachment),
+                    _ => Err(RepositoryError::NotFound),
+                })
+        })
+    }
+
+    fn total_size_by_user_id(&self, user_id: i32) -> RepositoryResult<i64> {
+        self.read(|store| Ok(store.attachments_size(user_id)))
+    }
+
+    fn create(&self, attachment: &NewAttachment) -> RepositoryResult<Attachment> {
+        self.write(|store| store.insert_attachment(attachment))
+    }
+
+    fn create_within_quota(
+        &self,
+        attachment: &NewAttachment,
+        user_id: i32,
+        quota: i64,
+    ) -> RepositoryResult<Attachment> {
+        self.write(|store| {
+            check_quota(store.attachments_size(user_id), attachment.size, quota)?;
+            store.insert_attachment(attachment)
+        })
+    }
+
+    fn delete(&self, id: i32, note_id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let attached = store
+                .attachments
+                .rows
+                .get(&id)
+                .map_or(false, |attachment| attachment.note_id == Some(note_id));
+
+            Ok(if attached {
+                store.attachments.remove(id)
+            } else {
+                0
+            })
+        })
+    }
+
+    fn find_orphans(&self, limit: i64) -> RepositoryResult<Vec<Attachment>> {
+        self.read(|store| {
+            Ok(store
+                .attachments
+                .all()
+                .into_iter()
+                .filter(|attachment| attachment.note_id.is_none())
+                .take(limit.max(0) as usize)
+                .collect())
+        })
+    }
+
+    fn delete_orphan(&self, id: i32) -> RepositoryResult<usize> {
+        self.write(|store| {
+            let orphan = store
+                .attachments
+                .rows
+                .get(&id)
+                .map_or(false, |attachment| attachment.note_id.is_none());
+
+            Ok(if orphan {
+                store.attachments.remove(id)
+            } else {
+                0
+            })
+        })
+    }
+}
+
+impl LinkRepository for MemoryRepository {
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteLink>> {
+        self.read(|store| {
+            Ok(store
+                .note_links
+                .all()
+                .into_iter()
+                .filter(|link| link.note_id == note_id)
+                .collect())
+        })
+    }
+
+    fn find_by_note_ids(&self, note_ids: &[i32]) -> RepositoryResult<Vec<NoteLink>> {
+        self.read(|store| {
+            Ok(store
+                .note_links
+                .all()
+                .into_iter()
+                .filter(|link| note_ids.contains(&link.note_id))
+                .collect())
+        })
+    }
+
+    fn find_by_target(
+        &self,
+        user_id: i32,
+        target_id: i32,
+        target_title: &str,
+    ) -> RepositoryResult<Vec<NoteLink>> {
+        let key = note_link::title_key(target_title)?;
+
+        self.read(|store| {
+            Ok(store
+                .note_links
+                .all()
+                .into_iter()
+                .filter(|link| {
+                    link.target_id == Some(target_id) || link.target_title.as_ref() == Some(&key)
+                })
+                .filter(|link| {
+                    store
+                        .active_note(link.note_id)
+                        .map_or(false, |note| note.user_id == user_id)
+                })
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,468 @@
+//! Storage agnostic access to the rnotes models.
+//!
+//! The server works against the `Repository` trait so it can run on top of the
+//! database (`db::DBRepository`) or fully in memory (`memory::MemoryRepository`).
+
+pub mod db;
+pub mod memory;
+
+use crate::models::db::api_key::{ApiKey, NewApiKey};
+use crate::models::db::attachment::{Attachment, NewAttachment};
+use crate::models::db::category::{Category, NewCategory};
+use crate::models::db::note::{NewNote, Note, NoteFilter, NotePage, NoteSearchHit};
+use crate::models::db::note_link::NoteLink;
+use crate::models::db::note_share::{NewNoteShare, NoteShare};
+use crate::models::db::public_link::{NewPublicLink, PublicLink};
+use crate::models::db::revision::NoteRevision;
+use crate::models::db::session::{NewSession, Session};
+use crate::models::db::tag::Tag;
+use crate::models::db::two_factor::{LoginChallenge, NewLoginChallenge, NewTotpSecret, TotpSecret};
+use crate::models::db::user::{NewUser, User};
+use crate::models::db::user_identity::{NewUserIdentity, UserIdentity};
+use crate::BDPoolError;
+
+use chrono::NaiveDateTime;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+use rocket::http::Status;
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request, State};
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+pub enum RepositoryError {
+    NotFound,
+    Conflict(String),
+    InvalidReference(String),
+    InternalError(String),
+}
+
+pub type RepositoryResult<T> = Result<T, RepositoryError>;
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &*self {
+            RepositoryError::NotFound => f.write_str("Record not found"),
+            RepositoryError::Conflict(o) => f.write_str(&format!("Conflict: {}", o)),
+            RepositoryError::InvalidReference(o) => {
+                f.write_str(&format!("Invalid reference: {}", o))
+            }
+            RepositoryError::InternalError(o) => f.write_str(&format!("Internal error: {}", o)),
+        }
+    }
+}
+
+impl From<DieselError> for RepositoryError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::NotFound => RepositoryError::NotFound,
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                RepositoryError::Conflict(info.message().to_string())
+            }
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                RepositoryError::InvalidReference(info.message().to_string())
+            }
+            other => RepositoryError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<BDPoolError> for RepositoryError {
+    fn from(err: BDPoolError) -> Self {
+        RepositoryError::InternalError(format!("{:?}", err))
+    }
+}
+
+// The parent of a category has to exist, belong to the same user or be shared
+// and be neither the category itself nor one of its descendants.
+fn check_category_parent(
+    categories: &[Category],
+    id: Option<i32>,
+    user_id: Option<i32>,
+    parent_id: Option<i32>,
+) -> RepositoryResult<()> {
+    let parent_id = match parent_id {
+        Some(parent_id) => parent_id,
+        None => return Ok(()),
+    };
+
+    match categories.iter().find(|category| category.id == parent_id) {
+        Some(parent) if parent.user_id.is_none() || parent.user_id == user_id => {}
+        _ => {
+            return Err(RepositoryError::InvalidReference(format!(
+                "category {} does not exist",
+                parent_id
+            )))
+        }
+    }
+    match id {
+        Some(id) if Category::creates_cycle(categories, id, Some(parent_id)) => {
+            Err(RepositoryError::InvalidReference(format!(
+                "category {} is a descendant of category {}",
+                parent_id, id
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+// The attachment has to fit in the quota along with the bytes already used.
+fn check_quota(used: i64, size: i64, quota: i64) -> RepositoryResult<()> {
+    if used + size > quota {
+        return Err(RepositoryError::Conflict(format!(
+            "Quota of {} bytes exceeded, {} bytes are used",
+            quota, used
+        )));
+    }
+
+    Ok(())
+}
+
+/// Access to the notes. Every query but the `*_trashed_*` ones and `delete`
+/// ignores the notes in the trash.
+pub trait NoteRepository: Send + Sync {
+    fn find_by_user_id(&self, user_id: i32, filter: &NoteFilter) -> RepositoryResult<NotePage>;
+
+    fn find_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Note>;
+
+    /// Full-text search over the titles and data of the notes of the user.
+    fn search(&self, user_id: i32, query: &str, limit: i64)
+        -> RepositoryResult<Vec<NoteSearchHit>>;
+
+    /// Creates the note along with its tags and the links written in its
+    /// data.
+    fn create(&self, note: &NewNote, tags: &[String]) -> RepositoryResult<Note>;
+
+    /// Updates the note replacing its tags and links. The previous content
+    /// is kept as a new revision.
+    fn update(&self, note: &Note, tags: &[String]) -> RepositoryResult<Note>;
+
+    /// Moves the note to the trash.
+    fn trash(&self, id: i32) -> RepositoryResult<usize>;
+
+    /// Notes of the user in the trash, the last deleted first.
+    fn find_trashed_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<Note>>;
+
+    fn find_trashed_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Note>;
+
+    /// Takes the note out of the trash.
+    fn restore(&self, id: i32) -> RepositoryResult<Note>;
+
+    /// Permanently deletes the note.
+    fn delete(&self, id: i32) -> RepositoryResult<usize>;
+
+    /// Permanently deletes the notes trashed before the given time.
+    fn purge(&self, before: NaiveDateTime) -> RepositoryResult<usize>;
+}
+
+pub trait UserRepository: Send + Sync {
+    fn find_all(&self) -> RepositoryResult<Vec<User>>;
+
+    fn find_by_id(&self, id: i32) -> RepositoryResult<User>;
+
+    /// The user with the email, its password has to be checked with
+    /// `password::verify`.
+    fn find_by_email(&self, email: &str) -> RepositoryResult<User>;
+
+    fn create(&self, user: &NewUser) -> RepositoryResult<User>;
+
+    fn update(&self, user: &User) -> RepositoryResult<User>;
+
+    fn delete(&self, id: i32) -> RepositoryResult<usize>;
+}
+
+pub trait CategoryRepository: Send + Sync {
+    fn find_all(&self) -> RepositoryResult<Vec<Category>>;
+
+    fn find_by_id(&self, id: i32) -> RepositoryResult<Category>;
+
+    /// Categories of the user along with the shared ones.
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<Category>>;
+
+    /// The category if it belongs to the user or it is shared.
+    fn find_by_id_and_user_id(&self, id: i32, user_id: i32) -> RepositoryResult<Category>;
+
+    /// Ids of the category and all its descendants.
+    fn find_descendant_ids(&self, id: i32) -> RepositoryResult<Vec<i32>>;
+
+    /// Creates the category. Its parent must be visible to its user.
+    fn create(&self, category: &NewCategory) -> RepositoryResult<Category>;
+
+    /// Updates the category. Its parent must be visible to its user and must
+    /// not be one of its descendants.
+    fn update(&self, category: &Category) -> RepositoryResult<Category>;
+
+    /// Deletes the category leaving its notes without category and moving its
+    /// children to the root.
+    fn delete(&self, id: i32) -> RepositoryResult<usize>;
+}
+
+pub trait TagRepository: Send + Sync {
+    /// Tags of the user along with the number of notes using each one.
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<(Tag, i64)>>;
+
+    /// Pairs of note id and tag name of the given notes.
+    fn find_by_note_ids(&self, note_ids: &[i32]) -> RepositoryResult<Vec<(i32, String)>>;
+
+    fn add(&self, user_id: i32, note_id: i32, name: &str) -> RepositoryResult<()>;
+
+    fn remove(&self, user_id: i32, note_id: i32, name: &str) -> RepositoryResult<usize>;
+}
+
+pub trait RevisionRepository: Send + Sync {
+    /// Revisions of the note, the oldest first.
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteRevision>>;
+
+    fn find_by_note_id_and_revision(
+        &self,
+        note_id: i32,
+        revision: i32,
+    ) -> RepositoryResult<NoteRevision>;
+}
+
+pub trait SessionRepository: Send + Sync {
+    fn find_by_jti(&self, jti: &str) -> RepositoryResult<Session>;
+
+    fn find_by_refresh_token_hash(&self, refresh_token_hash: &str) -> RepositoryResult<Session>;
+
+    fn create(&self, session: &NewSession) -> RepositoryResult<Session>;
+
+    /// Replaces the jti and the refresh token of an active session. Fails with
+    /// `NotFound` if the session was refreshed or revoked in the meantime, so
+    /// a refresh token can only be used once.
+    fn rotate(
+        &self,
+        session: &Session,
+        jti: &str,
+        refresh_token_hash: &str,
+        expire_time: NaiveDateTime,
+    ) -> RepositoryResult<Session>;
+
+    fn revoke(&self, id: i32) -> RepositoryResult<usize>;
+
+    /// Revokes every active session of the user.
+    fn revoke_by_user_id(&self, user_id: i32) -> RepositoryResult<usize>;
+}
+
+pub trait ApiKeyRepository: Send + Sync {
+    /// API keys of the user, the oldest first.
+    fn find_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<ApiKey>>;
+
+    fn find_by_key_hash(&self, key_hash: &str) -> RepositoryResult<ApiKey>;
+
+    fn create(&self, api_key: &NewApiKey) -> RepositoryResult<ApiKey>;
+
+    /// Revokes the API key if it belongs to the user.
+    fn delete(&self, id: i32, user_id: i32) -> RepositoryResult<usize>;
+}
+
+/// TOTP secrets, recovery codes and login challenges of the second factor.
+pub trait TwoFactorRepository: Send + Sync {
+    fn find_totp(&self, user_id: i32) -> RepositoryResult<TotpSecret>;
+
+    /// Creates a TOTP secret, not enabled yet, along with the hashes of its
+    /// recovery codes. The previous ones of the user are replaced.
+    fn create_totp(
+        &self,
+        totp_secret: &NewTotpSecret,
+        code_hashes: &[String],
+    ) -> RepositoryResult<TotpSecret>;
+
+    /// Enables the TOTP secret of the user with the step of its first code.
+    fn enable_totp(&self, user_id: i32, step: i64) -> RepositoryResult<usize>;
+
+    /// Marks the step as used. Returns 0 if it or a later one was already
+    /// used, so every code can only be used once.
+    fn use_totp_step(&self, user_id: i32, step: i64) -> RepositoryResult<usize>;
+
+    /// Counts a wrong code of the user, whatever its challenge. Once
+    /// `max_failed` are counted the login is locked until `locked_until` and
+    /// the count starts again.
+    fn add_failed_code(
+        &self,
+        user_id: i32,
+        max_failed: i32,
+        locked_until: NaiveDateTime,
+    ) -> RepositoryResult<TotpSecret>;
+
+    /// Forgets the wrong codes of the user, after a right one.
+    fn reset_failed_codes(&self, user_id: i32) -> RepositoryResult<usize>;
+
+    /// Deletes the TOTP secret of the user along with its recovery codes.
+    fn delete_totp(&self, user_id: i32) -> RepositoryResult<usize>;
+
+    /// Marks the recovery code as used. Returns 0 if it does not exist or was
+    /// already used.
+    fn use_recovery_code(&self, user_id: i32, code_hash: &str) -> RepositoryResult<usize>;
+
+    fn create_challenge(&self, challenge: &NewLoginChallenge) -> RepositoryResult<LoginChallenge>;
+
+    fn find_challenge(&self, token_hash: &str) -> RepositoryResult<LoginChallenge>;
+
+    /// Counts a wrong code sent with the challenge.
+    fn add_challenge_attempt(&self, id: i32) -> RepositoryResult<usize>;
+
+    fn delete_challenge(&self, id: i32) -> RepositoryResult<usize>;
+}
+
+/// Notes shared with other users.
+pub trait ShareRepository: Send + Sync {
+    /// Shares of the note, the oldest first.
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteShare>>;
+
+    fn find_by_note_id_and_user_id(
+        &self,
+        note_id: i32,
+        user_id: i32,
+    ) -> RepositoryResult<NoteShare>;
+
+    /// The note if it is out of the trash and shared with the user.
+    fn find_note_by_note_id_and_user_id(
+        &self,
+        note_id: i32,
+        user_id: i32,
+    ) -> RepositoryResult<(Note, NoteShare)>;
+
+    /// Notes out of the trash shared with the user, the last updated first.
+    fn find_notes_by_user_id(&self, user_id: i32) -> RepositoryResult<Vec<(Note, NoteShare)>>;
+
+    /// Shares the note, or changes the permission if it was already shared
+    /// with the user.
+    fn create(&self, note_share: &NewNoteShare) -> RepositoryResult<NoteShare>;
+
+    fn delete(&self, note_id: i32, user_id: i32) -> RepositoryResult<usize>;
+}
+
+/// Public links to the notes.
+pub trait PublicLinkRepository: Send + Sync {
+    /// Links of the note, the oldest first.
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<PublicLink>>;
+
+    /// The link of the token along with its note, if the note is not in the
+    /// trash.
+    fn find_by_token_hash(&self, token_hash: &str) -> RepositoryResult<(Note, PublicLink)>;
+
+    fn create(&self, public_link: &NewPublicLink) -> RepositoryResult<PublicLink>;
+
+    /// Revokes the link if it belongs to the note.
+    fn delete(&self, id: i32, note_id: i32) -> RepositoryResult<usize>;
+}
+
+/// Wiki links between the notes, set when the notes are created or updated.
+pub trait LinkRepository: Send + Sync {
+    /// Links written in the note, in the order they are written.
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<NoteLink>>;
+
+    /// Links written in the given notes.
+    fn find_by_note_ids(&self, note_ids: &[i32]) -> RepositoryResult<Vec<NoteLink>>;
+
+    /// Links written in the notes of the user out of the trash that
+    /// point to the given id or title, whatever its case and spaces.
+    fn find_by_target(
+        &self,
+        user_id: i32,
+        target_id: i32,
+        target_title: &str,
+    ) -> RepositoryResult<Vec<NoteLink>>;
+}
+
+/// Files attached to the notes, without their content.
+pub trait AttachmentRepository: Send + Sync {
+    /// Attachments of the note, the oldest first.
+    fn find_by_note_id(&self, note_id: i32) -> RepositoryResult<Vec<Attachment>>;
+
+    fn find_by_id_and_note_id(&self, id: i32, note_id: i32) -> RepositoryResult<Attachment>;
+
+    /// Bytes attached to the notes of the user, including the ones in the
+    /// trash.
+    fn total_size_by_user_id(&self, user_id: i32) -> RepositoryResult<i64>;
+
+    fn create(&self, attachment: &NewAttachment) -> RepositoryResult<Attachment>;
+
+    /// Creates the attachment if the ones of the user, along with it, fit in
+    /// `quota` bytes, `Conflict` otherwise. Concurrent uploads of the user are
+    /// checked one after the other.
+    fn create_within_quota(
+        &self,
+        attachment: &NewAttachment,
+        user_id: i32,
+        quota: i64,
+    ) -> RepositoryResult<Attachment>;
+
+    /// Deletes the attachment if it belongs to the note.
+    fn delete(&self, id: i32, note_id: i32) -> RepositoryResult<usize>;
+
+    /// Attachments left by the deleted notes, whose content has to be removed
+    /// from the storage.
+    fn find_orphans(&self, limit: i64) -> RepositoryResult<Vec<Attachment>>;
+
+    fn delete_orphan(&self, id: i32) -> RepositoryResult<usize>;
+}
+
+/// Identities of the users in OpenID Connect providers.
+pub trait IdentityRepository: Send + Sync {
+    fn find_by_subject(&self, issuer: &str, subject: &str) -> RepositoryResult<UserIdentity>;
+
+    fn create(&self, identity: &NewUserIdentity) -> RepositoryResult<UserIdentity>;
+}
+
+/// Storage of every rnotes model.
+pub trait Repository: Send + Sync {
+    fn notes(&self) -> &dyn NoteRepository;
+
+    fn users(&self) -> &dyn UserRepository;
+
+    fn categories(&self) -> &dyn CategoryRepository;
+
+    fn tags(&self) -> &dyn TagRepository;
+
+    fn revisions(&self) -> &dyn RevisionRepository;
+
+    fn sessions(&self) -> &dyn SessionRepository;
+
+    fn api_keys(&self) -> &dyn ApiKeyRepository;
+
+    fn two_factor(&self) -> &dyn TwoFactorRepository;
+
+    fn identities(&self) -> &dyn IdentityRepository;
+
+    fn shares(&self) -> &dyn ShareRepository;
+
+    fn public_links(&self) -> &dyn PublicLinkRepository;
+
+    fn attachments(&self) -> &dyn AttachmentRepository;
+
+    fn links(&self) -> &dyn LinkRepository;
+}
+
+/// Request guard giving access to the `Repo` managed by Rocket.
+#[derive(Clone)]
+pub struct Repo(pub Arc<dyn Repository>);
+
+impl Repo {
+    pub fn new<R: Repository + 'static>(repository: R) -> Self {
+        Repo(Arc::new(repository))
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Repo {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Repo, Self::Error> {
+        match request.guard::<State<Repo>>() {
+            Outcome::Success(repo) => Outcome::Success(repo.clone()),
+            _ => Outcome::Failure((Status::ServiceUnavailable, ())),
+        }
+    }
+}
+
+impl Deref for Repo {
+    type Target = dyn Repository;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
@@ -6,26 +6,215 @@ table! {
         password -> Varchar,
         create_time -> Timestamp,
         update_time -> Timestamp,
+        role -> Varchar,
+        disabled -> Bool,
     }
 }
 
 table! {
     categories (id) {
         id -> Integer,
+        user_id -> Nullable<Integer>,
+        parent_id -> Nullable<Integer>,
         name -> Varchar,
         create_time -> Timestamp,
         update_time -> Timestamp,
     }
 }
 
+// The PostgreSQL only `search_vector` column is left out, `Note::search` uses
+// it through raw SQL.
 table! {
     notes (id) {
         id -> Integer,
         user_id -> Integer,
         category_id -> Nullable<Integer>,
-        title -> Varchar,
+        title -> Text,
         data -> Text,
         create_time -> Timestamp,
         update_time -> Timestamp,
+        deleted_time -> Nullable<Timestamp>,
+        format -> Varchar,
+        key_check -> Nullable<Varchar>,
+        data_key -> Nullable<Varchar>,
+        key_id -> Nullable<Varchar>,
     }
 }
+
+table! {
+    tags (id) {
+        id -> Integer,
+        user_id -> Integer,
+        name -> Varchar,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    note_tags (note_id, tag_id) {
+        note_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
+table! {
+    note_revisions (id) {
+        id -> Integer,
+        note_id -> Integer,
+        revision -> Integer,
+        category_id -> Nullable<Integer>,
+        title -> Text,
+        data -> Text,
+        create_time -> Timestamp,
+        key_check -> Nullable<Varchar>,
+        data_key -> Nullable<Varchar>,
+        key_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    sessions (id) {
+        id -> Integer,
+        user_id -> Integer,
+        jti -> Varchar,
+        refresh_token_hash -> Varchar,
+        expire_time -> Timestamp,
+        revoked_time -> Nullable<Timestamp>,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+    }
+}
+
+table! {
+    api_keys (id) {
+        id -> Integer,
+        user_id -> Integer,
+        name -> Varchar,
+        prefix -> Varchar,
+        key_hash -> Varchar,
+        read_only -> Bool,
+        expire_time -> Nullable<Timestamp>,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    totp_secrets (id) {
+        id -> Integer,
+        user_id -> Integer,
+        secret -> Varchar,
+        enabled -> Bool,
+        last_step -> BigInt,
+        failed_codes -> Integer,
+        locked_until -> Nullable<Timestamp>,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+    }
+}
+
+table! {
+    recovery_codes (id) {
+        id -> Integer,
+        user_id -> Integer,
+        code_hash -> Varchar,
+        used_time -> Nullable<Timestamp>,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    login_challenges (id) {
+        id -> Integer,
+        user_id -> Integer,
+        token_hash -> Varchar,
+        attempts -> Integer,
+        expire_time -> Timestamp,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    user_identities (id) {
+        id -> Integer,
+        user_id -> Integer,
+        issuer -> Varchar,
+        subject -> Varchar,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    note_shares (id) {
+        id -> Integer,
+        note_id -> Integer,
+        user_id -> Integer,
+        permission -> Varchar,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+    }
+}
+
+table! {
+    public_links (id) {
+        id -> Integer,
+        note_id -> Integer,
+        token_hash -> Varchar,
+        password_hash -> Nullable<Varchar>,
+        expire_time -> Nullable<Timestamp>,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    attachments (id) {
+        id -> Integer,
+        note_id -> Nullable<Integer>,
+        file_name -> Varchar,
+        content_type -> Varchar,
+        size -> BigInt,
+        storage_key -> Varchar,
+        create_time -> Timestamp,
+    }
+}
+
+table! {
+    note_links (id) {
+        id -> Integer,
+        note_id -> Integer,
+        target_id -> Nullable<Integer>,
+        target_title -> Nullable<Varchar>,
+    }
+}
+
+joinable!(note_revisions -> notes (note_id));
+joinable!(sessions -> users (user_id));
+joinable!(api_keys -> users (user_id));
+joinable!(totp_secrets -> users (user_id));
+joinable!(recovery_codes -> users (user_id));
+joinable!(login_challenges -> users (user_id));
+joinable!(user_identities -> users (user_id));
+joinable!(note_shares -> notes (note_id));
+joinable!(note_shares -> users (user_id));
+joinable!(public_links -> notes (note_id));
+joinable!(note_links -> notes (note_id));
+joinable!(note_tags -> notes (note_id));
+joinable!(note_tags -> tags (tag_id));
+
+allow_tables_to_appear_in_same_query!(
+    users,
+    categories,
+    notes,
+    tags,
+    note_tags,
+    note_revisions,
+    sessions,
+    api_keys,
+    totp_secrets,
+    recovery_codes,
+    login_challenges,
+    user_identities,
+    note_shares,
+    public_links,
+    attachments,
+    note_links
+);
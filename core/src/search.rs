@@ -0,0 +1,173 @@
+//! Full-text search helpers shared by the backends without `tsvector` support.
+//!
+//! PostgreSQL ranks and highlights the results itself; SQLite and the in-memory
+//! repository match the terms of the query with these functions instead.
+
+/// Marks the start of a matched term in highlights and snippets.
+pub const HIGHLIGHT_START: &str = "<mark>";
+/// Marks the end of a matched term in highlights and snippets.
+pub const HIGHLIGHT_STOP: &str = "</mark>";
+
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Lowercased words of a search query.
+pub fn terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect();
+    terms.sort();
+    terms.dedup();
+
+    terms
+}
+
+// Byte ranges of the occurrences of the terms, sorted and without overlaps.
+fn matches(text: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    let lower = text.to_lowercase();
+    // Lowercasing may change byte lengths, in that case only whole text
+    // comparisons are reliable.
+    if lower.len() != text.len() {
+        return vec![];
+    }
+
+    let mut ranges: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| {
+            lower
+                .match_indices(term.as_str())
+                .map(|(start, m)| (start, start + m.len()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+/// Whether the text contains every term.
+pub fn contains_all(text: &str, terms: &[String]) -> bool {
+    let lower = text.to_lowercase();
+    terms.iter().all(|term| lower.contains(term.as_str()))
+}
+
+/// Relevance of a note, matches in the title weigh more than in the body.
+pub fn rank(title: &str, data: &str, terms: &[String]) -> f32 {
+    let hits = |text: &str| {
+        let lower = text.to_lowercase();
+        terms
+            .iter()
+            .map(|term| lower.matches(term.as_str()).count())
+            .sum::<usize>() as f32
+    };
+    let words = (title.split_whitespace().count() + data.split_whitespace().count()).max(1);
+
+    (hits(title) * 2.0 + hits(data)) / words as f32
+}
+
+/// The whole text with every term surrounded by the highlight markers.
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end) in matches(text, terms) {
+        result.push_str(&text[last..start]);
+        result.push_str(HIGHLIGHT_START);
+        result.push_str(&text[start..end]);
+        result.push_str(HIGHLIGHT_STOP);
+        last = end;
+    }
+    result.push_str(&text[last..]);
+
+    result
+}
+
+/// A highlighted fragment of the text around the first matched term.
+pub fn snippet(text: &str, terms: &[String]) -> String {
+    let ranges = matches(text, terms);
+    let (first, _) = match ranges.first() {
+        Some(range) => *range,
+        None => return text.chars().take(SNIPPET_CONTEXT * 2).collect(),
+    };
+
+    let mut start = first.saturating_sub(SNIPPET_CONTEXT);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (first + SNIPPET_CONTEXT * 2).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    // Do not cut a highlighted term.
+    if let Some((_, stop)) = ranges.iter().find(|(s, e)| *s < end && *e > end) {
+        end = *stop;
+    }
+
+    let mut result = highlight(&text[start..end], terms);
+    if start > 0 {
+        result.insert_str(0, "...");
+    }
+    if end < text.len() {
+        result.push_str("...");
+    }
+
+    result
+}
+
+mod tests {
+    #[test]
+    fn test_terms() {
+        use super::*;
+
+        assert_eq!(
+            terms("Rust, rust  NOTES!"),
+            vec!["notes".to_string(), "rust".to_string()]
+        );
+        assert!(terms(" ,;").is_empty());
+    }
+
+    #[test]
+    fn test_highlight() {
+        use super::*;
+
+        let terms = terms("rust note");
+
+        assert_eq!(
+            highlight("Some Rust notes", &terms),
+            "Some <mark>Rust</mark> <mark>note</mark>s"
+        );
+        assert_eq!(highlight("nothing", &terms), "nothing");
+    }
+
+    #[test]
+    fn test_snippet() {
+        use super::*;
+
+        let terms = terms("needle");
+        let text = format!("{} needle {}", "a".repeat(100), "b".repeat(100));
+
+        let result = snippet(&text, &terms);
+
+        assert!(result.starts_with("..."));
+        assert!(result.ends_with("..."));
+        assert!(result.contains("<mark>needle</mark>"));
+    }
+
+    #[test]
+    fn test_rank() {
+        use super::*;
+
+        let terms = terms("rust");
+
+        assert!(rank("rust", "other", &terms) > rank("other", "rust", &terms));
+        assert_eq!(rank("other", "other", &terms), 0.0);
+    }
+}
@@ -0,0 +1,37 @@
+//! Random tokens handed out to the clients.
+//!
+//! Tokens have enough entropy to not need a slow hash, so only their SHA-256
+//! is stored and a leaked table does not give access to any account.
+
+use sha2::{Digest, Sha256};
+
+use crate::utils::HexSlice;
+
+const TOKEN_LENGTH: usize = 32;
+
+/// New random token as a hex string.
+pub fn generate() -> String {
+    let bytes: [u8; TOKEN_LENGTH] = rand::random();
+
+    HexSlice::new(&bytes).to_string()
+}
+
+/// Hex SHA-256 of the token, the value to store and look up.
+pub fn hash(token: &str) -> String {
+    HexSlice::new(&Sha256::digest(token.as_bytes())).to_string()
+}
+
+mod tests {
+    #[test]
+    fn test_generate_and_hash() {
+        use super::*;
+
+        let token = generate();
+
+        assert_eq!(token.len(), TOKEN_LENGTH * 2);
+        assert_ne!(token, generate());
+        assert_eq!(hash(&token), hash(&token));
+        assert_ne!(hash(&token), token);
+        assert_eq!(hash(&token).len(), 64);
+    }
+}
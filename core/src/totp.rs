@@ -0,0 +1,202 @@
+//! Time-based one-time passwords (RFC 6238) used as second factor.
+//!
+//! Secrets are random keys shared with the authenticator apps as base32 in an
+//! `otpauth://` URI. Codes are 6 digits of the HMAC-SHA1 of the number of
+//! 30-second steps since the epoch.
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+use crate::password::constant_time_eq;
+use crate::utils::HexSlice;
+
+const SECRET_LENGTH: usize = 20;
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+/// Steps before and after the current one also accepted, for clock drift.
+const SKEW: u64 = 1;
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 5;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut result = String::new();
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for byte in data {
+        buffer = (buffer << 8) | u64::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        result.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+
+    result
+}
+
+fn base32_decode(data: &str) -> Option<Vec<u8>> {
+    let mut result = Vec::new();
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for c in data.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(result)
+}
+
+// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// New random secret as base32.
+pub fn generate_secret() -> String {
+    let bytes: [u8; SECRET_LENGTH] = rand::random();
+
+    base32_encode(&bytes)
+}
+
+/// Random single-use codes to login without the authenticator app.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let bytes: [u8; RECOVERY_CODE_LENGTH] = rand::random();
+            let hex = HexSlice::new(&bytes).to_string().to_lowercase();
+            format!(
+                "{}-{}",
+                &hex[..RECOVERY_CODE_LENGTH],
+                &hex[RECOVERY_CODE_LENGTH..]
+            )
+        })
+        .collect()
+}
+
+/// Recovery code as it is hashed, without case and blanks.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// URI of the secret to scan in the authenticator apps.
+pub fn uri(secret: &str, issuer: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = encode(issuer),
+        account = encode(account),
+        secret = secret,
+        digits = DIGITS,
+        period = STEP_SECONDS
+    )
+}
+
+/// Step of a Unix time in seconds.
+pub fn step(time: u64) -> u64 {
+    time / STEP_SECONDS
+}
+
+/// Code of the secret at the step, `None` if the secret is not base32.
+pub fn code(secret: &str, step: u64) -> Option<String> {
+    let key = base32_decode(secret)?;
+    let mut mac = Hmac::<Sha1>::new_varkey(&key).ok()?;
+    mac.input(&step.to_be_bytes());
+    let hash = mac.result().code();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = (u32::from(hash[offset] & 0x7f) << 24)
+        | (u32::from(hash[offset + 1]) << 16)
+        | (u32::from(hash[offset + 2]) << 8)
+        | u32::from(hash[offset + 3]);
+
+    Some(format!(
+        "{:0digits$}",
+        binary % 10u32.pow(DIGITS as u32),
+        digits = DIGITS
+    ))
+}
+
+/// The step of the code if it is the one of the time or of the steps next to
+/// it. Callers must reject the steps already used.
+pub fn verify(secret: &str, input: &str, time: u64) -> Option<u64> {
+    let input = input.trim();
+    let current = step(time);
+
+    (current.saturating_sub(SKEW)..=current + SKEW).find(|step| {
+        code(secret, *step).map_or(false, |expected| {
+            constant_time_eq(expected.as_bytes(), input.as_bytes())
+        })
+    })
+}
+
+mod tests {
+    #[test]
+    fn test_base32() {
+        use super::*;
+
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("MZXW6YTBOI======").unwrap(), b"foobar");
+        assert_eq!(base32_decode("mzxw 6ytb oi").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_none());
+
+        let secret = generate_secret();
+        assert_eq!(base32_decode(&secret).unwrap().len(), SECRET_LENGTH);
+    }
+
+    #[test]
+    fn test_code_and_verify() {
+        use super::*;
+
+        // Test vectors of RFC 6238, truncated to 6 digits.
+        let secret = base32_encode(b"12345678901234567890");
+
+        assert_eq!(code(&secret, step(59)).unwrap(), "287082");
+        assert_eq!(code(&secret, step(1111111109)).unwrap(), "081804");
+        assert_eq!(code(&secret, step(1234567890)).unwrap(), "005924");
+
+        assert_eq!(verify(&secret, "081804", 1111111109), Some(37037036));
+        assert_eq!(verify(&secret, " 081804 ", 1111111109 + 30), Some(37037036));
+        assert_eq!(verify(&secret, "081804", 1111111109 + 90), None);
+        assert_eq!(verify(&secret, "000000", 1111111109), None);
+    }
+
+    #[test]
+    fn test_uri_and_recovery_codes() {
+        use super::*;
+
+        assert_eq!(
+            uri("MZXW6YTBOI", "rnotes", "user a@email.com"),
+            "otpauth://totp/rnotes:user%20a%40email.com?secret=MZXW6YTBOI&issuer=rnotes&algorithm=SHA1&digits=6&period=30"
+        );
+
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODES);
+        assert!(codes.iter().all(|code| code.len() == 11));
+        assert_eq!(normalize_recovery_code(" AB12c-3D4e5 "), "ab12c-3d4e5");
+    }
+}
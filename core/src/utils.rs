@@ -1,5 +1,25 @@
+use chrono::{NaiveDateTime, Utc};
 use std::fmt;
 
+/// Current UTC time as stored in the `TIMESTAMP` columns.
+pub fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+/// Line by line diff of two texts. Every line is prefixed with `-` if it was
+/// removed, `+` if it was added or a space if it did not change.
+pub fn text_diff(old: &str, new: &str) -> String {
+    diff::lines(old, new)
+        .iter()
+        .map(|line| match line {
+            diff::Result::Left(l) => format!("-{}", l),
+            diff::Result::Both(l, _) => format!(" {}", l),
+            diff::Result::Right(r) => format!("+{}", r),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 pub fn eq_no_ord<T>(a: &[T], b: &[T]) -> bool
 where
     T: PartialEq + Ord + std::fmt::Debug,
@@ -55,6 +75,18 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_text_diff() {
+        let old = "line_a\nline_b\nline_c";
+        let new = "line_a\nline_x\nline_c";
+
+        assert_eq!(
+            text_diff(old, new),
+            " line_a\n-line_b\n+line_x\n line_c"
+        );
+        assert_eq!(text_diff("same", "same"), " same");
+    }
+
     #[test]
     fn test_eq_no_ord() {
         let vec_a = vec![0, 1, 2];
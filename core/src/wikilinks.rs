@@ -0,0 +1,111 @@
+//! Wiki links between notes, written in their data as `[[#<id>]]` to point to
+//! a note by id or `[[<title>]]` to point to it by title. The titles are
+//! matched regardless of the case and the spaces, see `normalize_title`.
+
+/// Longest title a link can point to, like the titles of the notes.
+pub const MAX_TITLE_LENGTH: usize = 256;
+
+const OPEN: &str = "[[";
+const CLOSE: &str = "]]";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WikiLink {
+    Id(i32),
+    Title(String),
+}
+
+impl WikiLink {
+    // The link of the text between the brackets, `None` if it is not one.
+    fn parse(text: &str) -> Option<WikiLink> {
+        let text = text.trim();
+        if text.is_empty()
+            || text.chars().count() > MAX_TITLE_LENGTH
+            || text.contains(|c: char| c == '[' || c == ']' || c == '\n')
+        {
+            return None;
+        }
+
+        match text.strip_prefix('#').map(|id| id.parse::<i32>()) {
+            Some(Ok(id)) if id > 0 => Some(WikiLink::Id(id)),
+            _ => Some(WikiLink::Title(text.to_string())),
+        }
+    }
+}
+
+/// Title as the links are matched with: lowercase, without leading and
+/// trailing spaces and with single spaces between the words.
+pub fn normalize_title(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Links of the data in the order they are written, without duplicates.
+pub fn parse(data: &str) -> Vec<WikiLink> {
+    let mut links: Vec<WikiLink> = vec![];
+
+    let mut rest = data;
+    while let Some(start) = rest.find(OPEN) {
+        rest = &rest[start + OPEN.len()..];
+        let end = match rest.find(CLOSE) {
+            Some(end) => end,
+            None => break,
+        };
+
+        // Otherwise the text may still hold the opening of a link, e.g. in
+        // `[[a [[b]]`.
+        if let Some(link) = WikiLink::parse(&rest[..end]) {
+            if !links.contains(&link) {
+                links.push(link);
+            }
+            rest = &rest[end + CLOSE.len()..];
+        }
+    }
+
+    links
+}
+
+mod tests {
+    #[test]
+    fn test_parse() {
+        use super::*;
+
+        assert_eq!(
+            parse("See [[Some note]], [[#12]] and [[ Some note ]]."),
+            vec![WikiLink::Title("Some note".to_string()), WikiLink::Id(12)]
+        );
+        assert_eq!(
+            parse("[[#abc]] [[#0]] [[#]]"),
+            vec![
+                WikiLink::Title("#abc".to_string()),
+                WikiLink::Title("#0".to_string()),
+                WikiLink::Title("#".to_string())
+            ]
+        );
+        assert_eq!(
+            parse("[[a [[b]] [[c]"),
+            vec![WikiLink::Title("b".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        use super::*;
+
+        assert_eq!(normalize_title("Some note"), "some note");
+        assert_eq!(normalize_title("  SOME \t note\n"), "some note");
+        assert_eq!(normalize_title("Ünïcode  Title"), "ünïcode title");
+        assert_eq!(normalize_title("   "), "");
+    }
+
+    #[test]
+    fn test_parse_none() {
+        use super::*;
+
+        assert!(parse("").is_empty());
+        assert!(parse("[[]] [[  ]] [[a\nb]] [single] [[open").is_empty());
+        assert!(parse(&format!("[[{}]]", "a".repeat(MAX_TITLE_LENGTH + 1))).is_empty());
+    }
+}
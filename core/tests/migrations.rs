@@ -0,0 +1,28 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::migrations::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_migrations_applied() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let result = applied_versions(&con).unwrap();
+
+    let expected: Vec<String> = MIGRATIONS.iter().map(|m| m.version.to_string()).collect();
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_migrations_run_pending_idempotent() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let result = run_pending(&con).unwrap();
+
+    assert!(result.is_empty());
+    assert!(pending(&con).unwrap().is_empty());
+}
@@ -0,0 +1,55 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::api_key::*;
+use rnotes_core::token;
+use rnotes_core::utils::now;
+use rnotes_core::BDPool;
+
+use chrono::Duration;
+
+#[test]
+fn test_api_key_create_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let key_hash = token::hash(&token::generate());
+    let expire_time = now() + Duration::days(1);
+
+    let api_key = NewApiKey::new(1, "some_name", "rn_0123", &key_hash)
+        .with_read_only(true)
+        .with_expire_time(Some(expire_time))
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(api_key.user_id, 1);
+    assert_eq!(api_key.name, "some_name");
+    assert!(api_key.read_only);
+    assert!(api_key.is_active(now()));
+    assert!(!api_key.is_active(expire_time));
+    assert_eq!(ApiKey::find_by_key_hash(&con, &key_hash).unwrap(), api_key);
+    assert!(ApiKey::find_by_user_id(&con, 1).unwrap().contains(&api_key));
+    assert!(ApiKey::find_by_key_hash(&con, "unknown").is_err());
+}
+
+#[test]
+fn test_api_key_delete() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let key_hash = token::hash(&token::generate());
+    let api_key = NewApiKey::new(1, "some_name", "rn_0123", &key_hash)
+        .create(&con)
+        .unwrap();
+
+    assert!(api_key.is_active(now()));
+    assert_eq!(
+        ApiKey::delete_by_id_and_user_id(&con, api_key.id, 2).unwrap(),
+        0
+    );
+    assert_eq!(
+        ApiKey::delete_by_id_and_user_id(&con, api_key.id, 1).unwrap(),
+        1
+    );
+    assert!(ApiKey::find_by_key_hash(&con, &key_hash).is_err());
+}
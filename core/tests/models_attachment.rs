@@ -0,0 +1,61 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::attachment::*;
+use rnotes_core::models::db::note::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_attachment_create_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let note = NewNote::new(
+        2,
+        None,
+        "note_test_attachment".to_string(),
+        "some_text_note_test_attachment".to_string(),
+    )
+    .create(&con)
+    .unwrap();
+    let size = Attachment::total_size_by_user_id(&con, 2).unwrap();
+
+    let attachment =
+        NewAttachment::new(note.id, "file.txt", "text/plain", 10, "key_test_attachment")
+            .create(&con)
+            .unwrap();
+
+    assert_eq!(attachment.note_id, Some(note.id));
+    assert_eq!(
+        Attachment::find_by_id_and_note_id(&con, attachment.id, note.id).unwrap(),
+        attachment.clone()
+    );
+    assert_eq!(
+        Attachment::find_by_note_id(&con, note.id).unwrap(),
+        vec![attachment.clone()]
+    );
+    assert_eq!(
+        Attachment::total_size_by_user_id(&con, 2).unwrap(),
+        size + 10
+    );
+    assert!(
+        NewAttachment::new(note.id, "other.txt", "text/plain", 5, "key_test_attachment")
+            .create(&con)
+            .is_err()
+    );
+
+    // The attachments of the deleted notes are kept until their content is
+    // removed from the storage.
+    Note::delete(&con, note.id).unwrap();
+    assert_eq!(Attachment::total_size_by_user_id(&con, 2).unwrap(), size);
+    assert!(Attachment::find_orphans(&con, 100)
+        .unwrap()
+        .iter()
+        .any(|orphan| orphan.id == attachment.id && orphan.note_id.is_none()));
+
+    assert_eq!(Attachment::delete_orphan(&con, attachment.id).unwrap(), 1);
+    assert!(Attachment::find_orphans(&con, 100)
+        .unwrap()
+        .iter()
+        .all(|orphan| orphan.id != attachment.id));
+}
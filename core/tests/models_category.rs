@@ -2,11 +2,11 @@ extern crate dotenv;
 extern crate rnotes_core;
 
 use rnotes_core::models::db::category::*;
-use rnotes_core::utils::eq_no_ord;
+use rnotes_core::models::db::note::*;
+use rnotes_core::utils::{eq_no_ord, now};
 use rnotes_core::BDPool;
 
 use std::panic;
-use std::time::SystemTime;
 
 #[test]
 fn test_category_find_all() {
@@ -18,15 +18,19 @@ fn test_category_find_all() {
     let expected: Vec<Category> = vec![
         Category {
             id: 1,
+            user_id: None,
+            parent_id: None,
             name: "cat_a".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
         },
         Category {
             id: 2,
+            user_id: None,
+            parent_id: None,
             name: "cat_b".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
         },
     ];
 
@@ -43,9 +47,11 @@ fn test_category_find_by_id() {
 
     let expected = Category {
         id: 1,
+        user_id: None,
+        parent_id: None,
         name: "cat_a".to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
     };
 
     assert_eq!(result, expected);
@@ -56,15 +62,17 @@ fn test_category_insert() {
     let pool = BDPool::new().unwrap();
     let con = pool.get().unwrap();
 
-    let new_category = NewCategory::new("cat_test_category_insert");
+    let new_category = NewCategory::new(Some(1), "cat_test_category_insert");
 
     let result = new_category.create(&con).unwrap();
 
     let expected = Category {
         id: result.id,
+        user_id: new_category.user_id,
+        parent_id: new_category.parent_id,
         name: new_category.name.to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
     };
 
     assert_eq!(result, expected);
@@ -80,7 +88,7 @@ fn test_category_delete() {
     let pool = BDPool::new().unwrap();
     let con = pool.get().unwrap();
 
-    let new_category = NewCategory::new("cat_test_category_delete");
+    let new_category = NewCategory::new(Some(1), "cat_test_category_delete");
 
     let category = new_category.create(&con).unwrap();
 
@@ -97,7 +105,7 @@ fn test_category_update() {
     let pool = BDPool::new().unwrap();
     let con = pool.get().unwrap();
 
-    let new_category = NewCategory::new("cat_test_category_update");
+    let new_category = NewCategory::new(Some(1), "cat_test_category_update");
 
     let mut category = new_category.create(&con).unwrap();
 
@@ -115,3 +123,95 @@ fn test_category_update() {
 
     Category::delete(&con, category.id).unwrap();
 }
+
+#[test]
+fn test_category_find_by_user_id() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let category = NewCategory::new(Some(2), "cat_test_category_find_by_user_id")
+        .create(&con)
+        .unwrap();
+
+    let result = Category::find_by_user_id(&con, 2).unwrap();
+    assert!(result.contains(&category));
+    assert!(result.iter().any(|c| c.id == 1 && c.user_id == None));
+    assert!(!Category::find_by_user_id(&con, 1)
+        .unwrap()
+        .contains(&category));
+
+    assert_eq!(
+        Category::find_by_id_and_user_id(&con, category.id, 2).unwrap(),
+        category
+    );
+    assert!(Category::find_by_id_and_user_id(&con, category.id, 1).is_err());
+    assert!(Category::find_by_id_and_user_id(&con, 1, 1).is_ok());
+
+    Category::delete(&con, category.id).unwrap();
+}
+
+#[test]
+fn test_category_delete_with_notes() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let category = NewCategory::new(Some(2), "cat_test_category_delete_with_notes")
+        .create(&con)
+        .unwrap();
+    let note = NewNote::new(
+        2,
+        Some(category.id),
+        "note_test_category_delete_with_notes".to_string(),
+        "some_text".to_string(),
+    )
+    .create(&con)
+    .unwrap();
+
+    assert_eq!(Category::delete(&con, category.id).unwrap(), 1);
+
+    let result = Note::find_by_id(&con, note.id).unwrap();
+    assert_eq!(result.category_id, None);
+
+    Note::delete(&con, note.id).unwrap();
+}
+
+#[test]
+fn test_category_find_descendant_ids() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let root = NewCategory::new(Some(3), "cat_test_category_descendants_root")
+        .create(&con)
+        .unwrap();
+    let child = NewCategory::new(Some(3), "cat_test_category_descendants_child")
+        .with_parent(Some(root.id))
+        .create(&con)
+        .unwrap();
+    let grandchild = NewCategory::new(Some(3), "cat_test_category_descendants_grandchild")
+        .with_parent(Some(child.id))
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(
+        Category::find_descendant_ids(&con, root.id).unwrap(),
+        vec![root.id, child.id, grandchild.id]
+    );
+    assert_eq!(
+        Category::find_descendant_ids(&con, grandchild.id).unwrap(),
+        vec![grandchild.id]
+    );
+    assert!(Category::creates_cycle(
+        &Category::find_all(&con).unwrap(),
+        root.id,
+        Some(grandchild.id)
+    ));
+
+    Category::delete(&con, child.id).unwrap();
+    assert_eq!(
+        Category::find_by_id(&con, grandchild.id).unwrap().parent_id,
+        None
+    );
+
+    Category::delete(&con, grandchild.id).unwrap();
+    Category::delete(&con, root.id).unwrap();
+}
@@ -0,0 +1,240 @@
+extern crate diesel;
+extern crate dotenv;
+extern crate rnotes_core;
+
+use diesel::prelude::*;
+use rnotes_core::encryption::{self, Keyring};
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::note_link::*;
+use rnotes_core::models::db::revision::*;
+use rnotes_core::schema::{note_links, note_revisions, notes};
+use rnotes_core::{BDPool, DBConn, DBConnection};
+
+use std::env;
+
+// The keyring is read once per process, so every test of this file runs with
+// the master keys key_a, the current one, and key_b. Each test runs in a
+// transaction that is never committed, so the rows of the other tests are
+// neither rotated nor left encrypted.
+fn connection() -> DBConn {
+    env::set_var(
+        "ENCRYPTION_KEYS",
+        "key_a:AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=,key_b:AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI=",
+    );
+    env::set_var("ENCRYPTION_KEY_ID", "key_a");
+
+    let con = BDPool::new().unwrap().get().unwrap();
+    con.begin_test_transaction().unwrap();
+    con
+}
+
+fn keyring_b() -> Keyring {
+    Keyring::new(
+        "key_b",
+        vec![
+            ("key_a".to_string(), vec![1u8; 32]),
+            ("key_b".to_string(), vec![2u8; 32]),
+        ],
+    )
+    .unwrap()
+}
+
+fn note_row(con: &DBConnection, id: i32) -> NoteRow {
+    notes::table.find(id).get_result::<NoteRow>(con).unwrap()
+}
+
+fn revision_rows(con: &DBConnection, id: i32) -> Vec<NoteRevisionRow> {
+    note_revisions::table
+        .filter(note_revisions::note_id.eq(id))
+        .order(note_revisions::revision.asc())
+        .load::<NoteRevisionRow>(con)
+        .unwrap()
+}
+
+fn create_note(con: &DBConnection, title: &str) -> Note {
+    NewNote::new(1, None, title.to_string(), format!("some_text_{}", title))
+        .create(con)
+        .unwrap()
+}
+
+#[test]
+fn test_note_decrypt() {
+    let pooled = connection();
+    let con: &DBConnection = &pooled;
+
+    let note = create_note(con, "note_test_note_decrypt");
+    assert_eq!(note.title, "note_test_note_decrypt");
+
+    let row = note_row(con, note.id);
+    assert_eq!(row.key_id, Some("key_a".to_string()));
+    assert!(row.data_key.is_some());
+    assert_ne!(row.title, "note_test_note_decrypt");
+    assert_ne!(row.data, "some_text_note_test_note_decrypt");
+
+    assert_eq!(Note::find_by_id(con, note.id).unwrap(), note);
+    assert_eq!(row.decrypt().unwrap(), note);
+
+    // The ciphertexts are bound to their row.
+    let other = create_note(con, "note_test_note_decrypt_other");
+    let row = note_row(con, note.id);
+    diesel::update(notes::table.find(other.id))
+        .set((
+            notes::title.eq(row.title),
+            notes::data.eq(row.data),
+            notes::data_key.eq(row.data_key),
+            notes::key_id.eq(row.key_id),
+        ))
+        .execute(con)
+        .unwrap();
+    assert!(Note::find_by_id(con, other.id).is_err());
+}
+
+#[test]
+fn test_revision_decrypt() {
+    let pooled = connection();
+    let con: &DBConnection = &pooled;
+
+    let mut note = create_note(con, "note_test_revision_decrypt");
+    note.data = "some_text_b".to_string();
+    Note::update(con, &note).unwrap();
+
+    let rows = revision_rows(con, note.id);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].key_id, Some("key_a".to_string()));
+    assert_ne!(rows[0].data, "some_text_note_test_revision_decrypt");
+
+    let revision = NoteRevision::find_by_note_id_and_revision(con, note.id, 1).unwrap();
+    assert_eq!(revision.title, "note_test_revision_decrypt");
+    assert_eq!(revision.data, "some_text_note_test_revision_decrypt");
+    assert_eq!(Note::find_by_id(con, note.id).unwrap().data, "some_text_b");
+}
+
+#[test]
+fn test_rotate_keys() {
+    let pooled = connection();
+    let con: &DBConnection = &pooled;
+
+    let mut note = create_note(con, "note_test_rotate_keys");
+    note.data = "some_text_b".to_string();
+    Note::update(con, &note).unwrap();
+
+    // Written before the encryption was enabled.
+    let plain = create_note(con, "note_test_rotate_keys_plain");
+    diesel::update(notes::table.find(plain.id))
+        .set((
+            notes::title.eq("note_test_rotate_keys_plain"),
+            notes::data.eq("some_text_plain"),
+            notes::data_key.eq(None::<String>),
+            notes::key_id.eq(None::<String>),
+        ))
+        .execute(con)
+        .unwrap();
+
+    // Batches of one row, as `rnotes_admin rotate-keys 1` does.
+    let keyring = keyring_b();
+    let (num_notes, num_revisions) = encryption::rotate_keys(con, &keyring, 1).unwrap();
+    assert!(num_notes >= 2);
+    assert!(num_revisions >= 1);
+    assert_eq!(NoteRow::rotate_keys(con, &keyring, 100).unwrap(), 0);
+    assert_eq!(NoteRevisionRow::rotate_keys(con, &keyring, 100).unwrap(), 0);
+
+    for id in &[note.id, plain.id] {
+        let row = note_row(con, *id);
+        assert_eq!(row.key_id, Some("key_b".to_string()));
+        assert!(!row.title.starts_with("note_test_rotate_keys"));
+    }
+    for row in revision_rows(con, note.id) {
+        assert_eq!(row.key_id, Some("key_b".to_string()));
+    }
+
+    assert_eq!(Note::find_by_id(con, note.id).unwrap(), note);
+    let plain_note = Note::find_by_id(con, plain.id).unwrap();
+    assert_eq!(plain_note.title, "note_test_rotate_keys_plain");
+    assert_eq!(plain_note.data, "some_text_plain");
+    assert_eq!(
+        NoteRevision::find_by_note_id_and_revision(con, note.id, 1)
+            .unwrap()
+            .data,
+        "some_text_note_test_rotate_keys"
+    );
+}
+
+#[test]
+fn test_note_link_blind_index() {
+    let pooled = connection();
+    let con: &DBConnection = &pooled;
+
+    let target = create_note(con, "note_test_reference_blind_index");
+    let note = NewNote::new(
+        1,
+        None,
+        "note_test_reference_blind_index_b".to_string(),
+        "[[ Note_Test_Reference_Blind_Index ]]".to_string(),
+    )
+    .create(con)
+    .unwrap();
+    NoteLink::set_note_links(con, &note).unwrap();
+
+    let links = NoteLink::find_by_note_id(con, note.id).unwrap();
+    let key = links[0].target_title.clone().unwrap();
+    assert!(key.starts_with("hmac:"));
+    assert!(!key
+        .to_lowercase()
+        .contains("note_test_reference_blind_index"));
+
+    assert_eq!(
+        NoteLink::find_by_target(con, 1, target.id, "note_test_reference_blind_index").unwrap(),
+        links
+    );
+    let notes = vec![target.clone()];
+    let targets = LinkTargets::new(&notes).unwrap();
+    assert_eq!(links[0].resolve(&targets), Some(&target));
+
+    // The rotation stores the keys of the new master key.
+    let keyring = keyring_b();
+    encryption::rotate_keys(con, &keyring, 100).unwrap();
+    assert_eq!(
+        note_links::table
+            .filter(note_links::note_id.eq(note.id))
+            .select(note_links::target_title)
+            .first::<Option<String>>(con)
+            .unwrap(),
+        Some(keyring.blind_index("note_test_reference_blind_index"))
+    );
+}
+
+#[test]
+fn test_decrypted_search_and_sort() {
+    let pooled = connection();
+    let con: &DBConnection = &pooled;
+
+    // More notes than a batch of decrypted rows, the matching one in the last.
+    for num in 0..510 {
+        create_note(con, &format!("note_test_decrypted_{:03}", num));
+    }
+    let note = NewNote::new(
+        1,
+        None,
+        "note_test_decrypted_match".to_string(),
+        "some_text_zebra".to_string(),
+    )
+    .create(con)
+    .unwrap();
+
+    let hits = Note::search(con, 1, "zebra", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].note, note);
+
+    let filter = NoteFilter {
+        sort: NoteSort::Title,
+        limit: Some(2),
+        ..Default::default()
+    };
+    let mut expected = Note::find_by_user_id(con, 1).unwrap();
+    expected.sort_by(|a, b| filter.compare(a, b));
+    expected.truncate(2);
+
+    let page = Note::find_by_user_id_and_filter(con, 1, &filter).unwrap();
+    assert_eq!(page.notes, expected);
+    assert!(page.next_cursor.is_some());
+}
@@ -2,11 +2,12 @@ extern crate dotenv;
 extern crate rnotes_core;
 
 use rnotes_core::models::db::note::*;
-use rnotes_core::utils::eq_no_ord;
+use rnotes_core::utils::{eq_no_ord, now};
 use rnotes_core::BDPool;
 
+use chrono::Duration;
+
 use std::panic;
-use std::time::SystemTime;
 
 #[test]
 fn test_note_find_all() {
@@ -22,8 +23,11 @@ fn test_note_find_all() {
             category_id: None,
             title: "note_a_user_a".to_string(),
             data: "some_text_note_a_user_a".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         Note {
             id: 2,
@@ -31,8 +35,11 @@ fn test_note_find_all() {
             category_id: Some(1),
             title: "note_b_user_a".to_string(),
             data: "some_text_note_a_user_a".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         Note {
             id: 3,
@@ -40,8 +47,11 @@ fn test_note_find_all() {
             category_id: Some(2),
             title: "note_c_user_c".to_string(),
             data: "some_text_note_c_user_c".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         Note {
             id: 4,
@@ -49,8 +59,11 @@ fn test_note_find_all() {
             category_id: None,
             title: "note_a_user_b".to_string(),
             data: "some_text_note_a_user_b".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
     ];
 
@@ -72,8 +85,11 @@ fn test_find_by_user_id() {
             category_id: None,
             title: "note_a_user_a".to_string(),
             data: "some_text_note_a_user_a".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         Note {
             id: 2,
@@ -81,8 +97,11 @@ fn test_find_by_user_id() {
             category_id: Some(1),
             title: "note_b_user_a".to_string(),
             data: "some_text_note_a_user_a".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         Note {
             id: 3,
@@ -90,8 +109,11 @@ fn test_find_by_user_id() {
             category_id: Some(2),
             title: "note_c_user_c".to_string(),
             data: "some_text_note_c_user_c".to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
     ];
 
@@ -112,8 +134,11 @@ fn test_note_find_by_user_id() {
         category_id: None,
         title: "note_a_user_a".to_string(),
         data: "some_text_note_a_user_a".to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
+        deleted_time: None,
+        format: "plain".to_string(),
+        key_check: None,
     };
 
     assert_eq!(result, Note::from(expected));
@@ -132,8 +157,11 @@ fn test_notefind_by_id_and_user_id_ok() {
         category_id: None,
         title: "note_a_user_a".to_string(),
         data: "some_text_note_a_user_a".to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
+        deleted_time: None,
+        format: "plain".to_string(),
+        key_check: None,
     };
 
     assert_eq!(result, Note::from(expected));
@@ -169,8 +197,11 @@ fn test_note_insert() {
         category_id: new_note.category_id,
         title: new_note.title.to_string(),
         data: new_note.data.to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
+        deleted_time: None,
+        format: "plain".to_string(),
+        key_check: None,
     };
 
     assert_eq!(result, expected);
@@ -181,6 +212,55 @@ fn test_note_insert() {
     Note::delete(&con, result.id).unwrap();
 }
 
+#[test]
+fn test_note_format() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let mut note = NewNote::new(
+        1,
+        None,
+        "note_test_note_format".to_string(),
+        "# some_text_note_test_note_format".to_string(),
+    )
+    .with_format(NoteFormat::Markdown)
+    .create(&con)
+    .unwrap();
+    assert_eq!(note.format(), NoteFormat::Markdown);
+
+    note.format = NoteFormat::Plain.to_string();
+    let note = Note::update(&con, &note).unwrap();
+    assert_eq!(note.format(), NoteFormat::Plain);
+
+    let mut invalid = note.clone();
+    invalid.format = "html".to_string();
+    assert!(Note::update(&con, &invalid).is_err());
+
+    Note::delete(&con, note.id).unwrap();
+}
+
+#[test]
+fn test_note_encrypted() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let note = NewNote::new(
+        1,
+        None,
+        "note_test_note_encrypted".to_string(),
+        "ciphertext_test_note_encrypted".to_string(),
+    )
+    .with_key_check(Some("some_key_check"))
+    .create(&con)
+    .unwrap();
+    assert!(note.is_encrypted());
+
+    let hits = Note::search(&con, 1, "ciphertext_test_note_encrypted", 10).unwrap();
+    assert!(hits.iter().all(|hit| hit.note.id != note.id));
+
+    Note::delete(&con, note.id).unwrap();
+}
+
 #[test]
 fn test_note_delete() {
     let pool = BDPool::new().unwrap();
@@ -237,3 +317,124 @@ fn test_note_update() {
 
     Note::delete(&con, note.id).unwrap();
 }
+
+#[test]
+fn test_note_search() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let new_note = NewNote::new(
+        1,
+        None,
+        "note_test_note_search".to_string(),
+        "some text about quokkas living in islands".to_string(),
+    );
+    let note = new_note.create(&con).unwrap();
+
+    let result = Note::search(&con, 1, "quokkas islands", 10).unwrap();
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].note, note);
+    assert!(result[0].snippet.contains("<mark>"));
+
+    let result = Note::search(&con, 2, "quokkas", 10).unwrap();
+    assert!(result.is_empty());
+
+    Note::delete(&con, note.id).unwrap();
+}
+
+#[test]
+fn test_note_find_by_user_id_and_filter_pages() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let created: Vec<Note> = vec!["note_c", "note_a", "note_b"]
+        .into_iter()
+        .map(|title| {
+            NewNote::new(3, Some(1), title.to_string(), "some_text".to_string())
+                .create(&con)
+                .unwrap()
+        })
+        .collect();
+
+    let mut filter = NoteFilter {
+        sort: NoteSort::Title,
+        descending: true,
+        limit: Some(2),
+        ..Default::default()
+    };
+
+    let page = Note::find_by_user_id_and_filter(&con, 3, &filter).unwrap();
+    assert_eq!(
+        page.notes
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["note_c", "note_b"]
+    );
+    assert!(page.next_cursor.is_some());
+
+    filter.cursor = page.next_cursor;
+    let page = Note::find_by_user_id_and_filter(&con, 3, &filter).unwrap();
+    assert_eq!(
+        page.notes
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["note_a"]
+    );
+    assert_eq!(page.next_cursor, None);
+
+    let filter = NoteFilter {
+        category_id: Some(2),
+        ..Default::default()
+    };
+    let page = Note::find_by_user_id_and_filter(&con, 3, &filter).unwrap();
+    assert!(page.notes.is_empty());
+
+    for note in created.iter() {
+        Note::delete(&con, note.id).unwrap();
+    }
+}
+
+#[test]
+fn test_note_trash_restore_and_purge() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let note = NewNote::new(
+        1,
+        None,
+        "note_test_note_trash".to_string(),
+        "some_text_note_test_note_trash".to_string(),
+    )
+    .create(&con)
+    .unwrap();
+
+    assert_eq!(Note::trash(&con, note.id).unwrap(), 1);
+    assert_eq!(Note::trash(&con, note.id).unwrap(), 0);
+
+    assert!(Note::find_by_id(&con, note.id).is_err());
+    assert!(Note::find_by_id_and_user_id(&con, note.id, 1).is_err());
+    assert!(Note::find_by_user_id(&con, 1)
+        .unwrap()
+        .iter()
+        .all(|n| n.id != note.id));
+
+    let trashed = Note::find_trashed_by_id_and_user_id(&con, note.id, 1).unwrap();
+    assert!(trashed.deleted_time.is_some());
+    assert_eq!(
+        Note::find_trashed_by_user_id(&con, 1).unwrap(),
+        vec![trashed]
+    );
+
+    let restored = Note::restore(&con, note.id).unwrap();
+    assert_eq!(restored, note);
+    assert_eq!(restored.deleted_time, None);
+    assert!(Note::restore(&con, note.id).is_err());
+
+    Note::trash(&con, note.id).unwrap();
+    assert_eq!(Note::purge(&con, now() - Duration::days(1)).unwrap(), 0);
+    assert_eq!(Note::purge(&con, now() + Duration::days(1)).unwrap(), 1);
+    assert!(Note::find_trashed_by_id_and_user_id(&con, note.id, 1).is_err());
+}
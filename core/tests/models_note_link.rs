@@ -0,0 +1,83 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::note_link::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_note_link_set_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let note_a = NewNote::new(
+        1,
+        None,
+        "note_test_note_link_a".to_string(),
+        "some_text_note_test_note_link_a".to_string(),
+    )
+    .create(&con)
+    .unwrap();
+    let mut note_b = NewNote::new(
+        1,
+        None,
+        "note_test_note_link_b".to_string(),
+        format!(
+            concat!(
+                "[[Note_Test_Note_Link_A]] [[#{}]] [[note_test_note_link_c]] ",
+                "[[note_test_note_link_a]]"
+            ),
+            note_a.id
+        ),
+    )
+    .create(&con)
+    .unwrap();
+
+    NoteLink::set_note_links(&con, &note_b).unwrap();
+    let links = NoteLink::find_by_note_id(&con, note_b.id).unwrap();
+    assert_eq!(
+        links
+            .iter()
+            .map(|link| (link.target_id, link.target_title.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (None, Some("note_test_note_link_a".to_string())),
+            (Some(note_a.id), None),
+            (None, Some("note_test_note_link_c".to_string())),
+        ]
+    );
+    assert_eq!(
+        NoteLink::find_by_target(&con, 1, note_a.id, "note_test_note_link_a").unwrap(),
+        links[0..2].to_vec()
+    );
+    assert_eq!(
+        NoteLink::find_by_target(&con, 1, 0, " NOTE_TEST_NOTE_LINK_A  ").unwrap(),
+        links[0..1].to_vec()
+    );
+    assert_eq!(
+        NoteLink::find_by_note_ids(&con, &[note_a.id, note_b.id]).unwrap(),
+        links
+    );
+    let notes = vec![note_a.clone()];
+    let targets = LinkTargets::new(&notes).unwrap();
+    assert_eq!(links[0].resolve(&targets), Some(&note_a));
+    assert_eq!(links[1].resolve(&targets), Some(&note_a));
+    assert_eq!(links[2].resolve(&targets), None);
+
+    note_b.data = "[[note_test_note_link_c]]".to_string();
+    NoteLink::set_note_links(&con, &note_b).unwrap();
+    assert_eq!(NoteLink::find_by_note_id(&con, note_b.id).unwrap().len(), 1);
+
+    Note::trash(&con, note_b.id).unwrap();
+    assert!(
+        NoteLink::find_by_target(&con, 1, note_a.id, "note_test_note_link_c")
+            .unwrap()
+            .is_empty()
+    );
+
+    Note::delete(&con, note_b.id).unwrap();
+    assert!(NoteLink::find_by_note_id(&con, note_b.id)
+        .unwrap()
+        .is_empty());
+    Note::delete(&con, note_a.id).unwrap();
+}
@@ -0,0 +1,86 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::note_share::*;
+use rnotes_core::models::db::user::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_note_share_create_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let user = NewUser::new(
+        "user_test_note_share@email.com",
+        "user_test_note_share",
+        "some_password",
+    )
+    .create(&con)
+    .unwrap();
+    let note = NewNote::new(
+        1,
+        None,
+        "note_test_note_share".to_string(),
+        "some_text_note_test_note_share".to_string(),
+    )
+    .create(&con)
+    .unwrap();
+
+    let note_share = NewNoteShare::new(note.id, user.id, Permission::Read)
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(note_share.permission(), Permission::Read);
+    assert_eq!(
+        NoteShare::find_by_note_id_and_user_id(&con, note.id, user.id).unwrap(),
+        note_share
+    );
+    assert_eq!(
+        NoteShare::find_by_note_id(&con, note.id).unwrap(),
+        vec![note_share.clone()]
+    );
+
+    let note_share = NewNoteShare::new(note.id, user.id, Permission::Write)
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(note_share.permission(), Permission::Write);
+    assert_eq!(NoteShare::find_by_note_id(&con, note.id).unwrap().len(), 1);
+
+    let shared = NoteShare::find_notes_by_user_id(&con, user.id).unwrap();
+    assert_eq!(shared, vec![(note.clone(), note_share.clone())]);
+    assert_eq!(
+        NoteShare::find_note_by_note_id_and_user_id(&con, note.id, user.id).unwrap(),
+        (note.clone(), note_share)
+    );
+    assert!(NoteShare::find_note_by_note_id_and_user_id(&con, note.id, note.user_id).is_err());
+
+    Note::trash(&con, note.id).unwrap();
+    assert!(NoteShare::find_notes_by_user_id(&con, user.id)
+        .unwrap()
+        .is_empty());
+    assert!(NoteShare::find_note_by_note_id_and_user_id(&con, note.id, user.id).is_err());
+
+    assert_eq!(
+        NoteShare::delete_by_note_id_and_user_id(&con, note.id, user.id).unwrap(),
+        1
+    );
+    assert!(NoteShare::find_by_note_id_and_user_id(&con, note.id, user.id).is_err());
+
+    Note::delete(&con, note.id).unwrap();
+    User::delete(&con, user.id).unwrap();
+}
+
+#[test]
+fn test_note_share_invalid_reference() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    assert!(NewNoteShare::new(1, 9999, Permission::Read)
+        .create(&con)
+        .is_err());
+    assert!(NewNoteShare::new(9999, 1, Permission::Read)
+        .create(&con)
+        .is_err());
+}
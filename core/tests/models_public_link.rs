@@ -0,0 +1,57 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::public_link::*;
+use rnotes_core::utils::now;
+use rnotes_core::BDPool;
+
+use chrono::Duration;
+
+#[test]
+fn test_public_link_create_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let note = NewNote::new(
+        1,
+        None,
+        "note_test_public_link".to_string(),
+        "some_text_note_test_public_link".to_string(),
+    )
+    .create(&con)
+    .unwrap();
+
+    let public_link = NewPublicLink::new(note.id, "hash_test_public_link")
+        .with_password_hash(Some("password_hash"))
+        .with_expire_time(Some(now() + Duration::days(1)))
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(public_link.note_id, note.id);
+    assert!(public_link.is_active(now()));
+    assert_eq!(
+        PublicLink::find_by_token_hash(&con, "hash_test_public_link").unwrap(),
+        (note.clone(), public_link.clone())
+    );
+    assert_eq!(
+        PublicLink::find_by_note_id(&con, note.id).unwrap(),
+        vec![public_link.clone()]
+    );
+    assert!(NewPublicLink::new(note.id, "hash_test_public_link")
+        .create(&con)
+        .is_err());
+
+    Note::trash(&con, note.id).unwrap();
+    assert!(PublicLink::find_by_token_hash(&con, "hash_test_public_link").is_err());
+
+    assert_eq!(
+        PublicLink::delete_by_id_and_note_id(&con, public_link.id, note.id).unwrap(),
+        1
+    );
+    assert!(PublicLink::find_by_note_id(&con, note.id)
+        .unwrap()
+        .is_empty());
+
+    Note::delete(&con, note.id).unwrap();
+}
@@ -0,0 +1,47 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::revision::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_revision_created_on_update() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let new_note = NewNote::new(
+        1,
+        None,
+        "note_test_revision_created_on_update".to_string(),
+        "some_text_a".to_string(),
+    );
+    let mut note = new_note.create(&con).unwrap();
+
+    assert!(NoteRevision::find_by_note_id(&con, note.id)
+        .unwrap()
+        .is_empty());
+
+    note.data = "some_text_b".to_string();
+    Note::update(&con, &note).unwrap();
+    note.data = "some_text_c".to_string();
+    Note::update(&con, &note).unwrap();
+
+    let result = NoteRevision::find_by_note_id(&con, note.id).unwrap();
+    assert_eq!(
+        result
+            .iter()
+            .map(|r| (r.revision, r.data.as_str()))
+            .collect::<Vec<(i32, &str)>>(),
+        vec![(1, "some_text_a"), (2, "some_text_b")]
+    );
+
+    let result = NoteRevision::find_by_note_id_and_revision(&con, note.id, 2).unwrap();
+    assert_eq!(result.data, "some_text_b");
+    assert!(NoteRevision::find_by_note_id_and_revision(&con, note.id, 3).is_err());
+
+    Note::delete(&con, note.id).unwrap();
+    assert!(NoteRevision::find_by_note_id(&con, note.id)
+        .unwrap()
+        .is_empty());
+}
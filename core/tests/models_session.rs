@@ -0,0 +1,95 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::session::*;
+use rnotes_core::token;
+use rnotes_core::utils::now;
+use rnotes_core::BDPool;
+
+use chrono::Duration;
+
+#[test]
+fn test_session_create_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let jti = token::generate();
+    let refresh_token_hash = token::hash(&token::generate());
+    let expire_time = now() + Duration::days(1);
+
+    let session = NewSession::new(1, &jti, &refresh_token_hash, expire_time)
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(session.user_id, 1);
+    assert!(session.is_active(now()));
+    assert!(!session.is_active(expire_time));
+    assert_eq!(Session::find_by_jti(&con, &jti).unwrap(), session);
+    assert_eq!(
+        Session::find_by_refresh_token_hash(&con, &refresh_token_hash).unwrap(),
+        session
+    );
+    assert!(Session::find_by_jti(&con, "unknown").is_err());
+}
+
+#[test]
+fn test_session_rotate_and_revoke() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let jti = token::generate();
+    let refresh_token_hash = token::hash(&token::generate());
+
+    let session = NewSession::new(1, &jti, &refresh_token_hash, now() + Duration::days(1))
+        .create(&con)
+        .unwrap();
+
+    let other_jti = token::generate();
+    let other_hash = token::hash(&token::generate());
+    let rotated = Session::rotate(
+        &con,
+        &session,
+        &other_jti,
+        &other_hash,
+        now() + Duration::days(2),
+    )
+    .unwrap();
+
+    assert_eq!(rotated.id, session.id);
+    assert_eq!(rotated.jti, other_jti);
+    assert_eq!(rotated.refresh_token_hash, other_hash);
+    assert!(Session::find_by_refresh_token_hash(&con, &refresh_token_hash).is_err());
+
+    // The old refresh token cannot be used twice.
+    assert!(Session::rotate(
+        &con,
+        &session,
+        &token::generate(),
+        &token::hash(&token::generate()),
+        now() + Duration::days(2),
+    )
+    .is_err());
+
+    assert_eq!(Session::revoke(&con, session.id).unwrap(), 1);
+    assert_eq!(Session::revoke(&con, session.id).unwrap(), 0);
+    assert!(!Session::find_by_jti(&con, &other_jti)
+        .unwrap()
+        .is_active(now()));
+}
+
+#[test]
+fn test_session_revoke_by_user_id() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let jti = token::generate();
+    let refresh_token_hash = token::hash(&token::generate());
+
+    NewSession::new(3, &jti, &refresh_token_hash, now() + Duration::days(1))
+        .create(&con)
+        .unwrap();
+
+    assert!(Session::revoke_by_user_id(&con, 3).unwrap() >= 1);
+    assert_eq!(Session::revoke_by_user_id(&con, 3).unwrap(), 0);
+    assert!(!Session::find_by_jti(&con, &jti).unwrap().is_active(now()));
+}
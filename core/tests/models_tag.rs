@@ -0,0 +1,91 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::tag::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_tag_normalize_names() {
+    let names = vec![
+        " Work".to_string(),
+        "work".to_string(),
+        "".to_string(),
+        "home".to_string(),
+    ];
+
+    assert_eq!(
+        Tag::normalize_names(&names),
+        vec!["home".to_string(), "work".to_string()]
+    );
+}
+
+#[test]
+fn test_tag_set_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let new_note = NewNote::new(
+        1,
+        None,
+        "note_test_tag_set_and_find".to_string(),
+        "some_text_note_test_tag_set_and_find".to_string(),
+    );
+    let note = new_note.create(&con).unwrap();
+
+    Tag::set_note_tags(
+        &con,
+        1,
+        note.id,
+        &["test_tag_b".to_string(), "test_tag_a".to_string()],
+    )
+    .unwrap();
+
+    let result = Tag::find_names_by_note_ids(&con, &[note.id]).unwrap();
+    assert_eq!(
+        result,
+        vec![
+            (note.id, "test_tag_a".to_string()),
+            (note.id, "test_tag_b".to_string())
+        ]
+    );
+
+    let result = Tag::find_note_ids_by_names(
+        &con,
+        1,
+        &["test_tag_a".to_string(), "test_tag_b".to_string()],
+    )
+    .unwrap();
+    assert_eq!(result, vec![note.id]);
+
+    let result = Tag::find_note_ids_by_names(&con, 2, &["test_tag_a".to_string()]).unwrap();
+    assert!(result.is_empty());
+
+    let result = Tag::remove_from_note(&con, 1, note.id, "test_tag_b").unwrap();
+    assert_eq!(result, 1 as usize);
+
+    let result = Tag::find_by_user_id_with_counts(&con, 1).unwrap();
+    assert!(result
+        .iter()
+        .any(|(tag, num)| tag.name == "test_tag_a" && *num >= 1));
+
+    let filter = NoteFilter {
+        tags: vec!["test_tag_a".to_string()],
+        ..Default::default()
+    };
+    let result = Note::find_by_user_id_and_filter(&con, 1, &filter).unwrap();
+    assert!(result.notes.iter().any(|n| n.id == note.id));
+
+    // Blank tags filter nothing.
+    let filter = NoteFilter {
+        tags: vec![" ".to_string()],
+        ..Default::default()
+    };
+    let result = Note::find_by_user_id_and_filter(&con, 1, &filter).unwrap();
+    assert!(result.notes.iter().any(|n| n.id == note.id));
+
+    let tag = Tag::find_or_create(&con, 1, "test_tag_a").unwrap();
+    assert_eq!(Tag::find_or_create(&con, 1, "test_tag_a").unwrap(), tag);
+
+    Note::delete(&con, note.id).unwrap();
+}
@@ -0,0 +1,111 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::two_factor::*;
+use rnotes_core::models::db::user::*;
+use rnotes_core::token;
+use rnotes_core::utils::now;
+use rnotes_core::BDPool;
+
+use chrono::Duration;
+
+#[test]
+fn test_totp_secret_create_and_use() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let user = NewUser::new(
+        "user_test_totp_secret@email.com",
+        "user_test_totp_secret",
+        "some_password",
+    )
+    .create(&con)
+    .unwrap();
+    let code_hashes = vec![token::hash("code_a"), token::hash("code_b")];
+
+    NewTotpSecret::new(user.id, "SECRETA")
+        .create(&con, &code_hashes)
+        .unwrap();
+    let totp_secret = NewTotpSecret::new(user.id, "SECRETB")
+        .create(&con, &code_hashes)
+        .unwrap();
+
+    assert_eq!(totp_secret.secret, "SECRETB");
+    assert!(!totp_secret.enabled);
+    assert_eq!(
+        TotpSecret::find_by_user_id(&con, user.id).unwrap(),
+        totp_secret
+    );
+    assert_eq!(TotpSecret::use_step(&con, user.id, 10).unwrap(), 0);
+
+    assert_eq!(TotpSecret::enable(&con, user.id, 10).unwrap(), 1);
+    assert_eq!(TotpSecret::enable(&con, user.id, 11).unwrap(), 0);
+    assert_eq!(TotpSecret::use_step(&con, user.id, 10).unwrap(), 0);
+    assert_eq!(TotpSecret::use_step(&con, user.id, 11).unwrap(), 1);
+    assert_eq!(
+        TotpSecret::find_by_user_id(&con, user.id)
+            .unwrap()
+            .last_step,
+        11
+    );
+
+    let locked_until = now() + Duration::minutes(15);
+    let totp_secret = TotpSecret::add_failed_code(&con, user.id, 2, locked_until).unwrap();
+    assert_eq!(totp_secret.failed_codes, 1);
+    assert!(!totp_secret.is_locked(now()));
+    assert_eq!(TotpSecret::reset_failed_codes(&con, user.id).unwrap(), 1);
+    TotpSecret::add_failed_code(&con, user.id, 2, locked_until).unwrap();
+    let totp_secret = TotpSecret::add_failed_code(&con, user.id, 2, locked_until).unwrap();
+    assert_eq!(totp_secret.failed_codes, 0);
+    assert!(totp_secret.is_locked(now()));
+
+    assert_eq!(
+        RecoveryCode::use_code(&con, user.id, &code_hashes[0]).unwrap(),
+        1
+    );
+    assert_eq!(
+        RecoveryCode::use_code(&con, user.id, &code_hashes[0]).unwrap(),
+        0
+    );
+    assert_eq!(
+        RecoveryCode::use_code(&con, user.id, &token::hash("unknown")).unwrap(),
+        0
+    );
+
+    assert_eq!(TotpSecret::delete_by_user_id(&con, user.id).unwrap(), 1);
+    assert!(TotpSecret::find_by_user_id(&con, user.id).is_err());
+    assert_eq!(
+        RecoveryCode::use_code(&con, user.id, &code_hashes[1]).unwrap(),
+        0
+    );
+
+    User::delete(&con, user.id).unwrap();
+}
+
+#[test]
+fn test_login_challenge() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let token_hash = token::hash(&token::generate());
+    let challenge = NewLoginChallenge::new(1, &token_hash, now() + Duration::minutes(5))
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(challenge.attempts, 0);
+    assert_eq!(
+        LoginChallenge::find_by_token_hash(&con, &token_hash).unwrap(),
+        challenge
+    );
+
+    assert_eq!(LoginChallenge::add_attempt(&con, challenge.id).unwrap(), 1);
+    assert_eq!(
+        LoginChallenge::find_by_token_hash(&con, &token_hash)
+            .unwrap()
+            .attempts,
+        1
+    );
+
+    assert_eq!(LoginChallenge::delete(&con, challenge.id).unwrap(), 1);
+    assert!(LoginChallenge::find_by_token_hash(&con, &token_hash).is_err());
+}
@@ -2,11 +2,10 @@ extern crate dotenv;
 extern crate rnotes_core;
 
 use rnotes_core::models::db::user::*;
-use rnotes_core::utils::eq_no_ord;
+use rnotes_core::utils::{eq_no_ord, now};
 use rnotes_core::BDPool;
 
 use std::panic;
-use std::time::SystemTime;
 
 #[test]
 fn test_pool() {
@@ -28,8 +27,10 @@ fn test_user_find_all() {
             name: "user_a".to_string(),
             password: "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B"
                 .to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            role: "user".to_string(),
+            disabled: false,
         },
         User {
             id: 2,
@@ -37,8 +38,10 @@ fn test_user_find_all() {
             name: "user_b".to_string(),
             password: "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B"
                 .to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            role: "user".to_string(),
+            disabled: false,
         },
         User {
             id: 3,
@@ -46,8 +49,10 @@ fn test_user_find_all() {
             name: "user_c".to_string(),
             password: "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B"
                 .to_string(),
-            create_time: SystemTime::now(),
-            update_time: SystemTime::now(),
+            create_time: now(),
+            update_time: now(),
+            role: "user".to_string(),
+            disabled: false,
         },
     ];
 
@@ -67,32 +72,31 @@ fn test_user_find_by_id() {
         email: "user_a@email.com".to_string(),
         name: "user_a".to_string(),
         password: "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B".to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
+        role: "user".to_string(),
+        disabled: false,
     };
 
     assert_eq!(result, expected);
 }
 
 #[test]
-fn test_user_find_by_email_and_password() {
+fn test_user_find_by_email() {
     let pool = BDPool::new().unwrap();
     let con = pool.get().unwrap();
 
-    let result = User::find_by_email_and_password(
-        &con,
-        "user_a@email.com".to_string(),
-        "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B".to_string(),
-    )
-    .unwrap();
+    let result = User::find_by_email(&con, "user_a@email.com".to_string()).unwrap();
 
     let expected = User {
         id: 1,
         email: "user_a@email.com".to_string(),
         name: "user_a".to_string(),
         password: "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B".to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
+        role: "user".to_string(),
+        disabled: false,
     };
 
     assert_eq!(result, expected);
@@ -116,8 +120,10 @@ fn test_user_insert() {
         email: "user_test_user_insert@email.com".to_string(),
         name: new_user.name.to_string(),
         password: new_user.password.to_string(),
-        create_time: SystemTime::now(),
-        update_time: SystemTime::now(),
+        create_time: now(),
+        update_time: now(),
+        role: "user".to_string(),
+        disabled: false,
     };
 
     assert_eq!(expected, result);
@@ -165,6 +171,8 @@ fn test_user_update() {
     user.email = user.email + "_other";
     user.name = user.name + "_other";
     user.password = user.password + "_other";
+    user.role = Role::Admin.to_string();
+    user.disabled = true;
 
     let result = User::update(&con, &user).unwrap();
 
@@ -172,6 +180,8 @@ fn test_user_update() {
     assert_eq!(result.email, user.email);
     assert_eq!(result.name, user.name);
     assert_eq!(result.password, user.password);
+    assert_eq!(result.role(), Role::Admin);
+    assert!(result.disabled);
     assert_eq!(result.create_time, user.create_time);
     assert!(result.update_time > user.update_time);
 
@@ -180,3 +190,39 @@ fn test_user_update() {
 
     User::delete(&con, user.id).unwrap();
 }
+
+#[test]
+fn test_user_insert_duplicated_email() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let result = NewUser::new(
+        "user_a@email.com",
+        "user_test_user_insert_duplicated_email",
+        "some_password",
+    )
+    .create(&con);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_user_insert_with_role() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let result = NewUser::new(
+        "user_test_user_insert_with_role@email.com",
+        "user_test_user_insert_with_role",
+        "some_password",
+    )
+    .with_role(Role::Admin)
+    .create(&con)
+    .unwrap();
+
+    assert_eq!(result.role, "admin");
+    assert_eq!(result.role(), Role::Admin);
+    assert!(!result.disabled);
+
+    User::delete(&con, result.id).unwrap();
+}
@@ -0,0 +1,43 @@
+extern crate dotenv;
+extern crate rnotes_core;
+
+use rnotes_core::models::db::user::*;
+use rnotes_core::models::db::user_identity::*;
+use rnotes_core::BDPool;
+
+#[test]
+fn test_user_identity_create_and_find() {
+    let pool = BDPool::new().unwrap();
+    let con = pool.get().unwrap();
+
+    let user = NewUser::new(
+        "user_test_user_identity@email.com",
+        "user_test_user_identity",
+        "some_password",
+    )
+    .create(&con)
+    .unwrap();
+
+    let identity = NewUserIdentity::new(user.id, "https://idp.example.com", "some_subject")
+        .create(&con)
+        .unwrap();
+
+    assert_eq!(identity.user_id, user.id);
+    assert_eq!(
+        UserIdentity::find_by_subject(&con, "https://idp.example.com", "some_subject").unwrap(),
+        identity
+    );
+    assert!(
+        UserIdentity::find_by_subject(&con, "https://other.example.com", "some_subject").is_err()
+    );
+    assert!(
+        NewUserIdentity::new(user.id, "https://idp.example.com", "some_subject")
+            .create(&con)
+            .is_err()
+    );
+
+    User::delete(&con, user.id).unwrap();
+    assert!(
+        UserIdentity::find_by_subject(&con, "https://idp.example.com", "some_subject").is_err()
+    );
+}
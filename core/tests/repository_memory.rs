@@ -0,0 +1,1125 @@
+extern crate rnotes_core;
+
+use rnotes_core::models::db::api_key::*;
+use rnotes_core::models::db::attachment::*;
+use rnotes_core::models::db::category::*;
+use rnotes_core::models::db::note::*;
+use rnotes_core::models::db::note_link::*;
+use rnotes_core::models::db::note_share::*;
+use rnotes_core::models::db::public_link::*;
+use rnotes_core::models::db::session::*;
+use rnotes_core::models::db::two_factor::*;
+use rnotes_core::models::db::user::*;
+use rnotes_core::models::db::user_identity::*;
+use rnotes_core::repository::memory::MemoryRepository;
+use rnotes_core::repository::{Repository, RepositoryError};
+use rnotes_core::utils::now;
+
+use chrono::Duration;
+
+fn tags(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn test_repository() -> MemoryRepository {
+    let repo = MemoryRepository::new();
+
+    repo.users()
+        .create(&NewUser::new("user_a@email.com", "user_a", "some_password"))
+        .unwrap();
+    repo.categories()
+        .create(&NewCategory::new(Some(1), "cat_a"))
+        .unwrap();
+
+    repo
+}
+
+#[test]
+fn test_note_create_and_find() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                Some(1),
+                "some_title".to_string(),
+                "some_data".to_string(),
+            ),
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(note.id, 1);
+    assert_eq!(repo.notes().find_by_id_and_user_id(1, 1).unwrap(), note);
+    assert_eq!(
+        repo.notes()
+            .find_by_user_id(1, &NoteFilter::default())
+            .unwrap()
+            .notes,
+        vec![note]
+    );
+    assert_eq!(
+        repo.notes().find_by_id_and_user_id(1, 2),
+        Err(RepositoryError::NotFound)
+    );
+}
+
+#[test]
+fn test_note_invalid_reference() {
+    let repo = test_repository();
+
+    let result = repo.notes().create(
+        &NewNote::new(
+            1,
+            Some(999),
+            "some_title".to_string(),
+            "some_data".to_string(),
+        ),
+        &[],
+    );
+
+    match result {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_note_update_and_delete() {
+    let repo = test_repository();
+
+    let mut note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "some_title".to_string(), "some_data".to_string()),
+            &[],
+        )
+        .unwrap();
+
+    note.title = "other_title".to_string();
+
+    let result = repo.notes().update(&note, &[]).unwrap();
+
+    assert_eq!(result, note);
+    assert!(result.update_time >= note.update_time);
+
+    assert_eq!(repo.notes().delete(note.id).unwrap(), 1);
+    assert_eq!(repo.notes().delete(note.id).unwrap(), 0);
+}
+
+#[test]
+fn test_note_tags() {
+    let repo = test_repository();
+
+    let note_a = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_a".to_string(), "some_data".to_string()),
+            &tags(&["Work", " urgent ", "work"]),
+        )
+        .unwrap();
+    let note_b = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_b".to_string(), "some_data".to_string()),
+            &tags(&["work"]),
+        )
+        .unwrap();
+
+    assert_eq!(
+        repo.tags().find_by_note_ids(&[note_a.id]).unwrap(),
+        vec![
+            (note_a.id, "urgent".to_string()),
+            (note_a.id, "work".to_string())
+        ]
+    );
+
+    let filter = NoteFilter {
+        tags: tags(&["work", "urgent"]),
+        ..Default::default()
+    };
+    assert_eq!(
+        repo.notes().find_by_user_id(1, &filter).unwrap().notes,
+        vec![note_a.clone()]
+    );
+
+    repo.tags().add(1, note_b.id, "urgent").unwrap();
+    assert_eq!(
+        repo.notes().find_by_user_id(1, &filter).unwrap().notes,
+        vec![note_a.clone(), note_b.clone()]
+    );
+
+    let blank = NoteFilter {
+        tags: tags(&[" "]),
+        ..Default::default()
+    };
+    assert_eq!(
+        repo.notes().find_by_user_id(1, &blank).unwrap(),
+        repo.notes()
+            .find_by_user_id(1, &NoteFilter::default())
+            .unwrap()
+    );
+
+    assert_eq!(repo.tags().remove(1, note_a.id, "work").unwrap(), 1);
+    assert_eq!(repo.tags().remove(1, note_a.id, "work").unwrap(), 0);
+
+    let counts: Vec<(String, i64)> = repo
+        .tags()
+        .find_by_user_id(1)
+        .unwrap()
+        .into_iter()
+        .map(|(tag, num)| (tag.name, num))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![("urgent".to_string(), 2), ("work".to_string(), 1)]
+    );
+}
+
+#[test]
+fn test_category_conflict() {
+    let repo = test_repository();
+
+    let result = repo
+        .categories()
+        .create(&NewCategory::new(Some(1), "cat_a"));
+
+    match result {
+        Err(RepositoryError::Conflict(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(repo
+        .categories()
+        .create(&NewCategory::new(None, "cat_a"))
+        .is_ok());
+
+    match repo.categories().create(&NewCategory::new(None, "cat_a")) {
+        Err(RepositoryError::Conflict(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_category_delete_with_notes() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, Some(1), "some_title".to_string(), "".to_string()),
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(repo.categories().delete(1).unwrap(), 1);
+    assert_eq!(
+        repo.notes()
+            .find_by_id_and_user_id(note.id, 1)
+            .unwrap()
+            .category_id,
+        None
+    );
+    assert_eq!(
+        repo.categories().find_by_id_and_user_id(1, 1),
+        Err(RepositoryError::NotFound)
+    );
+}
+
+#[test]
+fn test_category_tree() {
+    let repo = test_repository();
+
+    let child = repo
+        .categories()
+        .create(&NewCategory::new(Some(1), "cat_b").with_parent(Some(1)))
+        .unwrap();
+    let grandchild = repo
+        .categories()
+        .create(&NewCategory::new(Some(1), "cat_c").with_parent(Some(child.id)))
+        .unwrap();
+
+    assert_eq!(
+        repo.categories().find_descendant_ids(1).unwrap(),
+        vec![1, child.id, grandchild.id]
+    );
+
+    let mut root = repo.categories().find_by_id(1).unwrap();
+    root.parent_id = Some(grandchild.id);
+    match repo.categories().update(&root) {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+    root.parent_id = Some(root.id);
+    match repo.categories().update(&root) {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+    match repo
+        .categories()
+        .create(&NewCategory::new(Some(1), "cat_d").with_parent(Some(999)))
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                Some(grandchild.id),
+                "some_title".to_string(),
+                "".to_string(),
+            ),
+            &[],
+        )
+        .unwrap();
+    let filter = NoteFilter {
+        category_id: Some(1),
+        descendants: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        repo.notes().find_by_user_id(1, &filter).unwrap().notes,
+        vec![note]
+    );
+
+    repo.categories().delete(child.id).unwrap();
+    assert_eq!(
+        repo.categories()
+            .find_by_id(grandchild.id)
+            .unwrap()
+            .parent_id,
+        None
+    );
+}
+
+#[test]
+fn test_user_find_by_email() {
+    let repo = test_repository();
+
+    let result = repo.users().find_by_email("user_a@email.com").unwrap();
+
+    assert_eq!(result.id, 1);
+    assert_eq!(
+        repo.users().find_by_email("user_b@email.com"),
+        Err(RepositoryError::NotFound)
+    );
+}
+
+#[test]
+fn test_user_conflict() {
+    let repo = test_repository();
+
+    for (email, name) in &[
+        ("user_b@email.com", "user_a"),
+        ("user_a@email.com", "user_b"),
+    ] {
+        match repo
+            .users()
+            .create(&NewUser::new(email, name, "some_password"))
+        {
+            Err(RepositoryError::Conflict(_)) => {}
+            _ => panic!("Unexpected response"),
+        }
+    }
+}
+
+#[test]
+fn test_note_search() {
+    let repo = test_repository();
+
+    for (title, data) in &[
+        ("rust notes", "some data"),
+        ("other", "notes about rust"),
+        ("other", "nothing"),
+    ] {
+        repo.notes()
+            .create(
+                &NewNote::new(1, None, title.to_string(), data.to_string()),
+                &[],
+            )
+            .unwrap();
+    }
+
+    let result = repo.notes().search(1, "Rust", 10).unwrap();
+
+    assert_eq!(
+        result.iter().map(|hit| hit.note.id).collect::<Vec<i32>>(),
+        vec![1, 2]
+    );
+    assert_eq!(result[0].title, "<mark>rust</mark> notes");
+    assert_eq!(result[1].snippet, "notes about <mark>rust</mark>");
+
+    assert!(repo.notes().search(1, "rust", 1).unwrap().len() == 1);
+    assert!(repo.notes().search(2, "rust", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_note_pages() {
+    let repo = test_repository();
+
+    for (title, category_id) in &[("c", Some(1)), ("a", None), ("b", Some(1))] {
+        repo.notes()
+            .create(
+                &NewNote::new(1, *category_id, title.to_string(), "".to_string()),
+                &[],
+            )
+            .unwrap();
+    }
+
+    let mut filter = NoteFilter {
+        sort: NoteSort::Title,
+        limit: Some(2),
+        ..Default::default()
+    };
+
+    let page = repo.notes().find_by_user_id(1, &filter).unwrap();
+    assert_eq!(
+        page.notes
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["a", "b"]
+    );
+    let cursor = page.next_cursor.unwrap();
+    assert_eq!(NoteCursor::decode(&cursor.encode()), Some(cursor.clone()));
+
+    filter.cursor = Some(cursor);
+    let page = repo.notes().find_by_user_id(1, &filter).unwrap();
+    assert_eq!(
+        page.notes
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["c"]
+    );
+    assert_eq!(page.next_cursor, None);
+
+    let filter = NoteFilter {
+        sort: NoteSort::Title,
+        descending: true,
+        category_id: Some(1),
+        ..Default::default()
+    };
+    let page = repo.notes().find_by_user_id(1, &filter).unwrap();
+    assert_eq!(
+        page.notes
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["c", "b"]
+    );
+
+    assert_eq!(NoteCursor::decode("not_a_cursor"), None);
+}
+
+#[test]
+fn test_note_trash() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "some_title".to_string(), "some_data".to_string()),
+            &tags(&["work"]),
+        )
+        .unwrap();
+
+    assert_eq!(repo.notes().trash(note.id).unwrap(), 1);
+    assert_eq!(repo.notes().trash(note.id).unwrap(), 0);
+
+    assert_eq!(
+        repo.notes().find_by_id_and_user_id(note.id, 1),
+        Err(RepositoryError::NotFound)
+    );
+    assert!(repo
+        .notes()
+        .find_by_user_id(1, &NoteFilter::default())
+        .unwrap()
+        .notes
+        .is_empty());
+    assert!(repo.notes().search(1, "some_title", 10).unwrap().is_empty());
+    assert_eq!(repo.tags().find_by_user_id(1).unwrap()[0].1, 0);
+    assert_eq!(
+        repo.notes().update(&note, &[]),
+        Err(RepositoryError::NotFound)
+    );
+
+    assert_eq!(
+        repo.notes().find_trashed_by_user_id(1).unwrap(),
+        vec![note.clone()]
+    );
+    assert_eq!(repo.notes().restore(note.id).unwrap(), note);
+    assert_eq!(
+        repo.notes().restore(note.id),
+        Err(RepositoryError::NotFound)
+    );
+
+    repo.notes().trash(note.id).unwrap();
+    assert_eq!(repo.notes().purge(now() - Duration::days(1)).unwrap(), 0);
+    assert_eq!(repo.notes().purge(now() + Duration::days(1)).unwrap(), 1);
+    assert_eq!(
+        repo.notes().find_trashed_by_id_and_user_id(note.id, 1),
+        Err(RepositoryError::NotFound)
+    );
+}
+
+#[test]
+fn test_session_rotate_and_revoke() {
+    let repo = test_repository();
+
+    let session = repo
+        .sessions()
+        .create(&NewSession::new(
+            1,
+            "jti_a",
+            "hash_a",
+            now() + Duration::days(1),
+        ))
+        .unwrap();
+
+    assert_eq!(repo.sessions().find_by_jti("jti_a").unwrap(), session);
+    match repo.sessions().create(&NewSession::new(
+        2,
+        "jti_b",
+        "hash_b",
+        now() + Duration::days(1),
+    )) {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    let rotated = repo
+        .sessions()
+        .rotate(&session, "jti_b", "hash_b", now() + Duration::days(2))
+        .unwrap();
+    assert_eq!(
+        repo.sessions()
+            .find_by_refresh_token_hash("hash_b")
+            .unwrap(),
+        rotated
+    );
+    assert_eq!(
+        repo.sessions()
+            .rotate(&session, "jti_c", "hash_c", now() + Duration::days(2)),
+        Err(RepositoryError::NotFound)
+    );
+
+    assert_eq!(repo.sessions().revoke(session.id).unwrap(), 1);
+    assert_eq!(repo.sessions().revoke(session.id).unwrap(), 0);
+    assert!(!repo
+        .sessions()
+        .find_by_jti("jti_b")
+        .unwrap()
+        .is_active(now()));
+}
+
+#[test]
+fn test_api_key_create_and_delete() {
+    let repo = test_repository();
+
+    let api_key = repo
+        .api_keys()
+        .create(&NewApiKey::new(1, "key_a", "rn_a", "hash_a").with_read_only(true))
+        .unwrap();
+
+    assert_eq!(repo.api_keys().find_by_key_hash("hash_a").unwrap(), api_key);
+    assert_eq!(
+        repo.api_keys().find_by_user_id(1).unwrap(),
+        vec![api_key.clone()]
+    );
+    match repo
+        .api_keys()
+        .create(&NewApiKey::new(1, "key_b", "rn_a", "hash_a"))
+    {
+        Err(RepositoryError::Conflict(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+    match repo
+        .api_keys()
+        .create(&NewApiKey::new(2, "key_b", "rn_b", "hash_b"))
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    assert_eq!(repo.api_keys().delete(api_key.id, 2).unwrap(), 0);
+    assert_eq!(repo.api_keys().delete(api_key.id, 1).unwrap(), 1);
+    assert_eq!(
+        repo.api_keys().find_by_key_hash("hash_a"),
+        Err(RepositoryError::NotFound)
+    );
+}
+
+#[test]
+fn test_two_factor() {
+    let repo = test_repository();
+    let code_hashes = vec!["hash_a".to_string(), "hash_b".to_string()];
+
+    assert_eq!(
+        repo.two_factor().find_totp(1),
+        Err(RepositoryError::NotFound)
+    );
+    match repo
+        .two_factor()
+        .create_totp(&NewTotpSecret::new(2, "SECRET"), &code_hashes)
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    let totp_secret = repo
+        .two_factor()
+        .create_totp(&NewTotpSecret::new(1, "SECRET"), &code_hashes)
+        .unwrap();
+
+    assert!(!totp_secret.enabled);
+    assert_eq!(repo.two_factor().find_totp(1).unwrap(), totp_secret);
+    assert_eq!(repo.two_factor().use_totp_step(1, 10).unwrap(), 0);
+    assert_eq!(repo.two_factor().enable_totp(1, 10).unwrap(), 1);
+    assert_eq!(repo.two_factor().use_totp_step(1, 10).unwrap(), 0);
+    assert_eq!(repo.two_factor().use_totp_step(1, 11).unwrap(), 1);
+    assert_eq!(repo.two_factor().use_recovery_code(1, "hash_a").unwrap(), 1);
+    assert_eq!(repo.two_factor().use_recovery_code(1, "hash_a").unwrap(), 0);
+
+    let locked_until = now() + Duration::minutes(15);
+    let totp_secret = repo
+        .two_factor()
+        .add_failed_code(1, 2, locked_until)
+        .unwrap();
+    assert_eq!(totp_secret.failed_codes, 1);
+    assert!(!totp_secret.is_locked(now()));
+    assert_eq!(repo.two_factor().reset_failed_codes(1).unwrap(), 1);
+    repo.two_factor()
+        .add_failed_code(1, 2, locked_until)
+        .unwrap();
+    let totp_secret = repo
+        .two_factor()
+        .add_failed_code(1, 2, locked_until)
+        .unwrap();
+    assert_eq!(totp_secret.failed_codes, 0);
+    assert!(totp_secret.is_locked(now()));
+    assert!(!totp_secret.is_locked(locked_until));
+
+    let challenge = repo
+        .two_factor()
+        .create_challenge(&NewLoginChallenge::new(
+            1,
+            "token_hash",
+            now() + Duration::minutes(5),
+        ))
+        .unwrap();
+
+    assert_eq!(
+        repo.two_factor()
+            .add_challenge_attempt(challenge.id)
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        repo.two_factor()
+            .find_challenge("token_hash")
+            .unwrap()
+            .attempts,
+        1
+    );
+    assert_eq!(repo.two_factor().delete_challenge(challenge.id).unwrap(), 1);
+    assert_eq!(
+        repo.two_factor().find_challenge("token_hash"),
+        Err(RepositoryError::NotFound)
+    );
+
+    assert_eq!(repo.two_factor().delete_totp(1).unwrap(), 1);
+    assert_eq!(repo.two_factor().use_recovery_code(1, "hash_b").unwrap(), 0);
+}
+
+#[test]
+fn test_user_identity() {
+    let repo = test_repository();
+
+    let identity = repo
+        .identities()
+        .create(&NewUserIdentity::new(1, "issuer", "subject_a"))
+        .unwrap();
+
+    assert_eq!(
+        repo.identities()
+            .find_by_subject("issuer", "subject_a")
+            .unwrap(),
+        identity
+    );
+    assert_eq!(
+        repo.identities().find_by_subject("other", "subject_a"),
+        Err(RepositoryError::NotFound)
+    );
+    match repo
+        .identities()
+        .create(&NewUserIdentity::new(1, "issuer", "subject_a"))
+    {
+        Err(RepositoryError::Conflict(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+    match repo
+        .identities()
+        .create(&NewUserIdentity::new(2, "issuer", "subject_b"))
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_note_format() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_a".to_string(), "# text_a".to_string())
+                .with_format(NoteFormat::Markdown),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(note.format(), NoteFormat::Markdown);
+
+    let mut updated = note.clone();
+    updated.format = NoteFormat::Plain.to_string();
+    let note = repo.notes().update(&updated, &[]).unwrap();
+    assert_eq!(note.format(), NoteFormat::Plain);
+}
+
+#[test]
+fn test_note_encrypted() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                None,
+                "ciphertext_a".to_string(),
+                "ciphertext_b".to_string(),
+            )
+            .with_key_check(Some("some_key_check")),
+            &[],
+        )
+        .unwrap();
+    assert!(note.is_encrypted());
+    assert!(repo
+        .notes()
+        .search(1, "ciphertext_b", 10)
+        .unwrap()
+        .is_empty());
+
+    let mut updated = note.clone();
+    updated.title = "note_a".to_string();
+    updated.data = "text_a".to_string();
+    updated.key_check = None;
+    let updated = repo.notes().update(&updated, &[]).unwrap();
+    assert!(!updated.is_encrypted());
+    assert_eq!(repo.notes().search(1, "text_a", 10).unwrap().len(), 1);
+
+    let revision = repo
+        .revisions()
+        .find_by_note_id_and_revision(note.id, 1)
+        .unwrap();
+    assert_eq!(revision.key_check, Some("some_key_check".to_string()));
+}
+
+#[test]
+fn test_note_share() {
+    let repo = test_repository();
+
+    let user = repo
+        .users()
+        .create(&NewUser::new("user_b@email.com", "user_b", "some_password"))
+        .unwrap();
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_a".to_string(), "text_a".to_string()),
+            &[],
+        )
+        .unwrap();
+
+    let note_share = repo
+        .shares()
+        .create(&NewNoteShare::new(note.id, user.id, Permission::Read))
+        .unwrap();
+    assert_eq!(note_share.permission(), Permission::Read);
+    assert_eq!(
+        repo.shares()
+            .find_by_note_id_and_user_id(note.id, user.id)
+            .unwrap(),
+        note_share
+    );
+
+    let note_share = repo
+        .shares()
+        .create(&NewNoteShare::new(note.id, user.id, Permission::Write))
+        .unwrap();
+    assert_eq!(note_share.permission(), Permission::Write);
+    assert_eq!(
+        repo.shares().find_by_note_id(note.id).unwrap(),
+        vec![note_share.clone()]
+    );
+    assert_eq!(
+        repo.shares().find_notes_by_user_id(user.id).unwrap(),
+        vec![(note.clone(), note_share.clone())]
+    );
+    assert!(repo.shares().find_notes_by_user_id(1).unwrap().is_empty());
+    assert_eq!(
+        repo.shares()
+            .find_note_by_note_id_and_user_id(note.id, user.id)
+            .unwrap(),
+        (note.clone(), note_share)
+    );
+    assert_eq!(
+        repo.shares().find_note_by_note_id_and_user_id(note.id, 1),
+        Err(RepositoryError::NotFound)
+    );
+
+    match repo
+        .shares()
+        .create(&NewNoteShare::new(note.id, 9, Permission::Read))
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    repo.notes().trash(note.id).unwrap();
+    assert!(repo
+        .shares()
+        .find_notes_by_user_id(user.id)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        repo.shares()
+            .find_note_by_note_id_and_user_id(note.id, user.id),
+        Err(RepositoryError::NotFound)
+    );
+
+    assert_eq!(repo.shares().delete(note.id, user.id).unwrap(), 1);
+    assert_eq!(repo.shares().delete(note.id, user.id).unwrap(), 0);
+
+    repo.shares()
+        .create(&NewNoteShare::new(note.id, user.id, Permission::Read))
+        .unwrap();
+    repo.notes().delete(note.id).unwrap();
+    assert!(repo.shares().find_by_note_id(note.id).unwrap().is_empty());
+}
+
+#[test]
+fn test_public_link() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_a".to_string(), "text_a".to_string()),
+            &[],
+        )
+        .unwrap();
+
+    let public_link = repo
+        .public_links()
+        .create(
+            &NewPublicLink::new(note.id, "hash_a")
+                .with_password_hash(Some("password_hash"))
+                .with_expire_time(Some(now() + Duration::days(1))),
+        )
+        .unwrap();
+    assert!(public_link.is_active(now()));
+    assert!(!public_link.is_active(now() + Duration::days(2)));
+
+    assert_eq!(
+        repo.public_links().find_by_token_hash("hash_a").unwrap(),
+        (note.clone(), public_link.clone())
+    );
+    assert_eq!(
+        repo.public_links().find_by_note_id(note.id).unwrap(),
+        vec![public_link.clone()]
+    );
+    match repo
+        .public_links()
+        .create(&NewPublicLink::new(note.id, "hash_a"))
+    {
+        Err(RepositoryError::Conflict(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+    match repo
+        .public_links()
+        .create(&NewPublicLink::new(999, "hash_b"))
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    repo.notes().trash(note.id).unwrap();
+    assert_eq!(
+        repo.public_links().find_by_token_hash("hash_a"),
+        Err(RepositoryError::NotFound)
+    );
+    repo.notes().restore(note.id).unwrap();
+
+    assert_eq!(repo.public_links().delete(public_link.id, 999).unwrap(), 0);
+    assert_eq!(
+        repo.public_links().delete(public_link.id, note.id).unwrap(),
+        1
+    );
+    assert_eq!(
+        repo.public_links().find_by_token_hash("hash_a"),
+        Err(RepositoryError::NotFound)
+    );
+}
+
+#[test]
+fn test_attachment() {
+    let repo = test_repository();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_a".to_string(), "text_a".to_string()),
+            &[],
+        )
+        .unwrap();
+
+    let attachment_a = repo
+        .attachments()
+        .create(&NewAttachment::new(
+            note.id,
+            "a.txt",
+            "text/plain",
+            10,
+            "key_a",
+        ))
+        .unwrap();
+    let attachment_b = repo
+        .attachments()
+        .create(&NewAttachment::new(
+            note.id,
+            "b.png",
+            "image/png",
+            20,
+            "key_b",
+        ))
+        .unwrap();
+
+    assert_eq!(
+        repo.attachments().find_by_note_id(note.id).unwrap(),
+        vec![attachment_a.clone(), attachment_b.clone()]
+    );
+    assert_eq!(
+        repo.attachments()
+            .find_by_id_and_note_id(attachment_b.id, note.id)
+            .unwrap(),
+        attachment_b.clone()
+    );
+    assert_eq!(
+        repo.attachments()
+            .find_by_id_and_note_id(attachment_b.id, 999),
+        Err(RepositoryError::NotFound)
+    );
+    assert_eq!(repo.attachments().total_size_by_user_id(1).unwrap(), 30);
+    match repo.attachments().create(&NewAttachment::new(
+        note.id,
+        "c.txt",
+        "text/plain",
+        1,
+        "key_a",
+    )) {
+        Err(RepositoryError::Conflict(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+    match repo
+        .attachments()
+        .create(&NewAttachment::new(999, "c.txt", "text/plain", 1, "key_c"))
+    {
+        Err(RepositoryError::InvalidReference(_)) => {}
+        _ => panic!("Unexpected response"),
+    }
+
+    match repo.attachments().create_within_quota(
+        &NewAttachment::new(note.id, "c.txt", "text/plain", 11, "key_c"),
+        1,
+        40,
+    ) {
+        Err(RepositoryError::Conflict(message)) => {
+            assert_eq!(message, "Quota of 40 bytes exceeded, 30 bytes are used")
+        }
+        _ => panic!("Unexpected response"),
+    }
+    let attachment_c = repo
+        .attachments()
+        .create_within_quota(
+            &NewAttachment::new(note.id, "c.txt", "text/plain", 10, "key_c"),
+            1,
+            40,
+        )
+        .unwrap();
+    assert_eq!(repo.attachments().total_size_by_user_id(1).unwrap(), 40);
+    assert_eq!(
+        repo.attachments().delete(attachment_c.id, note.id).unwrap(),
+        1
+    );
+
+    assert_eq!(repo.attachments().delete(attachment_a.id, 999).unwrap(), 0);
+    assert_eq!(
+        repo.attachments().delete(attachment_a.id, note.id).unwrap(),
+        1
+    );
+    assert_eq!(repo.attachments().total_size_by_user_id(1).unwrap(), 20);
+    assert!(repo.attachments().find_orphans(10).unwrap().is_empty());
+
+    // The attachments of the deleted notes are orphaned until their content is
+    // removed from the storage.
+    repo.notes().delete(note.id).unwrap();
+    assert_eq!(repo.attachments().total_size_by_user_id(1).unwrap(), 0);
+    let orphans = repo.attachments().find_orphans(10).unwrap();
+    assert_eq!(
+        orphans.iter().map(|orphan| orphan.id).collect::<Vec<i32>>(),
+        vec![attachment_b.id]
+    );
+    assert_eq!(orphans[0].note_id, None);
+    assert_eq!(
+        repo.attachments().delete_orphan(attachment_b.id).unwrap(),
+        1
+    );
+    assert!(repo.attachments().find_orphans(10).unwrap().is_empty());
+}
+
+#[test]
+fn test_user_role_and_disable() {
+    let repo = test_repository();
+
+    let admin = repo
+        .users()
+        .create(&NewUser::new("admin@email.com", "admin", "some_password").with_role(Role::Admin))
+        .unwrap();
+    assert_eq!(admin.role(), Role::Admin);
+    assert_eq!(
+        repo.users()
+            .find_all()
+            .unwrap()
+            .iter()
+            .map(|user| user.id)
+            .collect::<Vec<i32>>(),
+        vec![1, admin.id]
+    );
+
+    repo.sessions()
+        .create(&NewSession::new(
+            1,
+            "jti_a",
+            "hash_a",
+            now() + Duration::days(1),
+        ))
+        .unwrap();
+    repo.sessions()
+        .create(&NewSession::new(
+            1,
+            "jti_b",
+            "hash_b",
+            now() + Duration::days(1),
+        ))
+        .unwrap();
+
+    let mut user = repo.users().find_by_id(1).unwrap();
+    user.disabled = true;
+    assert!(repo.users().update(&user).unwrap().disabled);
+    assert_eq!(repo.sessions().revoke_by_user_id(1).unwrap(), 2);
+    assert!(!repo
+        .sessions()
+        .find_by_jti("jti_b")
+        .unwrap()
+        .is_active(now()));
+}
+
+#[test]
+fn test_note_link() {
+    let repo = test_repository();
+
+    let note_a = repo
+        .notes()
+        .create(
+            &NewNote::new(1, None, "note_a".to_string(), "text_a".to_string()),
+            &[],
+        )
+        .unwrap();
+    let mut note_b = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                None,
+                "note_b".to_string(),
+                format!("See [[note_a]], [[#{}]] and [[note_c]]", note_a.id),
+            ),
+            &[],
+        )
+        .unwrap();
+
+    let links = repo.links().find_by_note_id(note_b.id).unwrap();
+    assert_eq!(links.len(), 3);
+    assert_eq!(links[0].target_title, Some("note_a".to_string()));
+    assert_eq!(links[1].target_id, Some(note_a.id));
+    assert_eq!(links[2].target_title, Some("note_c".to_string()));
+    assert_eq!(
+        repo.links()
+            .find_by_note_ids(&[note_a.id, note_b.id])
+            .unwrap(),
+        links
+    );
+    assert_eq!(
+        repo.links().find_by_target(1, note_a.id, "note_a").unwrap(),
+        links[0..2].to_vec()
+    );
+    assert!(repo
+        .links()
+        .find_by_target(2, note_a.id, "note_a")
+        .unwrap()
+        .is_empty());
+
+    // The titles are matched whatever their case and spaces.
+    assert_eq!(
+        repo.links().find_by_target(1, 0, "  Note_A ").unwrap(),
+        links[0..1].to_vec()
+    );
+
+    // The links are replaced on update, and the ones of encrypted notes
+    // cannot be read.
+    note_b.data = "See [[note_c]]".to_string();
+    note_b = repo.notes().update(&note_b, &[]).unwrap();
+    assert_eq!(
+        repo.links()
+            .find_by_note_id(note_b.id)
+            .unwrap()
+            .iter()
+            .map(|link| link.target_title.clone())
+            .collect::<Vec<_>>(),
+        vec![Some("note_c".to_string())]
+    );
+    note_b.key_check = Some("some_key_check".to_string());
+    note_b = repo.notes().update(&note_b, &[]).unwrap();
+    assert!(repo.links().find_by_note_id(note_b.id).unwrap().is_empty());
+
+    // The links of the notes in the trash are not found.
+    note_b.data = "See [[note_a]]".to_string();
+    note_b.key_check = None;
+    note_b = repo.notes().update(&note_b, &[]).unwrap();
+    repo.notes().trash(note_b.id).unwrap();
+    assert!(repo
+        .links()
+        .find_by_target(1, note_a.id, "note_a")
+        .unwrap()
+        .is_empty());
+
+    repo.notes().delete(note_b.id).unwrap();
+    assert!(repo.links().find_by_note_id(note_b.id).unwrap().is_empty());
+}
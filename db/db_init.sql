@@ -1,3 +1,5 @@
+-- Bootstrap of the docker PostgreSQL. The schema is owned by the migrations in
+-- core/migrations, which rnotes applies at startup; new changes go only there.
 CREATE SCHEMA rnotes;
 SET
   search_path TO rnotes;
@@ -36,4 +38,4 @@ CREATE TABLE notes (
     FOREIGN KEY (category_id) REFERENCES categories (id),
     FOREIGN KEY (user_id) REFERENCES users (id)
   );
-ALTER SEQUENCE notes_id_seq OWNED BY categories.id;
\ No newline at end of file
+ALTER SEQUENCE notes_id_seq OWNED BY notes.id;
\ No newline at end of file
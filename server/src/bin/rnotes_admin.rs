@@ -0,0 +1,139 @@
+extern crate rnotes_core;
+extern crate rnotes_server;
+
+use rnotes_core::encryption;
+use rnotes_core::migrations;
+use rnotes_core::models::db::session::Session;
+use rnotes_core::models::db::user::{Role, User};
+use rnotes_core::{BDPool, BDPoolError, DBConnection};
+use std::env;
+use std::process;
+
+const USAGE: &str = "Usage: rnotes_admin <COMMAND>
+
+Commands:
+    migrate    Apply the pending migrations.
+    revert     Revert the last applied migration.
+    status     List the migrations and whether they are applied.
+    set-role   Set the role of a user, admin or user, and revoke its sessions:
+               set-role <EMAIL> <ROLE>
+    rotate-keys
+               Encrypt again the notes and revisions of other master keys or in
+               plain text with ENCRYPTION_KEY_ID and index again the titles of
+               the wiki links: rotate-keys [BATCH_SIZE]";
+
+const DEFAULT_BATCH_SIZE: i64 = 100;
+
+// Re-encrypts the rows with the current master key, see
+// `encryption::rotate_keys`.
+fn rotate_keys(connection: &DBConnection, batch_size: i64) -> Result<String, BDPoolError> {
+    let keyring = match encryption::keyring() {
+        Ok(Some(keyring)) => keyring,
+        Ok(None) => {
+            return Err(BDPoolError::InternalError(
+                "ENCRYPTION_KEYS is not set".to_string(),
+            ))
+        }
+        Err(err) => return Err(BDPoolError::InternalError(err.to_string())),
+    };
+
+    let (notes, revisions) = encryption::rotate_keys(connection, keyring, batch_size)
+        .map_err(|err| BDPoolError::InternalError(err.to_string()))?;
+
+    Ok(format!(
+        "Re-encrypted {} notes and {} revisions with key {}",
+        notes,
+        revisions,
+        keyring.current_id()
+    ))
+}
+
+fn main() {
+    rnotes_server::init_log();
+
+    // The commands decide by themselves which migrations have to run.
+    env::set_var("DATABASE_AUTO_MIGRATE", "false");
+
+    let command = env::args().nth(1).unwrap_or_default();
+    let pool = BDPool::new().expect("Cannot obtain BDPool");
+
+    let result = match &*command {
+        "migrate" => pool
+            .migrate()
+            .map(|applied| format!("Applied migrations: {:?}", applied)),
+        "revert" => pool
+            .revert()
+            .map(|reverted| format!("Reverted migration: {:?}", reverted)),
+        "status" => pool.get().and_then(|connection| {
+            migrations::applied_versions(&connection)
+                .map(|applied| {
+                    migrations::MIGRATIONS
+                        .iter()
+                        .map(|m| {
+                            let status = if applied.iter().any(|v| v == m.version) {
+                                "applied"
+                            } else {
+                                "pending"
+                            };
+                            format!("{}_{}: {}", m.version, m.name, status)
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                })
+                .map_err(|err| rnotes_core::BDPoolError::MigrationError(err.to_string()))
+        }),
+        "set-role" => match (
+            env::args().nth(2),
+            env::args().nth(3).and_then(|role| Role::parse(&role)),
+        ) {
+            // The role is a claim of the access tokens, so the sessions are
+            // revoked to take it into account, like `PUT /admin/users/<id>`.
+            (Some(email), Some(role)) => pool.get().and_then(|connection| {
+                User::find_by_email(&connection, email)
+                    .and_then(|mut user| {
+                        user.role = role.to_string();
+                        User::update(&connection, &user)
+                    })
+                    .and_then(|user| {
+                        Session::revoke_by_user_id(&connection, user.id).map(|num| (user, num))
+                    })
+                    .map(|(user, num)| {
+                        format!(
+                            "User {} is now {}, {} sessions revoked",
+                            user.email, user.role, num
+                        )
+                    })
+                    .map_err(|err| rnotes_core::BDPoolError::InternalError(err.to_string()))
+            }),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        },
+        "rotate-keys" => match env::args()
+            .nth(2)
+            .map_or(Ok(DEFAULT_BATCH_SIZE), |batch_size| {
+                batch_size.parse::<i64>()
+            }) {
+            Ok(batch_size) if batch_size > 0 => pool
+                .get()
+                .and_then(|connection| rotate_keys(&connection, batch_size)),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    match result {
+        Ok(message) => println!("{}", message),
+        Err(err) => {
+            eprintln!("Error executing '{}': {:?}", command, err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::handlers::jwt::AdminKey;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use rnotes_core::models::api::category::{CategoryIn, CategoryOut};
+use rnotes_core::models::api::user::{UserAdminIn, UserOut};
+use rnotes_core::models::db::category::{Category, NewCategory};
+use rnotes_core::models::db::user::Role;
+use rnotes_core::repository::{Repo, RepositoryError};
+use rocket::http::Status;
+use rocket_contrib::json::Json;
+
+fn category_error<'r>(err: RepositoryError) -> StatusError<'r> {
+    let status = match err {
+        RepositoryError::Conflict(_) => Status::Conflict,
+        _ => Status::BadRequest,
+    };
+
+    status_error(status, format!("Category is not correct: {}", err))
+}
+
+// The category if it is shared, the ones of the users cannot be managed.
+fn find_shared<'r>(repo: &Repo, id: i32) -> Result<Category, StatusError<'r>> {
+    match repo.categories().find_by_id(id) {
+        Ok(category) if category.user_id.is_none() => Ok(category),
+        _ => Err(status_error(
+            Status::NotFound,
+            format!("Shared category {} does not exist", id),
+        )),
+    }
+}
+
+// Shared categories can only be children of shared categories.
+fn check_shared_parent<'r>(repo: &Repo, parent_id: Option<i32>) -> Result<(), StatusError<'r>> {
+    match parent_id {
+        Some(parent_id) => find_shared(repo, parent_id).map(|_| ()).map_err(|_| {
+            status_error(
+                Status::BadRequest,
+                format!(
+                    "Category is not correct: category {} is not shared",
+                    parent_id
+                ),
+            )
+        }),
+        None => Ok(()),
+    }
+}
+
+#[get("/users")]
+pub fn users<'r>(_key: AdminKey, repo: Repo) -> Result<ApiResponse<Vec<UserOut>>, StatusError<'r>> {
+    repo.users()
+        .find_all()
+        .map(|all| ApiResponse::ok(all.iter().map(UserOut::from).collect()))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot find users: {}", err),
+            )
+        })
+}
+
+/// Changes the role of a user or disables it. Its sessions are revoked, so
+/// it has to login again to use the new role.
+#[put("/users/<id>", format = "application/json", data = "<user_admin_in>")]
+pub fn update_user<'r>(
+    key: AdminKey,
+    repo: Repo,
+    id: i32,
+    user_admin_in: Json<UserAdminIn>,
+) -> Result<ApiResponse<UserOut>, StatusError<'r>> {
+    let mut user = repo
+        .users()
+        .find_by_id(id)
+        .map_err(|err| status_error(Status::NotFound, format!("User is not correct: {}", err)))?;
+
+    user_admin_in
+        .apply(&mut user)
+        .map_err(|err| status_error(Status::BadRequest, err))?;
+    if user.id == key.0.id_user && (user.disabled || user.role() != Role::Admin) {
+        return Err(status_error(
+            Status::BadRequest,
+            "Admins cannot disable or demote themselves".to_string(),
+        ));
+    }
+
+    let user = repo.users().update(&user).map_err(|err| {
+        status_error(
+            Status::InternalServerError,
+            format!("Cannot update user: {}", err),
+        )
+    })?;
+
+    repo.sessions()
+        .revoke_by_user_id(user.id)
+        .map(|_| ApiResponse::ok(UserOut::from(&user)))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot revoke the sessions: {}", err),
+            )
+        })
+}
+
+/// Deletes a user without notes, the ones with notes can be disabled instead.
+#[delete("/users/<id>")]
+pub fn delete_user<'r>(
+    key: AdminKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    if id == key.0.id_user {
+        return Err(status_error(
+            Status::BadRequest,
+            "Admins cannot delete themselves".to_string(),
+        ));
+    }
+
+    repo.users()
+        .delete(id)
+        .map(|num| {
+            ApiResponse::empty_new(if num > 0 {
+                Status::Ok
+            } else {
+                Status::NoContent
+            })
+        })
+        .map_err(|err| match err {
+            RepositoryError::InvalidReference(_) => {
+                status_error(Status::Conflict, format!("Cannot delete user: {}", err))
+            }
+            _ => status_error(
+                Status::InternalServerError,
+                format!("Cannot delete user: {}", err),
+            ),
+        })
+}
+
+/// Shared categories, visible to every user.
+#[get("/categories")]
+pub fn categories<'r>(
+    _key: AdminKey,
+    repo: Repo,
+) -> Result<ApiResponse<Vec<CategoryOut>>, StatusError<'r>> {
+    repo.categories()
+        .find_all()
+        .map(|all| {
+            ApiResponse::ok(
+                all.iter()
+                    .filter(|category| category.user_id.is_none())
+                    .map(CategoryOut::from)
+                    .collect(),
+            )
+        })
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot find categories: {}", err)))
+}
+
+#[post("/categories", format = "application/json", data = "<category_in>")]
+pub fn create_category<'r>(
+    _key: AdminKey,
+    repo: Repo,
+    category_in: Json<CategoryIn>,
+) -> Result<ApiResponse<CategoryOut>, StatusError<'r>> {
+    if category_in.name.trim().is_empty() {
+        return Err(status_error(
+            Status::BadRequest,
+            "Category name is empty".to_string(),
+        ));
+    }
+    check_shared_parent(&repo, category_in.parent_id)?;
+
+    repo.categories()
+        .create(&NewCategory::new(None, &category_in.name).with_parent(category_in.parent_id))
+        .map(|category| ApiResponse::new(CategoryOut::from(&category), Status::Created))
+        .map_err(category_error)
+}
+
+#[put(
+    "/categories/<id>",
+    format = "application/json",
+    data = "<category_in>"
+)]
+pub fn update_category<'r>(
+    _key: AdminKey,
+    repo: Repo,
+    id: i32,
+    category_in: Json<CategoryIn>,
+) -> Result<ApiResponse<CategoryOut>, StatusError<'r>> {
+    let mut category = find_shared(&repo, id)?;
+    if category_in.name.trim().is_empty() {
+        return Err(status_error(
+            Status::BadRequest,
+            "Category name is empty".to_string(),
+        ));
+    }
+    check_shared_parent(&repo, category_in.parent_id)?;
+
+    category.parent_id = category_in.parent_id;
+    category.name = category_in.0.name;
+    repo.categories()
+        .update(&category)
+        .map(|category| ApiResponse::ok(CategoryOut::from(&category)))
+        .map_err(category_error)
+}
+
+/// Deletes a shared category, the notes of every user using it are left
+/// without category.
+#[delete("/categories/<id>")]
+pub fn delete_category<'r>(
+    _key: AdminKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    match find_shared(&repo, id) {
+        Ok(_) => repo
+            .categories()
+            .delete(id)
+            .map(|num| {
+                ApiResponse::empty_new(if num > 0 {
+                    Status::Ok
+                } else {
+                    Status::NoContent
+                })
+            })
+            .map_err(|err| {
+                status_error(
+                    Status::InternalServerError,
+                    format!("Cannot delete category: {}", err),
+                )
+            }),
+        Err(_) => Ok(ApiResponse::empty_new(Status::NoContent)),
+    }
+}
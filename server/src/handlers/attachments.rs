@@ -0,0 +1,458 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::notes::find_note;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use crate::storage::{StorageError, Store};
+
+use rnotes_core::models::api::attachment::{AttachmentOut, AttachmentQuotaOut};
+use rnotes_core::models::db::attachment::{Attachment, NewAttachment};
+use rnotes_core::models::db::note::Note;
+use rnotes_core::models::db::note_share::Permission;
+use rnotes_core::repository::{Repo, RepositoryError, RepositoryResult};
+use rnotes_core::token;
+
+use log::*;
+use multipart::server::Multipart;
+
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::Data;
+
+use std::io::{Cursor, Read};
+
+/// Bytes of the multipart body besides the file, like its headers.
+const MULTIPART_OVERHEAD: u64 = 64 * 1024;
+const MAX_FILE_NAME_LENGTH: usize = 256;
+const SWEEP_BATCH_SIZE: i64 = 100;
+
+/// File sent in the `file` field of a multipart upload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upload {
+    pub file_name: String,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// `Range` header of a download.
+pub struct ByteRange(pub Option<String>);
+
+impl<'a, 'r> FromRequest<'a, 'r> for ByteRange {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ByteRange(
+            request
+                .headers()
+                .get_one("Range")
+                .map(|range| range.to_string()),
+        ))
+    }
+}
+
+impl ByteRange {
+    /// First and last byte of the range in a content of the given size, or
+    /// `Err` if it cannot be satisfied. Like RFC 7233 allows, a header that is
+    /// not a single byte range is ignored.
+    pub fn resolve(&self, size: u64) -> Result<Option<(u64, u64)>, String> {
+        let unsatisfiable = || format!("Range is not satisfiable for {} bytes", size);
+
+        let spec = match self
+            .0
+            .as_ref()
+            .and_then(|range| range.trim().strip_prefix("bytes="))
+        {
+            Some(spec) if !spec.contains(',') => spec.trim(),
+            _ => return Ok(None),
+        };
+        let (start, end) = match spec.find('-') {
+            Some(index) => (&spec[..index], &spec[index + 1..]),
+            None => return Ok(None),
+        };
+
+        let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            (Ok(start), Err(_)) if end.is_empty() => (start, size.saturating_sub(1)),
+            (Err(_), Ok(suffix)) if start.is_empty() && suffix > 0 => {
+                (size.saturating_sub(suffix), size.saturating_sub(1))
+            }
+            (Err(_), Ok(_)) if start.is_empty() => return Err(unsatisfiable()),
+            _ => return Ok(None),
+        };
+
+        if size == 0 || range.0 >= size {
+            Err(unsatisfiable())
+        } else {
+            Ok(Some(range))
+        }
+    }
+}
+
+/// Content of an attachment, or part of it if a range was requested.
+#[derive(Debug)]
+pub struct AttachmentFile {
+    pub attachment: Attachment,
+    pub range: Option<(u64, u64)>,
+    pub data: Vec<u8>,
+}
+
+impl<'r> Responder<'r> for AttachmentFile {
+    fn respond_to(self, _: &Request) -> response::Result<'r> {
+        let content_type = ContentType::parse_flexible(&self.attachment.content_type)
+            .unwrap_or(ContentType::Binary);
+        // Only printable ASCII is kept in the quoted file name.
+        let file_name: String = self
+            .attachment
+            .file_name
+            .chars()
+            .map(|c| match c {
+                ' '..='~' if c != '"' && c != '\\' => c,
+                _ => '_',
+            })
+            .collect();
+
+        let mut response = Response::build();
+        response
+            .header(content_type)
+            .raw_header(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", file_name),
+            )
+            .raw_header("Accept-Ranges", "bytes")
+            .raw_header("X-Content-Type-Options", "nosniff");
+        if let Some((start, end)) = self.range {
+            response.status(Status::PartialContent).raw_header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", start, end, self.attachment.size),
+            );
+        }
+
+        response.sized_body(Cursor::new(self.data)).ok()
+    }
+}
+
+fn internal_error<'r>(message: &str, err: impl std::fmt::Display) -> StatusError<'r> {
+    status_error(
+        Status::InternalServerError,
+        format!("Cannot {}: {}", message, err),
+    )
+}
+
+// The note if the user owns it or it is shared with write permission.
+fn find_writable_note<'r>(repo: &Repo, id: i32, user_id: i32) -> Result<Note, StatusError<'r>> {
+    match find_note(repo, id, user_id)? {
+        (_, Some(Permission::Read)) => Err(status_error(
+            Status::Forbidden,
+            "Note is shared read-only".to_string(),
+        )),
+        (note, _) => Ok(note),
+    }
+}
+
+// Name of the file without its directories, as some clients send its path.
+fn base_name(file_name: &str) -> Option<String> {
+    file_name
+        .rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty() && name.len() <= MAX_FILE_NAME_LENGTH)
+        .map(|name| name.to_string())
+}
+
+// Content type sent with the file, or else the one of its extension.
+fn content_type_of(upload: &Upload, file_name: &str) -> String {
+    upload
+        .content_type
+        .as_ref()
+        .and_then(|content_type| ContentType::parse_flexible(content_type))
+        .filter(|content_type| *content_type != ContentType::Binary)
+        .or_else(|| {
+            file_name
+                .rsplit('.')
+                .next()
+                .and_then(ContentType::from_extension)
+        })
+        .unwrap_or(ContentType::Binary)
+        .to_string()
+}
+
+/// Reads the `file` field of a `multipart/form-data` body, failing if it is
+/// bigger than `max_size` bytes.
+pub fn read_upload<'r, R: Read>(
+    body: R,
+    boundary: &str,
+    max_size: i64,
+) -> Result<Upload, StatusError<'r>> {
+    let mut multipart = Multipart::with_body(body, boundary);
+
+    loop {
+        let mut field = match multipart.read_entry() {
+            Ok(Some(field)) => field,
+            Ok(None) => {
+                return Err(status_error(
+                    Status::BadRequest,
+                    "Field 'file' is required".to_string(),
+                ))
+            }
+            Err(err) => {
+                return Err(status_error(
+                    Status::BadRequest,
+                    format!("Multipart body is not correct: {}", err),
+                ))
+            }
+        };
+        if &*field.headers.name != "file" {
+            continue;
+        }
+
+        let mut data = vec![];
+        (&mut field.data)
+            .take(max_size.max(0) as u64 + 1)
+            .read_to_end(&mut data)
+            .map_err(|err| {
+                status_error(
+                    Status::BadRequest,
+                    format!("Multipart body is not correct: {}", err),
+                )
+            })?;
+        if data.len() as i64 > max_size {
+            return Err(status_error(
+                Status::PayloadTooLarge,
+                format!("File is bigger than {} bytes", max_size),
+            ));
+        }
+
+        return Ok(Upload {
+            file_name: field.headers.filename.clone().unwrap_or_default(),
+            content_type: field
+                .headers
+                .content_type
+                .as_ref()
+                .map(|content_type| content_type.to_string()),
+            data: data,
+        });
+    }
+}
+
+/// Removes from the storage the content of the attachments of the deleted
+/// notes, and then the attachments.
+pub fn sweep(repo: &Repo, store: &Store) -> RepositoryResult<usize> {
+    let mut num = 0;
+    loop {
+        let orphans = repo.attachments().find_orphans(SWEEP_BATCH_SIZE)?;
+        let mut swept = 0;
+        for orphan in orphans.iter() {
+            match store.delete(&orphan.storage_key) {
+                Ok(_) => swept += repo.attachments().delete_orphan(orphan.id)?,
+                Err(err) => warn!(
+                    "Cannot delete attachment[key={}]: {}",
+                    orphan.storage_key, err
+                ),
+            }
+        }
+
+        num += swept;
+        if swept == 0 || (orphans.len() as i64) < SWEEP_BATCH_SIZE {
+            return Ok(num);
+        }
+    }
+}
+
+#[get("/<id>/attachments")]
+pub fn all<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<Vec<AttachmentOut>>, StatusError<'r>> {
+    let (note, _) = find_note(&repo, id, key.id_user)?;
+
+    repo.attachments()
+        .find_by_note_id(note.id)
+        .map(|attachments| ApiResponse::ok(attachments.iter().map(AttachmentOut::from).collect()))
+        .map_err(|err| internal_error("read attachments", err))
+}
+
+/// Attaches the file of a `multipart/form-data` body sent in its `file` field.
+#[post("/<id>/attachments", format = "multipart/form-data", data = "<data>")]
+pub fn upload<'r>(
+    key: JWTKey,
+    repo: Repo,
+    store: Store,
+    id: i32,
+    content_type: &ContentType,
+    data: Data,
+) -> Result<ApiResponse<AttachmentOut>, StatusError<'r>> {
+    let boundary = content_type
+        .params()
+        .find(|&(name, _)| name == "boundary")
+        .map(|(_, boundary)| boundary.to_string())
+        .ok_or_else(|| {
+            status_error(
+                Status::BadRequest,
+                "Multipart boundary is missing".to_string(),
+            )
+        })?;
+
+    let max_size = store.quota.max_file_size;
+    let upload = read_upload(
+        data.open()
+            .take(max_size.max(0) as u64 + MULTIPART_OVERHEAD),
+        &boundary,
+        max_size,
+    )?;
+
+    create(key, repo, store, id, upload)
+}
+
+/// Stores the uploaded file and attaches it to the note, as long as it fits in
+/// the quota of the owner of the note.
+pub fn create<'r>(
+    key: JWTKey,
+    repo: Repo,
+    store: Store,
+    id: i32,
+    upload: Upload,
+) -> Result<ApiResponse<AttachmentOut>, StatusError<'r>> {
+    let note = find_writable_note(&repo, id, key.id_user)?;
+
+    let file_name = base_name(&upload.file_name)
+        .ok_or_else(|| status_error(Status::BadRequest, "File name is not valid".to_string()))?;
+    let size = upload.data.len() as i64;
+    if size > store.quota.max_file_size {
+        return Err(status_error(
+            Status::PayloadTooLarge,
+            format!("File is bigger than {} bytes", store.quota.max_file_size),
+        ));
+    }
+
+    // Checked again along with the insert, against the concurrent uploads.
+    let used = repo
+        .attachments()
+        .total_size_by_user_id(note.user_id)
+        .map_err(|err| internal_error("read the quota", err))?;
+    if used + size > store.quota.max_user_size {
+        return Err(status_error(
+            Status::InsufficientStorage,
+            format!(
+                "Quota of {} bytes exceeded, {} bytes are used",
+                store.quota.max_user_size, used
+            ),
+        ));
+    }
+
+    let content_type = content_type_of(&upload, &file_name);
+    let storage_key = format!("attachments/{}", token::generate());
+    store
+        .put(&storage_key, &upload.data)
+        .map_err(|err| internal_error("store the file", err))?;
+
+    repo.attachments()
+        .create_within_quota(
+            &NewAttachment::new(note.id, &file_name, &content_type, size, &storage_key),
+            note.user_id,
+            store.quota.max_user_size,
+        )
+        .map(|attachment| ApiResponse::new(AttachmentOut::from(&attachment), Status::Created))
+        .map_err(|err| {
+            store.delete(&storage_key).ok();
+            match err {
+                RepositoryError::Conflict(message) => {
+                    status_error(Status::InsufficientStorage, message)
+                }
+                err => internal_error("create attachment", err),
+            }
+        })
+}
+
+/// Downloads the attachment, or the single byte range of the `Range` header.
+#[get("/<id>/attachments/<attachment_id>")]
+pub fn get<'r>(
+    key: JWTKey,
+    repo: Repo,
+    store: Store,
+    id: i32,
+    attachment_id: i32,
+    range: ByteRange,
+) -> Result<AttachmentFile, StatusError<'r>> {
+    let (note, _) = find_note(&repo, id, key.id_user)?;
+    let attachment = repo
+        .attachments()
+        .find_by_id_and_note_id(attachment_id, note.id)
+        .map_err(|err| {
+            status_error(
+                Status::NotFound,
+                format!("Attachment is not correct: {}", err),
+            )
+        })?;
+
+    let size = attachment.size.max(0) as u64;
+    let range = range.resolve(size).map_err(|err| {
+        let mut response = status_error(Status::RangeNotSatisfiable, err);
+        response.set_raw_header("Content-Range", format!("bytes */{}", size));
+        response
+    })?;
+
+    match store.get(&attachment.storage_key, range) {
+        Ok(data) => Ok(AttachmentFile {
+            attachment: attachment,
+            range: range,
+            data: data,
+        }),
+        Err(StorageError::NotFound) => Err(status_error(
+            Status::NotFound,
+            "Attachment content is missing".to_string(),
+        )),
+        Err(err) => Err(internal_error("read the file", err)),
+    }
+}
+
+#[delete("/<id>/attachments/<attachment_id>")]
+pub fn delete<'r>(
+    key: JWTKey,
+    repo: Repo,
+    store: Store,
+    id: i32,
+    attachment_id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    let note = find_writable_note(&repo, id, key.id_user)?;
+
+    let attachment = match repo
+        .attachments()
+        .find_by_id_and_note_id(attachment_id, note.id)
+    {
+        Ok(attachment) => attachment,
+        Err(_) => return Ok(ApiResponse::empty_new(Status::NoContent)),
+    };
+
+    store
+        .delete(&attachment.storage_key)
+        .map_err(|err| internal_error("delete the file", err))?;
+
+    repo.attachments()
+        .delete(attachment.id, note.id)
+        .map(|num| {
+            ApiResponse::empty_new(if num > 0 {
+                Status::Ok
+            } else {
+                Status::NoContent
+            })
+        })
+        .map_err(|err| internal_error("delete attachment", err))
+}
+
+/// Bytes used by the attachments of the notes of the user and its quota.
+#[get("/quota")]
+pub fn quota<'r>(
+    key: JWTKey,
+    repo: Repo,
+    store: Store,
+) -> Result<ApiResponse<AttachmentQuotaOut>, StatusError<'r>> {
+    repo.attachments()
+        .total_size_by_user_id(key.id_user)
+        .map(|used| {
+            ApiResponse::ok(AttachmentQuotaOut {
+                used: used,
+                quota: store.quota.max_user_size,
+            })
+        })
+        .map_err(|err| internal_error("read the quota", err))
+}
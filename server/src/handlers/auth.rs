@@ -1,46 +1,584 @@
+use crate::handlers::jwt::{get_refresh_time, Claims, JWTKey};
 use crate::handlers::{status_error, ApiResponse, StatusError};
+use chrono::Duration;
 use crypto::sha2::Sha256;
-use jwt::{Header, Registered, Token};
-use rnotes_core::models::api::auth::{LoginIn, LoginOut};
-use rnotes_core::models::db::user::User as DBUser;
-use rnotes_core::DBConn;
+use dotenv::dotenv;
+use jwt::{Header, Token};
+use log::*;
+use rnotes_core::models::api::auth::{
+    LoginIn, LoginOut, RefreshIn, RegisterIn, TotpCodeIn, TotpSetupOut, TwoFactorLoginIn,
+};
+use rnotes_core::models::api::user::UserOut;
+use rnotes_core::models::api::Empty;
+use rnotes_core::models::db::session::{NewSession, Session};
+use rnotes_core::models::db::two_factor::{NewLoginChallenge, NewTotpSecret, TotpSecret};
+use rnotes_core::models::db::user::{NewUser, Role, User};
+use rnotes_core::repository::{Repo, RepositoryError};
+use rnotes_core::utils::now;
+use rnotes_core::{password, token, totp};
 use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
 use rocket_contrib::json::Json;
+use std::env;
 
+/// Minutes to send the code of a login with 2FA.
+const CHALLENGE_MINUTES: i64 = 5;
+/// Wrong codes accepted before the login with 2FA has to start again.
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+/// Wrong codes of a user, across its challenges, before its login with 2FA
+/// is locked.
+const MAX_FAILED_CODES: i32 = 10;
+/// Minutes the login with 2FA stays locked after too many wrong codes.
+const LOCKOUT_MINUTES: i64 = 15;
+const TOTP_ISSUER: &str = "rnotes";
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u64
+}
+
+/// Who can register new users, set with `REGISTRATION_MODE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegistrationMode {
+    /// Anyone can register, the default.
+    Open,
+    /// Only admins or requests with the `ADMIN_TOKEN` in the `X-Admin-Token`
+    /// header.
+    Admin,
+}
+
+pub fn get_registration_mode() -> RegistrationMode {
+    dotenv().ok();
+
+    match env::var("REGISTRATION_MODE") {
+        Ok(mode) => match &*mode {
+            "open" => RegistrationMode::Open,
+            "admin" => RegistrationMode::Admin,
+            _ => panic!("REGISTRATION_MODE must be open or admin"),
+        },
+        Err(_) => RegistrationMode::Open,
+    }
+}
+
+/// Whether the request can register a user under the registration mode.
+pub struct RegistrationAccess {
+    pub allowed: bool,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for RegistrationAccess {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> Outcome<Self, Self::Error> {
+        let allowed = match get_registration_mode() {
+            RegistrationMode::Open => true,
+            RegistrationMode::Admin => {
+                let admin = request
+                    .guard::<JWTKey>()
+                    .succeeded()
+                    .map_or(false, |key| key.role == Role::Admin);
+
+                admin
+                    || match (
+                        request.headers().get_one("X-Admin-Token"),
+                        env::var("ADMIN_TOKEN"),
+                    ) {
+                        (Some(token), Ok(admin_token)) => {
+                            !admin_token.is_empty()
+                                && password::constant_time_eq(
+                                    token.as_bytes(),
+                                    admin_token.as_bytes(),
+                                )
+                        }
+                        _ => false,
+                    }
+            }
+        };
+
+        Outcome::Success(RegistrationAccess { allowed: allowed })
+    }
+}
+
+/// Checks the credentials. Users with 2FA get a challenge token with the
+/// status 202 instead of a session, see `two_factor_login`.
 #[post("/login", format = "application/json", data = "<request>")]
 pub fn login<'r>(
     request: Json<LoginIn>,
-    connection: DBConn,
+    repo: Repo,
+) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
+    let mut user = match repo.users().find_by_email(&request.email) {
+        Ok(user) => user,
+        Err(_) => {
+            // Spend the same time as a verification to not reveal the emails.
+            let _ = password::hash(&request.password);
+            return Err(status_error(
+                Status::NotFound,
+                format!("Invalid credentials"),
+            ));
+        }
+    };
+
+    if !password::verify(&request.password, &user.password) {
+        return Err(status_error(
+            Status::NotFound,
+            format!("Invalid credentials"),
+        ));
+    }
+
+    if user.disabled {
+        return Err(status_error(Status::Forbidden, format!("User is disabled")));
+    }
+
+    if password::needs_rehash(&user.password) {
+        match password::hash(&request.password) {
+            Ok(hashed_password) => {
+                user.password = hashed_password;
+                if let Err(err) = repo.users().update(&user) {
+                    warn!("Error re-hashing the password[user={}]: {}", user.id, err);
+                }
+            }
+            Err(err) => warn!("Error re-hashing the password[user={}]: {}", user.id, err),
+        }
+    }
+
+    match repo.two_factor().find_totp(user.id) {
+        Ok(ref totp_secret) if totp_secret.enabled => {
+            if totp_secret.is_locked(now()) {
+                return Err(locked());
+            }
+            return create_challenge(&repo, user.id);
+        }
+        Ok(_) | Err(RepositoryError::NotFound) => {}
+        Err(err) => {
+            return Err(status_error(
+                Status::InternalServerError,
+                format!("Cannot find the 2FA of the user: {}", err),
+            ))
+        }
+    }
+
+    create_session(&repo, &user)
+}
+
+fn create_challenge<'r>(
+    repo: &Repo,
+    user_id: i32,
+) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
+    let challenge_token = token::generate();
+
+    repo.two_factor()
+        .create_challenge(&NewLoginChallenge::new(
+            user_id,
+            &token::hash(&challenge_token),
+            now() + Duration::minutes(CHALLENGE_MINUTES),
+        ))
+        .map(|_| ApiResponse::new(LoginOut::challenge(challenge_token), Status::Accepted))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot create the login challenge: {}", err),
+            )
+        })
+}
+
+pub(crate) fn create_session<'r>(
+    repo: &Repo,
+    user: &User,
+) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
+    let refresh_token = token::generate();
+    let jti = token::generate();
+
+    repo.sessions()
+        .create(&NewSession::new(
+            user.id,
+            &jti,
+            &token::hash(&refresh_token),
+            now() + get_refresh_time(),
+        ))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot create the session: {}", err),
+            )
+        })
+        .and_then(|session| login_out(&session, user, refresh_token))
+}
+
+// Signs an access token of the session with the role of its user.
+fn login_out<'r>(
+    session: &Session,
+    user: &User,
+    refresh_token: String,
 ) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
     let header: Header = Default::default();
 
-    let email = request.email.clone();
-    let password = request.password.clone();
-    DBUser::find_by_email_and_password(&connection, email, password)
-        .map_err(|_| status_error(Status::NotFound, format!("Invalid credentials")))
-        .and_then(|user| {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as u64;
-
-            let claims = Registered {
-                sub: Some(user.id.to_string()),
-                iat: Some(now),
-                exp: Some(now + super::jwt::get_session_time()),
-                ..Default::default()
+    let now = unix_time();
+
+    let claims = Claims {
+        sub: Some(session.user_id.to_string()),
+        iat: Some(now),
+        exp: Some(now + super::jwt::get_session_time()),
+        jti: Some(session.jti.clone()),
+        role: Some(user.role().to_string()),
+    };
+    let token = Token::new(header, claims);
+
+    token
+        .signed(&super::jwt::get_secret_key(), Sha256::new())
+        .map(|jwt_token| {
+            ApiResponse::ok(LoginOut {
+                jwt_token: jwt_token,
+                refresh_token: refresh_token,
+                challenge_token: None,
+            })
+        })
+        .map_err(|_| {
+            status_error(
+                Status::InternalServerError,
+                format!("Unknown server error."),
+            )
+        })
+}
+
+/// Renews the tokens of a session. The refresh token is rotated, so the one
+/// of the request cannot be used again.
+#[post("/refresh", format = "application/json", data = "<request>")]
+pub fn refresh<'r>(
+    request: Json<RefreshIn>,
+    repo: Repo,
+) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
+    let invalid = || status_error(Status::Unauthorized, format!("Invalid refresh token"));
+
+    let session = repo
+        .sessions()
+        .find_by_refresh_token_hash(&token::hash(&request.refresh_token))
+        .map_err(|_| invalid())?;
+    if !session.is_active(now()) {
+        return Err(invalid());
+    }
+    let user = match repo.users().find_by_id(session.user_id) {
+        Ok(user) if !user.disabled => user,
+        _ => return Err(invalid()),
+    };
+
+    let refresh_token = token::generate();
+    let session = repo
+        .sessions()
+        .rotate(
+            &session,
+            &token::generate(),
+            &token::hash(&refresh_token),
+            now() + get_refresh_time(),
+        )
+        .map_err(|_| invalid())?;
+
+    login_out(&session, &user, refresh_token)
+}
+
+/// Revokes the session of the access token, along with its refresh token.
+#[post("/logout")]
+pub fn logout<'r>(key: JWTKey, repo: Repo) -> Result<ApiResponse<Empty>, StatusError<'r>> {
+    let session = key
+        .jti
+        .as_ref()
+        .ok_or_else(|| status_error(Status::BadRequest, format!("Token has no session")))
+        .and_then(|jti| {
+            repo.sessions().find_by_jti(jti).map_err(|err| {
+                status_error(Status::NotFound, format!("Session is not correct: {}", err))
+            })
+        })?;
+
+    repo.sessions()
+        .revoke(session.id)
+        .map(|_| ApiResponse::empty_ok())
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot revoke the session: {}", err),
+            )
+        })
+}
+
+#[post("/register", format = "application/json", data = "<request>")]
+pub fn register<'r>(
+    request: Json<RegisterIn>,
+    repo: Repo,
+    access: RegistrationAccess,
+) -> Result<ApiResponse<UserOut>, StatusError<'r>> {
+    if !access.allowed {
+        return Err(status_error(
+            Status::Forbidden,
+            format!("Only an admin can register users"),
+        ));
+    }
+
+    request
+        .validate()
+        .map_err(|err| status_error(Status::BadRequest, format!("User is not correct: {}", err)))?;
+
+    let hashed_password = password::hash(&request.password).map_err(|err| {
+        status_error(
+            Status::InternalServerError,
+            format!("Cannot hash the password: {}", err),
+        )
+    })?;
+
+    repo.users()
+        .create(&NewUser::new(
+            &request.email,
+            &request.name,
+            &hashed_password,
+        ))
+        .map(|user| ApiResponse::new(UserOut::from(&user), Status::Created))
+        .map_err(|err| {
+            let status = match err {
+                RepositoryError::Conflict(_) => Status::Conflict,
+                _ => Status::InternalServerError,
             };
-            let token = Token::new(header, claims);
-
-            token
-                .signed(&super::jwt::get_secret_key(), Sha256::new())
-                .map(|jwt_token| {
-                    ApiResponse::ok(LoginOut {
-                        jwt_token: jwt_token,
-                    })
-                })
-                .map_err(|_| {
-                    status_error(Status::InternalServerError, format!("Unknown server error."))
-                })
+
+            status_error(status, format!("User is not correct: {}", err))
+        })
+}
+
+// Whether the code is a TOTP code or a recovery code of the user, both can
+// only be used once.
+fn check_code<'r>(
+    repo: &Repo,
+    totp_secret: &TotpSecret,
+    code: &str,
+) -> Result<bool, StatusError<'r>> {
+    let internal_error = |err: RepositoryError| {
+        status_error(
+            Status::InternalServerError,
+            format!("Cannot check the code: {}", err),
+        )
+    };
+
+    match totp::verify(&totp_secret.secret, code, unix_time()) {
+        Some(step) => repo
+            .two_factor()
+            .use_totp_step(totp_secret.user_id, step as i64)
+            .map(|num| num > 0)
+            .map_err(internal_error),
+        None => repo
+            .two_factor()
+            .use_recovery_code(
+                totp_secret.user_id,
+                &token::hash(&totp::normalize_recovery_code(code)),
+            )
+            .map(|num| num > 0)
+            .map_err(internal_error),
+    }
+}
+
+// The TOTP secret of a user logged in with a session, API keys cannot manage
+// the 2FA.
+fn find_totp<'r>(key: &JWTKey, repo: &Repo) -> Result<Option<TotpSecret>, StatusError<'r>> {
+    if key.jti.is_none() {
+        return Err(status_error(
+            Status::Forbidden,
+            "API keys cannot manage the 2FA".to_string(),
+        ));
+    }
+
+    match repo.two_factor().find_totp(key.id_user) {
+        Ok(totp_secret) => Ok(Some(totp_secret)),
+        Err(RepositoryError::NotFound) => Ok(None),
+        Err(err) => Err(status_error(
+            Status::InternalServerError,
+            format!("Cannot find the 2FA of the user: {}", err),
+        )),
+    }
+}
+
+/// Creates a TOTP secret and its recovery codes. The 2FA is not required
+/// until a first code is verified, so it can be set up again meanwhile.
+#[post("/2fa/setup")]
+pub fn two_factor_setup<'r>(
+    key: JWTKey,
+    repo: Repo,
+) -> Result<ApiResponse<TotpSetupOut>, StatusError<'r>> {
+    if let Some(totp_secret) = find_totp(&key, &repo)? {
+        if totp_secret.enabled {
+            return Err(status_error(
+                Status::Conflict,
+                "2FA is already enabled".to_string(),
+            ));
+        }
+    }
+
+    let user = repo
+        .users()
+        .find_by_id(key.id_user)
+        .map_err(|err| status_error(Status::NotFound, format!("User is not correct: {}", err)))?;
+
+    let secret = totp::generate_secret();
+    let recovery_codes = totp::generate_recovery_codes();
+    let code_hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| token::hash(code))
+        .collect();
+
+    repo.two_factor()
+        .create_totp(&NewTotpSecret::new(user.id, &secret), &code_hashes)
+        .map(|_| {
+            ApiResponse::new(
+                TotpSetupOut {
+                    uri: totp::uri(&secret, TOTP_ISSUER, &user.email),
+                    secret: secret.clone(),
+                    recovery_codes: recovery_codes,
+                },
+                Status::Created,
+            )
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot create the 2FA: {}", err),
+            )
         })
 }
+
+/// Enables the 2FA set up with a first code of the authenticator app.
+#[post("/2fa/verify", format = "application/json", data = "<request>")]
+pub fn two_factor_verify<'r>(
+    key: JWTKey,
+    repo: Repo,
+    request: Json<TotpCodeIn>,
+) -> Result<ApiResponse<Empty>, StatusError<'r>> {
+    let totp_secret = match find_totp(&key, &repo)? {
+        Some(totp_secret) if !totp_secret.enabled => totp_secret,
+        Some(_) => {
+            return Err(status_error(
+                Status::Conflict,
+                "2FA is already enabled".to_string(),
+            ))
+        }
+        None => {
+            return Err(status_error(
+                Status::NotFound,
+                "2FA is not set up".to_string(),
+            ))
+        }
+    };
+
+    let step = totp::verify(&totp_secret.secret, &request.code, unix_time())
+        .ok_or_else(|| status_error(Status::BadRequest, "Invalid code".to_string()))?;
+
+    repo.two_factor()
+        .enable_totp(key.id_user, step as i64)
+        .map(|_| ApiResponse::empty_ok())
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot enable the 2FA: {}", err),
+            )
+        })
+}
+
+/// Disables the 2FA, it requires a code like the login.
+#[post("/2fa/disable", format = "application/json", data = "<request>")]
+pub fn two_factor_disable<'r>(
+    key: JWTKey,
+    repo: Repo,
+    request: Json<TotpCodeIn>,
+) -> Result<ApiResponse<Empty>, StatusError<'r>> {
+    let totp_secret = match find_totp(&key, &repo)? {
+        Some(totp_secret) if totp_secret.enabled => totp_secret,
+        _ => {
+            return Err(status_error(
+                Status::NotFound,
+                "2FA is not enabled".to_string(),
+            ))
+        }
+    };
+
+    if !check_code(&repo, &totp_secret, &request.code)? {
+        return Err(status_error(Status::BadRequest, "Invalid code".to_string()));
+    }
+
+    repo.two_factor()
+        .delete_totp(key.id_user)
+        .map(|_| ApiResponse::empty_ok())
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot disable the 2FA: {}", err),
+            )
+        })
+}
+
+fn locked<'r>() -> StatusError<'r> {
+    status_error(
+        Status::TooManyRequests,
+        format!(
+            "Too many wrong codes, try again in {} minutes",
+            LOCKOUT_MINUTES
+        ),
+    )
+}
+
+/// Second step of the login of a user with 2FA. The challenge is deleted
+/// once used, or after too many wrong codes.
+#[post("/2fa/login", format = "application/json", data = "<request>")]
+pub fn two_factor_login<'r>(
+    request: Json<TwoFactorLoginIn>,
+    repo: Repo,
+) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
+    let invalid = || status_error(Status::Unauthorized, format!("Invalid challenge token"));
+
+    let challenge = repo
+        .two_factor()
+        .find_challenge(&token::hash(&request.challenge_token))
+        .map_err(|_| invalid())?;
+    if challenge.expire_time <= now() || challenge.attempts >= MAX_CHALLENGE_ATTEMPTS {
+        let _ = repo.two_factor().delete_challenge(challenge.id);
+        return Err(invalid());
+    }
+
+    let user = match repo.users().find_by_id(challenge.user_id) {
+        Ok(user) if !user.disabled => user,
+        _ => return Err(invalid()),
+    };
+    let totp_secret = match repo.two_factor().find_totp(user.id) {
+        Ok(totp_secret) if totp_secret.enabled => totp_secret,
+        _ => return Err(invalid()),
+    };
+    if totp_secret.is_locked(now()) {
+        let _ = repo.two_factor().delete_challenge(challenge.id);
+        return Err(locked());
+    }
+
+    if !check_code(&repo, &totp_secret, &request.code)? {
+        if let Err(err) = repo.two_factor().add_challenge_attempt(challenge.id) {
+            warn!("Error counting a 2FA attempt[user={}]: {}", user.id, err);
+        }
+        match repo.two_factor().add_failed_code(
+            user.id,
+            MAX_FAILED_CODES,
+            now() + Duration::minutes(LOCKOUT_MINUTES),
+        ) {
+            Ok(ref totp_secret) if totp_secret.is_locked(now()) => {
+                warn!("Login with 2FA locked[user={}]", user.id);
+                let _ = repo.two_factor().delete_challenge(challenge.id);
+                return Err(locked());
+            }
+            Ok(_) => {}
+            Err(err) => warn!("Error counting a wrong 2FA code[user={}]: {}", user.id, err),
+        }
+        return Err(status_error(Status::Unauthorized, format!("Invalid code")));
+    }
+    if let Err(err) = repo.two_factor().reset_failed_codes(user.id) {
+        warn!(
+            "Error resetting the wrong 2FA codes[user={}]: {}",
+            user.id, err
+        );
+    }
+
+    // Only one request can use the challenge.
+    match repo.two_factor().delete_challenge(challenge.id) {
+        Ok(num) if num > 0 => create_session(&repo, &user),
+        _ => Err(invalid()),
+    }
+}
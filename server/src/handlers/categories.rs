@@ -1,16 +1,46 @@
 use crate::handlers::jwt::JWTKey;
 use crate::handlers::{status_error, ApiResponse, StatusError};
-use rnotes_core::models::api::category::CategoryOut;
+use rnotes_core::models::api::category::{CategoryIn, CategoryOut, CategoryTreeOut};
 use rnotes_core::models::db::category::Category;
-use rnotes_core::DBConn;
+use rnotes_core::repository::{Repo, RepositoryError};
 use rocket::http::Status;
+use rocket_contrib::json::Json;
+
+fn save_error<'r>(err: RepositoryError) -> StatusError<'r> {
+    let status = match err {
+        RepositoryError::Conflict(_) => Status::Conflict,
+        _ => Status::BadRequest,
+    };
+
+    status_error(status, format!("Category is not correct: {}", err))
+}
+
+// The category if the user owns it, shared categories cannot be modified.
+fn find_owned<'r>(key: &JWTKey, repo: &Repo, id: i32) -> Result<Category, StatusError<'r>> {
+    let category = repo
+        .categories()
+        .find_by_id_and_user_id(id, key.id_user)
+        .map_err(|err| {
+            status_error(
+                Status::NotFound,
+                format!("Category is not correct: {}", err),
+            )
+        })?;
+
+    if category.user_id == Some(key.id_user) {
+        Ok(category)
+    } else {
+        Err(status_error(
+            Status::Forbidden,
+            "Category is shared and cannot be modified".to_string(),
+        ))
+    }
+}
 
 #[get("/")]
-pub fn all<'r>(
-    _key: JWTKey,
-    connection: DBConn,
-) -> Result<ApiResponse<Vec<CategoryOut>>, StatusError<'r>> {
-    Category::find_all(&connection)
+pub fn all<'r>(key: JWTKey, repo: Repo) -> Result<ApiResponse<Vec<CategoryOut>>, StatusError<'r>> {
+    repo.categories()
+        .find_by_user_id(key.id_user)
         .map(|all| {
             ApiResponse::ok(
                 all.iter()
@@ -18,21 +48,28 @@ pub fn all<'r>(
                     .collect(),
             )
         })
-        .map_err(|err| {
-            status_error(
-                Status::NotFound,
-                format!("Cannot find categories: {}", err),
-            )
-        })
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot find categories: {}", err)))
+}
+
+#[get("/tree")]
+pub fn tree<'r>(
+    key: JWTKey,
+    repo: Repo,
+) -> Result<ApiResponse<Vec<CategoryTreeOut>>, StatusError<'r>> {
+    repo.categories()
+        .find_by_user_id(key.id_user)
+        .map(|all| ApiResponse::ok(CategoryTreeOut::build(&all)))
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot find categories: {}", err)))
 }
 
 #[get("/<id>")]
 pub fn get<'r>(
-    _key: JWTKey,
-    connection: DBConn,
+    key: JWTKey,
+    repo: Repo,
     id: i32,
 ) -> Result<ApiResponse<CategoryOut>, StatusError<'r>> {
-    Category::find_by_id(&connection, id)
+    repo.categories()
+        .find_by_id_and_user_id(id, key.id_user)
         .map(|category| ApiResponse::ok(CategoryOut::from(&category)))
         .map_err(|err| {
             status_error(
@@ -41,3 +78,74 @@ pub fn get<'r>(
             )
         })
 }
+
+#[post("/", format = "application/json", data = "<category_in>")]
+pub fn create<'r>(
+    key: JWTKey,
+    repo: Repo,
+    category_in: Json<CategoryIn>,
+) -> Result<ApiResponse<CategoryOut>, StatusError<'r>> {
+    if category_in.name.trim().is_empty() {
+        return Err(status_error(
+            Status::BadRequest,
+            "Category name is empty".to_string(),
+        ));
+    }
+
+    repo.categories()
+        .create(&category_in.to_new_category(key.id_user))
+        .map(|category| ApiResponse::new(CategoryOut::from(&category), Status::Created))
+        .map_err(save_error)
+}
+
+#[put("/<id>", format = "application/json", data = "<category_in>")]
+pub fn update<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    category_in: Json<CategoryIn>,
+) -> Result<ApiResponse<CategoryOut>, StatusError<'r>> {
+    let mut category = find_owned(&key, &repo, id)?;
+    if category_in.name.trim().is_empty() {
+        return Err(status_error(
+            Status::BadRequest,
+            "Category name is empty".to_string(),
+        ));
+    }
+
+    category.parent_id = category_in.parent_id;
+    category.name = category_in.0.name;
+    repo.categories()
+        .update(&category)
+        .map(|category| ApiResponse::new(CategoryOut::from(&category), Status::Created))
+        .map_err(save_error)
+}
+
+/// Deletes the category, its notes are left without category.
+#[delete("/<id>")]
+pub fn delete<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    match find_owned(&key, &repo, id) {
+        Ok(_) => repo
+            .categories()
+            .delete(id)
+            .map(|num| {
+                ApiResponse::empty_new(if num > 0 {
+                    Status::Ok
+                } else {
+                    Status::NoContent
+                })
+            })
+            .map_err(|err| {
+                status_error(
+                    Status::InternalServerError,
+                    format!("Cannot delete category: {}", err),
+                )
+            }),
+        Err(response) if response.status() == Status::Forbidden => Err(response),
+        Err(_) => Ok(ApiResponse::empty_new(Status::NoContent)),
+    }
+}
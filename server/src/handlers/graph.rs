@@ -0,0 +1,145 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use rnotes_core::graph::{self, GraphFormat};
+use rnotes_core::models::api::graph::GraphOut;
+use rnotes_core::models::db::category::Category;
+use rnotes_core::models::db::note::NoteFilter;
+use rnotes_core::models::db::note_link::LinkTargets;
+use rnotes_core::repository::{Repo, RepositoryResult};
+use rocket::http::{ContentType, Status};
+use rocket::request::Request;
+use rocket::response::{self, content, Responder};
+
+/// Graph of the notes as JSON or as a Graphviz DOT or GraphML file.
+#[derive(Debug)]
+pub enum GraphExport {
+    Json(GraphOut),
+    Dot(String),
+    GraphMl(String),
+}
+
+impl<'r> Responder<'r> for GraphExport {
+    fn respond_to(self, req: &Request) -> response::Result<'r> {
+        match self {
+            GraphExport::Json(graph) => ApiResponse::ok(graph).respond_to(req),
+            GraphExport::Dot(dot) => {
+                content::Content(ContentType::new("text", "vnd.graphviz"), dot).respond_to(req)
+            }
+            GraphExport::GraphMl(graphml) => {
+                content::Content(ContentType::new("application", "graphml+xml"), graphml)
+                    .respond_to(req)
+            }
+        }
+    }
+}
+
+// Graph of the notes of the user out of the trash and the categories they can
+// use, only the notes of the category and its descendants if it is given.
+fn build_graph(
+    repo: &Repo,
+    user_id: i32,
+    mut categories: Vec<Category>,
+    category_id: Option<i32>,
+    descendants: bool,
+) -> RepositoryResult<GraphOut> {
+    let all_notes = repo
+        .notes()
+        .find_by_user_id(user_id, &NoteFilter::default())?
+        .notes;
+
+    let notes = match category_id {
+        Some(category_id) => {
+            let category_ids = if descendants {
+                Category::descendant_ids(&categories, category_id)
+            } else {
+                vec![category_id]
+            };
+            categories.retain(|category| category_ids.contains(&category.id));
+
+            all_notes
+                .iter()
+                .filter(|note| {
+                    note.category_id
+                        .map_or(false, |id| category_ids.contains(&id))
+                })
+                .cloned()
+                .collect()
+        }
+        None => all_notes.clone(),
+    };
+
+    // The links point to the notes of the user even out of the category, like
+    // in `GET /notes/<id>/links`, but only the ones in the graph are kept.
+    let note_ids: Vec<i32> = notes.iter().map(|note| note.id).collect();
+    let targets = LinkTargets::new(&all_notes)?;
+    let links: Vec<(i32, i32)> = repo
+        .links()
+        .find_by_note_ids(&note_ids)?
+        .iter()
+        .filter_map(|reference| {
+            reference
+                .resolve(&targets)
+                .map(|target| (reference.note_id, target.id))
+        })
+        .filter(|(source_id, target_id)| source_id != target_id)
+        .collect();
+
+    Ok(graph::build(&notes, &categories, &links))
+}
+
+/// Graph of the notes, categories and wiki links of the user, `format` is
+/// `json`, `dot` or `graphml`. `category_id` only keeps the notes of that
+/// category, and of its descendants with `descendants=true`.
+#[get("/?<format>&<category_id>&<descendants>")]
+pub fn get<'r>(
+    key: JWTKey,
+    repo: Repo,
+    format: Option<String>,
+    category_id: Option<i32>,
+    descendants: Option<bool>,
+) -> Result<GraphExport, StatusError<'r>> {
+    let format = match format {
+        Some(format) => GraphFormat::parse(&format).ok_or_else(|| {
+            status_error(
+                Status::BadRequest,
+                format!("format '{}' is not valid", format),
+            )
+        })?,
+        None => GraphFormat::default(),
+    };
+
+    let categories = repo
+        .categories()
+        .find_by_user_id(key.id_user)
+        .map_err(|err| {
+            status_error(Status::NotFound, format!("Cannot find categories: {}", err))
+        })?;
+    if let Some(category_id) = category_id {
+        if !categories.iter().any(|category| category.id == category_id) {
+            return Err(status_error(
+                Status::NotFound,
+                format!("Category {} is not correct", category_id),
+            ));
+        }
+    }
+
+    let graph = build_graph(
+        &repo,
+        key.id_user,
+        categories,
+        category_id,
+        descendants.unwrap_or(false),
+    )
+    .map_err(|err| {
+        status_error(
+            Status::InternalServerError,
+            format!("Cannot build the graph: {}", err),
+        )
+    })?;
+
+    Ok(match format {
+        GraphFormat::Json => GraphExport::Json(graph),
+        GraphFormat::Dot => GraphExport::Dot(graph::to_dot(&graph)),
+        GraphFormat::GraphMl => GraphExport::GraphMl(graph::to_graphml(&graph)),
+    })
+}
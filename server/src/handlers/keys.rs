@@ -0,0 +1,123 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use chrono::Duration;
+use rnotes_core::models::api::api_key::{ApiKeyIn, ApiKeyOut};
+use rnotes_core::models::db::api_key::NewApiKey;
+use rnotes_core::repository::{Repo, RepositoryError};
+use rnotes_core::token;
+use rnotes_core::utils::now;
+use rocket::http::Status;
+use rocket_contrib::json::Json;
+
+/// Start of every API key, to tell them apart from the access tokens.
+const KEY_PREFIX: &str = "rn_";
+/// Characters of the key kept in clear to identify it.
+const PREFIX_LENGTH: usize = 10;
+/// Longest expiration of a key, about 10 years.
+const MAX_EXPIRES_IN_DAYS: i64 = 3650;
+
+#[get("/")]
+pub fn all<'r>(key: JWTKey, repo: Repo) -> Result<ApiResponse<Vec<ApiKeyOut>>, StatusError<'r>> {
+    repo.api_keys()
+        .find_by_user_id(key.id_user)
+        .map(|api_keys| ApiResponse::ok(api_keys.iter().map(ApiKeyOut::from).collect()))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot read API keys: {}", err),
+            )
+        })
+}
+
+/// Creates an API key, the key itself is only returned in this response. It
+/// needs a login, an API key cannot create other keys.
+#[post("/", format = "application/json", data = "<api_key_in>")]
+pub fn create<'r>(
+    key: JWTKey,
+    repo: Repo,
+    api_key_in: Json<ApiKeyIn>,
+) -> Result<ApiResponse<ApiKeyOut>, StatusError<'r>> {
+    if key.jti.is_none() {
+        return Err(status_error(
+            Status::Forbidden,
+            "API keys cannot create API keys".to_string(),
+        ));
+    }
+    if api_key_in.name.trim().is_empty() {
+        return Err(status_error(
+            Status::BadRequest,
+            "API key name is empty".to_string(),
+        ));
+    }
+    let expire_time = match api_key_in.expires_in_days {
+        Some(days) => Some(
+            Some(days)
+                .filter(|days| (1..=MAX_EXPIRES_IN_DAYS).contains(days))
+                .and_then(|days| now().checked_add_signed(Duration::days(days)))
+                .ok_or_else(|| {
+                    status_error(
+                        Status::BadRequest,
+                        format!(
+                            "API key expiration must be between 1 and {} days",
+                            MAX_EXPIRES_IN_DAYS
+                        ),
+                    )
+                })?,
+        ),
+        None => None,
+    };
+
+    let secret = format!("{}{}", KEY_PREFIX, token::generate());
+    let key_hash = token::hash(&secret);
+
+    repo.api_keys()
+        .create(
+            &NewApiKey::new(
+                key.id_user,
+                api_key_in.name.trim(),
+                &secret[..PREFIX_LENGTH],
+                &key_hash,
+            )
+            .with_read_only(api_key_in.read_only)
+            .with_expire_time(expire_time),
+        )
+        .map(|api_key| {
+            ApiResponse::new(
+                ApiKeyOut::from(&api_key).with_key(secret.clone()),
+                Status::Created,
+            )
+        })
+        .map_err(|err| match err {
+            RepositoryError::Conflict(_) => {
+                status_error(Status::Conflict, format!("Cannot create API key: {}", err))
+            }
+            _ => status_error(
+                Status::InternalServerError,
+                format!("Cannot create API key: {}", err),
+            ),
+        })
+}
+
+/// Revokes the API key, it cannot be used anymore.
+#[delete("/<id>")]
+pub fn delete<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    repo.api_keys()
+        .delete(id, key.id_user)
+        .map(|num| {
+            ApiResponse::empty_new(if num > 0 {
+                Status::Ok
+            } else {
+                Status::NoContent
+            })
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot delete API key: {}", err),
+            )
+        })
+}
@@ -0,0 +1,115 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::notes::find_note;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+
+use rnotes_core::models::api::note_link::{BacklinkOut, NoteLinkOut};
+use rnotes_core::models::db::note::{Note, NoteFilter};
+use rnotes_core::models::db::note_link::{LinkTargets, NoteLink};
+use rnotes_core::repository::{Repo, RepositoryResult};
+
+use rocket::http::Status;
+
+// Notes of the owner that the user can open, the only ones the links of a note
+// of the owner may point to for the user.
+fn linkable_notes(repo: &Repo, owner_id: i32, user_id: i32) -> RepositoryResult<Vec<Note>> {
+    let notes = repo
+        .notes()
+        .find_by_user_id(owner_id, &NoteFilter::default())?
+        .notes;
+    if owner_id == user_id {
+        return Ok(notes);
+    }
+
+    let shared: Vec<i32> = repo
+        .shares()
+        .find_notes_by_user_id(user_id)?
+        .iter()
+        .map(|(note, _)| note.id)
+        .collect();
+    Ok(notes
+        .into_iter()
+        .filter(|note| shared.contains(&note.id))
+        .collect())
+}
+
+/// Wiki links written in the note, the ones pointing to no note the user can
+/// open are flagged as broken.
+#[get("/<id>/links")]
+pub fn all<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<Vec<NoteLinkOut>>, StatusError<'r>> {
+    let (note, _) = find_note(&repo, id, key.id_user)?;
+
+    repo.links()
+        .find_by_note_id(note.id)
+        .and_then(|links| {
+            let notes = linkable_notes(&repo, note.user_id, key.id_user)?;
+            let targets = LinkTargets::new(&notes)?;
+            let titles = NoteLink::written_titles(&note)?;
+
+            Ok(links
+                .iter()
+                .map(|link| {
+                    NoteLinkOut::new(
+                        link,
+                        link.resolve(&targets),
+                        link.target_title
+                            .as_ref()
+                            .and_then(|key| titles.get(key))
+                            .map(|title| title.as_str()),
+                    )
+                })
+                .collect())
+        })
+        .map(|links| ApiResponse::ok(links))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot read links: {}", err),
+            )
+        })
+}
+
+/// Notes with a wiki link to the note, the oldest link first.
+#[get("/<id>/backlinks")]
+pub fn backlinks<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<Vec<BacklinkOut>>, StatusError<'r>> {
+    let (note, _) = find_note(&repo, id, key.id_user)?;
+
+    repo.links()
+        .find_by_target(note.user_id, note.id, &note.title)
+        .and_then(|links| {
+            let notes = linkable_notes(&repo, note.user_id, key.id_user)?;
+            let targets = LinkTargets::new(&notes)?;
+
+            let mut backlinks: Vec<BacklinkOut> = vec![];
+            for link in links.iter() {
+                // A title shared by several notes only points to one of them.
+                if link.note_id == note.id
+                    || link.resolve(&targets).map(|target| target.id) != Some(note.id)
+                    || backlinks
+                        .iter()
+                        .any(|backlink| backlink.note_id == link.note_id)
+                {
+                    continue;
+                }
+                if let Some(source) = notes.iter().find(|other| other.id == link.note_id) {
+                    backlinks.push(BacklinkOut::from(source));
+                }
+            }
+
+            Ok(backlinks)
+        })
+        .map(|backlinks| ApiResponse::ok(backlinks))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot read backlinks: {}", err),
+            )
+        })
+}
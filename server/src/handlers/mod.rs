@@ -12,10 +12,22 @@ use std::io::Cursor;
 
 use rnotes_core::models::api::Error;
 
+pub mod admin;
+pub mod attachments;
 pub mod auth;
 pub mod categories;
+pub mod graph;
 pub mod jwt;
+pub mod keys;
+pub mod links;
 pub mod notes;
+pub mod oidc;
+pub mod public;
+pub mod public_links;
+pub mod revisions;
+pub mod shares;
+pub mod tags;
+pub mod trash;
 
 pub fn catch_not_json() -> AdHoc {
     AdHoc::on_response("catch_errors", |_, res| {
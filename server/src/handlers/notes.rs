@@ -1,84 +1,441 @@
 use crate::handlers::jwt::JWTKey;
 use crate::handlers::{status_error, ApiResponse, StatusError};
 
-use rnotes_core::models::api::note::{NoteIn, NoteOut};
-use rnotes_core::models::db::note::{NewNote, Note};
-use rnotes_core::DBConn;
+use rnotes_core::models::api::note::{NoteIn, NoteOut, NoteRenderOut, NoteSearchOut};
+use rnotes_core::models::api::Page;
+use rnotes_core::models::db::note::{NewNote, Note, NoteCursor, NoteFilter, NoteFormat, NoteSort};
+use rnotes_core::models::db::note_share::Permission;
+use rnotes_core::models::db::tag::Tag;
+use rnotes_core::render;
+use rnotes_core::repository::{Repo, RepositoryResult};
+
+use chrono::DateTime;
 
 use rocket::http::Status;
+use rocket::request::{FromQuery, Query};
 use rocket_contrib::json::Json;
 
-#[get("/")]
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 200;
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+/// Query parameters of the notes listing, e.g.
+/// `/notes?tag=a&tag=b&sort=title&order=desc&limit=10&cursor=...`.
+/// `descendants=true` also lists the notes of the descendants of `category_id`.
+///
+/// Values are kept raw so that `to_filter` can reject the invalid ones with a
+/// meaningful error.
+#[derive(Debug, Default, PartialEq)]
+pub struct NotesQuery {
+    pub tags: Vec<String>,
+    pub category_id: Option<String>,
+    pub descendants: Option<String>,
+    pub updated_since: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub limit: Option<String>,
+    pub cursor: Option<String>,
+}
+
+impl NotesQuery {
+    pub fn to_filter(&self) -> Result<NoteFilter, String> {
+        let category_id = match &self.category_id {
+            Some(value) => Some(
+                value
+                    .parse::<i32>()
+                    .map_err(|_| format!("category_id '{}' is not valid", value))?,
+            ),
+            None => None,
+        };
+        let descendants = match self.descendants.as_ref().map(|value| value.as_str()) {
+            Some("false") | None => false,
+            Some("true") => true,
+            Some(value) => return Err(format!("descendants '{}' is not valid", value)),
+        };
+        let updated_since = match &self.updated_since {
+            Some(value) => Some(
+                DateTime::parse_from_rfc3339(value)
+                    .map_err(|_| format!("updated_since '{}' is not a RFC 3339 time", value))?
+                    .naive_utc(),
+            ),
+            None => None,
+        };
+        let sort = match &self.sort {
+            Some(value) => value.parse::<NoteSort>()?,
+            None => NoteSort::default(),
+        };
+        let descending = match self.order.as_ref().map(|value| value.as_str()) {
+            Some("asc") | None => false,
+            Some("desc") => true,
+            Some(value) => return Err(format!("order '{}' is not valid", value)),
+        };
+        let limit = match &self.limit {
+            Some(value) => value
+                .parse::<i64>()
+                .ok()
+                .filter(|limit| *limit > 0 && *limit <= MAX_PAGE_LIMIT)
+                .ok_or_else(|| {
+                    format!("limit '{}' must be between 1 and {}", value, MAX_PAGE_LIMIT)
+                })?,
+            None => DEFAULT_PAGE_LIMIT,
+        };
+        let cursor = match &self.cursor {
+            Some(value) => Some(
+                NoteCursor::decode(value)
+                    .filter(|cursor| cursor.sort == sort && cursor.descending == descending)
+                    .ok_or_else(|| format!("cursor '{}' is not valid", value))?,
+            ),
+            None => None,
+        };
+
+        Ok(NoteFilter {
+            // Blank tags are ignored.
+            tags: Tag::normalize_names(&self.tags),
+            category_id: category_id,
+            descendants: descendants,
+            updated_since: updated_since,
+            sort: sort,
+            descending: descending,
+            limit: Some(limit),
+            cursor: cursor,
+        })
+    }
+}
+
+impl<'q> FromQuery<'q> for NotesQuery {
+    type Error = ();
+
+    fn from_query(query: Query<'q>) -> Result<Self, Self::Error> {
+        let mut notes_query = NotesQuery::default();
+        for item in query {
+            let (key, value) = item.key_value_decoded();
+            match key.as_str() {
+                "tag" => notes_query.tags.push(value),
+                "category_id" => notes_query.category_id = Some(value),
+                "descendants" => notes_query.descendants = Some(value),
+                "updated_since" => notes_query.updated_since = Some(value),
+                "sort" => notes_query.sort = Some(value),
+                "order" => notes_query.order = Some(value),
+                "limit" => notes_query.limit = Some(value),
+                "cursor" => notes_query.cursor = Some(value),
+                _ => {}
+            }
+        }
+
+        Ok(notes_query)
+    }
+}
+
+pub(crate) fn with_tags(repo: &Repo, notes: &[Note]) -> RepositoryResult<Vec<NoteOut>> {
+    let ids: Vec<i32> = notes.iter().map(|note| note.id).collect();
+    let tags = repo.tags().find_by_note_ids(&ids)?;
+
+    Ok(notes
+        .iter()
+        .map(|note| {
+            NoteOut::from(note).with_tags(
+                tags.iter()
+                    .filter(|(note_id, _)| *note_id == note.id)
+                    .map(|(_, name)| name.clone())
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+// Notes can only use the categories of their user or the shared ones.
+fn check_category<'r>(
+    repo: &Repo,
+    user_id: i32,
+    category_id: Option<i32>,
+) -> Result<(), StatusError<'r>> {
+    match category_id {
+        Some(category_id) => repo
+            .categories()
+            .find_by_id_and_user_id(category_id, user_id)
+            .map(|_| ())
+            .map_err(|err| {
+                status_error(
+                    Status::BadRequest,
+                    format!("Category is not correct: {}", err),
+                )
+            }),
+        None => Ok(()),
+    }
+}
+
+fn check_format<'r>(format: Option<&String>) -> Result<(), StatusError<'r>> {
+    match format {
+        Some(format) if NoteFormat::parse(format).is_none() => Err(status_error(
+            Status::BadRequest,
+            format!("Format '{}' is not valid", format),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The note if the user owns it, or else if it is shared with the user along
+/// with the permission of the share.
+pub(crate) fn find_note<'r>(
+    repo: &Repo,
+    id: i32,
+    user_id: i32,
+) -> Result<(Note, Option<Permission>), StatusError<'r>> {
+    let err = match repo.notes().find_by_id_and_user_id(id, user_id) {
+        Ok(note) => return Ok((note, None)),
+        Err(err) => err,
+    };
+
+    repo.shares()
+        .find_note_by_note_id_and_user_id(id, user_id)
+        .map(|(note, note_share)| (note, Some(note_share.permission())))
+        .map_err(|_| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
+}
+
+/// Names of the tags of the note.
+pub(crate) fn note_tags<'r>(repo: &Repo, id: i32) -> Result<Vec<String>, StatusError<'r>> {
+    repo.tags()
+        .find_by_note_ids(&[id])
+        .map(|tags| tags.into_iter().map(|(_, name)| name).collect())
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot find tags: {}", err),
+            )
+        })
+}
+
+pub(crate) fn note_out<'r>(
+    repo: &Repo,
+    note: &Note,
+    status: Status,
+) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    with_tags(repo, &[note.clone()])
+        .map(|mut notes| ApiResponse::new(notes.remove(0), status))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot find tags: {}", err),
+            )
+        })
+}
+
+#[get("/?<query..>")]
 pub fn all<'r>(
     key: JWTKey,
-    connection: DBConn,
-) -> Result<ApiResponse<Vec<NoteOut>>, StatusError<'r>> {
-    Note::find_by_user_id(&connection, key.id_user)
-        .map(|all| ApiResponse::ok(all.iter().map(|note| NoteOut::from(note)).collect()))
+    repo: Repo,
+    query: NotesQuery,
+) -> Result<ApiResponse<Page<NoteOut>>, StatusError<'r>> {
+    let filter = query.to_filter().map_err(|err| {
+        status_error(Status::BadRequest, format!("Query is not correct: {}", err))
+    })?;
+
+    repo.notes()
+        .find_by_user_id(key.id_user, &filter)
+        .and_then(|page| {
+            with_tags(&repo, &page.notes).map(|items| Page {
+                items: items,
+                next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+            })
+        })
+        .map(|page| ApiResponse::ok(page))
         .map_err(|err| status_error(Status::NotFound, format!("Cannot find notes: {}", err)))
 }
 
+#[get("/search?<q>&<limit>")]
+pub fn search<'r>(
+    key: JWTKey,
+    repo: Repo,
+    q: Option<String>,
+    limit: Option<i64>,
+) -> Result<ApiResponse<Vec<NoteSearchOut>>, StatusError<'r>> {
+    let q = q.unwrap_or_default();
+    if q.trim().is_empty() {
+        return Err(status_error(
+            Status::BadRequest,
+            "Query 'q' is required".to_string(),
+        ));
+    }
+    let limit = limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .max(1)
+        .min(MAX_SEARCH_LIMIT);
+
+    repo.notes()
+        .search(key.id_user, &q, limit)
+        .and_then(|hits| {
+            let notes: Vec<Note> = hits.iter().map(|hit| hit.note.clone()).collect();
+            with_tags(&repo, &notes).map(|notes| {
+                hits.iter()
+                    .zip(notes.into_iter())
+                    .map(|(hit, note)| NoteSearchOut {
+                        note: note,
+                        ..NoteSearchOut::from(hit)
+                    })
+                    .collect()
+            })
+        })
+        .map(|hits| ApiResponse::ok(hits))
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot search notes: {}", err)))
+}
+
 #[get("/<id>")]
-pub fn get<'r>(
+pub fn get<'r>(key: JWTKey, repo: Repo, id: i32) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    find_note(&repo, id, key.id_user).and_then(|(note, _)| note_out(&repo, &note, Status::Ok))
+}
+
+/// Sanitized HTML of the note, rendered from its format. Encrypted notes cannot
+/// be rendered.
+#[get("/<id>/render")]
+pub fn render<'r>(
     key: JWTKey,
-    connection: DBConn,
+    repo: Repo,
     id: i32,
-) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
-    Note::find_by_id_and_user_id(&connection, id, key.id_user)
-        .map(|note| ApiResponse::ok(NoteOut::from(&note)))
-        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
+) -> Result<ApiResponse<NoteRenderOut>, StatusError<'r>> {
+    let (note, _) = find_note(&repo, id, key.id_user)?;
+    if note.is_encrypted() {
+        return Err(status_error(
+            Status::UnprocessableEntity,
+            "Note is encrypted and cannot be rendered".to_string(),
+        ));
+    }
+
+    Ok(ApiResponse::ok(NoteRenderOut {
+        id: note.id,
+        format: note.format().to_string(),
+        html: render::render(note.format(), &note.data),
+    }))
 }
 
 #[post("/", format = "application/json", data = "<note_in>")]
 pub fn create<'r>(
     key: JWTKey,
-    connection: DBConn,
+    repo: Repo,
     note_in: Json<NoteIn>,
 ) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    check_category(&repo, key.id_user, note_in.category_id)?;
+    check_format(note_in.format.as_ref())?;
+
+    let tags = note_in.tags.clone().unwrap_or_default();
     let mut new_note: NewNote = NoteIn::into(note_in.0);
     new_note.user_id = key.id_user;
-    new_note
-        .create(&connection)
-        .map(|note| ApiResponse::new(NoteOut::from(&note), Status::Created))
+    repo.notes()
+        .create(&new_note, &tags)
         .map_err(|err| status_error(Status::BadRequest, format!("Note is not correct: {}", err)))
+        .and_then(|note| note_out(&repo, &note, Status::Created))
 }
 
 #[put("/<id>", format = "application/json", data = "<note_in>")]
 pub fn update<'r>(
     key: JWTKey,
-    connection: DBConn,
+    repo: Repo,
     id: i32,
     note_in: Json<NoteIn>,
 ) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
-    Note::find_by_id_and_user_id(&connection, id, key.id_user)
-        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
-        .and_then(|_| {
-            let mut note: Note = NoteIn::into(note_in.0);
-            note.id = id;
-            note.user_id = key.id_user;
-            Note::update(&connection, &note)
-                .map(|note| ApiResponse::new(NoteOut::from(&note), Status::Created))
-                .map_err(|err| {
-                    status_error(Status::BadRequest, format!("Note is not correct: {}", err))
-                })
-        })
+    let (current, permission) = find_note(&repo, id, key.id_user)?;
+    if permission == Some(Permission::Read) {
+        return Err(status_error(
+            Status::Forbidden,
+            "Note is shared read-only".to_string(),
+        ));
+    }
+
+    check_format(note_in.format.as_ref())?;
+
+    // Plain text sent by an older client or a user it is shared with would be
+    // stored as the ciphertext of an encrypted note.
+    if current.is_encrypted() && note_in.key_check.is_none() {
+        return Err(status_error(
+            Status::BadRequest,
+            "Note is encrypted, the update needs its key_check".to_string(),
+        ));
+    }
+
+    // The note keeps its owner and the categories are the owner's, even if it
+    // is updated by a user it is shared with. It also keeps its format, tags
+    // and key-check value if they are not sent.
+    let format = note_in.format.clone().unwrap_or(current.format);
+    let key_check = note_in.key_check.clone().or(current.key_check);
+    let tags = match note_in.tags.clone() {
+        Some(tags) => tags,
+        None => note_tags(&repo, id)?,
+    };
+    check_category(&repo, current.user_id, note_in.category_id).and_then(|_| {
+        let mut note: Note = NoteIn::into(note_in.0);
+        note.id = id;
+        note.user_id = current.user_id;
+        note.format = format;
+        note.key_check = key_check;
+        repo.notes()
+            .update(&note, &tags)
+            .map_err(|err| {
+                status_error(Status::BadRequest, format!("Note is not correct: {}", err))
+            })
+            .and_then(|note| note_out(&repo, &note, Status::Created))
+    })
 }
 
+/// Moves the note to the trash, or only removes the share if the note is
+/// shared with the user.
 #[delete("/<id>")]
 pub fn delete<'r>(
     key: JWTKey,
-    connection: DBConn,
+    repo: Repo,
     id: i32,
 ) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
-    Note::find_by_id_and_user_id(&connection, id, key.id_user)
-        .and_then(|_| {
-            Note::delete(&connection, id).map(|num| {
-                ApiResponse::empty_new(if num > 0 {
-                    Status::Ok
-                } else {
-                    Status::NoContent
-                })
-            })
-        })
-        .or(Ok(ApiResponse::empty_new(Status::NoContent)))
+    let num = match find_note(&repo, id, key.id_user) {
+        Ok((_, None)) => repo.notes().trash(id).unwrap_or(0),
+        Ok((_, Some(_))) => repo.shares().delete(id, key.id_user).unwrap_or(0),
+        Err(_) => 0,
+    };
+
+    Ok(ApiResponse::empty_new(if num > 0 {
+        Status::Ok
+    } else {
+        Status::NoContent
+    }))
+}
+
+#[post("/<id>/tags/<tag>")]
+pub fn add_tag<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    tag: String,
+) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    let note = repo
+        .notes()
+        .find_by_id_and_user_id(id, key.id_user)
+        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))?;
+
+    let name = Tag::normalize_names(&[tag])
+        .pop()
+        .ok_or_else(|| status_error(Status::BadRequest, "Tag is empty".to_string()))?;
+
+    repo.tags()
+        .add(key.id_user, note.id, &name)
+        .map_err(|err| status_error(Status::BadRequest, format!("Tag is not correct: {}", err)))
+        .and_then(|_| note_out(&repo, &note, Status::Created))
+}
+
+#[delete("/<id>/tags/<tag>")]
+pub fn remove_tag<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    tag: String,
+) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    let note = repo
+        .notes()
+        .find_by_id_and_user_id(id, key.id_user)
+        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))?;
+
+    let names = Tag::normalize_names(&[tag]);
+    let name = names.first().map_or("", |name| name.as_str());
+
+    repo.tags()
+        .remove(key.id_user, note.id, name)
+        .map_err(|err| status_error(Status::BadRequest, format!("Tag is not correct: {}", err)))
+        .and_then(|_| note_out(&repo, &note, Status::Ok))
 }
@@ -0,0 +1,192 @@
+use crate::handlers::auth::create_session;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use crate::oidc::{
+    read_state, sign_state, state_cookie, IdTokenClaims, Oidc, OidcError, OidcProvider, StateCookie,
+};
+use log::*;
+use rnotes_core::models::api::auth::{LoginOut, OidcAuthorizeOut};
+use rnotes_core::models::db::user::{NewUser, User};
+use rnotes_core::models::db::user_identity::NewUserIdentity;
+use rnotes_core::repository::{Repo, RepositoryError};
+use rnotes_core::{password, token};
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use std::sync::Arc;
+
+/// Response of `authorize`, it also sets the state cookie checked by
+/// `callback` so that a state cannot be used from another browser.
+pub struct Authorize {
+    pub response: ApiResponse<OidcAuthorizeOut>,
+    pub state: String,
+}
+
+impl<'r> Responder<'r> for Authorize {
+    fn respond_to(self, req: &Request) -> response::Result<'r> {
+        let mut response = self.response.respond_to(req)?;
+        response.adjoin_header(state_cookie(&self.state));
+
+        Ok(response)
+    }
+}
+
+fn provider<'r>(oidc: Oidc) -> Result<Arc<OidcProvider>, StatusError<'r>> {
+    oidc.0.ok_or_else(|| {
+        status_error(
+            Status::NotFound,
+            "OpenID Connect is not configured".to_string(),
+        )
+    })
+}
+
+fn oidc_error<'r>(err: OidcError) -> StatusError<'r> {
+    let status = match err {
+        OidcError::Provider(_) => Status::BadGateway,
+        OidcError::Invalid(_) => Status::Unauthorized,
+    };
+
+    status_error(status, format!("Cannot login with OpenID Connect: {}", err))
+}
+
+fn internal_error<'r>(err: RepositoryError) -> StatusError<'r> {
+    status_error(
+        Status::InternalServerError,
+        format!("Cannot find the user: {}", err),
+    )
+}
+
+// Creates the user of an unknown identity, or links it to the user with its
+// email if the provider verified it.
+fn provision<'r>(
+    repo: &Repo,
+    issuer: &str,
+    claims: &IdTokenClaims,
+) -> Result<User, StatusError<'r>> {
+    let email = claims
+        .email
+        .as_ref()
+        .filter(|email| !email.is_empty())
+        .ok_or_else(|| status_error(Status::BadRequest, "ID token has no email".to_string()))?;
+
+    let user = match repo.users().find_by_email(email) {
+        Ok(user) if claims.email_verified == Some(true) => user,
+        Ok(_) => {
+            return Err(status_error(
+                Status::Conflict,
+                format!("Email {} is not verified by the provider", email),
+            ))
+        }
+        Err(RepositoryError::NotFound) => {
+            let name = claims
+                .name
+                .as_ref()
+                .or(claims.preferred_username.as_ref())
+                .unwrap_or(email);
+
+            // Names are unique too, the email is used if it is taken.
+            let created =
+                match repo
+                    .users()
+                    .create(&NewUser::new(email, name, password::NO_PASSWORD))
+                {
+                    Err(RepositoryError::Conflict(_)) if name != email => repo
+                        .users()
+                        .create(&NewUser::new(email, email, password::NO_PASSWORD)),
+                    result => result,
+                };
+
+            created.map_err(|err| {
+                status_error(Status::Conflict, format!("Cannot create the user: {}", err))
+            })?
+        }
+        Err(err) => return Err(internal_error(err)),
+    };
+
+    info!(
+        "Linking OpenID Connect identity[user={}, sub={}]",
+        user.id, claims.sub
+    );
+    repo.identities()
+        .create(&NewUserIdentity::new(user.id, issuer, &claims.sub))
+        .map(|_| user)
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot link the identity: {}", err),
+            )
+        })
+}
+
+/// URL of the provider to login, it redirects the user to `callback`.
+#[get("/oidc/authorize")]
+pub fn authorize<'r>(oidc: Oidc) -> Result<Authorize, StatusError<'r>> {
+    let provider = provider(oidc)?;
+
+    let nonce = token::generate();
+    let state = sign_state(&nonce).map_err(oidc_error)?;
+
+    provider
+        .authorization_url(&state, &nonce)
+        .map(|authorization_url| Authorize {
+            response: ApiResponse::ok(OidcAuthorizeOut {
+                authorization_url: authorization_url,
+            }),
+            state: state,
+        })
+        .map_err(oidc_error)
+}
+
+/// Exchanges the code of the provider for a session, like `auth::login`.
+/// Unknown identities are provisioned as new users. The state has to be the
+/// one of the cookie set by `authorize`, against login CSRF.
+#[get("/oidc/callback?<code>&<state>&<error>")]
+pub fn callback<'r>(
+    oidc: Oidc,
+    repo: Repo,
+    cookie: StateCookie,
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+) -> Result<ApiResponse<LoginOut>, StatusError<'r>> {
+    let provider = provider(oidc)?;
+
+    if cookie.0.as_ref() != Some(&state) {
+        return Err(oidc_error(OidcError::Invalid(
+            "state does not match the cookie".to_string(),
+        )));
+    }
+    let nonce = read_state(&state).map_err(oidc_error)?;
+    let code = match (code, error) {
+        (_, Some(error)) => {
+            return Err(status_error(
+                Status::Unauthorized,
+                format!("Provider denied the login: {}", error),
+            ))
+        }
+        (Some(code), None) => code,
+        (None, None) => return Err(status_error(Status::BadRequest, "No code".to_string())),
+    };
+
+    let id_token = provider.exchange_code(&code).map_err(oidc_error)?;
+    let claims = provider
+        .validate_id_token(&id_token, &nonce)
+        .map_err(oidc_error)?;
+
+    let user = match repo
+        .identities()
+        .find_by_subject(provider.issuer(), &claims.sub)
+    {
+        Ok(identity) => repo
+            .users()
+            .find_by_id(identity.user_id)
+            .map_err(internal_error)?,
+        Err(RepositoryError::NotFound) => provision(&repo, provider.issuer(), &claims)?,
+        Err(err) => return Err(internal_error(err)),
+    };
+
+    if user.disabled {
+        return Err(status_error(Status::Forbidden, format!("User is disabled")));
+    }
+
+    create_session(&repo, &user)
+}
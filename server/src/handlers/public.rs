@@ -0,0 +1,122 @@
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use rnotes_core::models::api::public_link::PublicNoteOut;
+use rnotes_core::models::db::note::Note;
+use rnotes_core::password::verify;
+use rnotes_core::render::{escape_html, render};
+use rnotes_core::repository::Repo;
+use rnotes_core::token;
+use rnotes_core::utils::now;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, content, Responder};
+
+/// Password of a public link sent in the `X-Link-Password` header.
+pub struct LinkPassword(pub Option<String>);
+
+impl<'a, 'r> FromRequest<'a, 'r> for LinkPassword {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(LinkPassword(
+            request
+                .headers()
+                .get_one("X-Link-Password")
+                .map(|link_password| link_password.to_string()),
+        ))
+    }
+}
+
+/// Note of a public link as JSON or as an HTML page.
+#[derive(Debug)]
+pub enum PublicNote {
+    Json(PublicNoteOut),
+    Html(String),
+}
+
+impl<'r> Responder<'r> for PublicNote {
+    fn respond_to(self, req: &Request) -> response::Result<'r> {
+        match self {
+            PublicNote::Json(note) => ApiResponse::ok(note).respond_to(req),
+            PublicNote::Html(html) => content::Html(html).respond_to(req),
+        }
+    }
+}
+
+fn render_html(note: &Note) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+</head>
+<body>
+<h1>{title}</h1>
+{data}
+</body>
+</html>
+"#,
+        title = escape_html(&note.title),
+        data = render(note.format(), &note.data)
+    )
+}
+
+/// Opens a public link without login, `format=html` returns an HTML page. The
+/// password of the link is only sent in the `X-Link-Password` header, so it
+/// does not end up in the URLs kept by browsers and proxies.
+#[get("/<link_token>?<format>")]
+pub fn get<'r>(
+    repo: Repo,
+    link_token: String,
+    format: Option<String>,
+    link_password: LinkPassword,
+) -> Result<PublicNote, StatusError<'r>> {
+    let html = match format.as_ref().map(|format| format.as_str()) {
+        Some("json") | None => false,
+        Some("html") => true,
+        Some(format) => {
+            return Err(status_error(
+                Status::BadRequest,
+                format!("format '{}' is not valid", format),
+            ))
+        }
+    };
+
+    let (note, public_link) = repo
+        .public_links()
+        .find_by_token_hash(&token::hash(&link_token))
+        .ok()
+        .filter(|(_, public_link)| public_link.is_active(now()))
+        .ok_or_else(|| status_error(Status::NotFound, "Link is not correct".to_string()))?;
+
+    if let Some(password_hash) = public_link.password_hash.as_ref() {
+        match link_password.0 {
+            Some(link_password) if verify(&link_password, password_hash) => {}
+            Some(_) => {
+                return Err(status_error(
+                    Status::Unauthorized,
+                    "Password is not correct".to_string(),
+                ))
+            }
+            None => {
+                return Err(status_error(
+                    Status::Unauthorized,
+                    "Link needs a password".to_string(),
+                ))
+            }
+        }
+    }
+
+    if html && note.is_encrypted() {
+        return Err(status_error(
+            Status::UnprocessableEntity,
+            "Note is encrypted and cannot be rendered".to_string(),
+        ));
+    }
+
+    Ok(if html {
+        PublicNote::Html(render_html(&note))
+    } else {
+        PublicNote::Json(PublicNoteOut::from(&note))
+    })
+}
@@ -0,0 +1,138 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use chrono::Duration;
+use rnotes_core::models::api::public_link::{PublicLinkIn, PublicLinkOut};
+use rnotes_core::models::db::note::Note;
+use rnotes_core::models::db::public_link::NewPublicLink;
+use rnotes_core::repository::Repo;
+use rnotes_core::utils::now;
+use rnotes_core::{password, token};
+use rocket::http::Status;
+use rocket_contrib::json::Json;
+
+/// Longest expiration of a link, about 10 years.
+const MAX_EXPIRES_IN_HOURS: i64 = 87_600;
+
+// Only the owner of the note manages its links.
+fn find_own_note<'r>(repo: &Repo, id: i32, user_id: i32) -> Result<Note, StatusError<'r>> {
+    repo.notes()
+        .find_by_id_and_user_id(id, user_id)
+        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
+}
+
+#[get("/<id>/public-links")]
+pub fn all<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<Vec<PublicLinkOut>>, StatusError<'r>> {
+    let note = find_own_note(&repo, id, key.id_user)?;
+
+    repo.public_links()
+        .find_by_note_id(note.id)
+        .map(|public_links| ApiResponse::ok(public_links.iter().map(PublicLinkOut::from).collect()))
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot read links: {}", err),
+            )
+        })
+}
+
+/// Creates a public link to the note, its token is only returned in this
+/// response and opens the note in `GET /public/<token>`.
+#[post(
+    "/<id>/public-links",
+    format = "application/json",
+    data = "<public_link_in>"
+)]
+pub fn create<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    public_link_in: Json<PublicLinkIn>,
+) -> Result<ApiResponse<PublicLinkOut>, StatusError<'r>> {
+    let note = find_own_note(&repo, id, key.id_user)?;
+
+    let expire_time = match public_link_in.expires_in_hours {
+        Some(hours) => Some(
+            Some(hours)
+                .filter(|hours| (1..=MAX_EXPIRES_IN_HOURS).contains(hours))
+                .and_then(|hours| now().checked_add_signed(Duration::hours(hours)))
+                .ok_or_else(|| {
+                    status_error(
+                        Status::BadRequest,
+                        format!(
+                            "Link expiration must be between 1 and {} hours",
+                            MAX_EXPIRES_IN_HOURS
+                        ),
+                    )
+                })?,
+        ),
+        None => None,
+    };
+    let password_hash = match public_link_in.password.as_ref() {
+        Some(link_password) if link_password.is_empty() => {
+            return Err(status_error(
+                Status::BadRequest,
+                "Link password is empty".to_string(),
+            ))
+        }
+        Some(link_password) => Some(password::hash(link_password).map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot hash the password: {}", err),
+            )
+        })?),
+        None => None,
+    };
+
+    let secret = token::generate();
+    let token_hash = token::hash(&secret);
+
+    repo.public_links()
+        .create(
+            &NewPublicLink::new(note.id, &token_hash)
+                .with_password_hash(password_hash.as_deref())
+                .with_expire_time(expire_time),
+        )
+        .map(|public_link| {
+            ApiResponse::new(
+                PublicLinkOut::from(&public_link).with_token(secret.clone()),
+                Status::Created,
+            )
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot create link: {}", err),
+            )
+        })
+}
+
+/// Revokes the link, it cannot be opened anymore.
+#[delete("/<id>/public-links/<link_id>")]
+pub fn delete<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    link_id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    let note = find_own_note(&repo, id, key.id_user)?;
+
+    repo.public_links()
+        .delete(link_id, note.id)
+        .map(|num| {
+            ApiResponse::empty_new(if num > 0 {
+                Status::Ok
+            } else {
+                Status::NoContent
+            })
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot delete link: {}", err),
+            )
+        })
+}
@@ -0,0 +1,120 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::notes::{note_out, note_tags};
+use crate::handlers::{status_error, ApiResponse, StatusError};
+
+use rnotes_core::models::api::note::NoteOut;
+use rnotes_core::models::api::revision::{RevisionDiffOut, RevisionOut};
+use rnotes_core::models::db::note::Note;
+use rnotes_core::repository::Repo;
+use rnotes_core::utils::text_diff;
+
+use rocket::http::Status;
+
+fn find_note<'r>(key: &JWTKey, repo: &Repo, id: i32) -> Result<Note, StatusError<'r>> {
+    repo.notes()
+        .find_by_id_and_user_id(id, key.id_user)
+        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
+}
+
+#[get("/<id>/revisions")]
+pub fn all<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<Vec<RevisionOut>>, StatusError<'r>> {
+    find_note(&key, &repo, id)?;
+
+    repo.revisions()
+        .find_by_note_id(id)
+        .map(|all| ApiResponse::ok(all.iter().map(|r| RevisionOut::from(r)).collect()))
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot find revisions: {}", err)))
+}
+
+#[get("/<id>/revisions/<rev>")]
+pub fn get<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    rev: i32,
+) -> Result<ApiResponse<RevisionOut>, StatusError<'r>> {
+    find_note(&key, &repo, id)?;
+
+    repo.revisions()
+        .find_by_note_id_and_revision(id, rev)
+        .map(|revision| ApiResponse::ok(RevisionOut::from(&revision)))
+        .map_err(|err| {
+            status_error(
+                Status::NotFound,
+                format!("Revision is not correct: {}", err),
+            )
+        })
+}
+
+#[get("/<id>/revisions/<rev>/diff?<to>")]
+pub fn diff<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    rev: i32,
+    to: Option<i32>,
+) -> Result<ApiResponse<RevisionDiffOut>, StatusError<'r>> {
+    let note = find_note(&key, &repo, id)?;
+
+    let find_revision = |rev: i32| {
+        repo.revisions()
+            .find_by_note_id_and_revision(id, rev)
+            .map_err(|err| {
+                status_error(
+                    Status::NotFound,
+                    format!("Revision is not correct: {}", err),
+                )
+            })
+    };
+
+    let from = find_revision(rev)?;
+    let (title, data) = match to {
+        Some(to) => {
+            let to = find_revision(to)?;
+            (to.title, to.data)
+        }
+        None => (note.title, note.data),
+    };
+
+    Ok(ApiResponse::ok(RevisionDiffOut {
+        from: rev,
+        to: to,
+        title: text_diff(&from.title, &title),
+        data: text_diff(&from.data, &data),
+    }))
+}
+
+#[post("/<id>/revisions/<rev>/restore")]
+pub fn restore<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    rev: i32,
+) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    let mut note = find_note(&key, &repo, id)?;
+
+    let revision = repo
+        .revisions()
+        .find_by_note_id_and_revision(id, rev)
+        .map_err(|err| {
+            status_error(
+                Status::NotFound,
+                format!("Revision is not correct: {}", err),
+            )
+        })?;
+    let tags = note_tags(&repo, id)?;
+
+    note.category_id = revision.category_id;
+    note.title = revision.title;
+    note.data = revision.data;
+    note.key_check = revision.key_check;
+
+    repo.notes()
+        .update(&note, &tags)
+        .map_err(|err| status_error(Status::BadRequest, format!("Note is not correct: {}", err)))
+        .and_then(|note| note_out(&repo, &note, Status::Created))
+}
@@ -0,0 +1,142 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use rnotes_core::models::api::note_share::{NoteShareIn, NoteShareOut, SharedNoteOut};
+use rnotes_core::models::db::note::Note;
+use rnotes_core::models::db::note_share::{NewNoteShare, NoteShare, Permission};
+use rnotes_core::repository::Repo;
+use rocket::http::Status;
+use rocket_contrib::json::Json;
+
+// Only the owner of the note manages its shares.
+fn find_own_note<'r>(repo: &Repo, id: i32, user_id: i32) -> Result<Note, StatusError<'r>> {
+    repo.notes()
+        .find_by_id_and_user_id(id, user_id)
+        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
+}
+
+fn note_share_out(repo: &Repo, note_share: &NoteShare) -> NoteShareOut {
+    let note_share_out = NoteShareOut::from(note_share);
+    match repo.users().find_by_id(note_share.user_id) {
+        Ok(user) => note_share_out.with_email(user.email),
+        Err(_) => note_share_out,
+    }
+}
+
+/// Notes shared with the user, the last updated first.
+#[get("/shared")]
+pub fn shared<'r>(
+    key: JWTKey,
+    repo: Repo,
+) -> Result<ApiResponse<Vec<SharedNoteOut>>, StatusError<'r>> {
+    repo.shares()
+        .find_notes_by_user_id(key.id_user)
+        .map(|shared| {
+            ApiResponse::ok(
+                shared
+                    .iter()
+                    .map(|(note, note_share)| SharedNoteOut::from((note, note_share)))
+                    .collect(),
+            )
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot read shared notes: {}", err),
+            )
+        })
+}
+
+#[get("/<id>/shares")]
+pub fn all<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<Vec<NoteShareOut>>, StatusError<'r>> {
+    let note = find_own_note(&repo, id, key.id_user)?;
+
+    repo.shares()
+        .find_by_note_id(note.id)
+        .map(|note_shares| {
+            ApiResponse::ok(
+                note_shares
+                    .iter()
+                    .map(|note_share| note_share_out(&repo, note_share))
+                    .collect(),
+            )
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot read shares: {}", err),
+            )
+        })
+}
+
+/// Shares the note with the user of the email, or changes the permission if it
+/// was already shared with it.
+#[post("/<id>/shares", format = "application/json", data = "<note_share_in>")]
+pub fn create<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    note_share_in: Json<NoteShareIn>,
+) -> Result<ApiResponse<NoteShareOut>, StatusError<'r>> {
+    let note = find_own_note(&repo, id, key.id_user)?;
+
+    let permission = match note_share_in.permission.as_ref() {
+        Some(permission) => Permission::parse(permission).ok_or_else(|| {
+            status_error(
+                Status::BadRequest,
+                format!("Permission '{}' is not valid", permission),
+            )
+        })?,
+        None => Permission::Read,
+    };
+    let user = repo
+        .users()
+        .find_by_email(note_share_in.email.trim())
+        .map_err(|err| status_error(Status::NotFound, format!("User is not correct: {}", err)))?;
+    if user.id == key.id_user {
+        return Err(status_error(
+            Status::BadRequest,
+            "Note cannot be shared with its owner".to_string(),
+        ));
+    }
+
+    repo.shares()
+        .create(&NewNoteShare::new(note.id, user.id, permission))
+        .map(|note_share| {
+            ApiResponse::new(
+                NoteShareOut::from(&note_share).with_email(user.email.clone()),
+                Status::Created,
+            )
+        })
+        .map_err(|err| status_error(Status::BadRequest, format!("Share is not correct: {}", err)))
+}
+
+/// Stops sharing the note with the user.
+#[delete("/<id>/shares/<user_id>")]
+pub fn delete<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+    user_id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    let note = find_own_note(&repo, id, key.id_user)?;
+
+    repo.shares()
+        .delete(note.id, user_id)
+        .map(|num| {
+            ApiResponse::empty_new(if num > 0 {
+                Status::Ok
+            } else {
+                Status::NoContent
+            })
+        })
+        .map_err(|err| {
+            status_error(
+                Status::InternalServerError,
+                format!("Cannot delete share: {}", err),
+            )
+        })
+}
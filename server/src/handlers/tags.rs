@@ -0,0 +1,13 @@
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use rnotes_core::models::api::tag::TagOut;
+use rnotes_core::repository::Repo;
+use rocket::http::Status;
+
+#[get("/")]
+pub fn all<'r>(key: JWTKey, repo: Repo) -> Result<ApiResponse<Vec<TagOut>>, StatusError<'r>> {
+    repo.tags()
+        .find_by_user_id(key.id_user)
+        .map(|all| ApiResponse::ok(all.iter().map(|tag| TagOut::from(tag)).collect()))
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot find tags: {}", err)))
+}
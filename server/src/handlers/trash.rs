@@ -0,0 +1,100 @@
+use crate::handlers::attachments::sweep;
+use crate::handlers::jwt::JWTKey;
+use crate::handlers::notes::{note_out, with_tags};
+use crate::handlers::{status_error, ApiResponse, StatusError};
+use crate::storage::Store;
+
+use rnotes_core::models::api::note::NoteOut;
+use rnotes_core::repository::{Repo, RepositoryResult};
+use rnotes_core::utils::now;
+
+use chrono::Duration;
+use dotenv::dotenv;
+use log::*;
+
+use rocket::http::Status;
+
+use std::env;
+use std::thread;
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+const PURGE_INTERVAL_SECS: u64 = 3600;
+
+/// Time the notes are kept in the trash, `TRASH_RETENTION_DAYS` days.
+pub fn get_retention() -> Duration {
+    dotenv().ok();
+
+    let days = env::var("TRASH_RETENTION_DAYS")
+        .map(|days| {
+            days.parse::<i64>()
+                .expect("TRASH_RETENTION_DAYS must be an number")
+        })
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+
+    Duration::days(days)
+}
+
+/// Permanently deletes the notes that have been in the trash longer than the
+/// retention.
+pub fn purge(repo: &Repo, retention: Duration) -> RepositoryResult<usize> {
+    repo.notes().purge(now() - retention)
+}
+
+/// Starts a thread purging the trash every hour, along with the attachments
+/// of the deleted notes.
+pub fn spawn_purge(repo: Repo, store: Store, retention: Duration) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        match purge(&repo, retention) {
+            Ok(num) => info!("Purged {} notes from the trash", num),
+            Err(err) => error!("Error purging the trash: {}", err),
+        }
+        match sweep(&repo, &store) {
+            Ok(num) => info!("Purged {} attachments of deleted notes", num),
+            Err(err) => error!("Error purging the attachments: {}", err),
+        }
+
+        thread::sleep(std::time::Duration::from_secs(PURGE_INTERVAL_SECS));
+    })
+}
+
+#[get("/")]
+pub fn all<'r>(key: JWTKey, repo: Repo) -> Result<ApiResponse<Vec<NoteOut>>, StatusError<'r>> {
+    repo.notes()
+        .find_trashed_by_user_id(key.id_user)
+        .and_then(|notes| with_tags(&repo, &notes))
+        .map(|notes| ApiResponse::ok(notes))
+        .map_err(|err| status_error(Status::NotFound, format!("Cannot find notes: {}", err)))
+}
+
+#[post("/<id>/restore")]
+pub fn restore<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<NoteOut>, StatusError<'r>> {
+    repo.notes()
+        .find_trashed_by_id_and_user_id(id, key.id_user)
+        .and_then(|_| repo.notes().restore(id))
+        .map_err(|err| status_error(Status::NotFound, format!("Note is not correct: {}", err)))
+        .and_then(|note| note_out(&repo, &note, Status::Created))
+}
+
+#[delete("/<id>")]
+pub fn delete<'r>(
+    key: JWTKey,
+    repo: Repo,
+    id: i32,
+) -> Result<ApiResponse<&'r str>, StatusError<'r>> {
+    repo.notes()
+        .find_trashed_by_id_and_user_id(id, key.id_user)
+        .and_then(|_| {
+            repo.notes().delete(id).map(|num| {
+                ApiResponse::empty_new(if num > 0 {
+                    Status::Ok
+                } else {
+                    Status::NoContent
+                })
+            })
+        })
+        .or(Ok(ApiResponse::empty_new(Status::NoContent)))
+}
@@ -9,18 +9,25 @@ extern crate rocket;
 extern crate env_logger;
 extern crate rnotes_core;
 extern crate rocket_contrib;
+extern crate rustc_serialize;
 extern crate serde_derive;
 
 pub mod handlers;
+pub mod oidc;
+pub mod storage;
 
 use chrono::Local;
 use dotenv::dotenv;
 use env_logger::Builder;
 use log::*;
+use oidc::Oidc;
+use rnotes_core::repository::db::DBRepository;
+use rnotes_core::repository::Repo;
 use rnotes_core::BDPool;
 use rocket::config::{Config, Environment};
 use std::env;
 use std::io::Write;
+use storage::Store;
 
 pub fn init_log() {
     Builder::new()
@@ -59,24 +66,123 @@ pub fn start_server() {
         .finalize()
         .expect("Cannot configure Rocket");
 
+    let repo = Repo::new(DBRepository::new(
+        BDPool::new().expect("Cannot obtain BDPool"),
+    ));
+
+    let store = Store::from_env().expect("Cannot configure the storage");
+
+    let retention = handlers::trash::get_retention();
+    info!("Purging the trash[retention={} days]", retention.num_days());
+    handlers::trash::spawn_purge(repo.clone(), store.clone(), retention);
+
+    match rnotes_core::encryption::keyring().expect("Cannot configure the encryption") {
+        Some(keyring) => info!(
+            "Encryption at rest enabled[key_id={}]",
+            keyring.current_id()
+        ),
+        None => info!("Encryption at rest disabled"),
+    }
+
+    let oidc = Oidc::from_env();
+    if let Some(provider) = &oidc.0 {
+        info!("OpenID Connect enabled[issuer={}]", provider.issuer());
+    }
+
     let rocket = rocket::custom(config)
-        .manage(BDPool::new().expect("Cannot obtain BDPool"))
+        .manage(repo)
+        .manage(oidc)
+        .manage(store)
         .mount("/", routes![handlers::index])
         .mount(
             "/notes",
             routes![
                 handlers::notes::all,
+                handlers::notes::search,
                 handlers::notes::get,
+                handlers::notes::render,
                 handlers::notes::create,
                 handlers::notes::update,
                 handlers::notes::delete,
+                handlers::notes::add_tag,
+                handlers::notes::remove_tag,
+                handlers::shares::shared,
+                handlers::shares::all,
+                handlers::shares::create,
+                handlers::shares::delete,
+                handlers::public_links::all,
+                handlers::public_links::create,
+                handlers::public_links::delete,
+                handlers::attachments::all,
+                handlers::attachments::upload,
+                handlers::attachments::get,
+                handlers::attachments::delete,
+                handlers::links::all,
+                handlers::links::backlinks,
+                handlers::revisions::all,
+                handlers::revisions::get,
+                handlers::revisions::diff,
+                handlers::revisions::restore,
             ],
         )
         .mount(
             "/categories",
-            routes![handlers::categories::all, handlers::categories::get,],
+            routes![
+                handlers::categories::all,
+                handlers::categories::tree,
+                handlers::categories::get,
+                handlers::categories::create,
+                handlers::categories::update,
+                handlers::categories::delete,
+            ],
+        )
+        .mount("/tags", routes![handlers::tags::all])
+        .mount("/graph", routes![handlers::graph::get])
+        .mount("/attachments", routes![handlers::attachments::quota])
+        .mount("/public", routes![handlers::public::get])
+        .mount(
+            "/trash",
+            routes![
+                handlers::trash::all,
+                handlers::trash::restore,
+                handlers::trash::delete,
+            ],
+        )
+        .mount(
+            "/auth",
+            routes![
+                handlers::auth::login,
+                handlers::auth::register,
+                handlers::auth::refresh,
+                handlers::auth::logout,
+                handlers::auth::two_factor_setup,
+                handlers::auth::two_factor_verify,
+                handlers::auth::two_factor_disable,
+                handlers::auth::two_factor_login,
+                handlers::oidc::authorize,
+                handlers::oidc::callback,
+            ],
+        )
+        .mount(
+            "/admin",
+            routes![
+                handlers::admin::users,
+                handlers::admin::update_user,
+                handlers::admin::delete_user,
+                handlers::admin::categories,
+                handlers::admin::create_category,
+                handlers::admin::update_category,
+                handlers::admin::delete_category,
+            ],
+        )
+        .mount(
+            "/auth/keys",
+            routes![
+                handlers::keys::all,
+                handlers::keys::create,
+                handlers::keys::delete,
+            ],
         )
-        .mount("/auth", routes![handlers::auth::login])
         .attach(handlers::catch_not_json());
 
     info!("Launching rocket[port={}]", port);
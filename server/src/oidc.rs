@@ -0,0 +1,373 @@
+//! OpenID Connect authorization-code flow against an external provider.
+//!
+//! It is enabled by setting `OIDC_ISSUER`, along with `OIDC_CLIENT_ID`,
+//! `OIDC_CLIENT_SECRET` and `OIDC_REDIRECT_URI`. The endpoints and the keys of
+//! the provider are discovered from `<issuer>/.well-known/openid-configuration`
+//! on first use, so the server starts even if the provider is down.
+
+use crate::handlers::jwt::get_secret_key;
+use dotenv::dotenv;
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use reqwest::blocking::Client;
+use reqwest::Url;
+use rocket::http::Header as HttpHeader;
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request, State};
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds to come back from the provider with the state.
+const STATE_SECONDS: u64 = 600;
+const STATE_TYPE: &str = "oidc_state";
+/// Cookie binding the state to the browser that started the login.
+pub const STATE_COOKIE: &str = "oidc_state";
+/// Seconds of clock skew accepted in the ID tokens.
+const LEEWAY_SECONDS: u64 = 60;
+
+#[derive(Debug, PartialEq)]
+pub enum OidcError {
+    /// The provider cannot be reached or answered something unexpected.
+    Provider(String),
+    /// The state, the code or the ID token are not valid.
+    Invalid(String),
+}
+
+impl fmt::Display for OidcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OidcError::Provider(err) => write!(f, "provider error: {}", err),
+            OidcError::Invalid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// URI of `GET /auth/oidc/callback` as seen by the users' browsers.
+    pub redirect_uri: String,
+    pub scopes: String,
+}
+
+impl OidcConfig {
+    /// The configuration if `OIDC_ISSUER` is set.
+    pub fn from_env() -> Option<OidcConfig> {
+        dotenv().ok();
+
+        let issuer = env::var("OIDC_ISSUER")
+            .ok()
+            .filter(|issuer| !issuer.is_empty())?;
+
+        Some(OidcConfig {
+            issuer: issuer,
+            client_id: env::var("OIDC_CLIENT_ID").expect("OIDC_CLIENT_ID must be set"),
+            client_secret: env::var("OIDC_CLIENT_SECRET").unwrap_or_default(),
+            redirect_uri: env::var("OIDC_REDIRECT_URI").expect("OIDC_REDIRECT_URI must be set"),
+            scopes: env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Claims of an ID token used to find or provision the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StateClaims {
+    typ: String,
+    nonce: String,
+    exp: u64,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Signs the nonce of an authorization request, so the callback needs no
+/// server-side storage.
+pub fn sign_state(nonce: &str) -> Result<String, OidcError> {
+    let claims = StateClaims {
+        typ: STATE_TYPE.to_string(),
+        nonce: nonce.to_string(),
+        exp: unix_time() + STATE_SECONDS,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&get_secret_key()),
+    )
+    .map_err(|err| OidcError::Invalid(format!("cannot sign the state: {}", err)))
+}
+
+/// The nonce of a state signed by `sign_state` if it has not expired.
+pub fn read_state(state: &str) -> Result<String, OidcError> {
+    let secret_key = get_secret_key();
+
+    decode::<StateClaims>(
+        state,
+        &DecodingKey::from_secret(&secret_key),
+        &Validation::new(Algorithm::HS256),
+    )
+    .ok()
+    .filter(|data| data.claims.typ == STATE_TYPE)
+    .map(|data| data.claims.nonce)
+    .ok_or_else(|| OidcError::Invalid("state is not valid".to_string()))
+}
+
+/// `Set-Cookie` header with the state, it lasts as long as the state itself.
+/// The provider redirects to the callback with a top-level navigation, so the
+/// cookie is sent back with `SameSite=Lax`.
+pub fn state_cookie(state: &str) -> HttpHeader<'static> {
+    HttpHeader::new(
+        "Set-Cookie",
+        format!(
+            "{}={}; Path=/auth/oidc; Max-Age={}; HttpOnly; SameSite=Lax",
+            STATE_COOKIE, state, STATE_SECONDS
+        ),
+    )
+}
+
+/// Client of the provider, it caches its metadata and keys.
+pub struct OidcProvider {
+    config: OidcConfig,
+    client: Client,
+    metadata: RwLock<Option<ProviderMetadata>>,
+    keys: RwLock<Vec<Jwk>>,
+}
+
+impl OidcProvider {
+    pub fn new(config: OidcConfig) -> Self {
+        OidcProvider {
+            config: config,
+            client: Client::new(),
+            metadata: RwLock::new(None),
+            keys: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.config.issuer
+    }
+
+    fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T, OidcError> {
+        self.client
+            .get(url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json::<T>())
+            .map_err(|err| OidcError::Provider(format!("GET {}: {}", url, err)))
+    }
+
+    fn metadata(&self) -> Result<ProviderMetadata, OidcError> {
+        if let Some(metadata) = self.metadata.read().unwrap().as_ref() {
+            return Ok(metadata.clone());
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer.trim_end_matches('/')
+        );
+        let metadata = self.get::<ProviderMetadata>(&url)?;
+        if metadata.issuer != self.config.issuer {
+            return Err(OidcError::Provider(format!(
+                "issuer {} does not match {}",
+                metadata.issuer, self.config.issuer
+            )));
+        }
+
+        *self.metadata.write().unwrap() = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    // The key of the ID tokens, the keys are fetched again once if it is not
+    // found in case the provider rotated them.
+    fn find_key(&self, kid: Option<&str>) -> Result<Jwk, OidcError> {
+        let find = |keys: &[Jwk]| {
+            keys.iter()
+                .find(|key| key.kty == "RSA" && (kid.is_none() || key.kid.as_deref() == kid))
+                .cloned()
+        };
+
+        if let Some(key) = find(&self.keys.read().unwrap()) {
+            return Ok(key);
+        }
+
+        let key_set = self.get::<JwkSet>(&self.metadata()?.jwks_uri)?;
+        let key = find(&key_set.keys);
+        *self.keys.write().unwrap() = key_set.keys;
+
+        key.ok_or_else(|| OidcError::Invalid("ID token key is not known".to_string()))
+    }
+
+    /// URL of the provider where the user logs in.
+    pub fn authorization_url(&self, state: &str, nonce: &str) -> Result<String, OidcError> {
+        Url::parse_with_params(
+            &self.metadata()?.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", &self.config.client_id),
+                ("redirect_uri", &self.config.redirect_uri),
+                ("scope", &self.config.scopes),
+                ("state", state),
+                ("nonce", nonce),
+            ],
+        )
+        .map(|url| url.to_string())
+        .map_err(|err| OidcError::Provider(format!("authorization endpoint: {}", err)))
+    }
+
+    /// Exchanges the code of the callback for an ID token.
+    pub fn exchange_code(&self, code: &str) -> Result<String, OidcError> {
+        let token_endpoint = self.metadata()?.token_endpoint;
+
+        let response = self
+            .client
+            .post(&token_endpoint)
+            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.config.redirect_uri),
+                ("client_id", &self.config.client_id),
+            ])
+            .send()
+            .map_err(|err| OidcError::Provider(format!("POST {}: {}", token_endpoint, err)))?;
+
+        if response.status().is_client_error() {
+            return Err(OidcError::Invalid(format!(
+                "code is not valid: {}",
+                response.status()
+            )));
+        }
+
+        response
+            .error_for_status()
+            .and_then(|response| response.json::<TokenResponse>())
+            .map(|token_response| token_response.id_token)
+            .map_err(|err| OidcError::Provider(format!("POST {}: {}", token_endpoint, err)))
+    }
+
+    /// Checks the signature, the issuer, the audience, the expiration and the
+    /// nonce of the ID token.
+    pub fn validate_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let invalid = |err: String| OidcError::Invalid(format!("ID token is not valid: {}", err));
+
+        let header = decode_header(id_token).map_err(|err| invalid(err.to_string()))?;
+        match header.alg {
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {}
+            alg => return Err(invalid(format!("algorithm {:?} is not allowed", alg))),
+        }
+
+        let key = self.find_key(header.kid.as_deref())?;
+        let (n, e) = match (&key.n, &key.e) {
+            (Some(n), Some(e)) => (n, e),
+            _ => return Err(invalid("key has no modulus or exponent".to_string())),
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = LEEWAY_SECONDS;
+        validation.iss = Some(self.metadata()?.issuer);
+        validation.set_audience(&[&self.config.client_id]);
+
+        let claims = decode::<IdTokenClaims>(
+            id_token,
+            &DecodingKey::from_rsa_components(n, e),
+            &validation,
+        )
+        .map_err(|err| invalid(err.to_string()))?
+        .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid("nonce does not match".to_string()));
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Request guard giving access to the OpenID Connect provider, `None` if it is
+/// not configured.
+#[derive(Clone)]
+pub struct Oidc(pub Option<Arc<OidcProvider>>);
+
+impl Oidc {
+    pub fn from_env() -> Self {
+        Oidc(OidcConfig::from_env().map(|config| Arc::new(OidcProvider::new(config))))
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Oidc {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Oidc, Self::Error> {
+        match request.guard::<State<Oidc>>() {
+            Outcome::Success(oidc) => Outcome::Success(oidc.clone()),
+            _ => Outcome::Success(Oidc(None)),
+        }
+    }
+}
+
+/// State cookie sent by the browser, see `state_cookie`.
+pub struct StateCookie(pub Option<String>);
+
+impl<'a, 'r> FromRequest<'a, 'r> for StateCookie {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<StateCookie, Self::Error> {
+        Outcome::Success(StateCookie(
+            request
+                .cookies()
+                .get(STATE_COOKIE)
+                .map(|cookie| cookie.value().to_string()),
+        ))
+    }
+}
@@ -0,0 +1,82 @@
+use crate::storage::{is_valid_key, Storage, StorageError, StorageResult};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+fn io_error(err: io::Error) -> StorageError {
+    match err.kind() {
+        io::ErrorKind::NotFound => StorageError::NotFound,
+        _ => StorageError::Backend(err.to_string()),
+    }
+}
+
+/// `Storage` keeping every object as a file under a root directory.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// Storage under the directory, which is created if it does not exist.
+    pub fn new<P: Into<PathBuf>>(root: P) -> StorageResult<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(io_error)?;
+
+        Ok(LocalStorage { root: root })
+    }
+
+    fn path(&self, key: &str) -> StorageResult<PathBuf> {
+        if !is_valid_key(key) {
+            return Err(StorageError::InvalidKey(key.to_string()));
+        }
+
+        Ok(key
+            .split('/')
+            .fold(self.root.clone(), |path, segment| path.join(segment)))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put(&self, key: &str, data: &[u8]) -> StorageResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        // Written aside and renamed, so a partial file is never read. Keys
+        // cannot start with a dot, so it does not clash with other objects.
+        let partial = path.with_file_name(format!(
+            ".{}.part",
+            key.rsplit('/').next().unwrap_or_default()
+        ));
+        File::create(&partial)
+            .and_then(|mut file| file.write_all(data).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(&partial, &path))
+            .map_err(|err| {
+                fs::remove_file(&partial).ok();
+                io_error(err)
+            })
+    }
+
+    fn get(&self, key: &str, range: Option<(u64, u64)>) -> StorageResult<Vec<u8>> {
+        let mut file = File::open(self.path(key)?).map_err(io_error)?;
+
+        let mut data = vec![];
+        match range {
+            Some((start, end)) => {
+                file.seek(SeekFrom::Start(start)).map_err(io_error)?;
+                file.take(end + 1 - start).read_to_end(&mut data)
+            }
+            None => file.read_to_end(&mut data),
+        }
+        .map_err(io_error)?;
+
+        Ok(data)
+    }
+
+    fn delete(&self, key: &str) -> StorageResult<()> {
+        match fs::remove_file(self.path(key)?).map_err(io_error) {
+            Err(StorageError::NotFound) => Ok(()),
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Storage of the content of the attachments.
+//!
+//! The server works against the `Storage` trait, selected with `STORAGE`:
+//! `local` keeps the files under `STORAGE_PATH` and `s3` keeps them in a bucket
+//! of an S3-compatible service, like MinIO.
+
+pub mod local;
+pub mod s3;
+
+use dotenv::dotenv;
+use log::*;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request, State};
+use std::env;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+const DEFAULT_STORAGE_PATH: &str = "attachments";
+const DEFAULT_MAX_FILE_SIZE: i64 = 10 * 1024 * 1024;
+const DEFAULT_USER_QUOTA: i64 = 100 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum StorageError {
+    NotFound,
+    InvalidKey(String),
+    /// The backend failed or cannot be reached.
+    Backend(String),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::NotFound => f.write_str("Object not found"),
+            StorageError::InvalidKey(o) => f.write_str(&format!("Key '{}' is not valid", o)),
+            StorageError::Backend(o) => f.write_str(&format!("Storage error: {}", o)),
+        }
+    }
+}
+
+/// Objects by key. Keys are paths of `/`-separated segments.
+pub trait Storage: Send + Sync {
+    fn put(&self, key: &str, data: &[u8]) -> StorageResult<()>;
+
+    /// Content of the object, or only the bytes from `start` to `end`, both
+    /// included, if a range is given.
+    fn get(&self, key: &str, range: Option<(u64, u64)>) -> StorageResult<Vec<u8>>;
+
+    /// Deletes the object, it does not fail if it does not exist.
+    fn delete(&self, key: &str) -> StorageResult<()>;
+}
+
+/// Whether the key is a path that cannot escape the root of the storage.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && !segment.starts_with('.')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        })
+}
+
+/// Limits of the attachments, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub max_file_size: i64,
+    /// Total size of the attachments of the notes of a user.
+    pub max_user_size: i64,
+}
+
+impl Quota {
+    /// Limits of `ATTACHMENTS_MAX_FILE_SIZE`, 10 MiB by default, and
+    /// `ATTACHMENTS_QUOTA`, 100 MiB by default.
+    pub fn from_env() -> Self {
+        dotenv().ok();
+
+        let bytes = |name: &str, default: i64| {
+            env::var(name)
+                .map(|value| {
+                    value
+                        .parse::<i64>()
+                        .unwrap_or_else(|_| panic!("{} must be a number of bytes", name))
+                })
+                .unwrap_or(default)
+        };
+
+        Quota {
+            max_file_size: bytes("ATTACHMENTS_MAX_FILE_SIZE", DEFAULT_MAX_FILE_SIZE),
+            max_user_size: bytes("ATTACHMENTS_QUOTA", DEFAULT_USER_QUOTA),
+        }
+    }
+}
+
+/// Request guard giving access to the `Storage` and the `Quota` managed by
+/// Rocket.
+#[derive(Clone)]
+pub struct Store {
+    pub storage: Arc<dyn Storage>,
+    pub quota: Quota,
+}
+
+impl Store {
+    pub fn new<S: Storage + 'static>(storage: S, quota: Quota) -> Self {
+        Store {
+            storage: Arc::new(storage),
+            quota: quota,
+        }
+    }
+
+    /// Store of the backend selected with `STORAGE`, `local` by default.
+    pub fn from_env() -> StorageResult<Self> {
+        dotenv().ok();
+
+        let quota = Quota::from_env();
+        match env::var("STORAGE").unwrap_or_default().as_str() {
+            "" | "local" => {
+                let path =
+                    env::var("STORAGE_PATH").unwrap_or_else(|_| DEFAULT_STORAGE_PATH.to_string());
+                info!("Storing attachments in the filesystem[path={}]", path);
+                Ok(Store::new(local::LocalStorage::new(path)?, quota))
+            }
+            "s3" => {
+                let config = s3::S3Config::from_env();
+                info!(
+                    "Storing attachments in S3[endpoint={}, bucket={}]",
+                    config.endpoint, config.bucket
+                );
+                Ok(Store::new(s3::S3Storage::new(config)?, quota))
+            }
+            other => Err(StorageError::Backend(format!(
+                "STORAGE '{}' is not valid",
+                other
+            ))),
+        }
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Store {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Store, Self::Error> {
+        match request.guard::<State<Store>>() {
+            Outcome::Success(store) => Outcome::Success(store.clone()),
+            _ => Outcome::Failure((Status::ServiceUnavailable, ())),
+        }
+    }
+}
+
+impl Deref for Store {
+    type Target = dyn Storage;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.storage
+    }
+}
@@ -0,0 +1,250 @@
+use crate::storage::{is_valid_key, Storage, StorageError, StorageResult};
+use chrono::Utc;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use dotenv::dotenv;
+use reqwest::blocking::{Client, Response};
+use reqwest::{Method, StatusCode, Url};
+use rustc_serialize::hex::ToHex;
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    /// Base URL of the service, e.g. `http://localhost:9000` for a local MinIO.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl S3Config {
+    /// The configuration of `S3_ENDPOINT`, `S3_BUCKET`, `S3_REGION`,
+    /// `us-east-1` by default, `S3_ACCESS_KEY` and `S3_SECRET_KEY`.
+    pub fn from_env() -> S3Config {
+        dotenv().ok();
+
+        S3Config {
+            endpoint: env::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set"),
+            bucket: env::var("S3_BUCKET").expect("S3_BUCKET must be set"),
+            region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            access_key: env::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY must be set"),
+            secret_key: env::var("S3_SECRET_KEY").expect("S3_SECRET_KEY must be set"),
+        }
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let mut sha = Sha256::new();
+    sha.input(data);
+    sha.result_str()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    hmac.input(data);
+    hmac.result().code().to_vec()
+}
+
+/// Percent-encoding of a path of the S3 API, only the unreserved characters
+/// and the slashes are kept.
+pub fn uri_encode(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// `Authorization` header of a request signed with AWS Signature Version 4.
+/// `headers` are the signed ones, with lowercase names, and `amz_date` is the
+/// `x-amz-date` of the request.
+pub fn authorization(
+    config: &S3Config,
+    method: &str,
+    path: &str,
+    headers: &BTreeMap<String, String>,
+    payload_hash: &str,
+    amz_date: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .keys()
+        .map(|name| name.as_str())
+        .collect::<Vec<&str>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, path, canonical_headers, signed_headers, payload_hash
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let signing_key = [config.region.as_str(), "s3", "aws4_request"].iter().fold(
+        hmac_sha256(
+            format!("AWS4{}", config.secret_key).as_bytes(),
+            date.as_bytes(),
+        ),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    );
+    let signature = hmac_sha256(&signing_key, string_to_sign.as_bytes()).to_hex();
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        config.access_key, scope, signed_headers, signature
+    )
+}
+
+/// `Storage` keeping every object in a bucket of an S3-compatible service,
+/// addressed with path-style URLs.
+pub struct S3Storage {
+    config: S3Config,
+    host: String,
+    client: Client,
+}
+
+impl S3Storage {
+    pub fn new(config: S3Config) -> StorageResult<Self> {
+        let url = Url::parse(&config.endpoint)
+            .map_err(|err| StorageError::Backend(format!("S3_ENDPOINT is not valid: {}", err)))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => return Err(StorageError::Backend("S3_ENDPOINT has no host".to_string())),
+        };
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|err| StorageError::Backend(err.to_string()))?;
+
+        Ok(S3Storage {
+            config: config,
+            host: host,
+            client: client,
+        })
+    }
+
+    fn path(&self, key: Option<&str>) -> StorageResult<String> {
+        match key {
+            Some(key) if !is_valid_key(key) => Err(StorageError::InvalidKey(key.to_string())),
+            Some(key) => Ok(format!("/{}/{}", uri_encode(&self.config.bucket), key)),
+            None => Ok(format!("/{}", uri_encode(&self.config.bucket))),
+        }
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        path: &str,
+        range: Option<(u64, u64)>,
+        body: Vec<u8>,
+    ) -> StorageResult<Response> {
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = sha256_hex(&body);
+
+        let mut headers = BTreeMap::new();
+        headers.insert("host".to_string(), self.host.clone());
+        headers.insert("x-amz-content-sha256".to_string(), payload_hash.clone());
+        headers.insert("x-amz-date".to_string(), amz_date.clone());
+        if let Some((start, end)) = range {
+            headers.insert("range".to_string(), format!("bytes={}-{}", start, end));
+        }
+        let authorization = authorization(
+            &self.config,
+            method.as_str(),
+            path,
+            &headers,
+            &payload_hash,
+            &amz_date,
+        );
+
+        // The client sets the same host from the URL.
+        let url = format!("{}{}", self.config.endpoint.trim_end_matches('/'), path);
+        headers
+            .iter()
+            .filter(|(name, _)| name.as_str() != "host")
+            .fold(
+                self.client.request(method, url.as_str()),
+                |request, (name, value)| request.header(name.as_str(), value.as_str()),
+            )
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .map_err(|err| StorageError::Backend(err.to_string()))
+    }
+
+    // The response if it succeeded, or else the error with its body.
+    fn check(response: Response) -> StorageResult<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Err(StorageError::NotFound);
+        }
+
+        Err(StorageError::Backend(format!(
+            "{} {}",
+            status,
+            response.text().unwrap_or_default()
+        )))
+    }
+
+    /// Creates the bucket, it does not fail if it already exists.
+    pub fn create_bucket(&self) -> StorageResult<()> {
+        let response = self.send(Method::PUT, &self.path(None)?, None, vec![])?;
+        if response.status() == StatusCode::CONFLICT {
+            return Ok(());
+        }
+
+        S3Storage::check(response).map(|_| ())
+    }
+}
+
+impl Storage for S3Storage {
+    fn put(&self, key: &str, data: &[u8]) -> StorageResult<()> {
+        self.send(Method::PUT, &self.path(Some(key))?, None, data.to_vec())
+            .and_then(S3Storage::check)
+            .map(|_| ())
+    }
+
+    fn get(&self, key: &str, range: Option<(u64, u64)>) -> StorageResult<Vec<u8>> {
+        self.send(Method::GET, &self.path(Some(key))?, range, vec![])
+            .and_then(S3Storage::check)
+            .and_then(|response| {
+                response
+                    .bytes()
+                    .map(|bytes| bytes.to_vec())
+                    .map_err(|err| StorageError::Backend(err.to_string()))
+            })
+    }
+
+    fn delete(&self, key: &str) -> StorageResult<()> {
+        match self
+            .send(Method::DELETE, &self.path(Some(key))?, None, vec![])
+            .and_then(S3Storage::check)
+        {
+            Ok(_) | Err(StorageError::NotFound) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
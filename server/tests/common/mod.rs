@@ -0,0 +1,74 @@
+#![allow(dead_code)]
+
+use rnotes_core::models::db::category::NewCategory;
+use rnotes_core::models::db::note::NewNote;
+use rnotes_core::models::db::user::NewUser;
+use rnotes_core::repository::db::DBRepository;
+use rnotes_core::repository::memory::MemoryRepository;
+use rnotes_core::repository::Repo;
+use rnotes_core::token;
+use rnotes_core::BDPool;
+use rnotes_server::storage::local::LocalStorage;
+use rnotes_server::storage::{Quota, Store};
+use std::env;
+
+/// Legacy SHA-256 of `some_password`.
+pub const PASSWORD: &str = "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B";
+
+/// In-memory copy of db/tests/test_data.sql.
+pub fn test_repo() -> Repo {
+    let repo = Repo::new(MemoryRepository::new());
+
+    for (email, name) in &[
+        ("user_a@email.com", "user_a"),
+        ("user_b@email.com", "user_b"),
+        ("user_c@email.com", "user_c"),
+    ] {
+        repo.users()
+            .create(&NewUser::new(email, name, PASSWORD))
+            .unwrap();
+    }
+
+    for name in &["cat_a", "cat_b"] {
+        repo.categories()
+            .create(&NewCategory::new(None, name))
+            .unwrap();
+    }
+
+    for (title, data, user_id, category_id) in &[
+        ("note_a_user_a", "some_text_note_a_user_a", 1, None),
+        ("note_b_user_a", "some_text_note_a_user_a", 1, Some(1)),
+        ("note_c_user_c", "some_text_note_c_user_c", 1, Some(2)),
+        ("note_a_user_b", "some_text_note_a_user_b", 2, None),
+    ] {
+        repo.notes()
+            .create(
+                &NewNote::new(*user_id, *category_id, title.to_string(), data.to_string()),
+                &[],
+            )
+            .unwrap();
+    }
+
+    repo
+}
+
+/// Repository of the database of `DATABASE_URL`, loaded with
+/// db/tests/test_data.sql by `make test` and `make test_sqlite`. The tests
+/// using it remove the rows they create.
+pub fn db_repo() -> Repo {
+    Repo::new(DBRepository::new(BDPool::new().unwrap()))
+}
+
+/// Store in a new temporary directory with small limits: files of up to 16
+/// bytes and 32 bytes per user.
+pub fn test_store() -> Store {
+    let path = env::temp_dir().join(format!("rnotes_test_{}", token::generate()));
+
+    Store::new(
+        LocalStorage::new(path).unwrap(),
+        Quota {
+            max_file_size: 16,
+            max_user_size: 32,
+        },
+    )
+}
@@ -0,0 +1,234 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use chrono::Duration;
+use rnotes_core::models::api::category::CategoryIn;
+use rnotes_core::models::api::user::UserAdminIn;
+use rnotes_core::models::db::session::NewSession;
+use rnotes_core::models::db::user::Role;
+use rnotes_core::utils::now;
+use rnotes_server::handlers::admin::*;
+use rnotes_server::handlers::jwt::{check_session, AdminKey, JWTKey};
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+use std::panic;
+
+fn admin_key() -> AdminKey {
+    AdminKey(JWTKey::new("1".to_string()).with_role(Role::Admin))
+}
+
+fn user_admin_in(role: Option<&str>, disabled: Option<bool>) -> Json<UserAdminIn> {
+    Json(UserAdminIn {
+        role: role.map(|role| role.to_string()),
+        disabled: disabled,
+    })
+}
+
+fn category_in(name: &str) -> Json<CategoryIn> {
+    Json(CategoryIn {
+        name: name.to_string(),
+        parent_id: None,
+    })
+}
+
+#[test]
+fn test_users() {
+    let repo = common::test_repo();
+
+    match users(admin_key(), repo) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result
+                    .iter()
+                    .map(|user| user.name.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["user_a", "user_b", "user_c"]
+            );
+            assert!(result.iter().all(|user| user.role == "user"));
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_update_user() {
+    let repo = common::test_repo();
+
+    repo.sessions()
+        .create(&NewSession::new(
+            2,
+            "jti_b",
+            "hash_b",
+            now() + Duration::days(1),
+        ))
+        .unwrap();
+
+    match update_user(
+        admin_key(),
+        repo.clone(),
+        2,
+        user_admin_in(None, Some(true)),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(result.role, "user");
+            assert!(result.disabled);
+        }
+        _ => panic!("Unexpected response"),
+    }
+    assert!(!check_session(&repo, 2, "jti_b"));
+
+    match update_user(
+        admin_key(),
+        repo.clone(),
+        3,
+        user_admin_in(Some("admin"), None),
+    ) {
+        Ok(ApiResponse { json, status: _ }) => assert_eq!(json.unwrap().0.role, "admin"),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_update_user_ko() {
+    let repo = common::test_repo();
+
+    match update_user(
+        admin_key(),
+        repo.clone(),
+        2,
+        user_admin_in(Some("root"), None),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+    match update_user(
+        admin_key(),
+        repo.clone(),
+        1,
+        user_admin_in(None, Some(true)),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+    match update_user(admin_key(), repo, 123, user_admin_in(None, Some(true))) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete_user() {
+    let repo = common::test_repo();
+
+    match delete_user(admin_key(), repo.clone(), 1) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+    match delete_user(admin_key(), repo.clone(), 2) {
+        Err(response) => assert_eq!(response.status().code, 409),
+        _ => panic!("Unexpected response"),
+    }
+    match delete_user(admin_key(), repo.clone(), 3) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+    match delete_user(admin_key(), repo.clone(), 3) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_categories() {
+    let repo = common::test_repo();
+
+    let id = match create_category(admin_key(), repo.clone(), category_in("cat_c")) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+
+            let result = json.unwrap().0;
+            assert!(result.shared);
+            result.id
+        }
+        _ => panic!("Unexpected response"),
+    };
+
+    match categories(admin_key(), repo.clone()) {
+        Ok(ApiResponse { json, status: _ }) => assert_eq!(
+            json.unwrap()
+                .0
+                .iter()
+                .map(|category| category.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["cat_a", "cat_b", "cat_c"]
+        ),
+        _ => panic!("Unexpected response"),
+    }
+
+    match update_category(admin_key(), repo.clone(), id, category_in("cat_d")) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(json.unwrap().0.name, "cat_d");
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match delete_category(admin_key(), repo.clone(), id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+    match delete_category(admin_key(), repo, id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_categories_of_users() {
+    let repo = common::test_repo();
+
+    let category = repo
+        .categories()
+        .create(&rnotes_core::models::db::category::NewCategory::new(
+            Some(2),
+            "cat_user_b",
+        ))
+        .unwrap();
+
+    match update_category(admin_key(), repo.clone(), category.id, category_in("cat_d")) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match delete_category(admin_key(), repo.clone(), category.id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    assert!(repo.categories().find_by_id(category.id).is_ok());
+
+    // Shared categories cannot be children of the categories of the users.
+    let mut child_in = category_in("cat_child");
+    child_in.parent_id = Some(category.id);
+    match create_category(admin_key(), repo.clone(), child_in) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    let mut parent_in = category_in("cat_a");
+    parent_in.parent_id = Some(category.id);
+    match update_category(admin_key(), repo.clone(), 1, parent_in) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+    assert_eq!(repo.categories().find_by_id(1).unwrap().parent_id, None);
+}
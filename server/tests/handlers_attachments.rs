@@ -0,0 +1,317 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::attachment::AttachmentOut;
+use rnotes_core::models::db::note_share::{NewNoteShare, Permission};
+use rnotes_core::repository::Repo;
+use rnotes_server::handlers::attachments::*;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::notes;
+use rnotes_server::handlers::ApiResponse;
+use rnotes_server::storage::Store;
+
+fn upload(file_name: &str, content_type: Option<&str>, data: &[u8]) -> Upload {
+    Upload {
+        file_name: file_name.to_string(),
+        content_type: content_type.map(|content_type| content_type.to_string()),
+        data: data.to_vec(),
+    }
+}
+
+fn attach(
+    repo: &Repo,
+    store: &Store,
+    user: &str,
+    id: i32,
+    file: Upload,
+) -> Result<AttachmentOut, u16> {
+    match create(
+        JWTKey::new(user.to_string()),
+        repo.clone(),
+        store.clone(),
+        id,
+        file,
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            Ok(json.unwrap().into_inner())
+        }
+        Err(response) => Err(response.status().code),
+    }
+}
+
+fn download(
+    repo: &Repo,
+    store: &Store,
+    user: &str,
+    id: i32,
+    attachment_id: i32,
+    range: Option<&str>,
+) -> Result<AttachmentFile, u16> {
+    get(
+        JWTKey::new(user.to_string()),
+        repo.clone(),
+        store.clone(),
+        id,
+        attachment_id,
+        ByteRange(range.map(|range| range.to_string())),
+    )
+    .map_err(|response| response.status().code)
+}
+
+#[test]
+fn test_create_and_all() {
+    let repo = common::test_repo();
+    let store = common::test_store();
+
+    let attachment = attach(
+        &repo,
+        &store,
+        "1",
+        1,
+        upload("C:\\docs\\file_a.txt", None, b"some_text"),
+    )
+    .unwrap();
+    assert_eq!(attachment.note_id, 1);
+    assert_eq!(attachment.file_name, "file_a.txt");
+    assert_eq!(attachment.content_type, "text/plain; charset=utf-8");
+    assert_eq!(attachment.size, 9);
+
+    let image = attach(
+        &repo,
+        &store,
+        "1",
+        1,
+        upload("image", Some("image/png"), b"\x89PNG"),
+    )
+    .unwrap();
+    assert_eq!(image.content_type, "image/png");
+
+    match all(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(json.unwrap().into_inner(), vec![attachment, image]);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match all(JWTKey::new("2".to_string()), repo.clone(), 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    assert_eq!(
+        attach(&repo, &store, "2", 1, upload("file.txt", None, b"text")).err(),
+        Some(404)
+    );
+    assert_eq!(
+        attach(&repo, &store, "1", 1, upload("", None, b"text")).err(),
+        Some(400)
+    );
+}
+
+#[test]
+fn test_quota() {
+    let repo = common::test_repo();
+    let store = common::test_store();
+
+    assert_eq!(
+        attach(&repo, &store, "1", 1, upload("big.bin", None, &[0u8; 17])).err(),
+        Some(413)
+    );
+
+    // The quota is shared by every note of the user.
+    attach(&repo, &store, "1", 1, upload("a.bin", None, &[0u8; 16])).unwrap();
+    attach(&repo, &store, "1", 2, upload("b.bin", None, &[0u8; 16])).unwrap();
+    assert_eq!(
+        attach(&repo, &store, "1", 3, upload("c.bin", None, &[0u8; 1])).err(),
+        Some(507)
+    );
+    attach(&repo, &store, "2", 4, upload("c.bin", None, &[0u8; 1])).unwrap();
+
+    match quota(JWTKey::new("1".to_string()), repo.clone(), store.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            let quota = json.unwrap().into_inner();
+            assert_eq!(quota.used, 32);
+            assert_eq!(quota.quota, 32);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_get() {
+    let repo = common::test_repo();
+    let store = common::test_store();
+
+    let attachment = attach(
+        &repo,
+        &store,
+        "1",
+        1,
+        upload("file.txt", None, b"0123456789"),
+    )
+    .unwrap();
+
+    let file = download(&repo, &store, "1", 1, attachment.id, None).unwrap();
+    assert_eq!(file.data, b"0123456789".to_vec());
+    assert_eq!(file.range, None);
+    assert_eq!(file.attachment.content_type, "text/plain; charset=utf-8");
+
+    for (range, expected, data) in &[
+        ("bytes=2-4", (2, 4), "234"),
+        ("bytes=7-", (7, 9), "789"),
+        ("bytes=-2", (8, 9), "89"),
+        ("bytes=5-100", (5, 9), "56789"),
+    ] {
+        let file = download(&repo, &store, "1", 1, attachment.id, Some(range)).unwrap();
+        assert_eq!(file.range, Some(*expected));
+        assert_eq!(file.data, data.as_bytes().to_vec());
+    }
+
+    // Other ranges than a single one of bytes are ignored.
+    for range in &["bytes=0-1,4-5", "items=0-1", "bytes=4-2"] {
+        let file = download(&repo, &store, "1", 1, attachment.id, Some(range)).unwrap();
+        assert_eq!(file.range, None);
+        assert_eq!(file.data.len(), 10);
+    }
+
+    for range in &["bytes=10-", "bytes=-0"] {
+        assert_eq!(
+            download(&repo, &store, "1", 1, attachment.id, Some(range)).err(),
+            Some(416)
+        );
+    }
+
+    assert_eq!(
+        download(&repo, &store, "1", 2, attachment.id, None).err(),
+        Some(404)
+    );
+    assert_eq!(
+        download(&repo, &store, "2", 1, attachment.id, None).err(),
+        Some(404)
+    );
+}
+
+#[test]
+fn test_shared() {
+    let repo = common::test_repo();
+    let store = common::test_store();
+
+    let attachment = attach(&repo, &store, "1", 1, upload("a.txt", None, b"text_a")).unwrap();
+
+    repo.shares()
+        .create(&NewNoteShare::new(1, 2, Permission::Read))
+        .unwrap();
+    assert_eq!(
+        download(&repo, &store, "2", 1, attachment.id, None)
+            .unwrap()
+            .data,
+        b"text_a".to_vec()
+    );
+    assert_eq!(
+        attach(&repo, &store, "2", 1, upload("b.txt", None, b"text_b")).err(),
+        Some(403)
+    );
+
+    // Attachments of shared notes count in the quota of their owner.
+    repo.shares()
+        .create(&NewNoteShare::new(1, 2, Permission::Write))
+        .unwrap();
+    attach(&repo, &store, "2", 1, upload("b.txt", None, b"text_b")).unwrap();
+    match quota(JWTKey::new("1".to_string()), repo.clone(), store.clone()) {
+        Ok(ApiResponse { json, .. }) => assert_eq!(json.unwrap().into_inner().used, 12),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete_and_sweep() {
+    let repo = common::test_repo();
+    let store = common::test_store();
+
+    let attachment_a = attach(&repo, &store, "1", 1, upload("a.txt", None, b"text_a")).unwrap();
+    let attachment_b = attach(&repo, &store, "1", 1, upload("b.txt", None, b"text_b")).unwrap();
+    let key_b = repo
+        .attachments()
+        .find_by_id_and_note_id(attachment_b.id, 1)
+        .unwrap()
+        .storage_key;
+
+    match delete(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        store.clone(),
+        1,
+        attachment_a.id,
+    ) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        store.clone(),
+        1,
+        attachment_a.id,
+    ) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    assert_eq!(
+        download(&repo, &store, "1", 1, attachment_a.id, None).err(),
+        Some(404)
+    );
+
+    // The content of the attachments of the deleted notes is removed by the
+    // sweep.
+    assert!(notes::delete(JWTKey::new("1".to_string()), repo.clone(), 1).is_ok());
+    repo.notes().delete(1).unwrap();
+    assert!(store.get(&key_b, None).is_ok());
+    assert_eq!(sweep(&repo, &store).unwrap(), 1);
+    assert!(store.get(&key_b, None).is_err());
+    assert_eq!(sweep(&repo, &store).unwrap(), 0);
+}
+
+#[test]
+fn test_read_upload() {
+    let body = "--boundary\r\n\
+                Content-Disposition: form-data; name=\"other\"\r\n\
+                \r\n\
+                other_value\r\n\
+                --boundary\r\n\
+                Content-Disposition: form-data; name=\"file\"; filename=\"file.txt\"\r\n\
+                Content-Type: text/plain\r\n\
+                \r\n\
+                some_text\r\n\
+                --boundary--\r\n";
+
+    match read_upload(body.as_bytes(), "boundary", 16) {
+        Ok(upload) => {
+            assert_eq!(upload.file_name, "file.txt");
+            assert_eq!(upload.content_type, Some("text/plain".to_string()));
+            assert_eq!(upload.data, b"some_text".to_vec());
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match read_upload(body.as_bytes(), "boundary", 4) {
+        Err(response) => assert_eq!(response.status().code, 413),
+        _ => panic!("Unexpected response"),
+    }
+
+    let body = "--boundary\r\n\
+                Content-Disposition: form-data; name=\"other\"\r\n\
+                \r\n\
+                other_value\r\n\
+                --boundary--\r\n";
+    match read_upload(body.as_bytes(), "boundary", 16) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+}
@@ -4,24 +4,26 @@ extern crate rnotes_server;
 extern crate rocket;
 extern crate rocket_contrib;
 
+mod common;
+
 use rnotes_core::models::api::auth::*;
-use rnotes_core::BDPool;
-use rnotes_server::handlers::auth::login;
+use rnotes_core::password;
+use rnotes_server::handlers::auth::{login, logout, refresh, register, RegistrationAccess};
+use rnotes_server::handlers::jwt::{check_session, read_token, JWTKey};
 use rnotes_server::handlers::ApiResponse;
-use std::panic;
 use rocket_contrib::json::Json;
+use std::panic;
 
 #[test]
 fn test_login_ok() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let login_in = LoginIn {
         email: "user_a@email.com".to_string(),
-        password: "1464ACD6765F91FCCD3F5BF4F14EBB7CA69F53AF91B0A5790C2BBA9D8819417B".to_string(),
+        password: "some_password".to_string(),
     };
 
-    match login(Json(login_in), con) {
+    match login(Json(login_in), repo) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 200);
             assert!(json.is_some() && !json.unwrap().jwt_token.is_empty());
@@ -30,20 +32,253 @@ fn test_login_ok() {
     }
 }
 
+#[test]
+fn test_login_role_and_disabled() {
+    let repo = common::test_repo();
+
+    let login_out = login_a(&repo);
+    assert_eq!(
+        read_token(&login_out.jwt_token).unwrap().role,
+        Some("user".to_string())
+    );
+
+    let mut user = repo.users().find_by_id(1).unwrap();
+    user.role = "admin".to_string();
+    repo.users().update(&user).unwrap();
+    let login_out = login_a(&repo);
+    assert_eq!(
+        read_token(&login_out.jwt_token).unwrap().role,
+        Some("admin".to_string())
+    );
+
+    user.disabled = true;
+    repo.users().update(&user).unwrap();
+    let login_in = LoginIn {
+        email: "user_a@email.com".to_string(),
+        password: "some_password".to_string(),
+    };
+    match login(Json(login_in), repo.clone()) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+    match refresh(refresh_in(&login_out.refresh_token), repo) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_login_rehash() {
+    let repo = common::test_repo();
+
+    let login_in = || {
+        Json(LoginIn {
+            email: "user_a@email.com".to_string(),
+            password: "some_password".to_string(),
+        })
+    };
+
+    assert!(login(login_in(), repo.clone()).is_ok());
+
+    let user = repo.users().find_by_id(1).unwrap();
+    assert!(user.password.starts_with("$argon2id$"));
+    assert!(!password::needs_rehash(&user.password));
+
+    assert!(login(login_in(), repo.clone()).is_ok());
+    assert_eq!(repo.users().find_by_id(1).unwrap().password, user.password);
+}
+
+#[test]
+fn test_login_legacy_hash_as_password() {
+    let repo = common::test_repo();
+
+    let login_in = LoginIn {
+        email: "user_a@email.com".to_string(),
+        password: common::PASSWORD.to_string(),
+    };
+
+    match login(Json(login_in), repo) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
 #[test]
 fn test_login_ko() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let login_in = LoginIn {
         email: "user_a@email.com".to_string(),
         password: "bad_password".to_string(),
     };
 
-    match login(Json(login_in), con) {
+    match login(Json(login_in), repo.clone()) {
         Err(response) => {
             assert_eq!(response.status().code, 404);
         }
         _ => panic!("Unexpected response"),
     }
+
+    let login_in = LoginIn {
+        email: "unknown@email.com".to_string(),
+        password: "some_password".to_string(),
+    };
+
+    match login(Json(login_in), repo) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+fn register_in(email: &str, name: &str, password: &str) -> Json<RegisterIn> {
+    Json(RegisterIn {
+        email: email.to_string(),
+        name: name.to_string(),
+        password: password.to_string(),
+    })
+}
+
+fn allowed() -> RegistrationAccess {
+    RegistrationAccess { allowed: true }
+}
+
+#[test]
+fn test_register() {
+    let repo = common::test_repo();
+
+    match register(
+        register_in("user_d@email.com", "user_d", "other_password"),
+        repo.clone(),
+        allowed(),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+
+            let result = json.unwrap().0;
+            assert_eq!(result.email, "user_d@email.com");
+            assert_eq!(result.name, "user_d");
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    let login_in = LoginIn {
+        email: "user_d@email.com".to_string(),
+        password: "other_password".to_string(),
+    };
+    assert!(login(Json(login_in), repo).is_ok());
+}
+
+#[test]
+fn test_register_ko() {
+    let repo = common::test_repo();
+
+    for (email, name, status) in &[
+        ("user_a@email.com", "user_d", 409),
+        ("user_d@email.com", "user_a", 409),
+        ("user_d", "user_d", 400),
+    ] {
+        match register(
+            register_in(email, name, "other_password"),
+            repo.clone(),
+            allowed(),
+        ) {
+            Err(response) => assert_eq!(response.status().code, *status),
+            _ => panic!("Unexpected response"),
+        }
+    }
+
+    match register(
+        register_in("user_d@email.com", "user_d", "short"),
+        repo.clone(),
+        allowed(),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    match register(
+        register_in("user_d@email.com", "user_d", "other_password"),
+        repo,
+        RegistrationAccess { allowed: false },
+    ) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+fn login_a(repo: &rnotes_core::repository::Repo) -> LoginOut {
+    let login_in = LoginIn {
+        email: "user_a@email.com".to_string(),
+        password: "some_password".to_string(),
+    };
+
+    login(Json(login_in), repo.clone())
+        .ok()
+        .and_then(|response| response.json)
+        .unwrap()
+        .0
+}
+
+fn refresh_in(refresh_token: &str) -> Json<RefreshIn> {
+    Json(RefreshIn {
+        refresh_token: refresh_token.to_string(),
+    })
+}
+
+#[test]
+fn test_refresh() {
+    let repo = common::test_repo();
+
+    let login_out = login_a(&repo);
+    let jti = read_token(&login_out.jwt_token).unwrap().jti.unwrap();
+    assert!(!login_out.refresh_token.is_empty());
+    assert!(check_session(&repo, 1, &jti));
+    assert!(!check_session(&repo, 2, &jti));
+
+    let refreshed = match refresh(refresh_in(&login_out.refresh_token), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            json.unwrap().0
+        }
+        _ => panic!("Unexpected response"),
+    };
+    let other_jti = read_token(&refreshed.jwt_token).unwrap().jti.unwrap();
+    assert_ne!(refreshed.refresh_token, login_out.refresh_token);
+    assert!(!check_session(&repo, 1, &jti));
+    assert!(check_session(&repo, 1, &other_jti));
+
+    match refresh(refresh_in(&login_out.refresh_token), repo.clone()) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+    match refresh(refresh_in("unknown"), repo) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_logout() {
+    let repo = common::test_repo();
+
+    let login_out = login_a(&repo);
+    let jti = read_token(&login_out.jwt_token).unwrap().jti.unwrap();
+
+    match logout(
+        JWTKey::new("1".to_string()).with_jti(jti.clone()),
+        repo.clone(),
+    ) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(!check_session(&repo, 1, &jti));
+    match refresh(refresh_in(&login_out.refresh_token), repo.clone()) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+    match logout(JWTKey::new("1".to_string()), repo) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
 }
@@ -4,34 +4,41 @@ extern crate rnotes_server;
 extern crate rocket;
 extern crate rocket_contrib;
 
-use rnotes_core::models::api::category::CategoryOut;
-use rnotes_core::BDPool;
+mod common;
+
+use rnotes_core::models::api::category::{CategoryIn, CategoryOut};
+use rnotes_core::models::api::note::NoteIn;
 use rnotes_server::handlers::categories::*;
 use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::notes;
 use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
 use std::panic;
 
 #[test]
 fn test_all() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let expected: Vec<CategoryOut> = vec![
         CategoryOut {
             id: 1,
             name: "cat_a".to_string(),
+            parent_id: None,
+            shared: true,
             create_time: "".to_string(),
             update_time: "".to_string(),
         },
         CategoryOut {
             id: 2,
             name: "cat_b".to_string(),
+            parent_id: None,
+            shared: true,
             create_time: "".to_string(),
             update_time: "".to_string(),
         },
     ];
 
-    match all(JWTKey::new("1".to_string()), con) {
+    match all(JWTKey::new("1".to_string()), repo) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 200);
 
@@ -44,17 +51,18 @@ fn test_all() {
 
 #[test]
 fn test_get_ok() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let expected = CategoryOut {
         id: 1,
         name: "cat_a".to_string(),
+        parent_id: None,
+        shared: true,
         create_time: "".to_string(),
         update_time: "".to_string(),
     };
 
-    match get(JWTKey::new("1".to_string()), con, 1) {
+    match get(JWTKey::new("1".to_string()), repo, 1) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 200);
 
@@ -67,13 +75,314 @@ fn test_get_ok() {
 
 #[test]
 fn test_get_ko() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
-    match get(JWTKey::new("1".to_string()), con, 999) {
+    match get(JWTKey::new("1".to_string()), repo, 999) {
         Err(response) => {
             assert_eq!(response.status().code, 404);
         }
         _ => panic!("Unexpected response"),
     }
 }
+
+fn category_in(name: &str) -> Json<CategoryIn> {
+    Json(CategoryIn {
+        name: name.to_string(),
+        parent_id: None,
+    })
+}
+
+fn child_in(name: &str, parent_id: i32) -> Json<CategoryIn> {
+    Json(CategoryIn {
+        name: name.to_string(),
+        parent_id: Some(parent_id),
+    })
+}
+
+#[test]
+fn test_create() {
+    let repo = common::test_repo();
+
+    let id = match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        category_in("cat_c"),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+
+            let result = json.unwrap().0;
+            assert_eq!(result.name, "cat_c");
+            assert_eq!(result.shared, false);
+            result.id
+        }
+        _ => panic!("Unexpected response"),
+    };
+
+    match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        category_in("cat_c"),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 409),
+        _ => panic!("Unexpected response"),
+    }
+    match create(JWTKey::new("1".to_string()), repo.clone(), category_in(" ")) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(create(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        category_in("cat_c")
+    )
+    .is_ok());
+
+    match get(JWTKey::new("2".to_string()), repo.clone(), id) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match all(JWTKey::new("1".to_string()), repo) {
+        Ok(ApiResponse { json, status: _ }) => assert_eq!(json.unwrap().0.len(), 3),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_update() {
+    let repo = common::test_repo();
+
+    let id = create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        category_in("cat_c"),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        id,
+        category_in("cat_d"),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            assert_eq!(json.unwrap().0.name, "cat_d");
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match update(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        id,
+        category_in("cat_e"),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match update(JWTKey::new("1".to_string()), repo, 1, category_in("cat_e")) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete() {
+    let repo = common::test_repo();
+
+    let id = create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        category_in("cat_c"),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+    let note = notes::create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        Json(NoteIn {
+            category_id: Some(id),
+            title: "some_title".to_string(),
+            data: "some_data".to_string(),
+            tags: None,
+            format: None,
+            key_check: None,
+        }),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0;
+
+    match delete(JWTKey::new("2".to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(JWTKey::new("1".to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+
+    match notes::get(JWTKey::new("1".to_string()), repo, note.id) {
+        Ok(ApiResponse { json, status: _ }) => assert_eq!(json.unwrap().0.category_id, None),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_note_with_other_user_category() {
+    let repo = common::test_repo();
+
+    let id = create(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        category_in("cat_c"),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+
+    match notes::create(
+        JWTKey::new("1".to_string()),
+        repo,
+        Json(NoteIn {
+            category_id: Some(id),
+            title: "some_title".to_string(),
+            data: "some_data".to_string(),
+            tags: None,
+            format: None,
+            key_check: None,
+        }),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_tree() {
+    let repo = common::test_repo();
+
+    let id = create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        child_in("cat_c", 2),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+    create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        child_in("cat_d", id),
+    )
+    .ok()
+    .unwrap();
+
+    match tree(JWTKey::new("1".to_string()), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result
+                    .iter()
+                    .map(|node| node.category.name.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["cat_a", "cat_b"]
+            );
+            assert_eq!(result[1].children[0].category.id, id);
+            assert_eq!(result[1].children[0].children[0].category.name, "cat_d");
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match tree(JWTKey::new("2".to_string()), repo) {
+        Ok(ApiResponse { json, status: _ }) => {
+            assert!(json.unwrap().0.iter().all(|node| node.children.is_empty()))
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_update_parent() {
+    let repo = common::test_repo();
+
+    let parent = create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        category_in("cat_c"),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+    let child = create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        child_in("cat_d", parent),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        parent,
+        child_in("cat_c", child),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    let other = create(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        category_in("cat_e"),
+    )
+    .ok()
+    .and_then(|response| response.json)
+    .unwrap()
+    .0
+    .id;
+    match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        child_in("cat_f", other),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    match update(
+        JWTKey::new("1".to_string()),
+        repo,
+        child,
+        category_in("cat_d"),
+    ) {
+        Ok(ApiResponse { json, status: _ }) => assert_eq!(json.unwrap().0.parent_id, None),
+        _ => panic!("Unexpected response"),
+    }
+}
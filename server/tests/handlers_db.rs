@@ -0,0 +1,138 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+// The other suites run the handlers on the in-memory repository, these ones
+// run them on the database to cover the transactions and queries of
+// `DBRepository`.
+
+use rnotes_core::models::api::category::CategoryIn;
+use rnotes_core::models::api::note::{NoteIn, NoteOut};
+use rnotes_core::repository::Repo;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::ApiResponse;
+use rnotes_server::handlers::{categories, notes, trash};
+use rocket_contrib::json::Json;
+
+fn note_in(title: &str, data: &str, tags: Option<Vec<String>>) -> Json<NoteIn> {
+    Json(NoteIn {
+        category_id: Some(1),
+        title: title.to_string(),
+        data: data.to_string(),
+        tags: tags,
+        format: None,
+        key_check: None,
+    })
+}
+
+fn category_in(name: &str, parent_id: Option<i32>) -> Json<CategoryIn> {
+    Json(CategoryIn {
+        name: name.to_string(),
+        parent_id: parent_id,
+    })
+}
+
+fn note_of(repo: &Repo, id: i32) -> Option<NoteOut> {
+    match notes::get(JWTKey::new("1".to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json, .. }) => Some(json.unwrap().into_inner()),
+        Err(_) => None,
+    }
+}
+
+#[test]
+fn test_note_lifecycle() {
+    let repo = common::db_repo();
+
+    let id = match notes::create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        note_in(
+            "note_test_db_lifecycle",
+            "See [[note_a_user_a]]",
+            Some(vec!["Tag_DB".to_string()]),
+        ),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            json.unwrap().into_inner().id
+        }
+        _ => panic!("Unexpected response"),
+    };
+
+    // The update keeps the tags and adds a revision.
+    assert!(notes::update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        id,
+        note_in("note_test_db_lifecycle", "some_text_b", None),
+    )
+    .is_ok());
+    let note = note_of(&repo, id).unwrap();
+    assert_eq!(note.data, "some_text_b");
+    assert_eq!(note.tags, vec!["tag_db".to_string()]);
+    assert_eq!(repo.revisions().find_by_note_id(id).unwrap().len(), 1);
+
+    // Others cannot update it.
+    assert!(notes::update(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        id,
+        note_in("note_test_db_lifecycle", "some_text_c", None),
+    )
+    .is_err());
+
+    assert_eq!(
+        notes::delete(JWTKey::new("1".to_string()), repo.clone(), id)
+            .unwrap()
+            .status
+            .code,
+        200
+    );
+    assert!(note_of(&repo, id).is_none());
+    assert_eq!(
+        trash::delete(JWTKey::new("1".to_string()), repo.clone(), id)
+            .unwrap()
+            .status
+            .code,
+        200
+    );
+    assert!(repo.revisions().find_by_note_id(id).unwrap().is_empty());
+}
+
+#[test]
+fn test_category_cycle() {
+    let repo = common::db_repo();
+
+    let create = |name: &str, parent_id: Option<i32>| {
+        categories::create(
+            JWTKey::new("1".to_string()),
+            repo.clone(),
+            category_in(name, parent_id),
+        )
+        .unwrap()
+        .json
+        .unwrap()
+        .into_inner()
+        .id
+    };
+    let parent = create("cat_test_db_parent", None);
+    let child = create("cat_test_db_child", Some(parent));
+
+    match categories::update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        parent,
+        category_in("cat_test_db_parent", Some(child)),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    for id in &[child, parent] {
+        assert!(categories::delete(JWTKey::new("1".to_string()), repo.clone(), *id).is_ok());
+    }
+}
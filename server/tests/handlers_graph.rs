@@ -0,0 +1,209 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::graph::GraphOut;
+use rnotes_core::models::db::category::NewCategory;
+use rnotes_core::models::db::note::NewNote;
+use rnotes_core::repository::Repo;
+use rnotes_server::handlers::graph::{get, GraphExport};
+use rnotes_server::handlers::jwt::JWTKey;
+
+fn graph_of(
+    repo: &Repo,
+    user: &str,
+    format: Option<&str>,
+    category_id: Option<i32>,
+    descendants: Option<bool>,
+) -> Result<GraphExport, u16> {
+    get(
+        JWTKey::new(user.to_string()),
+        repo.clone(),
+        format.map(|format| format.to_string()),
+        category_id,
+        descendants,
+    )
+    .map_err(|response| response.status().code)
+}
+
+fn json_graph_of(
+    repo: &Repo,
+    user: &str,
+    category_id: Option<i32>,
+    descendants: Option<bool>,
+) -> GraphOut {
+    match graph_of(repo, user, None, category_id, descendants) {
+        Ok(GraphExport::Json(graph)) => graph,
+        other => panic!("Unexpected graph: {:?}", other),
+    }
+}
+
+fn node_ids(graph: &GraphOut) -> Vec<&str> {
+    graph.nodes.iter().map(|node| node.id.as_str()).collect()
+}
+
+fn edges(graph: &GraphOut) -> Vec<(&str, &str, &str)> {
+    graph
+        .edges
+        .iter()
+        .map(|edge| {
+            (
+                edge.source.as_str(),
+                edge.target.as_str(),
+                edge.kind.as_str(),
+            )
+        })
+        .collect()
+}
+
+// Creates a note of user_a linking to note_a_user_a and to note_a_user_b,
+// which is not in the graph of user_a.
+fn create_linking(repo: &Repo) -> i32 {
+    repo.notes()
+        .create(
+            &NewNote::new(
+                1,
+                None,
+                "note_links_user_a".to_string(),
+                "See [[note_a_user_a]] and [[#4]]".to_string(),
+            ),
+            &[],
+        )
+        .unwrap()
+        .id
+}
+
+#[test]
+fn test_get_json() {
+    let repo = common::test_repo();
+    let id = create_linking(&repo);
+
+    let graph = json_graph_of(&repo, "1", None, None);
+    let source = format!("note:{}", id);
+    assert_eq!(
+        node_ids(&graph),
+        vec![
+            "category:1",
+            "category:2",
+            "note:1",
+            "note:2",
+            "note:3",
+            source.as_str(),
+        ]
+    );
+    assert_eq!(
+        edges(&graph),
+        vec![
+            ("note:2", "category:1", "category"),
+            ("note:3", "category:2", "category"),
+            (source.as_str(), "note:1", "reference"),
+        ]
+    );
+
+    // Notes in the trash are left out with their links.
+    repo.notes().trash(1).unwrap();
+    let graph = json_graph_of(&repo, "1", None, None);
+    assert_eq!(graph.nodes.len(), 5);
+    assert_eq!(graph.edges.len(), 2);
+
+    let graph = json_graph_of(&repo, "2", None, None);
+    assert_eq!(node_ids(&graph), vec!["category:1", "category:2", "note:4"]);
+    assert!(graph.edges.is_empty());
+}
+
+#[test]
+fn test_get_dot() {
+    let repo = common::test_repo();
+
+    match graph_of(&repo, "1", Some("dot"), None, None) {
+        Ok(GraphExport::Dot(dot)) => {
+            assert!(dot.starts_with("digraph notes {\n"));
+            assert!(dot.contains("  \"note:1\" [label=\"note_a_user_a\", shape=ellipse];\n"));
+            assert!(dot
+                .contains("  \"note:2\" -> \"category:1\" [label=\"category\", style=dashed];\n"));
+        }
+        other => panic!("Unexpected graph: {:?}", other),
+    }
+}
+
+#[test]
+fn test_get_graphml() {
+    let repo = common::test_repo();
+
+    match graph_of(&repo, "1", Some("graphml"), None, None) {
+        Ok(GraphExport::GraphMl(graphml)) => {
+            assert!(graphml.contains("<graph id=\"notes\" edgedefault=\"directed\">"));
+            assert!(graphml.contains(
+                "<node id=\"category:1\"><data key=\"kind\">category</data><data key=\"label\">cat_a</data></node>"
+            ));
+            assert!(graphml.contains("source=\"note:3\" target=\"category:2\""));
+        }
+        other => panic!("Unexpected graph: {:?}", other),
+    }
+}
+
+#[test]
+fn test_get_invalid() {
+    let repo = common::test_repo();
+
+    assert_eq!(
+        graph_of(&repo, "1", Some("svg"), None, None).err(),
+        Some(400)
+    );
+    assert_eq!(graph_of(&repo, "1", None, Some(99), None).err(), Some(404));
+
+    let category = repo
+        .categories()
+        .create(&NewCategory::new(Some(2), "cat_user_b"))
+        .unwrap();
+    assert_eq!(
+        graph_of(&repo, "1", None, Some(category.id), None).err(),
+        Some(404)
+    );
+}
+
+#[test]
+fn test_get_category() {
+    let repo = common::test_repo();
+    let child = repo
+        .categories()
+        .create(&NewCategory::new(Some(1), "cat_child").with_parent(Some(1)))
+        .unwrap();
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                Some(child.id),
+                "note_child".to_string(),
+                "Up to [[note_b_user_a]]".to_string(),
+            ),
+            &[],
+        )
+        .unwrap();
+
+    let graph = json_graph_of(&repo, "1", Some(1), None);
+    assert_eq!(node_ids(&graph), vec!["category:1", "note:2"]);
+    assert_eq!(edges(&graph), vec![("note:2", "category:1", "category")]);
+
+    let graph = json_graph_of(&repo, "1", Some(1), Some(true));
+    let child_id = format!("category:{}", child.id);
+    let note_id = format!("note:{}", note.id);
+    assert_eq!(
+        node_ids(&graph),
+        vec!["category:1", child_id.as_str(), "note:2", note_id.as_str()]
+    );
+    assert_eq!(
+        edges(&graph),
+        vec![
+            (child_id.as_str(), "category:1", "parent"),
+            ("note:2", "category:1", "category"),
+            (note_id.as_str(), child_id.as_str(), "category"),
+            (note_id.as_str(), "note:2", "reference"),
+        ]
+    );
+}
@@ -0,0 +1,148 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use chrono::Duration;
+use rnotes_core::models::api::api_key::*;
+use rnotes_core::models::db::api_key::NewApiKey;
+use rnotes_core::repository::Repo;
+use rnotes_core::token;
+use rnotes_core::utils::now;
+use rnotes_server::handlers::jwt::{check_api_key, JWTKey};
+use rnotes_server::handlers::keys::{all, create, delete};
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+use std::panic;
+
+fn login_key(id_user: &str) -> JWTKey {
+    JWTKey::new(id_user.to_string()).with_jti("some_jti".to_string())
+}
+
+fn api_key_in(name: &str, read_only: bool) -> Json<ApiKeyIn> {
+    Json(ApiKeyIn {
+        name: name.to_string(),
+        read_only: read_only,
+        expires_in_days: None,
+    })
+}
+
+fn create_a(repo: &Repo) -> ApiKeyOut {
+    match create(login_key("1"), repo.clone(), api_key_in("key_a", true)) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            json.unwrap().into_inner()
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_create() {
+    let repo = common::test_repo();
+
+    let api_key_out = create_a(&repo);
+    let secret = api_key_out.key.clone().unwrap();
+
+    assert!(secret.starts_with("rn_"));
+    assert!(secret.starts_with(&api_key_out.prefix));
+    assert!(api_key_out.read_only);
+
+    let api_key = check_api_key(&repo, &secret).unwrap();
+    assert_eq!(api_key.id, api_key_out.id);
+    assert_eq!(api_key.user_id, 1);
+    assert_ne!(api_key.key_hash, secret);
+}
+
+#[test]
+fn test_create_ko() {
+    let repo = common::test_repo();
+
+    match create(login_key("1"), repo.clone(), api_key_in(" ", false)) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+    for expires_in_days in &[0, -1, 3651, 1_000_000_000_000, i64::MAX] {
+        match create(
+            login_key("1"),
+            repo.clone(),
+            Json(ApiKeyIn {
+                name: "key_a".to_string(),
+                read_only: false,
+                expires_in_days: Some(*expires_in_days),
+            }),
+        ) {
+            Err(response) => assert_eq!(response.status().code, 400),
+            _ => panic!("Unexpected response"),
+        }
+    }
+    match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        api_key_in("key_a", false),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_all() {
+    let repo = common::test_repo();
+
+    let api_key_out = create_a(&repo);
+
+    match all(login_key("1"), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            let api_keys = json.unwrap().into_inner();
+            assert_eq!(api_keys.len(), 1);
+            assert_eq!(api_keys[0].id, api_key_out.id);
+            assert_eq!(api_keys[0].key, None);
+        }
+        _ => panic!("Unexpected response"),
+    }
+    match all(login_key("2"), repo) {
+        Ok(ApiResponse { json, status: _ }) => assert!(json.unwrap().is_empty()),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete() {
+    let repo = common::test_repo();
+
+    let api_key_out = create_a(&repo);
+    let secret = api_key_out.key.unwrap();
+
+    match delete(login_key("2"), repo.clone(), api_key_out.id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    assert!(check_api_key(&repo, &secret).is_some());
+
+    match delete(login_key("1"), repo.clone(), api_key_out.id) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+    assert!(check_api_key(&repo, &secret).is_none());
+}
+
+#[test]
+fn test_check_api_key_expired() {
+    let repo = common::test_repo();
+
+    let secret = format!("rn_{}", token::generate());
+    repo.api_keys()
+        .create(
+            &NewApiKey::new(1, "key_a", &secret[..10], &token::hash(&secret))
+                .with_expire_time(Some(now() - Duration::days(1))),
+        )
+        .unwrap();
+
+    assert!(check_api_key(&repo, &secret).is_none());
+    assert!(check_api_key(&repo, "unknown").is_none());
+}
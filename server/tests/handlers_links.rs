@@ -0,0 +1,201 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::note::NoteIn;
+use rnotes_core::models::api::note_link::*;
+use rnotes_core::models::db::note::NewNote;
+use rnotes_core::models::db::note_share::{NewNoteShare, Permission};
+use rnotes_core::repository::Repo;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::links::{all, backlinks};
+use rnotes_server::handlers::notes;
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+
+// Creates a note of user_a linking to note_a_user_a by title and to
+// note_b_user_a and note_a_user_b by id.
+fn create_linking(repo: &Repo) -> i32 {
+    repo.notes()
+        .create(
+            &NewNote::new(
+                1,
+                None,
+                "note_links_user_a".to_string(),
+                "See [[note_a_user_a]], [[#2]], [[#4]] and [[missing]]".to_string(),
+            ),
+            &[],
+        )
+        .unwrap()
+        .id
+}
+
+fn links_of(repo: &Repo, user: &str, id: i32) -> Result<Vec<NoteLinkOut>, u16> {
+    match all(JWTKey::new(user.to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            Ok(json.unwrap().into_inner())
+        }
+        Err(response) => Err(response.status().code),
+    }
+}
+
+fn backlinks_of(repo: &Repo, user: &str, id: i32) -> Result<Vec<BacklinkOut>, u16> {
+    match backlinks(JWTKey::new(user.to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            Ok(json.unwrap().into_inner())
+        }
+        Err(response) => Err(response.status().code),
+    }
+}
+
+fn link(target_id: Option<i32>, title: &str, broken: bool) -> NoteLinkOut {
+    NoteLinkOut {
+        target_id: target_id,
+        title: title.to_string(),
+        broken: broken,
+    }
+}
+
+#[test]
+fn test_all() {
+    let repo = common::test_repo();
+    let id = create_linking(&repo);
+
+    assert_eq!(
+        links_of(&repo, "1", id),
+        Ok(vec![
+            link(Some(1), "note_a_user_a", false),
+            link(Some(2), "note_b_user_a", false),
+            link(Some(4), "#4", true),
+            link(None, "missing", true),
+        ])
+    );
+
+    // Links to notes in the trash are broken.
+    repo.notes().trash(2).unwrap();
+    assert_eq!(
+        links_of(&repo, "1", id).unwrap()[1],
+        link(Some(2), "#2", true)
+    );
+
+    assert_eq!(links_of(&repo, "2", id), Err(404));
+}
+
+#[test]
+fn test_all_broken_and_resolved() {
+    let repo = common::test_repo();
+    let id = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                None,
+                "note_titles_user_a".to_string(),
+                "[[  NOTE_A_user_a ]] [[Note_B_User_A]] [[note_a_user_b]] [[Missing  Note]] [[#99]]"
+                    .to_string(),
+            ),
+            &[],
+        )
+        .unwrap()
+        .id;
+
+    // The titles are matched whatever their case and spaces, the broken links
+    // keep them as written.
+    assert_eq!(
+        links_of(&repo, "1", id),
+        Ok(vec![
+            link(Some(1), "note_a_user_a", false),
+            link(Some(2), "note_b_user_a", false),
+            link(None, "note_a_user_b", true),
+            link(None, "Missing  Note", true),
+            link(Some(99), "#99", true),
+        ])
+    );
+    assert_eq!(backlinks_of(&repo, "1", 1).unwrap()[0].note_id, id);
+
+    repo.notes().trash(1).unwrap();
+    assert_eq!(
+        links_of(&repo, "1", id).unwrap()[0],
+        link(None, "NOTE_A_user_a", true)
+    );
+}
+
+#[test]
+fn test_all_after_update() {
+    let repo = common::test_repo();
+    let id = create_linking(&repo);
+
+    let note_in = NoteIn {
+        category_id: None,
+        title: "note_links_user_a".to_string(),
+        data: "Only [[note_c_user_c]]".to_string(),
+        tags: None,
+        format: None,
+        key_check: None,
+    };
+    assert!(notes::update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        id,
+        Json(note_in)
+    )
+    .is_ok());
+
+    assert_eq!(
+        links_of(&repo, "1", id),
+        Ok(vec![link(Some(3), "note_c_user_c", false)])
+    );
+}
+
+#[test]
+fn test_backlinks() {
+    let repo = common::test_repo();
+    let id = create_linking(&repo);
+
+    let expected = vec![BacklinkOut {
+        note_id: id,
+        title: "note_links_user_a".to_string(),
+    }];
+    assert_eq!(backlinks_of(&repo, "1", 1), Ok(expected));
+    assert_eq!(backlinks_of(&repo, "1", 2).unwrap().len(), 1);
+    assert!(backlinks_of(&repo, "1", 3).unwrap().is_empty());
+
+    // Notes of other users never link to the note.
+    assert!(backlinks_of(&repo, "2", 4).unwrap().is_empty());
+    assert_eq!(backlinks_of(&repo, "2", 1), Err(404));
+
+    repo.notes().trash(id).unwrap();
+    assert!(backlinks_of(&repo, "1", 1).unwrap().is_empty());
+}
+
+#[test]
+fn test_shared() {
+    let repo = common::test_repo();
+    let id = create_linking(&repo);
+
+    repo.shares()
+        .create(&NewNoteShare::new(id, 2, Permission::Read))
+        .unwrap();
+    repo.shares()
+        .create(&NewNoteShare::new(1, 2, Permission::Read))
+        .unwrap();
+
+    // Only the notes also shared with the user can be opened from the links.
+    assert_eq!(
+        links_of(&repo, "2", id),
+        Ok(vec![
+            link(Some(1), "note_a_user_a", false),
+            link(Some(2), "#2", true),
+            link(Some(4), "#4", true),
+            link(None, "missing", true),
+        ])
+    );
+    assert_eq!(backlinks_of(&repo, "2", 1).unwrap().len(), 1);
+    assert_eq!(backlinks_of(&repo, "2", 2), Err(404));
+}
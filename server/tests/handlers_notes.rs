@@ -4,8 +4,11 @@ extern crate rnotes_server;
 extern crate rocket;
 extern crate rocket_contrib;
 
+mod common;
+
 use rnotes_core::models::api::note::{NoteIn, NoteOut};
-use rnotes_core::BDPool;
+use rnotes_core::models::db::category::NewCategory;
+use rnotes_core::models::db::note::{NewNote, NoteFormat};
 use rnotes_server::handlers::jwt::JWTKey;
 use rnotes_server::handlers::notes::*;
 use rnotes_server::handlers::ApiResponse;
@@ -14,8 +17,7 @@ use std::panic;
 
 #[test]
 fn test_all() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let expected: Vec<NoteOut> = vec![
         NoteOut {
@@ -23,53 +25,243 @@ fn test_all() {
             category_id: None,
             title: "note_a_user_a".to_string(),
             data: "some_text_note_a_user_a".to_string(),
+            tags: vec![],
             create_time: "".to_string(),
             update_time: "".to_string(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         NoteOut {
             id: 2,
             category_id: Some(1),
             title: "note_b_user_a".to_string(),
             data: "some_text_note_a_user_a".to_string(),
+            tags: vec![],
             create_time: "".to_string(),
             update_time: "".to_string(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
         NoteOut {
             id: 3,
             category_id: Some(2),
             title: "note_c_user_c".to_string(),
             data: "some_text_note_c_user_c".to_string(),
+            tags: vec![],
             create_time: "".to_string(),
             update_time: "".to_string(),
+            deleted_time: None,
+            format: "plain".to_string(),
+            key_check: None,
         },
     ];
 
-    match all(JWTKey::new("1".to_string()), con) {
+    match all(JWTKey::new("1".to_string()), repo, NotesQuery::default()) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 200);
 
             let result = json.unwrap();
-            assert_eq!(result.0, expected);
+            assert_eq!(result.0.items, expected);
+            assert_eq!(result.0.next_cursor, None);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_all_pages() {
+    let repo = common::test_repo();
+
+    let mut query = NotesQuery {
+        sort: Some("title".to_string()),
+        order: Some("desc".to_string()),
+        limit: Some("2".to_string()),
+        ..Default::default()
+    };
+
+    let next_cursor = match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result
+                    .items
+                    .iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<i32>>(),
+                vec![3, 2]
+            );
+            result.next_cursor.unwrap()
+        }
+        _ => panic!("Unexpected response"),
+    };
+
+    query = NotesQuery {
+        sort: Some("title".to_string()),
+        order: Some("desc".to_string()),
+        limit: Some("2".to_string()),
+        cursor: Some(next_cursor),
+        ..Default::default()
+    };
+    match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result
+                    .items
+                    .iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<i32>>(),
+                vec![1]
+            );
+            assert_eq!(result.next_cursor, None);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_all_filters() {
+    let repo = common::test_repo();
+
+    let query = NotesQuery {
+        category_id: Some("2".to_string()),
+        updated_since: Some("2000-01-01T00:00:00+00:00".to_string()),
+        ..Default::default()
+    };
+
+    match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result
+                    .items
+                    .iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<i32>>(),
+                vec![3]
+            );
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    let query = NotesQuery {
+        updated_since: Some("3000-01-01T00:00:00Z".to_string()),
+        ..Default::default()
+    };
+
+    match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert!(json.unwrap().0.items.is_empty());
         }
         _ => panic!("Unexpected response"),
     }
 }
 
+#[test]
+fn test_all_descendants() {
+    let repo = common::test_repo();
+
+    let child = repo
+        .categories()
+        .create(&NewCategory::new(Some(1), "cat_c").with_parent(Some(2)))
+        .unwrap();
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(1, Some(child.id), "note_d".to_string(), "".to_string()),
+            &[],
+        )
+        .unwrap();
+
+    let query = NotesQuery {
+        category_id: Some("2".to_string()),
+        descendants: Some("true".to_string()),
+        ..Default::default()
+    };
+
+    match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result
+                    .items
+                    .iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<i32>>(),
+                vec![3, note.id]
+            );
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    let query = NotesQuery {
+        category_id: Some("2".to_string()),
+        descendants: Some("yes".to_string()),
+        ..Default::default()
+    };
+
+    match all(JWTKey::new("1".to_string()), repo, query) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_all_ko() {
+    let repo = common::test_repo();
+
+    for query in vec![
+        NotesQuery {
+            sort: Some("other".to_string()),
+            ..Default::default()
+        },
+        NotesQuery {
+            limit: Some("0".to_string()),
+            ..Default::default()
+        },
+        NotesQuery {
+            cursor: Some("bad_cursor".to_string()),
+            ..Default::default()
+        },
+    ] {
+        match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+            Err(response) => {
+                assert_eq!(response.status().code, 400);
+            }
+            _ => panic!("Unexpected response"),
+        }
+    }
+}
+
 #[test]
 fn test_get_ok() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let expected = NoteOut {
         id: 1,
         category_id: None,
         title: "note_a_user_a".to_string(),
         data: "some_text_note_a_user_a".to_string(),
+        tags: vec![],
         create_time: "".to_string(),
         update_time: "".to_string(),
+        deleted_time: None,
+        format: "plain".to_string(),
+        key_check: None,
     };
 
-    match get(JWTKey::new("1".to_string()), con, 1) {
+    match get(JWTKey::new("1".to_string()), repo, 1) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 200);
 
@@ -82,10 +274,9 @@ fn test_get_ok() {
 
 #[test]
 fn test_get_ko_note_id() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
-    match get(JWTKey::new("1".to_string()), con, 999) {
+    match get(JWTKey::new("1".to_string()), repo, 999) {
         Err(response) => {
             assert_eq!(response.status().code, 404);
         }
@@ -95,10 +286,9 @@ fn test_get_ko_note_id() {
 
 #[test]
 fn test_get_ko_user_id() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
-    match get(JWTKey::new("999".to_string()), con, 1) {
+    match get(JWTKey::new("999".to_string()), repo, 1) {
         Err(response) => {
             assert_eq!(response.status().code, 404);
         }
@@ -108,13 +298,15 @@ fn test_get_ko_user_id() {
 
 #[test]
 fn test_create() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let input = NoteIn {
         category_id: Some(1),
         title: "new_note".to_string(),
         data: "some_text_new_note".to_string(),
+        tags: Some(vec!["Work".to_string(), "home".to_string()]),
+        format: None,
+        key_check: None,
     };
 
     let mut expected = NoteOut {
@@ -122,11 +314,15 @@ fn test_create() {
         category_id: Some(1),
         title: "new_note".to_string(),
         data: "some_text_new_note".to_string(),
+        tags: vec!["home".to_string(), "work".to_string()],
         create_time: "".to_string(),
         update_time: "".to_string(),
+        deleted_time: None,
+        format: "plain".to_string(),
+        key_check: None,
     };
 
-    match create(JWTKey::new("1".to_string()), con, Json(input)) {
+    match create(JWTKey::new("1".to_string()), repo.clone(), Json(input)) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 201);
 
@@ -136,12 +332,8 @@ fn test_create() {
 
             assert_eq!(result.0, expected);
 
-            delete(
-                JWTKey::new("1".to_string()),
-                pool.get().unwrap(),
-                expected.id,
-            )
-            .expect("Unexpected error");
+            delete(JWTKey::new("1".to_string()), repo.clone(), expected.id)
+                .expect("Unexpected error");
         }
         _ => panic!("Unexpected response"),
     }
@@ -149,19 +341,24 @@ fn test_create() {
 
 #[test]
 fn test_update() {
-    let pool = BDPool::new().unwrap();
-    let con = pool.get().unwrap();
+    let repo = common::test_repo();
 
     let input = NoteIn {
         category_id: Some(1),
         title: "new_note".to_string(),
         data: "some_text_new_note".to_string(),
+        tags: Some(vec!["work".to_string()]),
+        format: None,
+        key_check: None,
     };
 
     let updated_input = NoteIn {
         category_id: Some(1),
         title: "other_note".to_string(),
         data: "other_text_new_note".to_string(),
+        tags: Some(vec!["home".to_string()]),
+        format: None,
+        key_check: None,
     };
 
     let mut expected = NoteOut {
@@ -169,11 +366,15 @@ fn test_update() {
         category_id: Some(1),
         title: "other_note".to_string(),
         data: "other_text_new_note".to_string(),
+        tags: vec!["home".to_string()],
         create_time: "".to_string(),
         update_time: "".to_string(),
+        deleted_time: None,
+        format: "plain".to_string(),
+        key_check: None,
     };
 
-    match create(JWTKey::new("1".to_string()), con, Json(input)) {
+    match create(JWTKey::new("1".to_string()), repo.clone(), Json(input)) {
         Ok(ApiResponse { json, status }) => {
             assert_eq!(status.code, 201);
 
@@ -184,7 +385,7 @@ fn test_update() {
 
             match update(
                 JWTKey::new("1".to_string()),
-                pool.get().unwrap(),
+                repo.clone(),
                 id,
                 Json(updated_input),
             ) {
@@ -193,12 +394,8 @@ fn test_update() {
                     let result = json.unwrap();
                     assert_eq!(result.0, expected);
 
-                    delete(
-                        JWTKey::new("1".to_string()),
-                        pool.get().unwrap(),
-                        expected.id,
-                    )
-                    .expect("Unexpected error");
+                    delete(JWTKey::new("1".to_string()), repo.clone(), expected.id)
+                        .expect("Unexpected error");
                 }
                 _ => panic!("Unexpected response"),
             }
@@ -206,3 +403,339 @@ fn test_update() {
         _ => panic!("Unexpected response"),
     }
 }
+
+#[test]
+fn test_update_keeps_tags() {
+    let repo = common::test_repo();
+    repo.tags().add(1, 1, "work").unwrap();
+
+    // Clients sending no tags leave the ones of the note.
+    let note_in = |tags: Option<Vec<String>>| NoteIn {
+        category_id: None,
+        title: "note_a_user_a".to_string(),
+        data: "other_text".to_string(),
+        tags: tags,
+        format: None,
+        key_check: None,
+    };
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        Json(note_in(None)),
+    ) {
+        Ok(ApiResponse { json, .. }) => {
+            assert_eq!(json.unwrap().0.tags, vec!["work".to_string()])
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        Json(note_in(Some(vec![]))),
+    ) {
+        Ok(ApiResponse { json, .. }) => assert!(json.unwrap().0.tags.is_empty()),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_tags() {
+    let repo = common::test_repo();
+
+    match add_tag(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        2,
+        " Work ".to_string(),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            assert_eq!(json.unwrap().0.tags, vec!["work".to_string()]);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    // Blank tags are ignored.
+    let query = NotesQuery {
+        tags: vec![" ".to_string()],
+        ..Default::default()
+    };
+    match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(json.unwrap().0.items.len(), 3);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    let query = NotesQuery {
+        tags: vec!["work".to_string()],
+        ..Default::default()
+    };
+    match all(JWTKey::new("1".to_string()), repo.clone(), query) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap();
+            assert_eq!(
+                result
+                    .0
+                    .items
+                    .iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<i32>>(),
+                vec![2]
+            );
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match remove_tag(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        2,
+        "work".to_string(),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert!(json.unwrap().0.tags.is_empty());
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_add_tag_ko() {
+    let repo = common::test_repo();
+
+    match add_tag(JWTKey::new("2".to_string()), repo, 1, "work".to_string()) {
+        Err(response) => {
+            assert_eq!(response.status().code, 404);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_search() {
+    let repo = common::test_repo();
+
+    match search(
+        JWTKey::new("1".to_string()),
+        repo,
+        Some("Note B".to_string()),
+        None,
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].note.id, 2);
+            assert_eq!(result[0].title, "<mark>note</mark>_<mark>b</mark>_user_a");
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_search_ko() {
+    let repo = common::test_repo();
+
+    match search(JWTKey::new("1".to_string()), repo, None, None) {
+        Err(response) => {
+            assert_eq!(response.status().code, 400);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_format() {
+    let repo = common::test_repo();
+
+    let input = |format: Option<&str>| NoteIn {
+        category_id: None,
+        title: "new_note".to_string(),
+        data: "# Title\n\n- [x] done".to_string(),
+        tags: None,
+        format: format.map(|format| format.to_string()),
+        key_check: None,
+    };
+
+    match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        Json(input(Some("html"))),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    let id = match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        Json(input(Some("markdown"))),
+    ) {
+        Ok(ApiResponse { json, .. }) => {
+            let note = json.unwrap().into_inner();
+            assert_eq!(note.format, "markdown");
+            note.id
+        }
+        _ => panic!("Unexpected response"),
+    };
+
+    // The format is kept if it is not sent.
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        id,
+        Json(input(None)),
+    ) {
+        Ok(ApiResponse { json, .. }) => assert_eq!(json.unwrap().into_inner().format, "markdown"),
+        _ => panic!("Unexpected response"),
+    }
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        id,
+        Json(input(Some("html"))),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_render() {
+    let repo = common::test_repo();
+
+    let note = repo
+        .notes()
+        .create(
+            &NewNote::new(
+                1,
+                None,
+                "new_note".to_string(),
+                "# Title\n\n<script>alert(1)</script>\n\n| a |\n|---|\n| 1 |".to_string(),
+            )
+            .with_format(NoteFormat::Markdown),
+            &[],
+        )
+        .unwrap();
+
+    match render(JWTKey::new("1".to_string()), repo.clone(), note.id) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            let render_out = json.unwrap().into_inner();
+            assert_eq!(render_out.id, note.id);
+            assert_eq!(render_out.format, "markdown");
+            assert!(render_out.html.contains("<h1>Title</h1>"));
+            assert!(render_out.html.contains("<table>"));
+            assert!(!render_out.html.contains("<script>"));
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match render(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json, .. }) => assert_eq!(
+            json.unwrap().into_inner().html,
+            "<pre>some_text_note_a_user_a</pre>"
+        ),
+        _ => panic!("Unexpected response"),
+    }
+
+    match render(JWTKey::new("2".to_string()), repo, note.id) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_encrypted() {
+    let repo = common::test_repo();
+
+    let note_in = NoteIn {
+        category_id: None,
+        title: "c29tZV9jaXBoZXJ0ZXh0".to_string(),
+        data: "bm90ZSBiIHNlY3JldA==".to_string(),
+        tags: None,
+        format: None,
+        key_check: Some("v1$c29tZV9zYWx0$c29tZV9jaGVjaw==".to_string()),
+    };
+
+    let id = match create(JWTKey::new("1".to_string()), repo.clone(), Json(note_in)) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            let note_out = json.unwrap().into_inner();
+            assert_eq!(
+                note_out.key_check,
+                Some("v1$c29tZV9zYWx0$c29tZV9jaGVjaw==".to_string())
+            );
+            note_out.id
+        }
+        _ => panic!("Unexpected response"),
+    };
+
+    match get(JWTKey::new("1".to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json, .. }) => {
+            let note_out = json.unwrap().into_inner();
+            assert_eq!(note_out.data, "bm90ZSBiIHNlY3JldA==");
+            assert!(note_out.key_check.is_some());
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match render(JWTKey::new("1".to_string()), repo.clone(), id) {
+        Err(response) => assert_eq!(response.status().code, 422),
+        _ => panic!("Unexpected response"),
+    }
+
+    match search(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        Some("bm90ZSBiIHNlY3JldA".to_string()),
+        None,
+    ) {
+        Ok(ApiResponse { json, .. }) => assert!(json.unwrap().0.is_empty()),
+        _ => panic!("Unexpected response"),
+    }
+
+    // Updating it without key-check value would store plain text as its
+    // ciphertext.
+    let note_in = NoteIn {
+        category_id: None,
+        title: "new_note".to_string(),
+        data: "See [[note_a_user_a]]".to_string(),
+        tags: None,
+        format: None,
+        key_check: None,
+    };
+    match update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        id,
+        Json(note_in),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    match get(JWTKey::new("1".to_string()), repo.clone(), id) {
+        Ok(ApiResponse { json, .. }) => {
+            let note_out = json.unwrap().into_inner();
+            assert_eq!(note_out.data, "bm90ZSBiIHNlY3JldA==");
+            assert!(note_out.key_check.is_some());
+        }
+        _ => panic!("Unexpected response"),
+    }
+    assert!(repo.links().find_by_note_id(id).unwrap().is_empty());
+
+    match render(JWTKey::new("1".to_string()), repo, id) {
+        Err(response) => assert_eq!(response.status().code, 422),
+        _ => panic!("Unexpected response"),
+    }
+}
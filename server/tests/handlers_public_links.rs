@@ -0,0 +1,238 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::public_link::*;
+use rnotes_core::models::db::note::NoteFormat;
+use rnotes_core::repository::Repo;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::public::{self, LinkPassword, PublicNote};
+use rnotes_server::handlers::public_links::{all, create, delete};
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+use std::panic;
+
+fn link_in(password: Option<&str>, expires_in_hours: Option<i64>) -> Json<PublicLinkIn> {
+    Json(PublicLinkIn {
+        password: password.map(|password| password.to_string()),
+        expires_in_hours: expires_in_hours,
+    })
+}
+
+// Creates a link to the first note of user_a.
+fn create_a(repo: &Repo, password: Option<&str>) -> PublicLinkOut {
+    match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        link_in(password, Some(24)),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            json.unwrap().into_inner()
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+fn open(
+    repo: &Repo,
+    token: &str,
+    format: Option<&str>,
+    password: Option<&str>,
+) -> Result<PublicNote, u16> {
+    public::get(
+        repo.clone(),
+        token.to_string(),
+        format.map(|format| format.to_string()),
+        LinkPassword(password.map(|password| password.to_string())),
+    )
+    .map_err(|response| response.status().code)
+}
+
+#[test]
+fn test_create_and_all() {
+    let repo = common::test_repo();
+
+    let public_link = create_a(&repo, None);
+    assert_eq!(public_link.note_id, 1);
+    assert!(!public_link.has_password);
+    assert!(public_link.expire_time.is_some());
+    assert_eq!(public_link.token.as_ref().unwrap().len(), 64);
+
+    match all(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            let public_links = json.unwrap().into_inner();
+            assert_eq!(public_links.len(), 1);
+            assert_eq!(public_links[0].id, public_link.id);
+            assert_eq!(public_links[0].token, None);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match all(JWTKey::new("2".to_string()), repo, 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_create_invalid() {
+    let repo = common::test_repo();
+
+    for (id_user, id, password, expires_in_hours, code) in &[
+        ("1", 1, None, Some(0), 400),
+        ("1", 1, None, Some(87_601), 400),
+        ("1", 1, None, Some(1_000_000_000_000), 400),
+        ("1", 1, None, Some(i64::MIN), 400),
+        ("1", 1, Some(""), None, 400),
+        ("2", 1, None, None, 404),
+        ("1", 999, None, None, 404),
+    ] {
+        match create(
+            JWTKey::new(id_user.to_string()),
+            repo.clone(),
+            *id,
+            link_in(*password, *expires_in_hours),
+        ) {
+            Err(response) => assert_eq!(response.status().code, *code),
+            _ => panic!("Unexpected response"),
+        }
+    }
+}
+
+#[test]
+fn test_public_get() {
+    let repo = common::test_repo();
+
+    let token = create_a(&repo, None).token.unwrap();
+
+    match open(&repo, &token, None, None) {
+        Ok(PublicNote::Json(note)) => {
+            assert_eq!(note.title, "note_a_user_a");
+            assert_eq!(note.data, "some_text_note_a_user_a");
+        }
+        _ => panic!("Unexpected response"),
+    }
+    match open(&repo, &token, Some("html"), None) {
+        Ok(PublicNote::Html(html)) => assert!(html.contains("<h1>note_a_user_a</h1>")),
+        _ => panic!("Unexpected response"),
+    }
+    assert_eq!(open(&repo, &token, Some("pdf"), None).err(), Some(400));
+    assert_eq!(open(&repo, "unknown", None, None).err(), Some(404));
+
+    repo.notes().trash(1).unwrap();
+    assert_eq!(open(&repo, &token, None, None).err(), Some(404));
+}
+
+#[test]
+fn test_public_get_password() {
+    let repo = common::test_repo();
+
+    let token = create_a(&repo, Some("link_password")).token.unwrap();
+
+    assert_eq!(open(&repo, &token, None, None).err(), Some(401));
+    assert_eq!(open(&repo, &token, None, Some("other")).err(), Some(401));
+    match open(&repo, &token, None, Some("link_password")) {
+        Ok(PublicNote::Json(note)) => assert_eq!(note.title, "note_a_user_a"),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_public_get_html_escaped() {
+    let repo = common::test_repo();
+
+    let token = create_a(&repo, None).token.unwrap();
+    let mut note = repo.notes().find_by_id_and_user_id(1, 1).unwrap();
+    note.title = "<script>alert(1)</script>".to_string();
+    repo.notes().update(&note, &[]).unwrap();
+
+    match open(&repo, &token, Some("html"), None) {
+        Ok(PublicNote::Html(html)) => {
+            assert!(!html.contains("<script>"));
+            assert!(html.contains("&lt;script&gt;"));
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_public_get_markdown() {
+    let repo = common::test_repo();
+
+    let token = create_a(&repo, None).token.unwrap();
+    let mut note = repo.notes().find_by_id_and_user_id(1, 1).unwrap();
+    note.data = "*some_text*".to_string();
+    note.format = NoteFormat::Markdown.to_string();
+    repo.notes().update(&note, &[]).unwrap();
+
+    match open(&repo, &token, Some("html"), None) {
+        Ok(PublicNote::Html(html)) => assert!(html.contains("<em>some_text</em>")),
+        _ => panic!("Unexpected response"),
+    }
+    match open(&repo, &token, None, None) {
+        Ok(PublicNote::Json(note)) => assert_eq!(note.format, "markdown"),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_public_get_encrypted() {
+    let repo = common::test_repo();
+
+    let token = create_a(&repo, None).token.unwrap();
+    let mut note = repo.notes().find_by_id_and_user_id(1, 1).unwrap();
+    note.key_check = Some("some_key_check".to_string());
+    repo.notes().update(&note, &[]).unwrap();
+
+    assert_eq!(open(&repo, &token, Some("html"), None).err(), Some(422));
+    match open(&repo, &token, None, None) {
+        Ok(PublicNote::Json(note)) => {
+            assert_eq!(note.key_check, Some("some_key_check".to_string()))
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete() {
+    let repo = common::test_repo();
+
+    let public_link = create_a(&repo, None);
+    let token = public_link.token.clone().unwrap();
+
+    match delete(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        1,
+        public_link.id,
+    ) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        public_link.id,
+    ) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        public_link.id,
+    ) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    assert_eq!(open(&repo, &token, None, None).err(), Some(404));
+}
@@ -0,0 +1,137 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::note::NoteIn;
+use rnotes_core::models::api::revision::{RevisionDiffOut, RevisionOut};
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::notes;
+use rnotes_server::handlers::revisions::*;
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+
+fn update_note(repo: &rnotes_core::repository::Repo, title: &str, data: &str) {
+    notes::update(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        Json(NoteIn {
+            category_id: None,
+            title: title.to_string(),
+            data: data.to_string(),
+            tags: None,
+            format: None,
+            key_check: None,
+        }),
+    )
+    .expect("Unexpected error");
+}
+
+#[test]
+fn test_all() {
+    let repo = common::test_repo();
+
+    update_note(&repo, "title_b", "data_b");
+    update_note(&repo, "title_c", "data_c");
+
+    let expected = vec![
+        RevisionOut {
+            revision: 1,
+            category_id: None,
+            title: "note_a_user_a".to_string(),
+            data: "some_text_note_a_user_a".to_string(),
+            create_time: "".to_string(),
+        },
+        RevisionOut {
+            revision: 2,
+            category_id: None,
+            title: "title_b".to_string(),
+            data: "data_b".to_string(),
+            create_time: "".to_string(),
+        },
+    ];
+
+    match all(JWTKey::new("1".to_string()), repo, 1) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(json.unwrap().0, expected);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_get_ko() {
+    let repo = common::test_repo();
+
+    update_note(&repo, "title_b", "data_b");
+
+    match get(JWTKey::new("1".to_string()), repo.clone(), 1, 2) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match get(JWTKey::new("2".to_string()), repo.clone(), 1, 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_diff() {
+    let repo = common::test_repo();
+
+    update_note(&repo, "title", "line_a\nline_b");
+    update_note(&repo, "title", "line_a\nline_c");
+
+    match diff(JWTKey::new("1".to_string()), repo.clone(), 1, 2, None) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(
+                json.unwrap().0,
+                RevisionDiffOut {
+                    from: 2,
+                    to: None,
+                    title: " title".to_string(),
+                    data: " line_a\n-line_b\n+line_c".to_string(),
+                }
+            );
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match diff(JWTKey::new("1".to_string()), repo.clone(), 1, 1, Some(2)) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(json.unwrap().0.title, "-note_a_user_a\n+title");
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_restore() {
+    let repo = common::test_repo();
+
+    update_note(&repo, "title_b", "data_b");
+
+    match restore(JWTKey::new("1".to_string()), repo.clone(), 1, 1) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+
+            let result = json.unwrap().0;
+            assert_eq!(result.title, "note_a_user_a");
+            assert_eq!(result.data, "some_text_note_a_user_a");
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    // Restoring keeps the replaced content as a new revision.
+    match get(JWTKey::new("1".to_string()), repo.clone(), 1, 2) {
+        Ok(ApiResponse { json, .. }) => assert_eq!(json.unwrap().0.title, "title_b"),
+        _ => panic!("Unexpected response"),
+    }
+}
@@ -0,0 +1,216 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::note::NoteIn;
+use rnotes_core::models::api::note_share::*;
+use rnotes_core::repository::Repo;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::notes;
+use rnotes_server::handlers::shares::*;
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+use std::panic;
+
+fn share_in(email: &str, permission: Option<&str>) -> Json<NoteShareIn> {
+    Json(NoteShareIn {
+        email: email.to_string(),
+        permission: permission.map(|permission| permission.to_string()),
+    })
+}
+
+fn note_in(title: &str) -> Json<NoteIn> {
+    Json(NoteIn {
+        category_id: None,
+        title: title.to_string(),
+        data: "some_text".to_string(),
+        tags: None,
+        format: None,
+        key_check: None,
+    })
+}
+
+// Shares the first note of user_a with user_b.
+fn share_a(repo: &Repo, permission: Option<&str>) -> NoteShareOut {
+    match create(
+        JWTKey::new("1".to_string()),
+        repo.clone(),
+        1,
+        share_in("user_b@email.com", permission),
+    ) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+            json.unwrap().into_inner()
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_create_and_all() {
+    let repo = common::test_repo();
+
+    let note_share = share_a(&repo, None);
+    assert_eq!(note_share.note_id, 1);
+    assert_eq!(note_share.user_id, 2);
+    assert_eq!(note_share.email, Some("user_b@email.com".to_string()));
+    assert_eq!(note_share.permission, "read");
+
+    let note_share = share_a(&repo, Some("write"));
+    assert_eq!(note_share.permission, "write");
+
+    match all(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert_eq!(json.unwrap().into_inner(), vec![note_share]);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match all(JWTKey::new("2".to_string()), repo, 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_create_invalid() {
+    let repo = common::test_repo();
+
+    for (id_user, id, email, permission, code) in &[
+        ("1", 1, "user_b@email.com", Some("admin"), 400),
+        ("1", 1, "user_a@email.com", None, 400),
+        ("1", 1, "unknown@email.com", None, 404),
+        ("2", 1, "user_c@email.com", None, 404),
+        ("1", 999, "user_b@email.com", None, 404),
+    ] {
+        match create(
+            JWTKey::new(id_user.to_string()),
+            repo.clone(),
+            *id,
+            share_in(email, *permission),
+        ) {
+            Err(response) => assert_eq!(response.status().code, *code),
+            _ => panic!("Unexpected response"),
+        }
+    }
+}
+
+#[test]
+fn test_shared() {
+    let repo = common::test_repo();
+
+    share_a(&repo, None);
+
+    match shared(JWTKey::new("2".to_string()), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            let shared_notes = json.unwrap().into_inner();
+            assert_eq!(shared_notes.len(), 1);
+            assert_eq!(shared_notes[0].note.id, 1);
+            assert_eq!(shared_notes[0].owner_id, 1);
+            assert_eq!(shared_notes[0].permission, "read");
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match shared(JWTKey::new("3".to_string()), repo) {
+        Ok(ApiResponse { json, .. }) => assert!(json.unwrap().into_inner().is_empty()),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_read_share() {
+    let repo = common::test_repo();
+
+    share_a(&repo, None);
+
+    match notes::get(JWTKey::new("2".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json, .. }) => assert_eq!(json.unwrap().into_inner().id, 1),
+        _ => panic!("Unexpected response"),
+    }
+    match notes::get(JWTKey::new("3".to_string()), repo.clone(), 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match notes::update(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        1,
+        note_in("updated"),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_write_share() {
+    let repo = common::test_repo();
+
+    share_a(&repo, Some("write"));
+
+    match notes::update(
+        JWTKey::new("2".to_string()),
+        repo.clone(),
+        1,
+        note_in("updated"),
+    ) {
+        Ok(ApiResponse { json, .. }) => assert_eq!(json.unwrap().into_inner().title, "updated"),
+        _ => panic!("Unexpected response"),
+    }
+
+    // The note is still owned by user_a.
+    assert_eq!(
+        repo.notes().find_by_id_and_user_id(1, 1).unwrap().title,
+        "updated"
+    );
+}
+
+#[test]
+fn test_delete_by_grantee() {
+    let repo = common::test_repo();
+
+    share_a(&repo, Some("write"));
+
+    match notes::delete(JWTKey::new("2".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+
+    // Only the share is removed.
+    assert!(repo.notes().find_by_id_and_user_id(1, 1).is_ok());
+    match notes::get(JWTKey::new("2".to_string()), repo, 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete() {
+    let repo = common::test_repo();
+
+    share_a(&repo, None);
+
+    match delete(JWTKey::new("2".to_string()), repo.clone(), 1, 2) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(JWTKey::new("1".to_string()), repo.clone(), 1, 2) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(JWTKey::new("1".to_string()), repo.clone(), 1, 2) {
+        Ok(ApiResponse { status, .. }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    match notes::get(JWTKey::new("2".to_string()), repo, 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
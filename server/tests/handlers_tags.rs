@@ -0,0 +1,43 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::tag::TagOut;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::tags::*;
+use rnotes_server::handlers::ApiResponse;
+
+#[test]
+fn test_all() {
+    let repo = common::test_repo();
+
+    repo.tags().add(1, 1, "work").unwrap();
+    repo.tags().add(1, 2, "work").unwrap();
+    repo.tags().add(1, 2, "home").unwrap();
+    repo.tags().add(2, 4, "other").unwrap();
+
+    let expected: Vec<TagOut> = vec![
+        TagOut {
+            name: "home".to_string(),
+            count: 1,
+        },
+        TagOut {
+            name: "work".to_string(),
+            count: 2,
+        },
+    ];
+
+    match all(JWTKey::new("1".to_string()), repo) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap();
+            assert_eq!(result.0, expected);
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
@@ -0,0 +1,123 @@
+extern crate chrono;
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use chrono::Duration;
+use rnotes_core::models::db::note::NoteFilter;
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::notes;
+use rnotes_server::handlers::trash::*;
+use rnotes_server::handlers::ApiResponse;
+
+#[test]
+fn test_delete_moves_to_trash() {
+    let repo = common::test_repo();
+
+    match notes::delete(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(notes::get(JWTKey::new("1".to_string()), repo.clone(), 1).is_err());
+    assert!(repo
+        .notes()
+        .find_by_user_id(1, &NoteFilter::default())
+        .unwrap()
+        .notes
+        .iter()
+        .all(|note| note.id != 1));
+
+    match all(JWTKey::new("1".to_string()), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert_eq!(
+                result.iter().map(|note| note.id).collect::<Vec<i32>>(),
+                vec![1]
+            );
+            assert!(result[0].deleted_time.is_some());
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    match all(JWTKey::new("2".to_string()), repo) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+            assert!(json.unwrap().0.is_empty());
+        }
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_restore() {
+    let repo = common::test_repo();
+
+    notes::delete(JWTKey::new("1".to_string()), repo.clone(), 1).expect("Unexpected error");
+
+    match restore(JWTKey::new("2".to_string()), repo.clone(), 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+
+    match restore(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+
+            let result = json.unwrap().0;
+            assert_eq!(result.id, 1);
+            assert_eq!(result.deleted_time, None);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(notes::get(JWTKey::new("1".to_string()), repo.clone(), 1).is_ok());
+
+    match restore(JWTKey::new("1".to_string()), repo, 1) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_delete() {
+    let repo = common::test_repo();
+
+    match delete(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    assert!(notes::get(JWTKey::new("1".to_string()), repo.clone(), 1).is_ok());
+
+    notes::delete(JWTKey::new("1".to_string()), repo.clone(), 1).expect("Unexpected error");
+
+    match delete(JWTKey::new("2".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 204),
+        _ => panic!("Unexpected response"),
+    }
+    match delete(JWTKey::new("1".to_string()), repo.clone(), 1) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(repo.notes().find_trashed_by_user_id(1).unwrap().is_empty());
+}
+
+#[test]
+fn test_purge() {
+    let repo = common::test_repo();
+
+    notes::delete(JWTKey::new("1".to_string()), repo.clone(), 1).expect("Unexpected error");
+
+    assert_eq!(purge(&repo, Duration::days(1)).unwrap(), 0);
+    assert_eq!(repo.notes().find_trashed_by_user_id(1).unwrap().len(), 1);
+
+    assert_eq!(purge(&repo, Duration::days(-1)).unwrap(), 1);
+    assert!(repo.notes().find_trashed_by_user_id(1).unwrap().is_empty());
+}
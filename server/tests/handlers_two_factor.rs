@@ -0,0 +1,254 @@
+extern crate dotenv;
+extern crate rnotes_core;
+extern crate rnotes_server;
+extern crate rocket;
+extern crate rocket_contrib;
+
+mod common;
+
+use rnotes_core::models::api::auth::*;
+use rnotes_core::repository::Repo;
+use rnotes_core::totp;
+use rnotes_server::handlers::auth::{
+    login, two_factor_disable, two_factor_login, two_factor_setup, two_factor_verify,
+};
+use rnotes_server::handlers::jwt::JWTKey;
+use rnotes_server::handlers::ApiResponse;
+use rocket_contrib::json::Json;
+
+fn login_key() -> JWTKey {
+    JWTKey::new("1".to_string()).with_jti("some_jti".to_string())
+}
+
+fn current_step() -> u64 {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    totp::step(time)
+}
+
+fn code_in(code: &str) -> Json<TotpCodeIn> {
+    Json(TotpCodeIn {
+        code: code.to_string(),
+    })
+}
+
+fn login_a(repo: &Repo) -> ApiResponse<LoginOut> {
+    let login_in = LoginIn {
+        email: "user_a@email.com".to_string(),
+        password: "some_password".to_string(),
+    };
+
+    login(Json(login_in), repo.clone()).ok().unwrap()
+}
+
+fn two_factor_login_in(challenge_token: &str, code: &str) -> Json<TwoFactorLoginIn> {
+    Json(TwoFactorLoginIn {
+        challenge_token: challenge_token.to_string(),
+        code: code.to_string(),
+    })
+}
+
+// Sets up and enables the 2FA of user_a, along with the step used.
+fn enable(repo: &Repo) -> (TotpSetupOut, u64) {
+    let setup = two_factor_setup(login_key(), repo.clone())
+        .ok()
+        .and_then(|response| response.json)
+        .unwrap()
+        .0;
+    let step = current_step();
+    let code = totp::code(&setup.secret, step).unwrap();
+    assert!(two_factor_verify(login_key(), repo.clone(), code_in(&code)).is_ok());
+
+    (setup, step)
+}
+
+#[test]
+fn test_two_factor_setup_and_verify() {
+    let repo = common::test_repo();
+
+    match two_factor_setup(login_key(), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 201);
+
+            let result = json.unwrap().0;
+            assert!(result
+                .uri
+                .starts_with("otpauth://totp/rnotes:user_a%40email.com?secret="));
+            assert!(result.uri.contains(&result.secret));
+            assert_eq!(result.recovery_codes.len(), 10);
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    // Not enabled yet, the login does not need a code.
+    assert!(login_a(&repo).json.unwrap().challenge_token.is_none());
+    match two_factor_verify(login_key(), repo.clone(), code_in("000000")) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+
+    enable(&repo);
+
+    match two_factor_setup(login_key(), repo.clone()) {
+        Err(response) => assert_eq!(response.status().code, 409),
+        _ => panic!("Unexpected response"),
+    }
+    match two_factor_setup(JWTKey::new("1".to_string()), repo) {
+        Err(response) => assert_eq!(response.status().code, 403),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_two_factor_login() {
+    let repo = common::test_repo();
+    let (setup, step) = enable(&repo);
+
+    let ApiResponse { json, status } = login_a(&repo);
+    let result = json.unwrap().0;
+    assert_eq!(status.code, 202);
+    assert!(result.jwt_token.is_empty());
+    let challenge_token = result.challenge_token.unwrap();
+
+    // The code used to enable the 2FA cannot be used again.
+    let used_code = totp::code(&setup.secret, step).unwrap();
+    match two_factor_login(
+        two_factor_login_in(&challenge_token, &used_code),
+        repo.clone(),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+
+    let code = totp::code(&setup.secret, step + 1).unwrap();
+    match two_factor_login(two_factor_login_in(&challenge_token, &code), repo.clone()) {
+        Ok(ApiResponse { json, status }) => {
+            assert_eq!(status.code, 200);
+
+            let result = json.unwrap().0;
+            assert!(!result.jwt_token.is_empty());
+            assert!(!result.refresh_token.is_empty());
+        }
+        _ => panic!("Unexpected response"),
+    }
+
+    // The challenge is deleted once used.
+    match two_factor_login(
+        two_factor_login_in(&challenge_token, &setup.recovery_codes[0]),
+        repo.clone(),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+
+    let challenge_token = login_a(&repo).json.unwrap().0.challenge_token.unwrap();
+    let recovery_code = setup.recovery_codes[0].to_uppercase();
+    assert!(two_factor_login(
+        two_factor_login_in(&challenge_token, &recovery_code),
+        repo.clone()
+    )
+    .is_ok());
+
+    let challenge_token = login_a(&repo).json.unwrap().0.challenge_token.unwrap();
+    match two_factor_login(
+        two_factor_login_in(&challenge_token, &setup.recovery_codes[0]),
+        repo,
+    ) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_two_factor_login_attempts() {
+    let repo = common::test_repo();
+    let (setup, _) = enable(&repo);
+
+    let challenge_token = login_a(&repo).json.unwrap().0.challenge_token.unwrap();
+    for _ in 0..5 {
+        assert!(two_factor_login(
+            two_factor_login_in(&challenge_token, "000000"),
+            repo.clone()
+        )
+        .is_err());
+    }
+
+    match two_factor_login(
+        two_factor_login_in(&challenge_token, &setup.recovery_codes[1]),
+        repo.clone(),
+    ) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+    match two_factor_login(
+        two_factor_login_in("unknown", &setup.recovery_codes[1]),
+        repo,
+    ) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_two_factor_lockout() {
+    let repo = common::test_repo();
+    let (setup, _) = enable(&repo);
+
+    // The wrong codes are counted across the challenges of the user.
+    let mut challenge_token = String::new();
+    for attempt in 0..10 {
+        if attempt % 5 == 0 {
+            challenge_token = login_a(&repo).json.unwrap().0.challenge_token.unwrap();
+        }
+        let expected = if attempt < 9 { 401 } else { 429 };
+        match two_factor_login(
+            two_factor_login_in(&challenge_token, "000000"),
+            repo.clone(),
+        ) {
+            Err(response) => assert_eq!(response.status().code, expected),
+            _ => panic!("Unexpected response"),
+        }
+    }
+
+    let login_in = LoginIn {
+        email: "user_a@email.com".to_string(),
+        password: "some_password".to_string(),
+    };
+    match login(Json(login_in), repo.clone()) {
+        Err(response) => assert_eq!(response.status().code, 429),
+        _ => panic!("Unexpected response"),
+    }
+    match two_factor_login(
+        two_factor_login_in(&challenge_token, &setup.recovery_codes[0]),
+        repo,
+    ) {
+        Err(response) => assert_eq!(response.status().code, 401),
+        _ => panic!("Unexpected response"),
+    }
+}
+
+#[test]
+fn test_two_factor_disable() {
+    let repo = common::test_repo();
+
+    match two_factor_disable(login_key(), repo.clone(), code_in("000000")) {
+        Err(response) => assert_eq!(response.status().code, 404),
+        _ => panic!("Unexpected response"),
+    }
+
+    let (setup, _) = enable(&repo);
+
+    match two_factor_disable(login_key(), repo.clone(), code_in("000000")) {
+        Err(response) => assert_eq!(response.status().code, 400),
+        _ => panic!("Unexpected response"),
+    }
+    match two_factor_disable(login_key(), repo.clone(), code_in(&setup.recovery_codes[0])) {
+        Ok(ApiResponse { json: _, status }) => assert_eq!(status.code, 200),
+        _ => panic!("Unexpected response"),
+    }
+
+    assert!(login_a(&repo).json.unwrap().challenge_token.is_none());
+}